@@ -0,0 +1,113 @@
+//! Benchmarks draining a short bandwidth pattern repeated many times.
+//!
+//! The resettable sub-models (e.g. `StaticBw`) are reset and reused at each cycle, while the others
+//! (e.g. `NormalizedBw`) are rebuilt from their configs. The number of allocations per cycle of both
+//! cases is printed before the benchmarks.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use netem_trace::model::{
+    BwTraceConfig, NormalizedBwConfig, RepeatedBwPatternConfig, StaticBwConfig,
+};
+use netem_trace::{Bandwidth, BwTrace, Duration};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An allocator counting the number of allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const CYCLES: usize = 100_000;
+
+fn static_pattern() -> RepeatedBwPatternConfig {
+    RepeatedBwPatternConfig::new()
+        .pattern(vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_millis(10)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(24))
+                    .duration(Duration::from_millis(10)),
+            ) as Box<dyn BwTraceConfig>,
+        ])
+        .count(CYCLES)
+}
+
+fn normalized_pattern() -> RepeatedBwPatternConfig {
+    RepeatedBwPatternConfig::new()
+        .pattern(vec![
+            Box::new(
+                NormalizedBwConfig::new()
+                    .duration(Duration::from_millis(10))
+                    .step(Duration::from_millis(10)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                NormalizedBwConfig::new()
+                    .duration(Duration::from_millis(10))
+                    .step(Duration::from_millis(10)),
+            ) as Box<dyn BwTraceConfig>,
+        ])
+        .count(CYCLES)
+}
+
+fn drain(model: &mut impl BwTrace) {
+    while let Some(segment) = model.next_bw() {
+        black_box(segment);
+    }
+}
+
+fn report_allocations(name: &str, config: RepeatedBwPatternConfig) {
+    let mut model = config.build();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drain(&mut model);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "{}: {} allocations in {} cycles ({:.3} per cycle)",
+        name,
+        allocations,
+        CYCLES,
+        allocations as f64 / CYCLES as f64
+    );
+}
+
+fn bench_repeated_bw(c: &mut Criterion) {
+    report_allocations("cached reset (StaticBw)", static_pattern());
+    report_allocations("rebuild (NormalizedBw)", normalized_pattern());
+
+    let mut group = c.benchmark_group("repeated_bw");
+    group.bench_function("cached reset (StaticBw)", |b| {
+        b.iter_batched(
+            || static_pattern().build(),
+            |mut model| drain(&mut model),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("rebuild (NormalizedBw)", |b| {
+        b.iter_batched(
+            || normalized_pattern().build(),
+            |mut model| drain(&mut model),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_repeated_bw);
+criterion_main!(benches);
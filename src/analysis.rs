@@ -0,0 +1,661 @@
+//! This module provides utilities to analyze the traces generated by struct implementing [`BwTrace`]
+//! and [`DelayTrace`].
+//!
+//! Enable `analysis` feature to use this module.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::analysis::diff_bw;
+//! # use netem_trace::model::StaticBwConfig;
+//! # use netem_trace::{Bandwidth, Duration};
+//! let mut old = StaticBwConfig::new()
+//!     .bw(Bandwidth::from_mbps(12))
+//!     .duration(Duration::from_secs(1))
+//!     .build();
+//! let mut new = StaticBwConfig::new()
+//!     .bw(Bandwidth::from_mbps(12))
+//!     .duration(Duration::from_millis(500))
+//!     .build();
+//! assert_eq!(
+//!     diff_bw(&mut old, &mut new, Duration::from_secs(1), Duration::from_millis(250)),
+//!     vec![
+//!         (Duration::from_millis(500), Bandwidth::from_mbps(12), Bandwidth::ZERO),
+//!         (Duration::from_millis(750), Bandwidth::from_mbps(12), Bandwidth::ZERO),
+//!     ]
+//! );
+//! ```
+
+use crate::measure::{BwSampler, ByteCounter};
+use crate::{Bandwidth, BwTrace, Delay, DelayTrace, Duration};
+
+#[cfg(feature = "bw-model")]
+use crate::model::{StaticBwConfig, TraceBwConfig};
+
+/// Compare the bandwidth generated by two traces.
+///
+/// Both traces are sampled every `step` within `[0, total)`, and the time points where
+/// the sampled bandwidth differs are returned together with the bandwidth of `old` and `new`,
+/// in the form of `(time, old_bw, new_bw)`.
+///
+/// The bandwidth of a trace is regarded as [`Bandwidth::ZERO`] after it is exhausted.
+/// If `step` is zero, an empty vector is returned.
+pub fn diff_bw(
+    old: &mut (impl BwTrace + ?Sized),
+    new: &mut (impl BwTrace + ?Sized),
+    total: Duration,
+    step: Duration,
+) -> Vec<(Duration, Bandwidth, Bandwidth)> {
+    let mut diff = Vec::new();
+    if step.is_zero() {
+        return diff;
+    }
+    let mut old = BwSampler::new(old);
+    let mut new = BwSampler::new(new);
+    let mut t = Duration::ZERO;
+    while t < total {
+        let (old_bw, new_bw) = (old.sample(t), new.sample(t));
+        if old_bw != new_bw {
+            diff.push((t, old_bw, new_bw));
+        }
+        t = match t.checked_add(step) {
+            Some(t) => t,
+            None => break,
+        };
+    }
+    diff
+}
+
+/// Compute the total bytes transferable over the first `total` of a trace.
+///
+/// This is the integral of bandwidth over time, i.e. the sum of `bw * duration / 8`
+/// over all the segments, where the segment crossing `total` is truncated.
+/// The result saturates at [`u64::MAX`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::analysis::total_bytes;
+/// # use netem_trace::model::StaticBwConfig;
+/// # use netem_trace::{Bandwidth, Duration};
+/// let mut static_bw = StaticBwConfig::new()
+///     .bw(Bandwidth::from_mbps(12))
+///     .duration(Duration::from_secs(1))
+///     .build();
+/// assert_eq!(total_bytes(&mut static_bw, Duration::from_secs(2)), 1_500_000);
+/// ```
+pub fn total_bytes(trace: &mut (impl BwTrace + ?Sized), total: Duration) -> u64 {
+    crate::measure::total_bytes(trace, total)
+}
+
+/// The summary statistics of the first `total` of a bandwidth trace, see [`BwTraceAnalysis::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BwTraceStats {
+    /// The lowest bandwidth among the segments.
+    pub min: Bandwidth,
+    /// The highest bandwidth among the segments.
+    pub max: Bandwidth,
+    /// The time-weighted mean of the bandwidth, i.e. `total_bytes * 8 / duration`, rounded down.
+    pub mean: Bandwidth,
+    /// The total bytes transferable, the same as [`total_bytes`].
+    pub total_bytes: u64,
+    /// The duration covered by the segments, which is shorter than `total` if the trace ends earlier.
+    pub duration: Duration,
+}
+
+/// The `BwTraceAnalysis` trait provides methods to summarize a bandwidth trace.
+///
+/// This trait is automatically implemented for all types that implement [`BwTrace`].
+pub trait BwTraceAnalysis: BwTrace {
+    /// Compute the total bytes transferable over the first `total` of the trace, the method form
+    /// of [`total_bytes`].
+    ///
+    /// The segment crossing `total` is truncated, and the result saturates at [`u64::MAX`]. The
+    /// bytes are accumulated exactly in integers instead of summing
+    /// `bw.as_bps() * duration.as_secs_f64() / 8` over the segments, so no rounding error builds
+    /// up over long traces.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::analysis::BwTraceAnalysis;
+    /// # use netem_trace::model::StaticBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let mut static_bw = StaticBwConfig::new()
+    ///     .bw(Bandwidth::from_mbps(12))
+    ///     .duration(Duration::from_secs(1))
+    ///     .build();
+    /// assert_eq!(static_bw.total_bytes(Duration::from_millis(500)), 750_000);
+    /// ```
+    fn total_bytes(&mut self, total: Duration) -> u64 {
+        total_bytes(self, total)
+    }
+
+    /// Compute the summary statistics over the first `total` of the trace.
+    ///
+    /// The segment crossing `total` is truncated, and segments of zero duration are skipped as they
+    /// carry no traffic. The mean is weighted by the durations of the segments, so it generally
+    /// differs from the arithmetic mean of the bandwidths. All the bandwidths are
+    /// [`Bandwidth::ZERO`] if no segment is covered.
+    ///
+    /// Like [`total_bytes`], this consumes the segments of the trace.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::analysis::BwTraceAnalysis;
+    /// # use netem_trace::model::TraceBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let mut trace = TraceBwConfig::new()
+    ///     .pattern(vec![
+    ///         (Duration::from_secs(3), vec![Bandwidth::from_mbps(4)]),
+    ///         (Duration::from_secs(1), vec![Bandwidth::from_mbps(12)]),
+    ///     ])
+    ///     .build();
+    /// let stats = trace.stats(Duration::MAX);
+    /// assert_eq!(stats.min, Bandwidth::from_mbps(4));
+    /// assert_eq!(stats.max, Bandwidth::from_mbps(12));
+    /// assert_eq!(stats.mean, Bandwidth::from_mbps(6));
+    /// assert_eq!(stats.total_bytes, 3_000_000);
+    /// ```
+    fn stats(&mut self, total: Duration) -> BwTraceStats {
+        let mut counter = ByteCounter::default();
+        let mut range: Option<(Bandwidth, Bandwidth)> = None;
+        let mut elapsed = Duration::ZERO;
+        while elapsed < total {
+            let Some((bw, duration)) = self.next_bw() else {
+                break;
+            };
+            let duration = duration.min(total - elapsed);
+            if duration.is_zero() {
+                continue;
+            }
+            counter.add(bw, duration);
+            range = Some(range.map_or((bw, bw), |(min, max)| (min.min(bw), max.max(bw))));
+            elapsed += duration;
+        }
+        let (min, max) = range.unwrap_or((Bandwidth::ZERO, Bandwidth::ZERO));
+        let mean = if elapsed.is_zero() {
+            Bandwidth::ZERO
+        } else {
+            let bps = counter.bit_nanos / elapsed.as_nanos();
+            Bandwidth::from_bps(u64::try_from(bps).unwrap_or(u64::MAX))
+        };
+        BwTraceStats {
+            min,
+            max,
+            mean,
+            total_bytes: counter.bytes(),
+            duration: elapsed,
+        }
+    }
+}
+
+impl<T: BwTrace + ?Sized> BwTraceAnalysis for T {}
+
+/// The `DelayTraceAnalysis` trait provides a method to compute the quantiles of a delay trace.
+///
+/// This trait is automatically implemented for all types that implement [`DelayTrace`].
+pub trait DelayTraceAnalysis: DelayTrace {
+    /// Compute the time-weighted quantiles of the delays over the first `total` of the trace.
+    ///
+    /// Each delay is weighted by the duration of its segment (the segment crossing `total` is
+    /// truncated), and the quantile `q` is the lowest delay whose segments, together with the ones
+    /// of the lower delays, last for at least `q` of the covered duration. Each of `qs` is clamped
+    /// to `[0, 1]`, e.g. `0.5` for the median and `0.99` for p99.
+    ///
+    /// Returns one delay for each of `qs`, or an empty vector if no segment is covered.
+    ///
+    /// Like [`total_bytes`], this consumes the segments of the trace.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::analysis::DelayTraceAnalysis;
+    /// # use netem_trace::model::StaticDelayConfig;
+    /// # use netem_trace::{Delay, Duration};
+    /// let mut static_delay = StaticDelayConfig::new()
+    ///     .delay(Delay::from_millis(10))
+    ///     .duration(Duration::from_secs(1))
+    ///     .build();
+    /// assert_eq!(
+    ///     static_delay.delay_percentiles(Duration::MAX, &[0.5, 0.99]),
+    ///     [Delay::from_millis(10); 2]
+    /// );
+    /// ```
+    fn delay_percentiles(&mut self, total: Duration, qs: &[f64]) -> Vec<Delay> {
+        let mut segments: Vec<(Delay, Duration)> = vec![];
+        let mut elapsed = Duration::ZERO;
+        while elapsed < total {
+            let Some((delay, duration)) = self.next_delay() else {
+                break;
+            };
+            let duration = duration.min(total - elapsed);
+            if !duration.is_zero() {
+                segments.push((delay, duration));
+                elapsed += duration;
+            }
+        }
+        if segments.is_empty() {
+            return vec![];
+        }
+        segments.sort_by_key(|(delay, _)| *delay);
+        let covered = elapsed.as_nanos() as f64;
+        qs.iter()
+            .map(|q| {
+                let target = q.clamp(0.0, 1.0) * covered;
+                let mut weight = 0;
+                segments
+                    .iter()
+                    .find(|(_, duration)| {
+                        weight += duration.as_nanos();
+                        weight as f64 >= target
+                    })
+                    .unwrap_or(segments.last().unwrap())
+                    .0
+            })
+            .collect()
+    }
+}
+
+impl<T: DelayTrace + ?Sized> DelayTraceAnalysis for T {}
+
+/// Find the jumps in the bandwidth of a trace, i.e. where the bandwidth changes by more than
+/// `threshold` between consecutive segments.
+///
+/// The segments starting within `[0, total)` are checked, and each jump is returned in the form of
+/// `(time, before_bw, after_bw)`, where `time` is the start of the segment after the jump.
+///
+/// Segments of zero duration are skipped, and the end of the trace is not regarded as a jump.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::analysis::bw_jumps;
+/// # use netem_trace::model::TraceBwConfig;
+/// # use netem_trace::{Bandwidth, Duration};
+/// let mut trace = TraceBwConfig::new()
+///     .pattern(vec![(
+///         Duration::from_secs(1),
+///         vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(13), Bandwidth::from_mbps(2)],
+///     )])
+///     .build();
+/// assert_eq!(
+///     bw_jumps(&mut trace, Duration::from_secs(3), Bandwidth::from_mbps(5)),
+///     vec![(Duration::from_secs(2), Bandwidth::from_mbps(13), Bandwidth::from_mbps(2))]
+/// );
+/// ```
+pub fn bw_jumps(
+    trace: &mut (impl BwTrace + ?Sized),
+    total: Duration,
+    threshold: Bandwidth,
+) -> Vec<(Duration, Bandwidth, Bandwidth)> {
+    let mut jumps = Vec::new();
+    let mut last: Option<Bandwidth> = None;
+    let mut elapsed = Duration::ZERO;
+    while elapsed < total {
+        let Some((bw, duration)) = trace.next_bw() else {
+            break;
+        };
+        if duration.is_zero() {
+            continue;
+        }
+        if let Some(last) = last {
+            let change = if bw > last { bw - last } else { last - bw };
+            if change > threshold {
+                jumps.push((elapsed, last, bw));
+            }
+        }
+        last = Some(bw);
+        elapsed = elapsed.saturating_add(duration);
+    }
+    jumps
+}
+
+#[cfg(feature = "bw-model")]
+impl StaticBwConfig {
+    /// The same as [`total_bytes`], but computed from the configuration without building the model.
+    pub fn total_bytes_config(&self, total: Duration) -> u64 {
+        let mut counter = ByteCounter::default();
+        counter.add(self.bw_or_default(), self.finite_duration().min(total));
+        counter.bytes()
+    }
+}
+
+#[cfg(feature = "bw-model")]
+impl TraceBwConfig {
+    /// The same as [`total_bytes`], but computed from the configuration without building the model.
+    pub fn total_bytes_config(&self, total: Duration) -> u64 {
+        let mut counter = ByteCounter::default();
+        let mut remaining = total;
+        for bw in self
+            .pattern
+            .iter()
+            .flat_map(|(duration, bws)| bws.iter().map(move |bw| (*bw, *duration)))
+        {
+            if remaining.is_zero() {
+                break;
+            }
+            let duration = bw.1.min(remaining);
+            counter.add(bw.0, duration);
+            remaining -= duration;
+        }
+        counter.bytes()
+    }
+}
+
+#[cfg(all(test, feature = "bw-model"))]
+mod test {
+    use super::*;
+    use crate::model::{BwTraceConfig, RepeatedBwPatternConfig};
+
+    fn three_segments(mid: Bandwidth) -> RepeatedBwPatternConfig {
+        RepeatedBwPatternConfig::new().count(1).pattern(vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(mid)
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+        ])
+    }
+
+    #[test]
+    fn test_diff_bw() {
+        let mut old = Box::new(three_segments(Bandwidth::from_mbps(24))).into_model();
+        let mut new = Box::new(three_segments(Bandwidth::from_mbps(24))).into_model();
+        let diff = diff_bw(
+            &mut *old,
+            &mut *new,
+            Duration::from_secs(3),
+            Duration::from_millis(100),
+        );
+        assert!(diff.is_empty());
+
+        let mut old = Box::new(three_segments(Bandwidth::from_mbps(24))).into_model();
+        let mut new = Box::new(three_segments(Bandwidth::from_mbps(36))).into_model();
+        let diff = diff_bw(
+            &mut *old,
+            &mut *new,
+            Duration::from_secs(3),
+            Duration::from_millis(100),
+        );
+        assert_eq!(
+            diff,
+            (10..20)
+                .map(|i| (
+                    Duration::from_millis(100 * i),
+                    Bandwidth::from_mbps(24),
+                    Bandwidth::from_mbps(36)
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        // Only the tail differs when the new trace is shorter.
+        let mut old = Box::new(three_segments(Bandwidth::from_mbps(24))).into_model();
+        let mut new = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .build();
+        let diff = diff_bw(
+            &mut *old,
+            &mut new,
+            Duration::from_secs(4),
+            Duration::from_millis(500),
+        );
+        assert_eq!(
+            diff,
+            vec![
+                (
+                    Duration::from_millis(1000),
+                    Bandwidth::from_mbps(24),
+                    Bandwidth::ZERO
+                ),
+                (
+                    Duration::from_millis(1500),
+                    Bandwidth::from_mbps(24),
+                    Bandwidth::ZERO
+                ),
+                (
+                    Duration::from_millis(2000),
+                    Bandwidth::from_mbps(12),
+                    Bandwidth::ZERO
+                ),
+                (
+                    Duration::from_millis(2500),
+                    Bandwidth::from_mbps(12),
+                    Bandwidth::ZERO
+                ),
+            ]
+        );
+
+        assert!(diff_bw(&mut *old, &mut new, Duration::from_secs(1), Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_total_bytes() {
+        // 12Mbps for 1s = 12_000_000 / 8 bytes
+        let config = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1));
+        assert_eq!(
+            total_bytes(&mut config.clone().build(), Duration::from_secs(2)),
+            1_500_000
+        );
+        assert_eq!(config.total_bytes_config(Duration::from_secs(2)), 1_500_000);
+        assert_eq!(
+            config.clone().build().total_bytes(Duration::from_secs(1)),
+            1_500_000
+        );
+        assert_eq!(
+            total_bytes(&mut config.clone().build(), Duration::from_millis(500)),
+            750_000
+        );
+        assert_eq!(
+            config.total_bytes_config(Duration::from_millis(500)),
+            750_000
+        );
+
+        // 8Mbps for 1s + 16Mbps for 1s = 1_000_000 + 2_000_000 bytes
+        let config = TraceBwConfig::new().pattern(vec![(
+            Duration::from_secs(1),
+            vec![Bandwidth::from_mbps(8), Bandwidth::from_mbps(16)],
+        )]);
+        assert_eq!(
+            total_bytes(&mut config.clone().build(), Duration::from_secs(3)),
+            3_000_000
+        );
+        assert_eq!(config.total_bytes_config(Duration::from_secs(3)), 3_000_000);
+        // 8Mbps for 1s + 16Mbps for 0.5s = 1_000_000 + 1_000_000 bytes
+        assert_eq!(
+            total_bytes(&mut config.clone().build(), Duration::from_millis(1500)),
+            2_000_000
+        );
+        assert_eq!(
+            config.total_bytes_config(Duration::from_millis(1500)),
+            2_000_000
+        );
+
+        let mut model = Box::new(three_segments(Bandwidth::from_mbps(24))).into_model();
+        assert_eq!(total_bytes(&mut *model, Duration::MAX), 6_000_000);
+
+        let mut max_bw = StaticBwConfig::new()
+            .bw(Bandwidth::MAX)
+            .duration(Duration::MAX)
+            .build();
+        assert_eq!(total_bytes(&mut max_bw, Duration::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_stats() {
+        // 4Mbps for 3s and 12Mbps for 1s
+        let config = TraceBwConfig::new().pattern(vec![
+            (Duration::from_secs(3), vec![Bandwidth::from_mbps(4)]),
+            (Duration::from_secs(1), vec![Bandwidth::from_mbps(12)]),
+        ]);
+        let stats = config.clone().build().stats(Duration::MAX);
+        assert_eq!(
+            stats,
+            BwTraceStats {
+                min: Bandwidth::from_mbps(4),
+                max: Bandwidth::from_mbps(12),
+                mean: Bandwidth::from_mbps(6),
+                total_bytes: 3_000_000,
+                duration: Duration::from_secs(4),
+            }
+        );
+        // the time-weighted mean is not the arithmetic mean of the bandwidths
+        assert_ne!(stats.mean, Bandwidth::from_mbps(8));
+
+        // the segment crossing `total` is truncated
+        let stats = config.clone().build().stats(Duration::from_secs(2));
+        assert_eq!(stats.max, Bandwidth::from_mbps(4));
+        assert_eq!(stats.mean, Bandwidth::from_mbps(4));
+        assert_eq!(stats.total_bytes, 1_000_000);
+        assert_eq!(stats.duration, Duration::from_secs(2));
+
+        // segments of zero duration are skipped
+        let stats = TraceBwConfig::new()
+            .pattern(vec![
+                (Duration::ZERO, vec![Bandwidth::from_mbps(100)]),
+                (Duration::from_secs(1), vec![Bandwidth::from_mbps(12)]),
+            ])
+            .build()
+            .stats(Duration::MAX);
+        assert_eq!(stats.max, Bandwidth::from_mbps(12));
+
+        let stats = config.build().stats(Duration::ZERO);
+        assert_eq!(stats.mean, Bandwidth::ZERO);
+        assert_eq!(stats.duration, Duration::ZERO);
+    }
+
+    #[test]
+    #[cfg(feature = "delay-model")]
+    fn test_delay_percentiles() {
+        use crate::model::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+
+        // 10ms for 98% of the time, and 100ms for the rest
+        let config = RepeatedDelayPatternConfig::once(vec![
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(100))
+                    .duration(Duration::from_millis(20)),
+            ) as Box<dyn DelayTraceConfig>,
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .duration(Duration::from_millis(980)),
+            ),
+        ]);
+        assert_eq!(
+            config
+                .clone()
+                .build()
+                .delay_percentiles(Duration::MAX, &[0.0, 0.5, 0.98, 0.99, 1.0]),
+            [10, 10, 10, 100, 100].map(Delay::from_millis)
+        );
+        // the segments are weighted by durations, not by counts
+        assert_eq!(
+            config
+                .clone()
+                .build()
+                .delay_percentiles(Duration::MAX, &[0.5]),
+            [Delay::from_millis(10)]
+        );
+        // only the first 40ms are covered, half of which is 100ms
+        assert_eq!(
+            config
+                .build()
+                .delay_percentiles(Duration::from_millis(40), &[0.5, 0.51]),
+            [10, 100].map(Delay::from_millis)
+        );
+
+        // a single segment
+        let mut single = StaticDelayConfig::new()
+            .delay(Delay::from_millis(30))
+            .duration(Duration::from_secs(1))
+            .build();
+        assert_eq!(
+            single.delay_percentiles(Duration::MAX, &[0.01, 0.5, 0.99]),
+            [Delay::from_millis(30); 3]
+        );
+
+        // an empty trace
+        let mut empty = RepeatedDelayPatternConfig::once(vec![]).build();
+        assert!(empty.delay_percentiles(Duration::MAX, &[0.5]).is_empty());
+        let mut static_delay = StaticDelayConfig::new().build();
+        assert!(static_delay
+            .delay_percentiles(Duration::ZERO, &[0.5])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_bw_jumps() {
+        let staircase = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(100),
+            [10, 12, 20, 21, 5, 10]
+                .into_iter()
+                .map(Bandwidth::from_mbps)
+                .collect(),
+        )]);
+        // the step of exactly 5Mbps does not exceed the threshold
+        assert_eq!(
+            bw_jumps(
+                &mut staircase.clone().build(),
+                Duration::MAX,
+                Bandwidth::from_mbps(5)
+            ),
+            vec![
+                (
+                    Duration::from_millis(200),
+                    Bandwidth::from_mbps(12),
+                    Bandwidth::from_mbps(20)
+                ),
+                (
+                    Duration::from_millis(400),
+                    Bandwidth::from_mbps(21),
+                    Bandwidth::from_mbps(5)
+                ),
+            ]
+        );
+        // every step is reported with zero threshold
+        assert_eq!(
+            bw_jumps(
+                &mut staircase.clone().build(),
+                Duration::MAX,
+                Bandwidth::ZERO
+            )
+            .len(),
+            5
+        );
+        // only the segments starting before `total` are checked
+        assert_eq!(
+            bw_jumps(
+                &mut staircase.build(),
+                Duration::from_millis(400),
+                Bandwidth::from_mbps(5)
+            )
+            .len(),
+            1
+        );
+
+        // segments of zero duration are skipped
+        let mut trace = TraceBwConfig::new()
+            .pattern(vec![
+                (Duration::from_millis(100), vec![Bandwidth::from_mbps(12)]),
+                (Duration::ZERO, vec![Bandwidth::ZERO]),
+                (Duration::from_millis(100), vec![Bandwidth::from_mbps(12)]),
+            ])
+            .build();
+        assert!(bw_jumps(&mut trace, Duration::MAX, Bandwidth::from_mbps(1)).is_empty());
+    }
+}
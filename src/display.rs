@@ -0,0 +1,114 @@
+//! This module formats bandwidths and delays as short human-readable strings, e.g. for logging.
+//!
+//! The value is written in the largest unit in which it is at least 1, with at most 3 fractional
+//! digits (truncated) and a space before the unit, e.g. `"12 Mbps"`, `"1.5 Gbps"` or `"10 ms"`.
+//! The output is the same whether or not the `human` feature is enabled. Use the `human` feature
+//! for the format of configuration files instead.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::display::{fmt_bandwidth, fmt_delay};
+//! # use netem_trace::{Bandwidth, Delay};
+//! assert_eq!(fmt_bandwidth(Bandwidth::from_mbps(12)), "12 Mbps");
+//! assert_eq!(fmt_delay(Delay::from_millis(10)), "10 ms");
+//! ```
+use crate::units::bps_u128;
+use crate::{Bandwidth, Delay};
+
+const BANDWIDTH_UNITS: [(u128, &str); 4] = [
+    (1_000_000_000, "Gbps"),
+    (1_000_000, "Mbps"),
+    (1_000, "kbps"),
+    (1, "bps"),
+];
+
+const DELAY_UNITS: [(u128, &str); 4] = [
+    (1_000_000_000, "s"),
+    (1_000_000, "ms"),
+    (1_000, "us"),
+    (1, "ns"),
+];
+
+/// Format `value` in the largest of `units` (sorted in descending order) in which it is at least 1.
+fn fmt_with_units(value: u128, units: &[(u128, &str)]) -> String {
+    let (scale, unit) = units
+        .iter()
+        .find(|(scale, _)| value >= *scale)
+        .unwrap_or(&units[units.len() - 1]);
+    let (int, frac) = (value / scale, value % scale * 1000 / scale);
+    if frac == 0 {
+        format!("{} {}", int, unit)
+    } else {
+        let frac = format!("{:03}", frac);
+        format!("{}.{} {}", int, frac.trim_end_matches('0'), unit)
+    }
+}
+
+/// Format a number scaled by `10^digits` as a decimal number, without trailing zeros, e.g. `"1.5"`
+/// for `1500` scaled by `10^3`.
+#[cfg(any(feature = "tc", feature = "ns3"))]
+pub(crate) fn format_scaled(value: u128, digits: u32) -> String {
+    let scale = 10u128.pow(digits);
+    let (int, frac) = (value / scale, value % scale);
+    if frac == 0 {
+        int.to_string()
+    } else {
+        let frac = format!("{:0width$}", frac, width = digits as usize);
+        format!("{}.{}", int, frac.trim_end_matches('0'))
+    }
+}
+
+/// Format a bandwidth as a short human-readable string, e.g. `"12 Mbps"`.
+///
+/// See the [`display`](crate::display) module for the format.
+pub fn fmt_bandwidth(bw: Bandwidth) -> String {
+    let bps = bps_u128(bw);
+    fmt_with_units(bps, &BANDWIDTH_UNITS)
+}
+
+/// Format a delay (or any duration) as a short human-readable string, e.g. `"10 ms"`.
+///
+/// See the [`display`](crate::display) module for the format.
+pub fn fmt_delay(d: Delay) -> String {
+    fmt_with_units(d.as_nanos(), &DELAY_UNITS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fmt_bandwidth() {
+        for (bw, expected) in [
+            (Bandwidth::ZERO, "0 bps"),
+            (Bandwidth::from_bps(999), "999 bps"),
+            (Bandwidth::from_kbps(1), "1 kbps"),
+            (Bandwidth::from_kbps(500), "500 kbps"),
+            (Bandwidth::from_bps(1_234_567), "1.234 Mbps"),
+            (Bandwidth::from_mbps(12), "12 Mbps"),
+            (Bandwidth::from_kbps(1500), "1.5 Mbps"),
+            (Bandwidth::from_gbps(1), "1 Gbps"),
+            (Bandwidth::from_mbps(2500), "2.5 Gbps"),
+            (Bandwidth::from_gbps(1000), "1000 Gbps"),
+        ] {
+            assert_eq!(fmt_bandwidth(bw), expected);
+        }
+    }
+
+    #[test]
+    fn test_fmt_delay() {
+        for (delay, expected) in [
+            (Delay::ZERO, "0 ns"),
+            (Delay::from_nanos(10), "10 ns"),
+            (Delay::from_micros(250), "250 us"),
+            (Delay::from_micros(1500), "1.5 ms"),
+            (Delay::from_millis(10), "10 ms"),
+            (Delay::from_secs(1), "1 s"),
+            (Delay::from_millis(1250), "1.25 s"),
+            (Delay::from_secs(3600), "3600 s"),
+        ] {
+            assert_eq!(fmt_delay(delay), expected);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Temporary fixture files shared by the tests of the file loaders.
+
+use std::path::PathBuf;
+
+/// A fixture directory under the system temporary directory, removed on drop.
+pub(crate) struct Fixtures(PathBuf);
+
+impl Fixtures {
+    /// Creates the directory, whose name includes `name` and the process id to avoid clashes
+    /// between tests and concurrent test runs.
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("netem-trace-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    /// Returns the path of `file` in the directory, without creating it.
+    pub(crate) fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+    /// Writes `content` to `file` in the directory and returns its path.
+    pub(crate) fn write(&self, file: &str, content: &str) -> PathBuf {
+        let path = self.path(file);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for Fixtures {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
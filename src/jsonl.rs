@@ -0,0 +1,104 @@
+//! This module exports bandwidth traces in [JSON Lines](https://jsonlines.org) format, which is
+//! suitable for long traces and line-oriented tools like `jq`.
+//!
+//! Each segment of the trace is written as one line of `{"bw":...,"duration":...}`, which is the
+//! serialization of a [`StaticBwConfig`] (so it follows the `human` and `ns-number` features as the
+//! configuration files do). The segments are written while draining the model, so the whole trace
+//! is never materialized in memory.
+//!
+//! Enable `jsonl` feature to use this module.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::StaticBwConfig;
+//! # use netem_trace::{Bandwidth, Duration};
+//! use netem_trace::jsonl::BwTraceJsonl;
+//!
+//! let mut static_bw = StaticBwConfig::new()
+//!     .bw(Bandwidth::from_mbps(12))
+//!     .duration(Duration::from_secs(1))
+//!     .build();
+//! let mut output = vec![];
+//! static_bw.to_jsonl_writer(Duration::from_secs(10), &mut output).unwrap();
+//! # #[cfg(feature = "human")]
+//! assert_eq!(String::from_utf8(output).unwrap(), "{\"bw\":\"12Mbps\",\"duration\":\"1s\"}\n");
+//! ```
+use crate::model::StaticBwConfig;
+use crate::{BwTrace, Duration};
+use std::io::Write;
+
+/// The `BwTraceJsonl` trait provides a method to export a bandwidth trace in JSON Lines format.
+///
+/// This trait is automatically implemented for all types that implement [`BwTrace`].
+pub trait BwTraceJsonl: BwTrace {
+    /// Write the segments of the trace within `total` as JSON Lines, one segment per line.
+    ///
+    /// The last segment is truncated to end at `total`, and the trace ends earlier if the model
+    /// does. Segments are written as they are drained from the model, so wrap the writer with
+    /// [`BufWriter`](std::io::BufWriter) for files.
+    ///
+    /// See the [`jsonl`](crate::jsonl) module for the format.
+    fn to_jsonl_writer<W: Write>(&mut self, total: Duration, mut w: W) -> std::io::Result<()> {
+        let mut remaining = total;
+        while !remaining.is_zero() {
+            let Some((bw, duration)) = self.next_bw() else {
+                break;
+            };
+            let duration = duration.min(remaining);
+            remaining -= duration;
+            let segment = StaticBwConfig::new().bw(bw).duration(duration);
+            serde_json::to_writer(&mut w, &segment)?;
+            w.write_all(b"\n")?;
+        }
+        w.flush()
+    }
+}
+
+impl<T: BwTrace + ?Sized> BwTraceJsonl for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{BwTraceConfig, NormalizedBwConfig};
+    use crate::Bandwidth;
+    use std::io::BufRead;
+
+    #[test]
+    fn test_jsonl() {
+        let config = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .std_dev(Bandwidth::from_mbps(2))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let segments: Vec<_> = config.clone().build().into_iter().collect();
+        assert_eq!(segments.len(), 100);
+
+        let mut output = vec![];
+        config
+            .clone()
+            .build()
+            .to_jsonl_writer(Duration::from_secs(10), &mut output)
+            .unwrap();
+        let lines: Vec<_> = output.lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), segments.len());
+        // each line reads back as the segment
+        for (line, (bw, duration)) in lines.iter().zip(&segments) {
+            let mut model =
+                Box::new(serde_json::from_str::<StaticBwConfig>(line).unwrap()).into_model();
+            assert_eq!(model.next_bw(), Some((*bw, *duration)));
+        }
+
+        // the trace is cut at `total`
+        let mut output = vec![];
+        config
+            .build()
+            .to_jsonl_writer(Duration::from_millis(25), &mut output)
+            .unwrap();
+        let lines: Vec<_> = output.lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 3);
+        let last: StaticBwConfig = serde_json::from_str(&lines[2]).unwrap();
+        assert_eq!(last.duration, Some(Duration::from_millis(5)));
+    }
+}
@@ -88,25 +88,91 @@
 //!
 //! - `model`: Enable this feature if you want to use all pre-defined models.
 //!     - `bw-model`: Enable this feature if you want to use the pre-defined [`BwTrace`] models.
+//!     - `delay-per-packet-model`: Enable this feature if you want to use the pre-defined [`DelayPerPacketTrace`] models.
 //!     - `truncated-normal`: Enable this feature if you want to use truncated normal distribution in [`model::NormalizedBw`] models.
 //!
 //! ### Trace Format Features
 //!
-//! - `mahimahi`: Enable this feature if you want to load or output traces in [mahimahi](https://github.com/ravinet/mahimahi) format.
+//! - `mahimahi`: Enable this feature if you want to load or output traces in [mahimahi](https://github.com/ravinet/mahimahi) format (also delay traces if `delay-model` is enabled).
+//! - `csv`: Enable this feature if you want to import or export [`model::TraceBwConfig`] in CSV format with [`model::csv`] module.
+//! - `jsonl`: Enable this feature if you want to export bandwidth traces in JSON Lines format with [`jsonl`] module.
+//! - `loader`: Enable this feature if you want to load bandwidth trace configurations from json or mahimahi files with [`loader`] module.
+//! - `tc`: Enable this feature if you want to translate delay and loss traces into Linux `tc qdisc ... netem` commands with [`tc`] module.
+//! - `ns3`: Enable this feature if you want to translate bandwidth and delay traces into [ns-3](https://www.nsnam.org) `Simulator::Schedule` calls with [`ns3`] module.
 //!
 //! ### Other Features
 //!
 //! - `serde`: Enable this features if you want some structs to be serializable/deserializable. Often used with model features.
 //! - `human`: Enable this feature if you want to use human-readable format in configuration files. Often used with model features.
+//! - `ns-number`: Enable this feature if you want to write durations as integer nanoseconds in configuration files. If `human` is also enabled, durations are still serialized in human-readable format. See [`model::ns_duration`] for the precedence.
+//! - `logging`: Enable this feature if you want the models to emit `debug` events and configuration warnings through the [log](https://docs.rs/log) crate.
+//! - `checksum`: Enable this feature if you want to attach a SHA-256 checksum to bandwidth trace configurations with [`model::ChecksummedConfig`].
+//! - `schema`: Enable this feature if you want to export the fields of the built-in model configurations as JSON with [`model::config_schema_json`].
+//! - `analysis`: Enable this feature if you want to use the utilities in [`analysis`] module to inspect the generated traces.
+//! - `async`: Enable this feature if you want to play bandwidth traces in real time as async streams driven by [tokio](https://docs.rs/tokio) with [`stream`] module.
+//! - `rng-state`: Enable this feature if you want to checkpoint and restore the RNG of the random models with [`model::RngState`]. Implies `bw-model`.
+//! - `testing`: Enable this feature if you want to reuse the round-trip checks of mahimahi traces in your own property tests with [`testing`] module.
+
+/// Emit a `debug` event through the `log` crate. Expands to nothing if `logging` feature is disabled.
+#[cfg(feature = "logging")]
+#[allow(unused_macros)]
+macro_rules! debug_log {
+    ($($arg:tt)+) => {
+        log::debug!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "logging"))]
+#[allow(unused_macros)]
+macro_rules! debug_log {
+    ($($arg:tt)+) => {};
+}
+
+pub mod display;
+
+pub mod units;
+
+#[cfg(feature = "analysis")]
+pub mod analysis;
+
+#[cfg(any(feature = "analysis", feature = "bw-model", feature = "mahimahi"))]
+mod measure;
+
+#[cfg(feature = "async")]
+pub mod stream;
+
+#[cfg(feature = "loader")]
+pub mod loader;
 
 #[cfg(feature = "mahimahi")]
 pub mod mahimahi;
+#[cfg(all(feature = "mahimahi", feature = "delay-model"))]
+pub use mahimahi::{load_mahimahi_delay_trace, DelayMahimahi};
 #[cfg(feature = "mahimahi")]
-pub use mahimahi::{load_mahimahi_trace, Mahimahi, MahimahiExt};
+pub use mahimahi::{
+    load_mahimahi_trace, load_mahimahi_trace_from_file, load_mahimahi_trace_streaming,
+    load_mahimahi_trace_with_bin, Mahimahi, MahimahiError, MahimahiExt,
+};
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(all(test, feature = "mahimahi"))]
+mod fixtures;
+
+#[cfg(feature = "tc")]
+pub mod tc;
+
+#[cfg(feature = "ns3")]
+pub mod ns3;
+
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
 
 #[cfg(any(
     feature = "bw-model",
     feature = "delay-model",
+    feature = "delay-per-packet-model",
     feature = "loss-model",
     feature = "duplicate-model",
     feature = "model",
@@ -115,6 +181,156 @@ pub mod model;
 
 pub use bandwidth::Bandwidth;
 pub use std::time::Duration;
+pub use units::BandwidthExt;
+
+/// The error type unifying the errors of this crate.
+///
+/// The functions in each module may keep returning their own error types, which can be converted
+/// into `TraceError` with `?`, while the top-level convenience functions (e.g. [`load_bw_config`])
+/// return `TraceError` directly.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TraceError {
+    /// Failed to read or write a file.
+    Io(std::io::Error),
+    /// The mahimahi trace is invalid, see [`load_mahimahi_trace`].
+    #[cfg(feature = "mahimahi")]
+    Mahimahi(MahimahiError),
+    /// Failed to load a configuration from a file, see [`loader`].
+    #[cfg(feature = "loader")]
+    Config(loader::ConfigError),
+    /// The configuration is invalid.
+    Validation(String),
+    /// Failed to build a model, e.g. with [`try_build`](model::SquareBwConfig::try_build).
+    #[cfg(any(
+        feature = "bw-model",
+        feature = "delay-model",
+        feature = "delay-per-packet-model",
+        feature = "loss-model",
+        feature = "duplicate-model"
+    ))]
+    Build(model::ModelBuildError),
+}
+
+impl std::fmt::Display for TraceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceError::Io(e) => write!(f, "I/O error: {}", e),
+            #[cfg(feature = "mahimahi")]
+            TraceError::Mahimahi(e) => write!(f, "invalid mahimahi trace: {}", e),
+            #[cfg(feature = "loader")]
+            TraceError::Config(e) => write!(f, "{}", e),
+            TraceError::Validation(e) => write!(f, "invalid configuration: {}", e),
+            #[cfg(any(
+                feature = "bw-model",
+                feature = "delay-model",
+                feature = "delay-per-packet-model",
+                feature = "loss-model",
+                feature = "duplicate-model"
+            ))]
+            TraceError::Build(e) => write!(f, "failed to build the model: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TraceError::Io(e) => Some(e),
+            #[cfg(feature = "mahimahi")]
+            TraceError::Mahimahi(e) => Some(e),
+            #[cfg(feature = "loader")]
+            TraceError::Config(e) => Some(e),
+            #[cfg(any(
+                feature = "bw-model",
+                feature = "delay-model",
+                feature = "delay-per-packet-model",
+                feature = "loss-model",
+                feature = "duplicate-model"
+            ))]
+            TraceError::Build(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TraceError {
+    fn from(e: std::io::Error) -> Self {
+        TraceError::Io(e)
+    }
+}
+
+#[cfg(feature = "mahimahi")]
+impl From<MahimahiError> for TraceError {
+    fn from(e: MahimahiError) -> Self {
+        TraceError::Mahimahi(e)
+    }
+}
+
+#[cfg(feature = "loader")]
+impl From<loader::ConfigError> for TraceError {
+    fn from(e: loader::ConfigError) -> Self {
+        TraceError::Config(e)
+    }
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl From<model::ModelBuildError> for TraceError {
+    fn from(e: model::ModelBuildError) -> Self {
+        TraceError::Build(e)
+    }
+}
+
+/// Load a bandwidth trace configuration from a json or mahimahi file, see [`loader::load_bw`].
+///
+/// The nesting depth of the configuration is limited to [`model::DEFAULT_MAX_NESTING_DEPTH`].
+///
+/// Returns `Err` if the file fails to load, or if the configuration is nested too deep.
+#[cfg(feature = "loader")]
+pub fn load_bw_config<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Box<dyn model::BwTraceConfig>, TraceError> {
+    Ok(loader::load_bw(path)?)
+}
+
+/// Derive a family of `n` seeds from a `master` seed, e.g. to seed the models of a multi-model
+/// experiment independently but reproducibly from one seed.
+///
+/// The seeds are the first `n` outputs of [SplitMix64](https://prng.di.unimi.it/splitmix64.c)
+/// seeded with `master`: the `i`-th seed (starting from 1) is the 64-bit finalizer (a variant of
+/// MurmurHash3's `fmix64`) applied to `master + i * 0x9E3779B97F4A7C15` (wrapping). Since the
+/// finalizer is a bijection, the seeds of a family are distinct. The same master always yields the
+/// same family, and families of different masters are uncorrelated, except that masters differing
+/// by a small multiple of `0x9E3779B97F4A7C15` yield shifted copies of the same family.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::derive_seeds;
+/// let seeds = derive_seeds(42, 3);
+/// assert_eq!(seeds.len(), 3);
+/// assert_eq!(seeds, derive_seeds(42, 3));
+/// // a family is a prefix of any larger family of the same master
+/// assert_eq!(seeds[..2], derive_seeds(42, 2)[..]);
+/// ```
+pub fn derive_seeds(master: u64, n: usize) -> Vec<u64> {
+    let mut state = master;
+    (0..n)
+        .map(|_| {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        })
+        .collect()
+}
 
 /// The delay describes how long a packet is delayed when going through.
 pub type Delay = std::time::Duration;
@@ -175,6 +391,126 @@ pub type DuplicatePattern = Vec<f64>;
 /// in the sequence, or **None** if the trace goes to end.
 pub trait BwTrace: Send {
     fn next_bw(&mut self) -> Option<(Bandwidth, Duration)>;
+
+    /// The same as `next_bw`, but also returns the tag attached to the segment (if any).
+    ///
+    /// Tags are attached with the `TaggedBw` model and propagated by combinators, which helps to
+    /// track which source a segment comes from in a composed trace.
+    ///
+    /// The default implementation returns `None` as the tag.
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        self.next_bw().map(|(bw, duration)| (bw, duration, None))
+    }
+
+    /// Returns the model as [`Resettable`] if it can be replayed from the beginning in place.
+    ///
+    /// Combinators like `RepeatedBwPattern` use this to reset and reuse the sub-models at each cycle
+    /// instead of rebuilding them from their configs.
+    ///
+    /// The default implementation returns `None`.
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        None
+    }
+
+    /// Converts the trace into an [`Iterator`] over the `(bandwidth, duration)` pairs.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::StaticBwConfig;
+    /// # use netem_trace::{Bandwidth, BwTrace, Duration};
+    /// let static_bw = StaticBwConfig::new()
+    ///     .bw(Bandwidth::from_mbps(24))
+    ///     .duration(Duration::from_secs(1))
+    ///     .build();
+    /// assert_eq!(static_bw.into_iter().count(), 1);
+    /// ```
+    fn into_iter(self) -> BwTraceIter<Self>
+    where
+        Self: Sized,
+    {
+        BwTraceIter { trace: self }
+    }
+
+    /// Wraps the trace into a [`PeekableBwTrace`], which can look at the next segment without
+    /// consuming it, like [`Iterator::peekable`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::{Bandwidth, BwTrace, Duration, IterBw};
+    /// let mut trace = IterBw::new([(Bandwidth::from_mbps(12), Duration::from_secs(1))]).peekable();
+    /// assert_eq!(trace.peek(), Some(&(Bandwidth::from_mbps(12), Duration::from_secs(1))));
+    /// assert_eq!(trace.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_secs(1))));
+    /// assert_eq!(trace.peek(), None);
+    /// ```
+    fn peekable(self) -> PeekableBwTrace<Self>
+    where
+        Self: Sized,
+    {
+        PeekableBwTrace {
+            trace: self,
+            peeked: None,
+            peeked_tag: None,
+        }
+    }
+}
+
+impl<T: BwTrace + ?Sized> BwTrace for Box<T> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        (**self).next_bw()
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        (**self).next_bw_tagged()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        (**self).as_resettable()
+    }
+}
+
+/// This is a trait that represents a trace model which can be replayed from the beginning.
+///
+/// After `reset`, the model must produce exactly the same output as if it were newly built
+/// from its config.
+pub trait Resettable {
+    fn reset(&mut self);
+}
+
+/// A bandwidth trace pulling `(bandwidth, duration)` pairs lazily from an iterator.
+///
+/// This is useful for very long traces whose source is itself an iterator (e.g. streaming from a file),
+/// as there is no need to materialize the whole trace upfront like [`model::TraceBw`].
+///
+/// It is not configurable via a configuration struct, and hence not serializable.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::{Bandwidth, BwTrace, Duration, IterBw};
+/// let mut iter_bw = IterBw::new((1..3).map(|i| (Bandwidth::from_mbps(i), Duration::from_secs(1))));
+/// assert_eq!(iter_bw.next_bw(), Some((Bandwidth::from_mbps(1), Duration::from_secs(1))));
+/// assert_eq!(iter_bw.next_bw(), Some((Bandwidth::from_mbps(2), Duration::from_secs(1))));
+/// assert_eq!(iter_bw.next_bw(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IterBw<I: Iterator<Item = (Bandwidth, Duration)> + Send> {
+    iter: I,
+}
+
+impl<I: Iterator<Item = (Bandwidth, Duration)> + Send> IterBw<I> {
+    pub fn new<T: IntoIterator<IntoIter = I>>(iter: T) -> Self {
+        Self {
+            iter: iter.into_iter(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = (Bandwidth, Duration)> + Send> BwTrace for IterBw<I> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.iter.next()
+    }
 }
 
 /// This is a trait that represents a trace of delays.
@@ -190,6 +526,82 @@ pub trait BwTrace: Send {
 /// in the sequence, or **None** if the trace goes to end.
 pub trait DelayTrace: Send {
     fn next_delay(&mut self) -> Option<(Delay, Duration)>;
+
+    /// Returns the model as [`Resettable`] if it can be replayed from the beginning in place.
+    ///
+    /// The default implementation returns `None`.
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        None
+    }
+
+    /// Converts the trace into an [`Iterator`] over the `(delay, duration)` pairs.
+    fn into_iter(self) -> DelayTraceIter<Self>
+    where
+        Self: Sized,
+    {
+        DelayTraceIter { trace: self }
+    }
+
+    /// Wraps the trace into a [`PeekableDelayTrace`], which can look at the next segment without
+    /// consuming it, like [`Iterator::peekable`].
+    fn peekable(self) -> PeekableDelayTrace<Self>
+    where
+        Self: Sized,
+    {
+        PeekableDelayTrace {
+            trace: self,
+            peeked: None,
+        }
+    }
+}
+
+impl<T: DelayTrace + ?Sized> DelayTrace for Box<T> {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        (**self).next_delay()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        (**self).as_resettable()
+    }
+}
+
+/// This is a trait that represents a trace of per-packet delays.
+///
+/// The trace is a sequence of delays, each of which is applied to a packet in order.
+/// The delay describes how long the packet is delayed when going through.
+///
+/// For example, if the sequence is [10ms, 20ms, 30ms],
+/// then the first packet will be delayed by 10ms, the second by 20ms and the third by 30ms.
+///
+/// The next_delay function either returns **the delay of the next packet**
+/// in the sequence, or **None** if the trace goes to end.
+pub trait DelayPerPacketTrace: Send {
+    fn next_delay(&mut self) -> Option<Delay>;
+
+    /// Returns the model as [`Resettable`] if it can be replayed from the beginning in place.
+    ///
+    /// The default implementation returns `None`.
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        None
+    }
+
+    /// Converts the trace into an [`Iterator`] over the per-packet delays.
+    fn into_iter(self) -> DelayPerPacketTraceIter<Self>
+    where
+        Self: Sized,
+    {
+        DelayPerPacketTraceIter { trace: self }
+    }
+}
+
+impl<T: DelayPerPacketTrace + ?Sized> DelayPerPacketTrace for Box<T> {
+    fn next_delay(&mut self) -> Option<Delay> {
+        (**self).next_delay()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        (**self).as_resettable()
+    }
 }
 
 /// This is a trait that represents a trace of loss patterns.
@@ -202,6 +614,43 @@ pub trait DelayTrace: Send {
 /// in the sequence, or **None** if the trace goes to end.
 pub trait LossTrace: Send {
     fn next_loss(&mut self) -> Option<(LossPattern, Duration)>;
+
+    /// Returns the model as [`Resettable`] if it can be replayed from the beginning in place.
+    ///
+    /// The default implementation returns `None`.
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        None
+    }
+
+    /// Converts the trace into an [`Iterator`] over the `(loss_pattern, duration)` pairs.
+    fn into_iter(self) -> LossTraceIter<Self>
+    where
+        Self: Sized,
+    {
+        LossTraceIter { trace: self }
+    }
+
+    /// Wraps the trace into a [`PeekableLossTrace`], which can look at the next segment without
+    /// consuming it, like [`Iterator::peekable`].
+    fn peekable(self) -> PeekableLossTrace<Self>
+    where
+        Self: Sized,
+    {
+        PeekableLossTrace {
+            trace: self,
+            peeked: None,
+        }
+    }
+}
+
+impl<T: LossTrace + ?Sized> LossTrace for Box<T> {
+    fn next_loss(&mut self) -> Option<(LossPattern, Duration)> {
+        (**self).next_loss()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        (**self).as_resettable()
+    }
 }
 
 /// This is a trait that represents a trace of duplicate patterns.
@@ -214,18 +663,258 @@ pub trait LossTrace: Send {
 /// the sequence, or **None** if the trace goes to end.
 pub trait DuplicateTrace: Send {
     fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)>;
+
+    /// Returns the model as [`Resettable`] if it can be replayed from the beginning in place.
+    ///
+    /// The default implementation returns `None`.
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        None
+    }
+
+    /// Converts the trace into an [`Iterator`] over the `(duplicate_pattern, duration)` pairs.
+    fn into_iter(self) -> DuplicateTraceIter<Self>
+    where
+        Self: Sized,
+    {
+        DuplicateTraceIter { trace: self }
+    }
+}
+
+impl<T: DuplicateTrace + ?Sized> DuplicateTrace for Box<T> {
+    fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)> {
+        (**self).next_duplicate()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        (**self).as_resettable()
+    }
+}
+
+macro_rules! impl_trace_iter {
+    ($iter:ident, $trait:ident, $next:ident, $item:ty) => {
+        #[doc = concat!("An [`Iterator`] adapter over a [`", stringify!($trait), "`], returned by [`", stringify!($trait), "::into_iter`].")]
+        ///
+        /// It owns the trace (which can be a boxed trait object) and delegates `next()` to it.
+        #[derive(Debug, Clone)]
+        pub struct $iter<T: $trait> {
+            trace: T,
+        }
+
+        impl<T: $trait> $iter<T> {
+            /// Returns the underlying trace.
+            pub fn into_inner(self) -> T {
+                self.trace
+            }
+        }
+
+        impl<T: $trait> Iterator for $iter<T> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.trace.$next()
+            }
+        }
+    };
+}
+
+impl_trace_iter!(BwTraceIter, BwTrace, next_bw, (Bandwidth, Duration));
+impl_trace_iter!(DelayTraceIter, DelayTrace, next_delay, (Delay, Duration));
+impl_trace_iter!(
+    DelayPerPacketTraceIter,
+    DelayPerPacketTrace,
+    next_delay,
+    Delay
+);
+impl_trace_iter!(LossTraceIter, LossTrace, next_loss, (LossPattern, Duration));
+impl_trace_iter!(
+    DuplicateTraceIter,
+    DuplicateTrace,
+    next_duplicate,
+    (DuplicatePattern, Duration)
+);
+
+/// A [`BwTrace`] adapter which can look at the next segment without consuming it, returned by
+/// [`BwTrace::peekable`].
+///
+/// At most one segment (with its tag) is buffered from the inner trace. Resetting the adapter
+/// discards the buffered segment and resets the inner trace, if it is [`Resettable`].
+#[derive(Debug, Clone)]
+pub struct PeekableBwTrace<T: BwTrace> {
+    trace: T,
+    /// The peeked segment, `Some(None)` if the inner trace has ended.
+    peeked: Option<Option<(Bandwidth, Duration)>>,
+    /// The tag of the peeked segment, kept after the segment is returned so that it can be borrowed.
+    peeked_tag: Option<String>,
+}
+
+impl<T: BwTrace> PeekableBwTrace<T> {
+    /// Returns a reference to the next segment without consuming it, or `None` if the trace has
+    /// ended.
+    pub fn peek(&mut self) -> Option<&(Bandwidth, Duration)> {
+        let (trace, tag) = (&mut self.trace, &mut self.peeked_tag);
+        self.peeked
+            .get_or_insert_with(|| {
+                trace.next_bw_tagged().map(|(bw, duration, peeked_tag)| {
+                    *tag = peeked_tag.map(str::to_string);
+                    (bw, duration)
+                })
+            })
+            .as_ref()
+    }
+
+    /// Returns the underlying trace, dropping the peeked segment (if any).
+    pub fn into_inner(self) -> T {
+        self.trace
+    }
+}
+
+impl<T: BwTrace> BwTrace for PeekableBwTrace<T> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.trace.next_bw(),
+        }
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        match self.peeked.take() {
+            Some(peeked) => peeked.map(|(bw, duration)| (bw, duration, self.peeked_tag.as_deref())),
+            None => self.trace.next_bw_tagged(),
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        if self.trace.as_resettable().is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: BwTrace> Resettable for PeekableBwTrace<T> {
+    fn reset(&mut self) {
+        self.peeked = None;
+        self.peeked_tag = None;
+        if let Some(trace) = self.trace.as_resettable() {
+            trace.reset();
+        }
+    }
+}
+
+macro_rules! impl_peekable_trace {
+    ($peekable:ident, $trait:ident, $next:ident, $item:ty) => {
+        #[doc = concat!("A [`", stringify!($trait), "`] adapter which can look at the next segment without consuming it, returned by [`", stringify!($trait), "::peekable`].")]
+        ///
+        /// At most one segment is buffered from the inner trace. Resetting the adapter discards the
+        /// buffered segment and resets the inner trace, if it is [`Resettable`].
+        #[derive(Debug, Clone)]
+        pub struct $peekable<T: $trait> {
+            trace: T,
+            /// The peeked segment, `Some(None)` if the inner trace has ended.
+            peeked: Option<Option<$item>>,
+        }
+
+        impl<T: $trait> $peekable<T> {
+            /// Returns a reference to the next segment without consuming it, or `None` if the
+            /// trace has ended.
+            pub fn peek(&mut self) -> Option<&$item> {
+                let trace = &mut self.trace;
+                self.peeked.get_or_insert_with(|| trace.$next()).as_ref()
+            }
+
+            /// Returns the underlying trace, dropping the peeked segment (if any).
+            pub fn into_inner(self) -> T {
+                self.trace
+            }
+        }
+
+        impl<T: $trait> $trait for $peekable<T> {
+            fn $next(&mut self) -> Option<$item> {
+                match self.peeked.take() {
+                    Some(peeked) => peeked,
+                    None => self.trace.$next(),
+                }
+            }
+
+            fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+                if self.trace.as_resettable().is_some() {
+                    Some(self)
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl<T: $trait> Resettable for $peekable<T> {
+            fn reset(&mut self) {
+                self.peeked = None;
+                if let Some(trace) = self.trace.as_resettable() {
+                    trace.reset();
+                }
+            }
+        }
+    };
 }
 
+impl_peekable_trace!(
+    PeekableDelayTrace,
+    DelayTrace,
+    next_delay,
+    (Delay, Duration)
+);
+impl_peekable_trace!(
+    PeekableLossTrace,
+    LossTrace,
+    next_loss,
+    (LossPattern, Duration)
+);
+
 #[cfg(test)]
 mod test {
     use model::TraceBwConfig;
 
-    use self::model::bw::Forever;
+    use self::model::Repeatable;
 
     use super::*;
-    #[cfg(feature = "serde")]
-    use crate::model::RepeatedBwPatternConfig;
-    use crate::model::{BwTraceConfig, NormalizedBwConfig, SawtoothBwConfig, StaticBwConfig};
+    use crate::model::{
+        BwTraceConfig, ClampedBwConfig, DelayedStartBwConfig, EmpiricalBwConfig, EnvBwConfig,
+        ExponentialDwellBwConfig, FloorPlusVariableBwConfig, GammaBwConfig, HysteresisBwConfig,
+        Interpolation, MarkovBwConfig, ModelBuildError, NormalizedBwConfig, ParetoBwConfig,
+        PoissonBwConfig, QuantizedBwConfig, RampBwConfig, RandomOutageBwConfig,
+        RandomPatternBwConfig, RandomPhaseBwConfig, RepeatedBwPatternConfig, ReverseBwConfig,
+        SawtoothBwConfig, ScaledBwConfig, ScheduleBwConfig, SequenceBwConfig, SineBwConfig,
+        SpliceBwConfig, SplineBwConfig, SquareBwConfig, StaticBwConfig, StepBwConfig, SumBwConfig,
+        TaggedBwConfig, WeibullBwConfig, WindowMaxBwConfig, DEFAULT_MAX_NESTING_DEPTH,
+    };
+
+    #[test]
+    fn test_derive_seeds() {
+        // the reference outputs of SplitMix64 seeded with 0
+        assert_eq!(
+            derive_seeds(0, 3),
+            [0xE220A8397B1DCDAF, 0x6E789E6AA1B965F4, 0x06C45D188009454F]
+        );
+        assert!(derive_seeds(42, 0).is_empty());
+
+        for master in [0, 1, 42, u64::MAX] {
+            let seeds = derive_seeds(master, 1000);
+            // deterministic
+            assert_eq!(seeds, derive_seeds(master, 1000));
+            assert_eq!(seeds[..10], derive_seeds(master, 10)[..]);
+            // distinct
+            let mut sorted = seeds.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(sorted.len(), 1000);
+        }
+
+        // families of different (even adjacent) masters do not overlap
+        let a = derive_seeds(42, 1000);
+        let b = derive_seeds(43, 1000);
+        assert!(a.iter().all(|seed| !b.contains(seed)));
+        assert!(a.iter().zip(&b).all(|(a, b)| (a ^ b).count_ones() > 8));
+    }
 
     #[test]
     fn test_static_bw_model() {
@@ -250,7 +939,7 @@ mod test {
             .build();
         assert_eq!(
             normal_bw.next_bw(),
-            Some((Bandwidth::from_bps(12069427), Duration::from_millis(100)))
+            Some((Bandwidth::from_bps(12069428), Duration::from_millis(100)))
         );
         assert_eq!(
             normal_bw.next_bw(),
@@ -267,7 +956,7 @@ mod test {
             .build();
         assert_eq!(
             normal_bw.next_bw(),
-            Some((Bandwidth::from_bps(12069427), Duration::from_millis(100)))
+            Some((Bandwidth::from_bps(12069428), Duration::from_millis(100)))
         );
         assert_eq!(
             normal_bw.next_bw(),
@@ -275,6 +964,159 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_normalized_bw_exact_duration() {
+        let config = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .std_dev(Bandwidth::from_mbps(1))
+            .duration(Duration::from_millis(250))
+            .step(Duration::from_millis(100))
+            .seed(42);
+        let exact: Vec<_> = config.clone().build().into_iter().collect();
+        let durations: Vec<_> = exact.iter().map(|(_, duration)| *duration).collect();
+        assert_eq!(
+            durations,
+            [
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+                Duration::from_millis(50)
+            ]
+        );
+
+        // the trailing partial step is dropped, with the same samples before it
+        let mut model = config.clone().exact_duration(false).build();
+        let dropped: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(dropped, exact[..2]);
+        assert_eq!(model.next_bw(), None);
+
+        assert_eq!(
+            config
+                .duration(Duration::from_millis(50))
+                .exact_duration(false)
+                .lint(),
+            ["NormalizedBw: step 100ms is longer than duration 50ms, the trace will be empty without exact_duration"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rng-state")]
+    fn test_normalized_bw_rng_state() {
+        use crate::model::RngStateError;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let config = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .std_dev(Bandwidth::from_mbps(1))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(100));
+        let mut model = config
+            .clone()
+            .build_with_rng(ChaCha20Rng::seed_from_u64(42));
+        for _ in 0..3 {
+            model.next_bw();
+        }
+        let state = model.rng_state();
+        let duration = model.duration;
+        let expected: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(expected.len(), 7);
+
+        // a model built with another seed resumes to the identical continuation
+        let mut resumed = config.build_with_rng(ChaCha20Rng::seed_from_u64(7));
+        resumed.restore_rng_state(&state).unwrap();
+        resumed.duration = duration;
+        let continuation: Vec<_> = std::iter::from_fn(|| resumed.next_bw()).collect();
+        assert_eq!(continuation, expected);
+
+        assert_eq!(
+            resumed.restore_rng_state(&state[..8]),
+            Err(RngStateError::InvalidLength {
+                expected: 56,
+                actual: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_bw_build_with_generic_rng() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        fn run(model: &mut dyn BwTrace) -> Vec<(Bandwidth, Duration)> {
+            std::iter::from_fn(|| model.next_bw()).collect()
+        }
+
+        let config = PoissonBwConfig::new()
+            .rate_pps(1000.0)
+            .duration(Duration::from_millis(10))
+            .seed(7);
+        let mut model = config.clone().build_with_rng(ChaCha20Rng::seed_from_u64(7));
+        let first = run(&mut model);
+        assert_eq!(first.len(), 10);
+        // reproducible with the same RNG, and replayed after a reset re-seeding it with `seed`
+        let mut again = config.clone().build_with_rng(ChaCha20Rng::seed_from_u64(7));
+        assert_eq!(run(&mut again), first);
+        model.reset();
+        assert_eq!(run(&mut model), first);
+        let mut boxed: Box<dyn BwTrace> =
+            Box::new(config.build_with_rng(ChaCha20Rng::seed_from_u64(7)));
+        assert_eq!(run(boxed.as_mut()), first);
+
+        let mut markov = MarkovBwConfig::new()
+            .states(vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2)])
+            .transitions(vec![vec![0.5, 0.5], vec![0.5, 0.5]])
+            .duration(Duration::from_millis(10))
+            .build_with_rng(ChaCha20Rng::seed_from_u64(7));
+        assert_eq!(run(&mut markov).len(), 10);
+
+        // the default RNG is a `StdRng` seeded by `seed`
+        let std_rng = || rand::rngs::StdRng::seed_from_u64(7);
+        let empirical = EmpiricalBwConfig::new()
+            .cdf(vec![
+                (Bandwidth::from_mbps(1), 0.5),
+                (Bandwidth::from_mbps(9), 1.0),
+            ])
+            .duration(Duration::from_millis(10))
+            .seed(7);
+        assert_eq!(
+            run(&mut empirical.clone().build_with_rng(std_rng())),
+            run(&mut empirical.clone().build())
+        );
+        let mut chacha = empirical.build_with_rng(ChaCha20Rng::seed_from_u64(7));
+        let first = run(&mut chacha);
+        chacha.reset_with_seed(7);
+        assert_eq!(run(&mut chacha), first);
+        let outage = RandomOutageBwConfig::new()
+            .inner(Box::new(StaticBwConfig::new()))
+            .drop_prob(0.5)
+            .seed(7);
+        assert_eq!(
+            run(&mut outage.clone().build_with_rng(std_rng())),
+            run(&mut outage.build())
+        );
+        let phase = RandomPhaseBwConfig::new()
+            .inner(Box::new(StaticBwConfig::new()))
+            .period(Duration::from_secs(1))
+            .seed(7);
+        let mut model = phase.clone().build_with_rng(std_rng());
+        assert_eq!(model.next_bw(), phase.build().next_bw());
+        #[cfg(feature = "loss-model")]
+        {
+            let gaps = model::LossAsGapsBwConfig::new()
+                .bw(Box::new(StaticBwConfig::new().repeat(10)))
+                .loss(Box::new(
+                    model::StaticLossConfig::new()
+                        .loss(vec![0.5])
+                        .duration(Duration::from_secs(10)),
+                ))
+                .seed(7);
+            assert_eq!(
+                run(&mut gaps.clone().build_with_rng(std_rng())),
+                run(&mut gaps.build())
+            );
+        }
+    }
+
     #[test]
     fn test_sawtooth_bw_model() {
         let mut sawtooth_bw = SawtoothBwConfig::new()
@@ -334,11 +1176,11 @@ mod test {
             .build();
         assert_eq!(
             sawtooth_bw.next_bw(),
-            Some((Bandwidth::from_bps(12347139), Duration::from_millis(100)))
+            Some((Bandwidth::from_bps(12347140), Duration::from_millis(100)))
         );
         assert_eq!(
             sawtooth_bw.next_bw(),
-            Some((Bandwidth::from_bps(13664690), Duration::from_millis(100)))
+            Some((Bandwidth::from_bps(13664691), Duration::from_millis(100)))
         );
         assert_eq!(
             sawtooth_bw.next_bw(),
@@ -351,56 +1193,1971 @@ mod test {
     }
 
     #[test]
-    fn test_trace_bw() {
-        let mut trace_bw = TraceBwConfig::new()
-            .pattern(vec![
-                (
-                    Duration::from_millis(1),
-                    vec![
-                        Bandwidth::from_kbps(29123),
-                        Bandwidth::from_kbps(41242),
-                        Bandwidth::from_kbps(7395),
-                    ],
-                ),
-                (
-                    Duration::from_millis(2),
-                    vec![Bandwidth::from_mbps(1), Bandwidth::from_kbps(8542)],
-                ),
-            ])
-            .build();
-
-        assert_eq!(
-            trace_bw.next_bw(),
-            Some((Bandwidth::from_bps(29123000), Duration::from_millis(1)))
-        );
+    fn test_sawtooth_bw_duty_ratio_edges() {
+        let config = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(0))
+            .top(Bandwidth::from_mbps(10))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(100))
+            .interval(Duration::from_millis(500));
+        // a pure rising ramp restarting from the bottom each interval
+        let rising: Vec<_> = config
+            .clone()
+            .duty_ratio(1.0)
+            .build()
+            .into_iter()
+            .map(|(bw, _)| bw.as_bps())
+            .collect();
         assert_eq!(
-            trace_bw.next_bw(),
-            Some((Bandwidth::from_bps(41242000), Duration::from_millis(1)))
+            rising,
+            [0, 2, 4, 6, 8, 0, 2, 4, 6, 8].map(|mbps| mbps * 1_000_000)
         );
+        // a pure falling ramp restarting from the top each interval
+        let falling: Vec<_> = config
+            .clone()
+            .duty_ratio(0.0)
+            .build()
+            .into_iter()
+            .map(|(bw, _)| bw.as_bps())
+            .collect();
         assert_eq!(
-            trace_bw.next_bw(),
-            Some((Bandwidth::from_bps(7395000), Duration::from_millis(1)))
+            falling,
+            [10, 8, 6, 4, 2, 10, 8, 6, 4, 2].map(|mbps| mbps * 1_000_000)
         );
+        // a zero interval holds the start of the ramp
+        for duty_ratio in [0.0, 1.0] {
+            let mut sawtooth_bw = config
+                .clone()
+                .interval(Duration::ZERO)
+                .duty_ratio(duty_ratio)
+                .build();
+            assert!(sawtooth_bw.next_bw().is_some());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "SawtoothBw: duty_ratio must be within [0, 1]")]
+    fn test_sawtooth_bw_invalid_duty_ratio() {
+        SawtoothBwConfig::new().duty_ratio(1.5).build();
+    }
+
+    #[test]
+    fn test_try_build() {
+        let err = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(24))
+            .top(Bandwidth::from_mbps(12))
+            .try_build()
+            .err();
         assert_eq!(
-            trace_bw.next_bw(),
-            Some((Bandwidth::from_bps(1000000), Duration::from_millis(2)))
+            err,
+            Some(ModelBuildError::InvalidBounds {
+                model: "SawtoothBw",
+                lower: "bottom",
+                upper: "top"
+            })
         );
+        let err = SawtoothBwConfig::new()
+            .duty_ratio(f64::NAN)
+            .try_build()
+            .err()
+            .map(|e| e.to_string());
         assert_eq!(
-            trace_bw.next_bw(),
-            Some((Bandwidth::from_bps(8542000), Duration::from_millis(2)))
+            err.as_deref(),
+            Some("SawtoothBw: duty_ratio must be within [0, 1], got NaN")
         );
-        assert_eq!(trace_bw.next_bw(), None);
+        assert!(SawtoothBwConfig::new().try_build().is_ok());
+
+        let config = NormalizedBwConfig::new()
+            .lower_bound(Bandwidth::from_mbps(20))
+            .upper_bound(Bandwidth::from_mbps(10));
+        assert!(matches!(
+            config.clone().try_build(),
+            Err(ModelBuildError::InvalidBounds {
+                model: "NormalizedBw",
+                ..
+            })
+        ));
+        assert!(config
+            .upper_bound(Bandwidth::from_mbps(20))
+            .try_build()
+            .is_ok());
     }
 
     #[test]
-    #[cfg(feature = "serde")]
-    fn test_model_serde() {
-        let a = vec![
+    fn test_random_pattern_bw() {
+        let static_bw = |mbps| {
             Box::new(
                 StaticBwConfig::new()
-                    .bw(Bandwidth::from_mbps(12))
-                    .duration(Duration::from_secs(1)),
-            ) as Box<dyn BwTraceConfig>,
+                    .bw(Bandwidth::from_mbps(mbps))
+                    .duration(Duration::from_millis(10)),
+            ) as Box<dyn BwTraceConfig>
+        };
+        let config = RandomPatternBwConfig::new()
+            .pattern(vec![
+                (1.0, static_bw(12)),
+                (2.0, static_bw(24)),
+                (0.0, static_bw(36)),
+            ])
+            .count(8)
+            .seed(42);
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(80)));
+        assert!(!config.is_infinite());
+        assert!(config.clone().forever().is_infinite());
+
+        let mut model = config.clone().build();
+        let picks: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, _)| bw.as_bps() / 1_000_000)
+            .collect();
+        assert_eq!(picks, [24, 24, 24, 24, 12, 24, 24, 24]);
+
+        // the same seed reproduces the sequence, also after reset
+        let mut other = config.build();
+        other.next_bw();
+        other.as_resettable().unwrap().reset();
+        let replay: Vec<_> = std::iter::from_fn(|| other.next_bw())
+            .map(|(bw, _)| bw.as_bps() / 1_000_000)
+            .collect();
+        assert_eq!(replay, picks);
+    }
+
+    #[test]
+    #[should_panic(expected = "RandomPatternBw: at least one weight must be positive")]
+    fn test_random_pattern_bw_zero_weights() {
+        RandomPatternBwConfig::new()
+            .pattern(vec![(0.0, Box::new(StaticBwConfig::new()))])
+            .build();
+    }
+
+    #[test]
+    fn test_reverse_bw() {
+        let sequence = SequenceBwConfig::new().sequence(vec![
+            (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+            (Bandwidth::from_mbps(24), Duration::from_millis(200)),
+            (Bandwidth::from_mbps(36), Duration::from_millis(300)),
+        ]);
+        let config = ReverseBwConfig::new().inner(Box::new(sequence.clone()));
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(600)));
+        assert!(config.lint().is_empty());
+        let mut model = ReverseBwConfig::new()
+            .inner(Box::new(
+                TaggedBwConfig::new().inner(Box::new(sequence)).tag("seq"),
+            ))
+            .build();
+        assert_eq!(
+            model.next_bw_tagged(),
+            Some((
+                Bandwidth::from_mbps(36),
+                Duration::from_millis(300),
+                Some("seq")
+            ))
+        );
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(24), Duration::from_millis(200)))
+        );
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_millis(100)))
+        );
+        assert_eq!(model.next_bw(), None);
+
+        // an infinite inner model cannot be drained
+        let config = ReverseBwConfig::new().inner(Box::new(
+            StaticBwConfig::new()
+                .duration(Duration::from_secs(1))
+                .forever(),
+        ));
+        assert!(!config.is_infinite());
+        assert_eq!(config.total_duration(), Some(Duration::ZERO));
+        assert_eq!(config.lint().len(), 1);
+        assert_eq!(config.build().next_bw(), None);
+        assert_eq!(ReverseBwConfig::new().build().next_bw(), None);
+    }
+
+    #[test]
+    fn test_poisson_bw() {
+        let config = PoissonBwConfig::new()
+            .rate_pps(1000.0)
+            .packet_size(1500)
+            .duration(Duration::from_millis(50))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(50)));
+        let mut model = config.clone().build();
+        let samples: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, duration)| {
+                assert_eq!(duration, Duration::from_millis(10));
+                // packets of 1500 bytes within 10ms are 1.2Mbps each
+                assert_eq!(bw.as_bps() % 1_200_000, 0);
+                bw.as_bps() / 1_200_000
+            })
+            .collect();
+        assert_eq!(samples, [9, 6, 8, 12, 10]);
+
+        // the same seed reproduces the trace, also after reset
+        let mut model = config.clone().build();
+        model.next_bw();
+        model.reset();
+        let replay: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, _)| bw.as_bps() / 1_200_000)
+            .collect();
+        assert_eq!(replay, samples);
+
+        // the mean bandwidth is rate_pps * packet_size * 8
+        let mut model = config.clone().duration(Duration::from_secs(10)).build();
+        let total: u128 = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, _)| bw.as_bps())
+            .sum();
+        let mean = total as f64 / 1000.0;
+        assert!((mean - 12_000_000.0).abs() < 360_000.0, "{}", mean);
+
+        // no packet arrives at a zero rate
+        let mut model = config.rate_pps(0.0).build();
+        assert!(std::iter::from_fn(|| model.next_bw()).all(|(bw, _)| bw == Bandwidth::ZERO));
+    }
+
+    #[test]
+    #[should_panic(expected = "PoissonBw: rate_pps must be non-negative and finite")]
+    fn test_poisson_bw_invalid_rate() {
+        PoissonBwConfig::new().rate_pps(-1.0).build();
+    }
+
+    #[test]
+    fn test_sum_bw() {
+        let static_bw = |mbps, millis| {
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(mbps))
+                    .duration(Duration::from_millis(millis)),
+            ) as Box<dyn BwTraceConfig>
+        };
+        let config = SumBwConfig::new().inputs(vec![
+            static_bw(12, 300),
+            Box::new(SequenceBwConfig::new().sequence(vec![
+                (Bandwidth::from_mbps(24), Duration::from_millis(100)),
+                (Bandwidth::from_mbps(36), Duration::ZERO),
+                (Bandwidth::from_mbps(48), Duration::from_millis(400)),
+            ])),
+        ]);
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(500)));
+        assert!(!config.is_infinite());
+        let mut model = config.build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            [
+                (Bandwidth::from_mbps(36), Duration::from_millis(100)),
+                (Bandwidth::from_mbps(60), Duration::from_millis(200)),
+                (Bandwidth::from_mbps(48), Duration::from_millis(200)),
+            ]
+        );
+
+        assert_eq!(SumBwConfig::new().build().next_bw(), None);
+        let forever = SumBwConfig::new().inputs(vec![Box::new(StaticBwConfig::new().forever())]);
+        assert!(forever.is_infinite());
+        assert_eq!(forever.total_duration(), None);
+    }
+
+    #[test]
+    fn test_weibull_bw() {
+        let config = WeibullBwConfig::new()
+            .scale(Bandwidth::from_mbps(10))
+            .shape(0.5)
+            .duration(Duration::from_millis(50))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let mut model = config.clone().build();
+        let samples: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, duration)| {
+                assert_eq!(duration, Duration::from_millis(10));
+                bw.as_bps() / 1000
+            })
+            .collect();
+        // mostly below the scale, with a heavy tail
+        assert_eq!(samples, [4113, 3735, 2041, 8129, 113660]);
+        model.reset();
+        assert_eq!(model.next_bw().unwrap().0.as_bps() / 1000, samples[0]);
+
+        // bounded, with the same samples
+        let mut model = config
+            .clone()
+            .lower_bound(Bandwidth::from_mbps(5))
+            .upper_bound(Bandwidth::from_mbps(20))
+            .build();
+        let bounded: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, _)| bw.as_bps() / 1000)
+            .collect();
+        assert_eq!(
+            bounded,
+            samples
+                .iter()
+                .map(|kbps| (*kbps).clamp(5000, 20000))
+                .collect::<Vec<_>>()
+        );
+
+        // a zero scale always gives zero bandwidth
+        let mut model = config.scale(Bandwidth::ZERO).build();
+        assert!(std::iter::from_fn(|| model.next_bw()).all(|(bw, _)| bw == Bandwidth::ZERO));
+
+        let err = WeibullBwConfig::new().shape(0.0).try_build().err();
+        assert_eq!(
+            err.map(|e| e.to_string()).as_deref(),
+            Some("WeibullBw: shape must be positive, got 0")
+        );
+        assert!(matches!(
+            WeibullBwConfig::new()
+                .lower_bound(Bandwidth::from_mbps(2))
+                .upper_bound(Bandwidth::from_mbps(1))
+                .try_build(),
+            Err(ModelBuildError::InvalidBounds {
+                model: "WeibullBw",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "WeibullBw: shape must be positive, got NaN")]
+    fn test_weibull_bw_invalid_shape() {
+        WeibullBwConfig::new().shape(f64::NAN).build();
+    }
+
+    #[test]
+    fn test_delayed_start_bw() {
+        let inner = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(100),
+            vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)],
+        )]);
+        let config = DelayedStartBwConfig::new()
+            .inner(Box::new(inner.clone()))
+            .offset(Duration::from_millis(250));
+        let mut model = config.clone().build();
+        let mut expected = inner.build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::ZERO, Duration::from_millis(250)))
+        );
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            std::iter::from_fn(|| expected.next_bw()).collect::<Vec<_>>()
+        );
+
+        // no leading segment without an offset, and only the leading segment without an inner model
+        let mut model = config.clone().offset(Duration::ZERO).build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_millis(100)))
+        );
+        let mut model = DelayedStartBwConfig::new()
+            .offset(Duration::from_millis(250))
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::ZERO, Duration::from_millis(250)))
+        );
+        assert_eq!(model.next_bw(), None);
+
+        let config: Box<dyn BwTraceConfig> = Box::new(config);
+        assert!(!config.is_infinite());
+        let forever: Box<dyn BwTraceConfig> =
+            Box::new(DelayedStartBwConfig::new().inner(Box::new(StaticBwConfig::new().forever())));
+        assert!(forever.is_infinite());
+    }
+
+    #[test]
+    fn test_quantized_bw() {
+        let levels = vec![
+            Bandwidth::from_mbps(16),
+            Bandwidth::from_mbps(2),
+            Bandwidth::from_mbps(8),
+        ];
+        let sawtooth = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(0))
+            .top(Bandwidth::from_mbps(20))
+            .interval(Duration::from_millis(100))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(1))
+            .std_dev(Bandwidth::from_mbps(4));
+        let mut model = QuantizedBwConfig::new()
+            .inner(Box::new(sawtooth.clone()))
+            .levels(levels.clone())
+            .build();
+        let mut sawtooth = sawtooth.build();
+        while let Some((bw, duration)) = model.next_bw() {
+            let (raw, raw_duration) = sawtooth.next_bw().unwrap();
+            assert!(levels.contains(&bw));
+            // no level is nearer than the chosen one
+            let distance = |level: Bandwidth| level.max(raw) - level.min(raw);
+            assert!(levels.iter().all(|level| distance(*level) >= distance(bw)));
+            assert_eq!(duration, raw_duration);
+        }
+        assert_eq!(sawtooth.next_bw(), None);
+
+        // ties to the lower level, and passed through without levels
+        let trace = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(100),
+            vec![
+                Bandwidth::from_mbps(5),
+                Bandwidth::from_kbps(5001),
+                Bandwidth::from_mbps(1),
+                Bandwidth::from_mbps(30),
+            ],
+        )]);
+        let quantized = |levels: Vec<Bandwidth>| {
+            let mut model = QuantizedBwConfig::new()
+                .inner(Box::new(trace.clone()))
+                .levels(levels)
+                .build();
+            std::iter::from_fn(|| model.next_bw())
+                .map(|(bw, _)| bw.as_bps() / 1_000_000)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(quantized(levels), [2, 8, 2, 16]);
+        assert_eq!(quantized(vec![]), [5, 5, 1, 30]);
+        assert_eq!(QuantizedBwConfig::new().build().next_bw(), None);
+    }
+
+    #[test]
+    fn test_step_bw() {
+        let steps = vec![
+            Bandwidth::from_mbps(10),
+            Bandwidth::from_mbps(20),
+            Bandwidth::from_mbps(40),
+        ];
+        let config = StepBwConfig::new()
+            .steps(steps.clone())
+            .hold(Duration::from_secs(5))
+            .repeat(2);
+        let mut model = config.clone().build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(
+            segments,
+            steps
+                .iter()
+                .chain(&steps)
+                .map(|bw| (*bw, Duration::from_secs(5)))
+                .collect::<Vec<_>>()
+        );
+        model.reset();
+        assert_eq!(model.next_bw(), Some(segments[0]));
+
+        assert!(!config.is_infinite());
+        assert_eq!(config.total_duration(), Some(Duration::from_secs(30)));
+        assert!(config.clone().forever().is_infinite());
+        let forever = config.clone().repeat(0);
+        assert!(forever.is_infinite());
+        assert_eq!(forever.total_duration(), None);
+        let mut model = forever.build();
+        for _ in 0..3 {
+            for bw in &steps {
+                assert_eq!(model.next_bw(), Some((*bw, Duration::from_secs(5))));
+            }
+        }
+
+        // nothing to emit
+        let empty = config.clone().repeat(0).steps(vec![]);
+        assert!(!empty.is_infinite());
+        assert_eq!(empty.build().next_bw(), None);
+        assert_eq!(config.hold(Duration::ZERO).build().next_bw(), None);
+    }
+
+    #[test]
+    fn test_pareto_bw() {
+        let config = ParetoBwConfig::new()
+            .scale(0.01)
+            .shape(1.5)
+            .on_bw(Bandwidth::from_mbps(24))
+            .off_bw(Bandwidth::from_mbps(1))
+            .duration(Duration::from_millis(200))
+            .seed(42);
+        let mut model = config.clone().build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(
+            segments
+                .iter()
+                .map(|(bw, duration)| (bw.as_bps() / 1_000_000, duration.as_micros()))
+                .collect::<Vec<_>>(),
+            [
+                (24, 15335),
+                (1, 15029),
+                (24, 13515),
+                (1, 18241),
+                (24, 94647),
+                (1, 17974),
+                (24, 12251),
+                (1, 11150),
+                (24, 1853)
+            ]
+        );
+        model.reset();
+        assert_eq!(model.next_bw(), Some(segments[0]));
+
+        // the last period is truncated to fit the duration
+        let total: Duration = segments.iter().map(|(_, duration)| *duration).sum();
+        assert_eq!(total, Duration::from_millis(200));
+        let last = segments.len() - 1;
+        let mut longer = config.clone().duration(Duration::from_secs(10)).build();
+        let untruncated: Vec<_> = (0..segments.len())
+            .map(|_| longer.next_bw().unwrap())
+            .collect();
+        assert_eq!(untruncated[..last], segments[..last]);
+        assert!(untruncated[last].1 > segments[last].1);
+
+        let err = |config: ParetoBwConfig| config.try_build().err().map(|e| e.to_string());
+        assert_eq!(
+            err(config.clone().shape(0.0)).as_deref(),
+            Some("ParetoBw: shape must be positive and finite, got 0")
+        );
+        assert_eq!(
+            err(config.scale(-1.0)).as_deref(),
+            Some("ParetoBw: scale must be positive and finite, got -1")
+        );
+    }
+
+    #[test]
+    fn test_exponential_dwell_bw() {
+        let config = ExponentialDwellBwConfig::new()
+            .levels(vec![
+                Bandwidth::from_mbps(4),
+                Bandwidth::from_mbps(12),
+                Bandwidth::from_mbps(24),
+            ])
+            .rate(10.0)
+            .duration(Duration::from_millis(500))
+            .seed(42);
+        let mut model = config.clone().build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        let micros = |segments: &[(Bandwidth, Duration)]| {
+            segments
+                .iter()
+                .map(|(bw, duration)| (bw.as_bps() / 1_000_000, duration.as_micros()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            micros(&segments),
+            [
+                (4, 51393),
+                (12, 48764),
+                (24, 171771),
+                (4, 50113),
+                (12, 9221),
+                (24, 28131),
+                (12, 140604)
+            ]
+        );
+        model.reset();
+        assert_eq!(model.next_bw(), Some(segments[0]));
+
+        // the last segment is truncated to fit the duration
+        let total: Duration = segments.iter().map(|(_, duration)| *duration).sum();
+        assert_eq!(total, Duration::from_millis(500));
+        let mut longer = config.clone().duration(Duration::from_secs(10)).build();
+        let untruncated: Vec<_> = (0..segments.len())
+            .map(|_| longer.next_bw().unwrap())
+            .collect();
+        assert_eq!(untruncated[..6], segments[..6]);
+        assert!(untruncated[6].1 > segments[6].1);
+
+        let err =
+            |config: ExponentialDwellBwConfig| config.try_build().err().map(|e| e.to_string());
+        assert_eq!(
+            err(config.clone().rate(0.0)).as_deref(),
+            Some("ExponentialDwellBw: rate must be positive and finite, got 0")
+        );
+        // nothing without any level
+        let empty = config.levels(vec![]);
+        assert_eq!(empty.total_duration(), Some(Duration::ZERO));
+        assert_eq!(empty.build().next_bw(), None);
+    }
+
+    #[test]
+    fn test_gamma_bw() {
+        let config = GammaBwConfig::new()
+            .shape(2.0)
+            .scale(Bandwidth::from_mbps(5))
+            .duration(Duration::from_millis(50))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let mut model = config.clone().build();
+        let samples: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, duration)| {
+                assert_eq!(duration, Duration::from_millis(10));
+                bw.as_bps() / 1000
+            })
+            .collect();
+        assert_eq!(samples, [8789, 10145, 4739, 9648, 2414]);
+        model.reset();
+        assert_eq!(model.next_bw().unwrap().0.as_bps() / 1000, samples[0]);
+
+        // the mean is shape * scale
+        let mut model = config.clone().duration(Duration::from_secs(10)).build();
+        let bws: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(bw, _)| bw.as_bps() as f64)
+            .collect();
+        let mean = bws.iter().sum::<f64>() / bws.len() as f64;
+        assert!((mean - 10e6).abs() < 0.5e6, "{}", mean);
+
+        let err = GammaBwConfig::new().shape(0.0).try_build().err();
+        assert_eq!(
+            err.map(|e| e.to_string()).as_deref(),
+            Some("GammaBw: shape must be positive, got 0")
+        );
+        let err = GammaBwConfig::new()
+            .scale(Bandwidth::ZERO)
+            .try_build()
+            .err();
+        assert_eq!(
+            err.map(|e| e.to_string()).as_deref(),
+            Some("GammaBw: scale must be positive, got 0")
+        );
+    }
+
+    #[test]
+    fn test_gamma_bw_bounds() {
+        let config = GammaBwConfig::new()
+            .shape(2.0)
+            .scale(Bandwidth::from_mbps(5))
+            .duration(Duration::from_millis(50))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let samples: Vec<_> = config
+            .clone()
+            .build()
+            .into_iter()
+            .map(|(bw, _)| bw.as_bps() / 1000)
+            .collect();
+        // bounded, with the same samples
+        let bounded: Vec<_> = config
+            .lower_bound(Bandwidth::from_mbps(8))
+            .upper_bound(Bandwidth::from_mbps(12))
+            .build()
+            .into_iter()
+            .map(|(bw, _)| bw.as_bps() / 1000)
+            .collect();
+        assert_eq!(
+            bounded,
+            samples
+                .iter()
+                .map(|kbps| (*kbps).clamp(8000, 12000))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(matches!(
+            GammaBwConfig::new()
+                .lower_bound(Bandwidth::from_mbps(2))
+                .upper_bound(Bandwidth::from_mbps(1))
+                .try_build(),
+            Err(ModelBuildError::InvalidBounds {
+                model: "GammaBw",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_ramp_bw() {
+        let config = RampBwConfig::new()
+            .start(Bandwidth::from_mbps(10))
+            .end(Bandwidth::from_mbps(20))
+            .duration(Duration::from_millis(1100))
+            .step(Duration::from_millis(100));
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(1100)));
+        let mut ramp_bw = config.build();
+        let samples: Vec<_> = std::iter::from_fn(|| ramp_bw.next_bw()).collect();
+        assert_eq!(samples.len(), 11);
+        assert_eq!(
+            samples[0],
+            (Bandwidth::from_mbps(10), Duration::from_millis(100))
+        );
+        assert_eq!(
+            samples[5],
+            (Bandwidth::from_mbps(15), Duration::from_millis(100))
+        );
+        // the final step reaches `end`
+        assert_eq!(
+            samples[10],
+            (Bandwidth::from_mbps(20), Duration::from_millis(100))
+        );
+        assert_eq!(ramp_bw.next_bw(), None);
+        ramp_bw.reset();
+        assert_eq!(
+            ramp_bw.next_bw(),
+            Some((Bandwidth::from_mbps(10), Duration::from_millis(100)))
+        );
+
+        // a dropping ramp, whose last segment is cut at the end of duration
+        let samples: Vec<_> = RampBwConfig::new()
+            .start(Bandwidth::from_mbps(12))
+            .end(Bandwidth::ZERO)
+            .duration(Duration::from_millis(300))
+            .step(Duration::from_millis(120))
+            .build()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            samples,
+            [
+                (Bandwidth::from_mbps(12), Duration::from_millis(120)),
+                (Bandwidth::from_mbps(6), Duration::from_millis(120)),
+                (Bandwidth::ZERO, Duration::from_millis(60)),
+            ]
+        );
+
+        // a ramp of a single step is at `end`
+        let samples: Vec<_> = RampBwConfig::new()
+            .start(Bandwidth::from_mbps(12))
+            .end(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(50))
+            .step(Duration::from_millis(100))
+            .build()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            samples,
+            [(Bandwidth::from_mbps(24), Duration::from_millis(50))]
+        );
+    }
+
+    #[test]
+    fn test_trace_bw() {
+        let mut trace_bw = TraceBwConfig::new()
+            .pattern(vec![
+                (
+                    Duration::from_millis(1),
+                    vec![
+                        Bandwidth::from_kbps(29123),
+                        Bandwidth::from_kbps(41242),
+                        Bandwidth::from_kbps(7395),
+                    ],
+                ),
+                (
+                    Duration::from_millis(2),
+                    vec![Bandwidth::from_mbps(1), Bandwidth::from_kbps(8542)],
+                ),
+            ])
+            .build();
+
+        assert_eq!(
+            trace_bw.next_bw(),
+            Some((Bandwidth::from_bps(29123000), Duration::from_millis(1)))
+        );
+        assert_eq!(
+            trace_bw.next_bw(),
+            Some((Bandwidth::from_bps(41242000), Duration::from_millis(1)))
+        );
+        assert_eq!(
+            trace_bw.next_bw(),
+            Some((Bandwidth::from_bps(7395000), Duration::from_millis(1)))
+        );
+        assert_eq!(
+            trace_bw.next_bw(),
+            Some((Bandwidth::from_bps(1000000), Duration::from_millis(2)))
+        );
+        assert_eq!(
+            trace_bw.next_bw(),
+            Some((Bandwidth::from_bps(8542000), Duration::from_millis(2)))
+        );
+        assert_eq!(trace_bw.next_bw(), None);
+    }
+
+    #[test]
+    fn test_config_conversion() {
+        fn run(mut model: impl BwTrace) -> Vec<(Bandwidth, Duration)> {
+            std::iter::from_fn(|| model.next_bw()).collect()
+        }
+
+        let static_config = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(300));
+        let trace_config = TraceBwConfig::from(static_config.clone());
+        assert_eq!(
+            run(trace_config.clone().build()),
+            run(static_config.clone().build())
+        );
+        let converted = StaticBwConfig::try_from(trace_config).unwrap();
+        assert_eq!(run(converted.build()), run(static_config.build()));
+
+        // defaults are applied
+        assert_eq!(
+            run(TraceBwConfig::from(StaticBwConfig::new()).build()),
+            run(StaticBwConfig::new().build())
+        );
+
+        // zero duration
+        let static_config = StaticBwConfig::new().duration(Duration::ZERO);
+        let trace_config = TraceBwConfig::from(static_config.clone());
+        assert!(trace_config.pattern.is_empty());
+        assert_eq!(run(trace_config.clone().build()), vec![]);
+        let converted = StaticBwConfig::try_from(trace_config).unwrap();
+        assert_eq!(run(converted.build()), vec![]);
+
+        // empty bandwidth lists are skipped
+        let trace_config = TraceBwConfig::new().pattern(vec![
+            (Duration::from_millis(1), vec![]),
+            (Duration::from_millis(2), vec![Bandwidth::from_mbps(1)]),
+        ]);
+        let converted = StaticBwConfig::try_from(trace_config.clone()).unwrap();
+        assert_eq!(run(converted.build()), run(trace_config.build()));
+
+        // more than one segment
+        let trace_config = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(1),
+            vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2)],
+        )]);
+        assert_eq!(
+            StaticBwConfig::try_from(trace_config)
+                .err()
+                .unwrap()
+                .to_string(),
+            "StaticBw: can only have one segment, but the TraceBwConfig has 2"
+        );
+        let trace_config =
+            TraceBwConfig::new().pattern(vec![(Duration::ZERO, vec![Bandwidth::from_mbps(1)])]);
+        assert!(StaticBwConfig::try_from(trace_config).is_err());
+    }
+
+    #[test]
+    fn test_tagged_bw() {
+        let inner_pattern = model::RepeatedBwPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    model::TaggedBwConfig::new()
+                        .inner(Box::new(
+                            StaticBwConfig::new()
+                                .bw(Bandwidth::from_mbps(12))
+                                .duration(Duration::from_secs(1)),
+                        ))
+                        .tag("cellular"),
+                ) as Box<dyn BwTraceConfig>,
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(24))
+                        .duration(Duration::from_secs(1)),
+                ) as Box<dyn BwTraceConfig>,
+            ])
+            .count(2);
+        let mut model = model::TaggedBwConfig::new()
+            .inner(Box::new(inner_pattern))
+            .tag("fallback")
+            .build();
+        for _ in 0..2 {
+            assert_eq!(
+                model.next_bw_tagged(),
+                Some((
+                    Bandwidth::from_mbps(12),
+                    Duration::from_secs(1),
+                    Some("cellular")
+                ))
+            );
+            assert_eq!(
+                model.next_bw_tagged(),
+                Some((
+                    Bandwidth::from_mbps(24),
+                    Duration::from_secs(1),
+                    Some("fallback")
+                ))
+            );
+        }
+        assert_eq!(model.next_bw_tagged(), None);
+
+        let mut untagged = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .build();
+        assert_eq!(
+            untagged.next_bw_tagged(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1), None))
+        );
+    }
+
+    #[test]
+    fn test_trace_bw_from_unsorted() {
+        use rand::seq::SliceRandom;
+        use rand::SeedableRng;
+
+        let mbps = Bandwidth::from_mbps;
+        let ms = Duration::from_millis;
+        let sorted = vec![
+            (ms(10), mbps(1)),
+            (ms(20), mbps(2)),
+            (ms(30), mbps(3)),
+            (ms(50), mbps(4)),
+            (ms(70), mbps(5)),
+            (ms(71), mbps(6)),
+        ];
+        let expected = vec![
+            (ms(10), vec![mbps(1), mbps(2), mbps(3)]),
+            (ms(20), vec![mbps(4), mbps(5)]),
+            (ms(1), vec![mbps(6)]),
+        ];
+        assert_eq!(
+            TraceBwConfig::from_unsorted(sorted.clone())
+                .unwrap()
+                .pattern,
+            expected
+        );
+
+        // any order gives the same pattern
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            let mut shuffled = sorted.clone();
+            shuffled.shuffle(&mut rng);
+            let config = TraceBwConfig::from_unsorted(shuffled).unwrap();
+            assert_eq!(config.pattern, expected);
+            let mut model = config.build();
+            let mut now = Duration::ZERO;
+            for (offset, bw) in &sorted {
+                let (next_bw, duration) = model.next_bw().unwrap();
+                now += duration;
+                assert_eq!((now, next_bw), (*offset, *bw));
+            }
+            assert_eq!(model.next_bw(), None);
+        }
+
+        assert!(TraceBwConfig::from_unsorted(vec![])
+            .unwrap()
+            .pattern
+            .is_empty());
+
+        // a point at offset 0 covers no time
+        assert_eq!(
+            TraceBwConfig::from_unsorted(vec![(ms(10), mbps(1)), (ms(0), mbps(2))])
+                .unwrap()
+                .pattern,
+            vec![(ms(10), vec![mbps(1)])]
+        );
+    }
+
+    #[test]
+    fn test_trace_bw_from_timestamps() {
+        let config = TraceBwConfig::from_timestamps(vec![
+            (Duration::from_millis(100), vec![Bandwidth::from_mbps(12)]),
+            (
+                Duration::from_millis(150),
+                vec![Bandwidth::from_mbps(24), Bandwidth::from_mbps(6)],
+            ),
+            (Duration::from_secs(1), vec![Bandwidth::ZERO]),
+        ])
+        .unwrap();
+        assert_eq!(
+            config.pattern,
+            vec![
+                (Duration::from_millis(100), vec![Bandwidth::from_mbps(12)]),
+                (
+                    Duration::from_millis(25),
+                    vec![Bandwidth::from_mbps(24), Bandwidth::from_mbps(6)]
+                ),
+                (Duration::from_millis(850), vec![Bandwidth::ZERO]),
+            ]
+        );
+        // the segments end at the timestamps
+        let mut model = config.build();
+        let mut end = Duration::ZERO;
+        let ends: Vec<_> = std::iter::from_fn(|| model.next_bw())
+            .map(|(_, duration)| {
+                end += duration;
+                end
+            })
+            .collect();
+        assert_eq!(ends, [100, 125, 150, 1000].map(Duration::from_millis));
+        assert!(TraceBwConfig::from_timestamps(vec![])
+            .unwrap()
+            .pattern
+            .is_empty());
+
+        // a first timestamp of 0 covers no time
+        let entries = [0, 100]
+            .map(|ms| (Duration::from_millis(ms), vec![Bandwidth::from_mbps(12)]))
+            .to_vec();
+        assert_eq!(
+            TraceBwConfig::from_timestamps(entries).unwrap().pattern,
+            vec![(Duration::from_millis(100), vec![Bandwidth::from_mbps(12)])]
+        );
+
+        // the remainder of an uneven interval goes to the last bandwidth
+        let config = TraceBwConfig::from_timestamps(vec![
+            (Duration::from_nanos(100), vec![Bandwidth::from_mbps(12); 3]),
+            (Duration::from_nanos(201), vec![Bandwidth::from_mbps(24); 2]),
+        ])
+        .unwrap();
+        assert_eq!(
+            config.pattern,
+            vec![
+                (Duration::from_nanos(33), vec![Bandwidth::from_mbps(12); 2]),
+                (Duration::from_nanos(34), vec![Bandwidth::from_mbps(12)]),
+                (Duration::from_nanos(50), vec![Bandwidth::from_mbps(24)]),
+                (Duration::from_nanos(51), vec![Bandwidth::from_mbps(24)]),
+            ]
+        );
+        assert_eq!(config.total_duration(), Some(Duration::from_nanos(201)));
+
+        // decreasing and repeated timestamps
+        for timestamps in [[200, 100], [100, 100], [0, 0]] {
+            let entries = timestamps
+                .map(|ms| (Duration::from_millis(ms), vec![Bandwidth::from_mbps(12)]))
+                .to_vec();
+            assert!(matches!(
+                TraceBwConfig::from_timestamps(entries),
+                Err(ModelBuildError::InvalidConfig { .. })
+            ));
+        }
+        assert_eq!(
+            TraceBwConfig::from_timestamps(vec![(Duration::from_millis(100), vec![])])
+                .err()
+                .unwrap()
+                .to_string(),
+            "TraceBw: entry at timestamp 100ms has no bandwidths"
+        );
+    }
+
+    #[test]
+    fn test_trace_bw_from_unsorted_duplicate() {
+        let err = TraceBwConfig::from_unsorted(vec![
+            (Duration::from_millis(20), Bandwidth::from_mbps(1)),
+            (Duration::from_millis(10), Bandwidth::from_mbps(2)),
+            (Duration::from_millis(20), Bandwidth::from_mbps(3)),
+        ])
+        .unwrap_err();
+        assert_eq!(err.to_string(), "TraceBw: duplicate offset 20ms");
+    }
+
+    #[test]
+    fn test_env_bw() {
+        const VAR: &str = "NETEM_TRACE_TEST_ENV_BW";
+        let config = EnvBwConfig::new()
+            .var(VAR)
+            .default_bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(2));
+        // the variable is looked up with a fake environment, instead of mutating the process one
+        let resolve = |value: Option<&str>| {
+            config
+                .clone()
+                .build_with_lookup(|var| {
+                    assert_eq!(var, VAR);
+                    value.map(str::to_string)
+                })
+                .next_bw()
+        };
+
+        assert_eq!(
+            resolve(None),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(2)))
+        );
+        #[cfg(feature = "human")]
+        let value = "24Mbps";
+        #[cfg(not(feature = "human"))]
+        let value = "24000000";
+        assert_eq!(
+            resolve(Some(value)),
+            Some((Bandwidth::from_mbps(24), Duration::from_secs(2)))
+        );
+        assert_eq!(
+            resolve(Some("not a bandwidth")),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(2)))
+        );
+        // the process environment is read by `build`, the variable is never set there
+        assert_eq!(
+            config.clone().build().next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(2)))
+        );
+        assert_eq!(config.total_duration(), Some(Duration::from_secs(2)));
+
+        // without a variable, the default is used
+        assert_eq!(
+            EnvBwConfig::new().build().next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let ser: Box<dyn BwTraceConfig> = Box::new(config.clone());
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            #[cfg(feature = "human")]
+            assert_eq!(
+                ser_str,
+                "{\"EnvBwConfig\":{\"var\":\"NETEM_TRACE_TEST_ENV_BW\",\"default_bw\":\"12Mbps\",\"duration\":\"2s\"}}"
+            );
+            let des: Box<dyn BwTraceConfig> = serde_json::from_str(&ser_str).unwrap();
+            assert_eq!(
+                des.into_model().next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_secs(2)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_trace_bw_interpolation() {
+        let config = TraceBwConfig::new()
+            .pattern(vec![(
+                Duration::from_secs(1),
+                vec![Bandwidth::from_mbps(10), Bandwidth::from_mbps(20)],
+            )])
+            .step(Duration::from_millis(300));
+        let sample = |interpolation| {
+            let mut model = config.clone().interpolation(interpolation).build();
+            std::iter::from_fn(move || model.next_bw()).collect::<Vec<_>>()
+        };
+        let durations = [300, 300, 300, 100, 300, 300, 300, 100].map(Duration::from_millis);
+        let expected = |mbps: [f64; 8]| {
+            mbps.iter()
+                .map(|mbps| Bandwidth::from_kbps((mbps * 1000.0) as u64))
+                .zip(durations)
+                .collect::<Vec<_>>()
+        };
+
+        // sampled at 0ms, 300ms, 600ms and 900ms of each segment, the last segment is held
+        assert_eq!(
+            sample(Interpolation::Hold),
+            expected([10.0, 10.0, 10.0, 10.0, 20.0, 20.0, 20.0, 20.0])
+        );
+        assert_eq!(
+            sample(Interpolation::Linear),
+            expected([10.0, 13.0, 16.0, 19.0, 20.0, 20.0, 20.0, 20.0])
+        );
+        assert_eq!(
+            sample(Interpolation::Nearest),
+            expected([10.0, 10.0, 20.0, 20.0, 20.0, 20.0, 20.0, 20.0])
+        );
+
+        // without a step, the segments are emitted whole as before
+        let mut model = TraceBwConfig::new()
+            .pattern(config.pattern.clone())
+            .interpolation(Interpolation::Linear)
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(10), Duration::from_secs(1)))
+        );
+
+        // reset in the middle of a segment
+        let mut model = config.clone().interpolation(Interpolation::Linear).build();
+        model.next_bw();
+        model.reset();
+        assert_eq!(model.next_bw(), Some(expected([10.0; 8])[0]));
+
+        #[cfg(feature = "serde")]
+        {
+            let ser: Box<dyn BwTraceConfig> =
+                Box::new(config.clone().interpolation(Interpolation::Nearest));
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            #[cfg(feature = "human")]
+            assert_eq!(
+                ser_str,
+                "{\"TraceBwConfig\":{\"pattern\":[[\"1s\",[\"10Mbps\",\"20Mbps\"]]],\"interpolation\":\"Nearest\",\"step\":\"300ms\"}}"
+            );
+            let des: Box<dyn BwTraceConfig> = serde_json::from_str(&ser_str).unwrap();
+            let mut model = des.into_model();
+            assert_eq!(
+                std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+                sample(Interpolation::Nearest)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "TraceBw: step must be positive")]
+    fn test_trace_bw_zero_step() {
+        TraceBwConfig::new().step(Duration::ZERO).build();
+    }
+
+    #[test]
+    fn test_sequence_bw() {
+        // an empty sequence produces nothing
+        let mut model = SequenceBwConfig::new().build();
+        assert_eq!(model.next_bw(), None);
+        assert_eq!(
+            SequenceBwConfig::new().total_duration(),
+            Some(Duration::ZERO)
+        );
+
+        // segments of zero duration are skipped
+        let sequence = vec![
+            (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+            (Bandwidth::from_mbps(24), Duration::ZERO),
+            (Bandwidth::from_mbps(6), Duration::from_millis(50)),
+            (Bandwidth::from_mbps(6), Duration::from_millis(50)),
+        ];
+        let config = SequenceBwConfig::from(sequence);
+        assert_eq!(config.total_duration(), Some(Duration::from_millis(200)));
+        let mut model = config.clone().build();
+        let expected = [
+            (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+            (Bandwidth::from_mbps(6), Duration::from_millis(50)),
+            (Bandwidth::from_mbps(6), Duration::from_millis(50)),
+        ];
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            expected
+        );
+        model.reset();
+        assert_eq!(model.next_bw(), Some(expected[0]));
+
+        let mut model = config.forever().build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).take(6).collect();
+        assert_eq!(segments, [expected, expected].concat());
+
+        #[cfg(feature = "serde")]
+        {
+            let config =
+                Box::new(SequenceBwConfig::from(expected.to_vec())) as Box<dyn BwTraceConfig>;
+            let ser_str = serde_json::to_string(&config).unwrap();
+            #[cfg(feature = "human")]
+            assert_eq!(
+                ser_str,
+                "{\"SequenceBwConfig\":{\"sequence\":[[\"12Mbps\",\"100ms\"],[\"6Mbps\",\"50ms\"],[\"6Mbps\",\"50ms\"]]}}"
+            );
+            #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+            assert_eq!(
+                ser_str,
+                "{\"SequenceBwConfig\":{\"sequence\":[[{\"gbps\":0,\"bps\":12000000},100000000],[{\"gbps\":0,\"bps\":6000000},50000000],[{\"gbps\":0,\"bps\":6000000},50000000]]}}"
+            );
+            let des: Box<dyn BwTraceConfig> = serde_json::from_str(&ser_str).unwrap();
+            let mut model = des.into_model();
+            assert_eq!(
+                std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+                expected
+            );
+
+            #[cfg(feature = "human")]
+            {
+                let err = serde_json::from_str::<Box<dyn BwTraceConfig>>(
+                    "{\"SequenceBwConfig\":{\"sequence\":[[\"12Mbps\",\"soon\"]]}}",
+                )
+                .err()
+                .unwrap();
+                assert!(err
+                    .to_string()
+                    .starts_with("Failed to parse duration 'soon'"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bw_trace_iter() {
+        let config = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_secs(1));
+        assert_eq!(config.clone().build().into_iter().count(), 1);
+
+        let pattern: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(config.clone()),
+            Box::new(config.bw(Bandwidth::from_mbps(12))),
+        ];
+        let model = Box::new(RepeatedBwPatternConfig::new().pattern(pattern).count(0)).into_model();
+        let mbps: Vec<_> = model
+            .into_iter()
+            .take(3)
+            .map(|(bw, _)| bw.as_gbps_f64() * 1000.0)
+            .collect();
+        assert_eq!(mbps, [24.0, 12.0, 24.0]);
+
+        let total: Duration = Box::new(
+            StaticBwConfig::new()
+                .duration(Duration::from_secs(1))
+                .repeat(3),
+        )
+        .into_model()
+        .into_iter()
+        .map(|(_, duration)| duration)
+        .sum();
+        assert_eq!(total, Duration::from_secs(3));
+
+        // the trace can be taken back from the iterator
+        let mut iter = StaticBwConfig::new().build().into_iter();
+        assert!(iter.next().is_some());
+        assert_eq!(iter.into_inner().next_bw(), None);
+    }
+
+    #[test]
+    fn test_peekable_bw() {
+        let pattern: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ),
+            Box::new(
+                TaggedBwConfig::new()
+                    .tag("second")
+                    .inner(Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(24)))),
+            ),
+        ];
+        let mut model = Box::new(RepeatedBwPatternConfig::new().pattern(pattern).count(1))
+            .into_model()
+            .peekable();
+        let first = (Bandwidth::from_mbps(12), Duration::from_secs(1));
+        assert_eq!(model.peek(), Some(&first));
+        assert_eq!(model.peek(), Some(&first));
+        assert_eq!(model.next_bw(), Some(first));
+
+        // the tag of the peeked segment is kept
+        let second = (Bandwidth::from_mbps(24), Duration::from_secs(1));
+        assert_eq!(model.peek(), Some(&second));
+        assert_eq!(
+            model.next_bw_tagged(),
+            Some((second.0, second.1, Some("second")))
+        );
+        assert_eq!(model.peek(), None);
+        assert_eq!(model.next_bw(), None);
+
+        // resetting discards the peeked segment
+        model.as_resettable().unwrap().reset();
+        assert_eq!(model.next_bw(), Some(first));
+        assert_eq!(model.peek(), Some(&second));
+        model.as_resettable().unwrap().reset();
+        assert_eq!(model.next_bw(), Some(first));
+    }
+
+    #[test]
+    fn test_floor_plus_variable_bw() {
+        let variable = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(2))
+            .std_dev(Bandwidth::from_mbps(4))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let config = FloorPlusVariableBwConfig::new()
+            .floor(Bandwidth::from_mbps(5))
+            .variable(Box::new(variable.clone()));
+        let mut model = config.clone().build();
+        let mut variable = variable.build();
+        let mut count = 0;
+        while let Some((bw, duration)) = model.next_bw() {
+            let (variable_bw, variable_duration) = variable.next_bw().unwrap();
+            assert!(bw >= Bandwidth::from_mbps(5));
+            assert_eq!(bw, Bandwidth::from_mbps(5) + variable_bw);
+            assert_eq!(duration, variable_duration);
+            count += 1;
+        }
+        assert_eq!(count, 100);
+        assert_eq!(variable.next_bw(), None);
+
+        // saturating
+        let mut model = FloorPlusVariableBwConfig::new()
+            .floor(Bandwidth::MAX)
+            .variable(Box::new(StaticBwConfig::new()))
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::MAX, Duration::from_secs(1)))
+        );
+
+        // nothing without a variable model
+        assert_eq!(FloorPlusVariableBwConfig::new().build().next_bw(), None);
+
+        // the nesting depth of the variable model is checked
+        let nested = FloorPlusVariableBwConfig::new().variable(Box::new(config));
+        assert!(!nested.exceeds_depth(3));
+        assert!(nested.exceeds_depth(2));
+
+        #[cfg(feature = "serde")]
+        {
+            let ser: Box<dyn BwTraceConfig> = Box::new(nested);
+            let des: Box<dyn BwTraceConfig> =
+                serde_json::from_str(&serde_json::to_string(&ser).unwrap()).unwrap();
+            assert!(des.output_eq(
+                ser.as_ref(),
+                Duration::from_millis(10),
+                Duration::from_secs(1)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_scaled_bw() {
+        let trace = TraceBwConfig::new().pattern(vec![
+            (
+                Duration::from_millis(100),
+                vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(4)],
+            ),
+            (Duration::from_millis(50), vec![Bandwidth::from_kbps(200)]),
+        ]);
+        let durations = [
+            Duration::from_millis(100),
+            Duration::from_millis(100),
+            Duration::from_millis(50),
+        ];
+        for (factor, expected) in [
+            (0.0, [Bandwidth::ZERO; 3]),
+            (
+                1.0,
+                [
+                    Bandwidth::from_mbps(12),
+                    Bandwidth::from_mbps(4),
+                    Bandwidth::from_kbps(200),
+                ],
+            ),
+            (
+                2.5,
+                [
+                    Bandwidth::from_mbps(30),
+                    Bandwidth::from_mbps(10),
+                    Bandwidth::from_kbps(500),
+                ],
+            ),
+        ] {
+            let config = ScaledBwConfig::new()
+                .inner(Box::new(trace.clone()))
+                .factor(factor);
+            let mut model = config.clone().build();
+            for (bw, duration) in expected.into_iter().zip(durations) {
+                assert_eq!(model.next_bw(), Some((bw, duration)), "factor {}", factor);
+            }
+            assert_eq!(model.next_bw(), None);
+
+            // repeated with `Forever`
+            let mut model = Box::new(config.repeat(2)).into_model();
+            assert_eq!(std::iter::from_fn(|| model.next_bw()).count(), 6);
+        }
+
+        // the factor defaults to 1
+        let mut model = ScaledBwConfig::new()
+            .inner(Box::new(StaticBwConfig::new()))
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+        );
+        assert_eq!(ScaledBwConfig::new().build().next_bw(), None);
+
+        #[cfg(feature = "serde")]
+        {
+            let config: Box<dyn BwTraceConfig> = Box::new(
+                ScaledBwConfig::new()
+                    .inner(Box::new(trace.clone()))
+                    .factor(2.5),
+            );
+            let des: Box<dyn BwTraceConfig> =
+                serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+            assert!(des.output_eq(
+                config.as_ref(),
+                Duration::from_millis(10),
+                Duration::from_millis(250)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_scaled_bw_saturates() {
+        let mut model = ScaledBwConfig::new()
+            .inner(Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_gbps(u64::MAX / 2))
+                    .duration(Duration::from_secs(1)),
+            ))
+            .factor(4.0)
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::MAX, Duration::from_secs(1)))
+        );
+        assert_eq!(model.next_bw(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ScaledBw: factor must be non-negative and finite")]
+    fn test_scaled_bw_invalid_factor() {
+        ScaledBwConfig::new().factor(-1.0).build();
+    }
+
+    #[test]
+    fn test_hysteresis_bw() {
+        // a noisy trace around 12Mbps with a step to 24Mbps and back
+        let noise = [0, 1, -1, 2, -2, 1];
+        let bws: Vec<_> = [12i64, 24, 12]
+            .iter()
+            .flat_map(|level| {
+                noise
+                    .iter()
+                    .map(move |n| Bandwidth::from_mbps((level + n) as u64))
+            })
+            .collect();
+        let inner = TraceBwConfig::new().pattern(vec![(Duration::from_millis(100), bws.clone())]);
+        let config = HysteresisBwConfig::new()
+            .inner(Box::new(inner.clone()))
+            .threshold(Bandwidth::from_mbps(2));
+        let mut model = config.clone().build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            [
+                (Bandwidth::from_mbps(12), Duration::from_millis(600)),
+                (Bandwidth::from_mbps(24), Duration::from_millis(600)),
+                (Bandwidth::from_mbps(12), Duration::from_millis(600)),
+            ]
+        );
+
+        // a zero threshold only merges equal bandwidths, keeping the total duration
+        let mut model = config.clone().threshold(Bandwidth::ZERO).build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(segments.len(), bws.len());
+        let mut model = config
+            .clone()
+            .inner(Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1))
+                    .repeat(3),
+            ))
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            [(Bandwidth::from_mbps(12), Duration::from_secs(3))]
+        );
+
+        // the tag of the first segment of a level is kept
+        let mut model = config
+            .clone()
+            .inner(Box::new(
+                TaggedBwConfig::new().inner(Box::new(inner)).tag("noisy"),
+            ))
+            .build();
+        assert_eq!(
+            model.next_bw_tagged(),
+            Some((
+                Bandwidth::from_mbps(12),
+                Duration::from_millis(600),
+                Some("noisy")
+            ))
+        );
+
+        assert_eq!(HysteresisBwConfig::new().build().next_bw(), None);
+
+        // an infinite inner model within the threshold is emitted in pieces of `max_hold`
+        let mut model = HysteresisBwConfig::new()
+            .inner(Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_millis(300))
+                    .forever(),
+            ))
+            .threshold(Bandwidth::from_mbps(2))
+            .max_hold(Duration::from_secs(1))
+            .build();
+        for _ in 0..3 {
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_millis(1200)))
+            );
+        }
+        // the default cap applies as well
+        let mut model = HysteresisBwConfig::new()
+            .inner(Box::new(StaticBwConfig::new().forever()))
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(60)))
+        );
+        // the level cut by `max_hold` is held on
+        let mut model = config.clone().max_hold(Duration::from_millis(250)).build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_bw()).collect();
+        assert_eq!(segments.len(), 6);
+        assert!(segments[..2]
+            .iter()
+            .all(|(bw, duration)| *bw == Bandwidth::from_mbps(12)
+                && *duration == Duration::from_millis(300)));
+        assert!(matches!(
+            HysteresisBwConfig::new()
+                .max_hold(Duration::ZERO)
+                .try_build(),
+            Err(ModelBuildError::ZeroDuration {
+                name: "max_hold",
+                ..
+            })
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            let ser: Box<dyn BwTraceConfig> = Box::new(config);
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            let des: Box<dyn BwTraceConfig> = serde_json::from_str(&ser_str).unwrap();
+            let mut model = des.into_model();
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_millis(600)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_splice_bw() {
+        let base = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(100),
+            vec![
+                Bandwidth::from_mbps(12),
+                Bandwidth::from_mbps(24),
+                Bandwidth::from_mbps(36),
+            ],
+        )]);
+        let splice = |start: u64, end: u64| {
+            let mut model = SpliceBwConfig::new()
+                .base(Box::new(base.clone()))
+                .start(Duration::from_millis(start))
+                .end(Duration::from_millis(end))
+                .override_bw(Bandwidth::from_mbps(1))
+                .build();
+            std::iter::from_fn(|| model.next_bw())
+                .map(|(bw, duration)| (bw.as_bps() / 1_000_000, duration.as_millis()))
+                .collect::<Vec<_>>()
+        };
+
+        // the window lands in the middle of segments
+        assert_eq!(
+            splice(50, 250),
+            [(12, 50), (1, 50), (1, 100), (1, 50), (36, 50)]
+        );
+        // the window lands on the boundaries of segments
+        assert_eq!(splice(100, 200), [(12, 100), (1, 100), (36, 100)]);
+        assert_eq!(splice(0, 300), [(1, 100), (1, 100), (1, 100)]);
+        // the window is fully outside of the trace, or empty
+        assert_eq!(splice(300, 400), [(12, 100), (24, 100), (36, 100)]);
+        assert_eq!(splice(500, 600), [(12, 100), (24, 100), (36, 100)]);
+        assert_eq!(splice(200, 100), [(12, 100), (24, 100), (36, 100)]);
+        // the window does not extend the trace
+        assert_eq!(splice(250, 1000), [(12, 100), (24, 100), (36, 50), (1, 50)]);
+
+        assert_eq!(SpliceBwConfig::new().build().next_bw(), None);
+    }
+
+    #[test]
+    fn test_clamped_bw() {
+        let sawtooth = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(0))
+            .top(Bandwidth::from_mbps(20))
+            .interval(Duration::from_millis(100))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(1))
+            .std_dev(Bandwidth::from_mbps(4));
+        let config = ClampedBwConfig::new()
+            .inner(Box::new(sawtooth.clone()))
+            .min(Bandwidth::from_mbps(2))
+            .max(Bandwidth::from_mbps(12));
+        let mut model = config.clone().build();
+        let mut sawtooth = sawtooth.build();
+        let mut clamped = 0;
+        while let Some((bw, duration)) = model.next_bw() {
+            let (raw, raw_duration) = sawtooth.next_bw().unwrap();
+            assert!(bw <= Bandwidth::from_mbps(12));
+            assert!(bw >= Bandwidth::from_mbps(2));
+            assert_eq!(
+                bw,
+                raw.clamp(Bandwidth::from_mbps(2), Bandwidth::from_mbps(12))
+            );
+            assert_eq!(duration, raw_duration);
+            if bw != raw {
+                clamped += 1;
+            }
+        }
+        assert!(clamped > 0);
+        assert_eq!(sawtooth.next_bw(), None);
+
+        // also works on a repeated pattern, with only one bound
+        let mut model = Box::new(
+            ClampedBwConfig::new()
+                .inner(Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(24))
+                        .duration(Duration::from_secs(1))
+                        .repeat(2),
+                ))
+                .max(Bandwidth::from_mbps(12))
+                .forever(),
+        )
+        .into_model();
+        for _ in 0..5 {
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+            );
+        }
+
+        // max wins if min is larger than max
+        let mut model = ClampedBwConfig::new()
+            .inner(Box::new(StaticBwConfig::new()))
+            .min(Bandwidth::from_mbps(20))
+            .max(Bandwidth::from_mbps(10))
+            .build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(10), Duration::from_secs(1)))
+        );
+        assert_eq!(ClampedBwConfig::new().build().next_bw(), None);
+
+        #[cfg(feature = "serde")]
+        {
+            let config: Box<dyn BwTraceConfig> = Box::new(config);
+            let des: Box<dyn BwTraceConfig> =
+                serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+            assert!(des.output_eq(
+                config.as_ref(),
+                Duration::from_millis(1),
+                Duration::from_secs(1)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_total_duration() {
+        let drain = |config: Box<dyn BwTraceConfig>| {
+            let mut model = config.into_model();
+            std::iter::from_fn(|| model.next_bw()).fold(Duration::ZERO, |total, (_, d)| total + d)
+        };
+        let static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_millis(300));
+        let trace = TraceBwConfig::new().pattern(vec![
+            (
+                Duration::from_millis(100),
+                vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2)],
+            ),
+            (Duration::from_millis(50), vec![]),
+            (Duration::from_millis(25), vec![Bandwidth::from_mbps(3)]),
+        ]);
+        assert_eq!(static_bw.total_duration(), Some(Duration::from_millis(300)));
+        assert_eq!(
+            StaticBwConfig::new().total_duration(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(trace.total_duration(), Some(Duration::from_millis(225)));
+
+        // a two-element repeated pattern
+        let repeated = RepeatedBwPatternConfig::new()
+            .pattern(vec![Box::new(static_bw.clone()), Box::new(trace.clone())])
+            .count(3);
+        assert_eq!(repeated.total_duration(), Some(Duration::from_millis(1575)));
+        assert_eq!(
+            drain(Box::new(repeated.clone())),
+            repeated.total_duration().unwrap()
+        );
+        // nested
+        let nested = repeated.clone().repeat(2);
+        assert_eq!(nested.total_duration(), Some(Duration::from_millis(3150)));
+        assert_eq!(
+            RepeatedBwPatternConfig::new().count(2).total_duration(),
+            Some(Duration::ZERO)
+        );
+
+        // known for every model with a duration fixed by its config, even if random
+        let duration = Duration::from_millis(250);
+        let step = Duration::from_millis(100);
+        let fixed: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(NormalizedBwConfig::new().duration(duration).step(step)),
+            Box::new(
+                NormalizedBwConfig::new()
+                    .duration(duration)
+                    .step(step)
+                    .exact_duration(false),
+            ),
+            Box::new(SawtoothBwConfig::new().duration(duration).step(step)),
+            Box::new(SineBwConfig::new().duration(duration).step(step)),
+            Box::new(SquareBwConfig::new().duration(duration).step(step)),
+            Box::new(
+                EmpiricalBwConfig::new()
+                    .cdf(vec![(Bandwidth::from_mbps(1), 1.0)])
+                    .duration(duration)
+                    .step(step),
+            ),
+            Box::new(
+                MarkovBwConfig::new()
+                    .states(vec![Bandwidth::from_mbps(1)])
+                    .transitions(vec![vec![1.0]])
+                    .duration(duration)
+                    .step(step),
+            ),
+            Box::new(SplineBwConfig::new().points(vec![
+                (Duration::ZERO, Bandwidth::from_mbps(1)),
+                (duration, Bandwidth::from_mbps(2)),
+            ])),
+            Box::new(PoissonBwConfig::new().duration(duration)),
+        ];
+        for config in fixed {
+            let total = config.total_duration().unwrap();
+            assert_eq!(drain(config), total);
+        }
+        assert_eq!(
+            NormalizedBwConfig::new()
+                .duration(duration)
+                .step(step)
+                .exact_duration(false)
+                .total_duration(),
+            Some(Duration::from_millis(200))
+        );
+
+        // infinite or unknown in advance
+        assert_eq!(repeated.clone().forever().total_duration(), None);
+        assert_eq!(static_bw.clone().forever().total_duration(), None);
+        let tagged = TaggedBwConfig::new().inner(Box::new(static_bw.clone()));
+        assert_eq!(tagged.total_duration(), None);
+        assert_eq!(
+            RepeatedBwPatternConfig::new()
+                .pattern(vec![Box::new(static_bw.clone()), Box::new(tagged)])
+                .count(1)
+                .total_duration(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_repeated_bw_many_empty_sub_models() {
+        // a long run of sub-models producing nothing must not overflow the stack
+        let mut pattern: Vec<Box<dyn BwTraceConfig>> = (0..100_000)
+            .map(|_| {
+                Box::new(StaticBwConfig::new().duration(Duration::ZERO)) as Box<dyn BwTraceConfig>
+            })
+            .collect();
+        pattern.push(Box::new(StaticBwConfig::new()));
+        let mut model = RepeatedBwPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            [(Bandwidth::from_mbps(12), Duration::from_secs(1)); 2]
+        );
+
+        // a pattern repeated forever ends if none of its sub-models produces anything
+        let mut model = StaticBwConfig::new()
+            .duration(Duration::ZERO)
+            .forever()
+            .build();
+        assert_eq!(model.next_bw(), None);
+        assert_eq!(model.next_bw(), None);
+        model.reset();
+        assert_eq!(model.next_bw(), None);
+        let mut model = RandomPatternBwConfig::new()
+            .pattern(vec![
+                (
+                    1.0,
+                    Box::new(StaticBwConfig::new().duration(Duration::ZERO)),
+                ),
+                (0.0, Box::new(StaticBwConfig::new())),
+            ])
+            .build();
+        assert_eq!(model.next_bw(), None);
+    }
+
+    #[test]
+    fn test_random_phase_bw() {
+        let trace = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(100),
+            vec![
+                Bandwidth::from_mbps(1),
+                Bandwidth::from_mbps(2),
+                Bandwidth::from_mbps(3),
+                Bandwidth::from_mbps(4),
+            ],
+        )]);
+        let period = Duration::from_millis(400);
+        let config = RandomPhaseBwConfig::new()
+            .inner(Box::new(trace.clone()))
+            .period(period);
+        // the bandwidth of the looped trace at `t`
+        let bw_at = |t: Duration| Bandwidth::from_mbps((t.as_millis() % 400 / 100) as u64 + 1);
+
+        let mut offsets = vec![];
+        for seed in 0..8 {
+            let config = config.clone().seed(seed);
+            let mut model = config.clone().build();
+            assert!(model.offset < period);
+            // reproducible with the same seed
+            let mut again = config.build();
+            assert_eq!(again.offset, model.offset);
+            offsets.push(model.offset);
+
+            // the looped trace starting from the offset
+            let mut now = model.offset;
+            let mut total = Duration::ZERO;
+            for _ in 0..10 {
+                let (bw, duration) = model.next_bw().unwrap();
+                assert_eq!(again.next_bw(), Some((bw, duration)));
+                assert_eq!(bw, bw_at(now));
+                assert_eq!(
+                    duration,
+                    Duration::from_millis(100)
+                        - Duration::from_nanos((now.as_nanos() % 100_000_000) as u64)
+                );
+                now += duration;
+                total += duration;
+            }
+            assert_eq!(
+                total,
+                Duration::from_secs(1)
+                    - Duration::from_nanos((model.offset.as_nanos() % 100_000_000) as u64)
+            );
+        }
+        // different seeds give different offsets
+        offsets.sort();
+        offsets.dedup();
+        assert!(offsets.len() > 1);
+
+        // no offset with a zero period, and nothing without an inner model
+        let mut model = RandomPhaseBwConfig::new()
+            .inner(Box::new(trace.clone()))
+            .seed(1)
+            .build();
+        assert_eq!(model.offset, Duration::ZERO);
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(1), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            RandomPhaseBwConfig::new().period(period).build().next_bw(),
+            None
+        );
+
+        // nothing with an inner model producing nothing, instead of hanging
+        let zero = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(1))
+            .duration(Duration::ZERO);
+        let empty = RepeatedBwPatternConfig::new().pattern(vec![Box::new(zero.clone())]);
+        for inner in [
+            Box::new(zero) as Box<dyn BwTraceConfig>,
+            Box::new(empty),
+            Box::new(RepeatedBwPatternConfig::new()),
+        ] {
+            let mut model = RandomPhaseBwConfig::new()
+                .inner(inner)
+                .period(period)
+                .seed(1)
+                .build();
+            assert_eq!(model.next_bw(), None);
+        }
+
+        // the tag of the cut segment is kept
+        let mut model = RandomPhaseBwConfig::new()
+            .inner(Box::new(
+                TaggedBwConfig::new()
+                    .inner(Box::new(trace.clone()))
+                    .tag("t"),
+            ))
+            .period(period)
+            .seed(3)
+            .build();
+        assert_eq!(model.next_bw_tagged().unwrap().2, Some("t"));
+
+        assert!(config.is_infinite());
+        assert!(!RandomPhaseBwConfig::new().is_infinite());
+
+        #[cfg(feature = "serde")]
+        {
+            let config: Box<dyn BwTraceConfig> = Box::new(config.seed(7));
+            let des: Box<dyn BwTraceConfig> =
+                serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+            assert!(des.output_eq(
+                config.as_ref(),
+                Duration::from_millis(1),
+                Duration::from_secs(2)
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_model_serde() {
+        let a = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
             Box::new(
                 StaticBwConfig::new()
                     .bw(Bandwidth::from_mbps(24))
@@ -410,61 +3167,1503 @@ mod test {
         let ser =
             Box::new(RepeatedBwPatternConfig::new().pattern(a).count(2)) as Box<dyn BwTraceConfig>;
         let ser_str = serde_json::to_string(&ser).unwrap();
-        #[cfg(not(feature = "human"))]
+        #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
         let des_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+        #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+        let des_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":1000000000}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":1000000000}}],\"count\":2}}";
         #[cfg(feature = "human")]
         let des_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":\"12Mbps\",\"duration\":\"1s\"}},{\"StaticBwConfig\":{\"bw\":\"24Mbps\",\"duration\":\"1s\"}}],\"count\":2}}";
         assert_eq!(ser_str, des_str);
         let des: Box<dyn BwTraceConfig> = serde_json::from_str(des_str).unwrap();
         let mut model = des.into_model();
         assert_eq!(
-            model.next_bw(),
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+        );
+    }
+
+    #[test]
+    fn test_forever() {
+        let mut normal_bw = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .std_dev(Bandwidth::from_mbps(1))
+            .duration(Duration::from_millis(200))
+            .step(Duration::from_millis(100))
+            .seed(42)
+            .build();
+        assert_eq!(
+            normal_bw.next_bw(),
+            Some((Bandwidth::from_bps(12069428), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            normal_bw.next_bw(),
+            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+        );
+        assert_eq!(normal_bw.next_bw(), None);
+        let normal_bw_config = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .std_dev(Bandwidth::from_mbps(1))
+            .duration(Duration::from_millis(200))
+            .step(Duration::from_millis(100))
+            .seed(42);
+        let normal_bw_repeated = normal_bw_config.forever();
+        let mut model = Box::new(normal_bw_repeated).into_model();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_bps(12069428), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_bps(12069428), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+        );
+    }
+
+    #[test]
+    fn test_random_outage_bw() {
+        let inner = TraceBwConfig::new().pattern(vec![
+            (
+                Duration::from_millis(250),
+                vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)],
+            ),
+            (Duration::from_millis(500), vec![Bandwidth::from_mbps(36)]),
+        ]);
+        let config = RandomOutageBwConfig::new()
+            .inner(Box::new(inner.clone()))
+            .drop_prob(0.3)
+            .drop_dur(Duration::from_millis(50))
+            .step(Duration::from_millis(100))
+            .seed(42);
+        let mut outage_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| outage_bw.next_bw()).collect::<Vec<_>>();
+        let ms = Duration::from_millis;
+        // outages start at 400ms, 800ms and 900ms
+        assert_eq!(
+            trace,
+            vec![
+                (Bandwidth::from_mbps(12), ms(100)),
+                (Bandwidth::from_mbps(12), ms(100)),
+                (Bandwidth::from_mbps(12), ms(50)),
+                (Bandwidth::from_mbps(24), ms(50)),
+                (Bandwidth::from_mbps(24), ms(100)),
+                (Bandwidth::ZERO, ms(50)),
+                (Bandwidth::from_mbps(24), ms(50)),
+                (Bandwidth::from_mbps(36), ms(100)),
+                (Bandwidth::from_mbps(36), ms(100)),
+                (Bandwidth::from_mbps(36), ms(100)),
+                (Bandwidth::ZERO, ms(50)),
+                (Bandwidth::from_mbps(36), ms(50)),
+                (Bandwidth::ZERO, ms(50)),
+                (Bandwidth::from_mbps(36), ms(50)),
+            ]
+        );
+        // the same seed gives the same drops
+        let mut outage_bw = Box::new(config.clone()).into_model();
+        assert_eq!(
+            std::iter::from_fn(|| outage_bw.next_bw()).collect::<Vec<_>>(),
+            trace
+        );
+
+        #[cfg(feature = "human")]
+        {
+            let ser = Box::new(config.clone()) as Box<dyn BwTraceConfig>;
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            assert_eq!(
+                ser_str,
+                "{\"RandomOutageBwConfig\":{\"inner\":{\"TraceBwConfig\":{\"pattern\":[[\"250ms\",[\"12Mbps\",\"24Mbps\"]],[\"500ms\",[\"36Mbps\"]]]}},\"drop_prob\":0.3,\"drop_dur\":\"50ms\",\"step\":\"100ms\",\"seed\":42}}"
+            );
+            let mut outage_bw = serde_json::from_str::<Box<dyn BwTraceConfig>>(&ser_str)
+                .unwrap()
+                .into_model();
+            assert_eq!(
+                std::iter::from_fn(|| outage_bw.next_bw()).collect::<Vec<_>>(),
+                trace
+            );
+        }
+
+        // a drop probability out of [0, 1] is rejected
+        for drop_prob in [f64::NAN, -0.1, 1.5] {
+            assert!(matches!(
+                config.clone().drop_prob(drop_prob).try_build(),
+                Err(ModelBuildError::InvalidParameter {
+                    name: "drop_prob",
+                    ..
+                })
+            ));
+        }
+
+        // no drop leaves the inner trace unchanged
+        let mut outage_bw = config.drop_prob(0.0).build();
+        let mut inner_bw = inner.build();
+        assert_eq!(
+            std::iter::from_fn(|| outage_bw.next_bw()).collect::<Vec<_>>(),
+            std::iter::from_fn(|| inner_bw.next_bw()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_window_max_bw() {
+        // 12, 13, 14, 15, 16Mbps in each 500ms cycle
+        let sawtooth = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(12))
+            .top(Bandwidth::from_mbps(16))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(100))
+            .interval(Duration::from_millis(500))
+            .duty_ratio(0.8);
+        let config = WindowMaxBwConfig::new()
+            .inner(Box::new(sawtooth.clone()))
+            .window(Duration::from_millis(200));
+        let mut window_max_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| window_max_bw.next_bw()).collect::<Vec<_>>();
+        // the peak of 16Mbps is held for 200ms after the drop
+        assert_eq!(
+            trace,
+            [12, 13, 14, 15, 16, 16, 16, 14, 15, 16]
+                .into_iter()
+                .map(|mbps| (Bandwidth::from_mbps(mbps), Duration::from_millis(100)))
+                .collect::<Vec<_>>()
+        );
+
+        #[cfg(feature = "human")]
+        {
+            let ser = Box::new(config.clone()) as Box<dyn BwTraceConfig>;
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            assert!(ser_str.starts_with("{\"WindowMaxBwConfig\":{\"inner\":{\"SawtoothBwConfig\""));
+            assert!(ser_str.ends_with("\"window\":\"200ms\"}}"));
+            let mut window_max_bw = serde_json::from_str::<Box<dyn BwTraceConfig>>(&ser_str)
+                .unwrap()
+                .into_model();
+            assert_eq!(
+                std::iter::from_fn(|| window_max_bw.next_bw()).collect::<Vec<_>>(),
+                trace
+            );
+        }
+
+        // a peak is split where it leaves a window not aligned with the segments
+        let mut window_max_bw = config.window(Duration::from_millis(250)).build();
+        let ms = Duration::from_millis;
+        assert_eq!(
+            std::iter::from_fn(|| window_max_bw.next_bw()).collect::<Vec<_>>(),
+            vec![
+                (Bandwidth::from_mbps(12), ms(100)),
+                (Bandwidth::from_mbps(13), ms(100)),
+                (Bandwidth::from_mbps(14), ms(100)),
+                (Bandwidth::from_mbps(15), ms(100)),
+                (Bandwidth::from_mbps(16), ms(100)),
+                (Bandwidth::from_mbps(16), ms(100)),
+                (Bandwidth::from_mbps(16), ms(100)),
+                (Bandwidth::from_mbps(16), ms(50)),
+                (Bandwidth::from_mbps(14), ms(50)),
+                (Bandwidth::from_mbps(15), ms(100)),
+                (Bandwidth::from_mbps(16), ms(100)),
+            ]
+        );
+
+        // zero window leaves the inner trace unchanged
+        let mut window_max_bw = WindowMaxBwConfig::new()
+            .inner(Box::new(sawtooth.clone()))
+            .window(Duration::ZERO)
+            .build();
+        let mut sawtooth_bw = sawtooth.build();
+        assert_eq!(
+            std::iter::from_fn(|| window_max_bw.next_bw()).collect::<Vec<_>>(),
+            std::iter::from_fn(|| sawtooth_bw.next_bw()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_trace_error() {
+        let err: TraceError =
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no such file").into();
+        assert!(matches!(err, TraceError::Io(_)));
+        assert_eq!(err.to_string(), "I/O error: no such file");
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err = TraceError::Validation("bad".to_string());
+        assert_eq!(err.to_string(), "invalid configuration: bad");
+        assert!(std::error::Error::source(&err).is_none());
+
+        #[cfg(feature = "mahimahi")]
+        {
+            let err: TraceError = load_mahimahi_trace(vec![2, 1], None).err().unwrap().into();
+            assert_eq!(
+                err.to_string(),
+                "invalid mahimahi trace: timestamps must be monotonically nondecreasing"
+            );
+            assert!(std::error::Error::source(&err).is_some());
+        }
+
+        #[cfg(feature = "loader")]
+        {
+            let path = std::path::PathBuf::from("trace.csv");
+            let err: TraceError = loader::ConfigError::UnknownFormat(path.clone()).into();
+            assert!(matches!(err, TraceError::Config(_)));
+            assert_eq!(err.to_string(), "unknown format of trace.csv");
+
+            let err = load_bw_config(&path).err().unwrap();
+            assert!(matches!(
+                err,
+                TraceError::Config(loader::ConfigError::UnknownFormat(_))
+            ));
+        }
+
+        #[cfg(feature = "bw-model")]
+        {
+            fn build(config: SquareBwConfig) -> Result<model::SquareBw, TraceError> {
+                Ok(config.try_build()?)
+            }
+            assert!(build(SquareBwConfig::new()).is_ok());
+            let err = build(SquareBwConfig::new().step(Duration::ZERO))
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err,
+                TraceError::Build(ModelBuildError::ZeroDuration { .. })
+            ));
+            assert_eq!(
+                err.to_string(),
+                "failed to build the model: SquareBw: step must be positive"
+            );
+            assert!(std::error::Error::source(&err).is_some());
+        }
+    }
+
+    #[test]
+    fn test_output_eq() {
+        let step = Duration::from_millis(100);
+        let total = Duration::from_secs(3);
+        let static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(2));
+        // structurally different but output-identical
+        let trace_bw = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(500),
+            vec![Bandwidth::from_mbps(12); 4],
+        )]);
+        let repeated_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .repeat(2);
+        assert!(static_bw.output_eq(&trace_bw, step, total));
+        assert!(trace_bw.output_eq(&repeated_bw, step, total));
+        assert!(static_bw.output_eq(&static_bw, step, total));
+        // the configurations are not consumed
+        assert!(static_bw.output_eq(&repeated_bw, step, total));
+
+        // different bandwidth
+        let other_bw = TraceBwConfig::new().pattern(vec![(
+            Duration::from_millis(500),
+            vec![
+                Bandwidth::from_mbps(12),
+                Bandwidth::from_mbps(12),
+                Bandwidth::from_mbps(24),
+                Bandwidth::from_mbps(12),
+            ],
+        )]);
+        assert!(!static_bw.output_eq(&other_bw, step, total));
+        // the difference is not sampled
+        assert!(static_bw.output_eq(&other_bw, step, Duration::from_secs(1)));
+        // different length
+        let longer_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_millis(2500));
+        assert!(!static_bw.output_eq(&longer_bw, step, total));
+        assert!(static_bw.output_eq(&longer_bw, Duration::ZERO, total));
+    }
+
+    #[test]
+    fn test_spline_bw() {
+        let points = vec![
+            (Duration::ZERO, Bandwidth::from_mbps(12)),
+            (Duration::from_secs(1), Bandwidth::from_mbps(24)),
+            (Duration::from_secs(3), Bandwidth::from_mbps(6)),
+            (Duration::from_secs(4), Bandwidth::from_mbps(18)),
+        ];
+        let config = SplineBwConfig::new()
+            .points(points.clone())
+            .step(Duration::from_millis(10));
+        let mut spline_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| spline_bw.next_bw()).collect::<Vec<_>>();
+        assert_eq!(trace.len(), 400);
+        assert!(trace
+            .iter()
+            .all(|(_, duration)| *duration == Duration::from_millis(10)));
+        // the spline passes through the control points
+        for (time, bw) in &points[..3] {
+            let sampled = trace[(time.as_millis() / 10) as usize].0;
+            assert!(sampled.as_bps().abs_diff(bw.as_bps()) <= 1);
+        }
+        // and is smooth in between, i.e. no jump between consecutive samples
+        for w in trace.windows(2) {
+            assert!(w[0].0.as_bps().abs_diff(w[1].0.as_bps()) < 300_000);
+        }
+        // the peak is not always at the control points
+        let max = trace.iter().map(|(bw, _)| *bw).max().unwrap();
+        assert!(max > Bandwidth::from_mbps(24));
+
+        // negative values are clamped to zero
+        let mut spline_bw = SplineBwConfig::new()
+            .points(vec![
+                (Duration::ZERO, Bandwidth::from_mbps(1)),
+                (Duration::from_secs(1), Bandwidth::ZERO),
+                (Duration::from_secs(2), Bandwidth::from_mbps(10)),
+            ])
+            .step(Duration::from_millis(500))
+            .build();
+        assert_eq!(
+            spline_bw.next_bw(),
+            Some((Bandwidth::from_mbps(1), Duration::from_millis(500)))
+        );
+        assert_eq!(
+            spline_bw.next_bw(),
+            Some((Bandwidth::ZERO, Duration::from_millis(500)))
+        );
+
+        // a single point is held
+        let mut spline_bw = SplineBwConfig::new()
+            .points(vec![(Duration::from_secs(1), Bandwidth::from_mbps(12))])
+            .duration(Duration::from_millis(200))
+            .build();
+        assert_eq!(
+            spline_bw.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_millis(100)))
+        );
+        assert_eq!(
+            spline_bw.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_millis(100)))
+        );
+        assert_eq!(spline_bw.next_bw(), None);
+
+        #[cfg(feature = "human")]
+        {
+            let ser = Box::new(config) as Box<dyn BwTraceConfig>;
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            assert_eq!(
+                ser_str,
+                "{\"SplineBwConfig\":{\"points\":[[\"0s\",\"12Mbps\"],[\"1s\",\"24Mbps\"],[\"3s\",\"6Mbps\"],[\"4s\",\"18Mbps\"]],\"step\":\"10ms\"}}"
+            );
+            let mut spline_bw = serde_json::from_str::<Box<dyn BwTraceConfig>>(&ser_str)
+                .unwrap()
+                .into_model();
+            assert_eq!(
+                std::iter::from_fn(|| spline_bw.next_bw()).collect::<Vec<_>>(),
+                trace
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "SplineBw: points must be sorted by strictly increasing time")]
+    fn test_spline_bw_unsorted_points() {
+        SplineBwConfig::new()
+            .points(vec![
+                (Duration::from_secs(1), Bandwidth::from_mbps(12)),
+                (Duration::from_secs(1), Bandwidth::from_mbps(24)),
+            ])
+            .build();
+    }
+
+    #[test]
+    fn test_registered_bw_config_tags() {
+        let tags = crate::model::registered_bw_config_tags();
+        for tag in [
+            "StaticBwConfig",
+            "NormalizedBwConfig",
+            "SawtoothBwConfig",
+            "RepeatedBwPatternConfig",
+            "TraceBwConfig",
+            "SequenceBwConfig",
+            "TaggedBwConfig",
+            "RandomOutageBwConfig",
+            "EmpiricalBwConfig",
+            "WindowMaxBwConfig",
+            "SplineBwConfig",
+            "SineBwConfig",
+            "SquareBwConfig",
+            "ScheduleBwConfig",
+            "FloorPlusVariableBwConfig",
+            "ScaledBwConfig",
+            "ClampedBwConfig",
+            "RandomPhaseBwConfig",
+            "MarkovBwConfig",
+            "EnvBwConfig",
+            "HysteresisBwConfig",
+            "RampBwConfig",
+            "RandomPatternBwConfig",
+            "ReverseBwConfig",
+            "PoissonBwConfig",
+            "SumBwConfig",
+            "WeibullBwConfig",
+            "GammaBwConfig",
+            "SpliceBwConfig",
+            "ExponentialDwellBwConfig",
+            "DelayedStartBwConfig",
+            "ParetoBwConfig",
+            "StepBwConfig",
+            "QuantizedBwConfig",
+            #[cfg(feature = "loss-model")]
+            "LossAsGapsBwConfig",
+        ] {
+            assert!(tags.contains(&tag), "{} is not registered", tag);
+        }
+
+        // the registered tags are the ones used by the configuration files
+        #[cfg(feature = "serde")]
+        for tag in tags {
+            let content = format!("{{\"{}\":{{}}}}", tag);
+            assert!(serde_json::from_str::<Box<dyn BwTraceConfig>>(&content).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_sine_bw_model() {
+        let mut sine_bw = SineBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .amplitude(Bandwidth::from_mbps(4))
+            .period(Duration::from_millis(400))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(100))
+            .build();
+        for _ in 0..2 {
+            for mbps in [12, 16, 12, 8] {
+                assert_eq!(
+                    sine_bw.next_bw(),
+                    Some((Bandwidth::from_mbps(mbps), Duration::from_millis(100)))
+                );
+            }
+        }
+        let config = SineBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .amplitude(Bandwidth::from_mbps(4))
+            .period(Duration::from_millis(400))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(100))
+            .std_dev(Bandwidth::from_mbps(5))
+            .upper_noise_bound(Bandwidth::from_mbps(1))
+            .lower_noise_bound(Bandwidth::from_kbps(500));
+        let mut sine_bw = config.clone().build();
+        let expected = [12347140, 16664691, 13000000, 7500000]
+            .into_iter()
+            .map(|bps| Some((Bandwidth::from_bps(bps), Duration::from_millis(100))))
+            .collect::<Vec<_>>();
+        for bw in &expected {
+            assert_eq!(sine_bw.next_bw(), *bw);
+        }
+        sine_bw.reset_with_seed(42);
+        for bw in &expected {
+            assert_eq!(sine_bw.next_bw(), *bw);
+        }
+        // the default RNG is a `StdRng` seeded by `seed`
+        let mut sine_bw = config
+            .seed(7)
+            .build_with_rng(<rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(42));
+        for bw in &expected {
+            assert_eq!(sine_bw.next_bw(), *bw);
+        }
+        assert_eq!(sine_bw.seed, 7);
+
+        // negative values are clamped to zero
+        let mut sine_bw = SineBwConfig::new()
+            .mean(Bandwidth::from_mbps(4))
+            .amplitude(Bandwidth::from_mbps(8))
+            .period(Duration::from_millis(400))
+            .duration(Duration::from_millis(400))
+            .step(Duration::from_millis(100))
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| sine_bw.next_bw())
+                .map(|(bw, _)| bw)
+                .collect::<Vec<_>>(),
+            [4, 12, 4, 0].map(Bandwidth::from_mbps)
+        );
+    }
+
+    #[test]
+    fn test_square_bw_model() {
+        // the period is not an integer multiple of the step
+        let mut square_bw = SquareBwConfig::new()
+            .low(Bandwidth::from_mbps(2))
+            .high(Bandwidth::from_mbps(10))
+            .period(Duration::from_millis(1000))
+            .duty_ratio(0.35)
+            .step(Duration::from_millis(300))
+            .duration(Duration::from_millis(1300))
+            .build();
+        let high = Bandwidth::from_mbps(10);
+        let low = Bandwidth::from_mbps(2);
+        assert_eq!(
+            std::iter::from_fn(|| square_bw.next_bw()).collect::<Vec<_>>(),
+            vec![
+                (high, Duration::from_millis(300)),
+                (high, Duration::from_millis(50)),
+                (low, Duration::from_millis(300)),
+                (low, Duration::from_millis(300)),
+                (low, Duration::from_millis(50)),
+                (high, Duration::from_millis(300)),
+            ]
+        );
+
+        // the transition lands exactly at the duty boundary
+        let mut square_bw = SquareBwConfig::new()
+            .period(Duration::from_millis(7))
+            .duty_ratio(3.0 / 7.0)
+            .step(Duration::from_millis(2))
+            .duration(Duration::from_millis(70))
+            .build();
+        let mut elapsed = Duration::ZERO;
+        while let Some((bw, duration)) = square_bw.next_bw() {
+            let position = elapsed.as_nanos() % 7_000_000;
+            if bw == Bandwidth::from_mbps(12) {
+                assert!(position + duration.as_nanos() <= 3_000_000);
+            } else {
+                assert_eq!(bw, Bandwidth::ZERO);
+                assert!(position >= 3_000_000);
+            }
+            elapsed += duration;
+        }
+        assert_eq!(elapsed, Duration::from_millis(70));
+
+        // the bandwidth stays at one level for duty ratio of 0 or 1
+        for (duty_ratio, bw) in [(0.0, Bandwidth::ZERO), (1.0, Bandwidth::from_mbps(12))] {
+            let mut square_bw = SquareBwConfig::new()
+                .duty_ratio(duty_ratio)
+                .step(Duration::from_millis(300))
+                .build();
+            assert!(std::iter::from_fn(|| square_bw.next_bw()).all(|(b, _)| b == bw));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "SquareBw: duty_ratio must be between 0 and 1")]
+    fn test_square_bw_invalid_duty_ratio() {
+        SquareBwConfig::new().duty_ratio(1.5).build();
+    }
+
+    #[test]
+    fn test_schedule_bw() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+        use std::time::UNIX_EPOCH;
+
+        // seconds since midnight of the mocked clock, on the second day since epoch
+        let now = Arc::new(AtomicU64::new(0));
+        let clock = {
+            let now = now.clone();
+            move || UNIX_EPOCH + Duration::from_secs(86400 + now.load(Ordering::Relaxed))
+        };
+        let mut schedule_bw = ScheduleBwConfig::new()
+            .slots(vec![
+                (8 * 60, 12 * 60, Bandwidth::from_mbps(6)),
+                (18 * 60, 23 * 60, Bandwidth::from_mbps(2)),
+                // wraps around midnight
+                (23 * 60, 60, Bandwidth::from_mbps(24)),
+                // overlapped by the first slot
+                (10 * 60, 11 * 60, Bandwidth::from_mbps(1)),
+            ])
+            .default_bw(Bandwidth::from_mbps(12))
+            .build_with_clock(clock);
+
+        let hour = |h: u64| h * 3600;
+        for (time, bw, duration) in [
+            (hour(0), 24, hour(1)),
+            (hour(1), 12, hour(7)),
+            (hour(7) + 1800, 12, 1800),
+            (hour(8), 6, hour(2)),
+            (hour(10) + 59, 6, hour(1) - 59),
+            (hour(11), 6, hour(1)),
+            (hour(12), 12, hour(6)),
+            (hour(20), 2, hour(3)),
+            (hour(23) + 1, 24, hour(1) - 1),
+        ] {
+            now.store(time, Ordering::Relaxed);
+            assert_eq!(
+                schedule_bw.next_bw(),
+                Some((Bandwidth::from_mbps(bw), Duration::from_secs(duration))),
+                "at {}s of day",
+                time
+            );
+        }
+
+        // the default bandwidth lasts until midnight without slots
+        let mut schedule_bw = ScheduleBwConfig::new()
+            .build_with_clock(|| UNIX_EPOCH + Duration::from_millis(86_399_500));
+        assert_eq!(
+            schedule_bw.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_millis(500)))
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let config = ScheduleBwConfig::new().slots(vec![(480, 720, Bandwidth::from_mbps(6))]);
+            let ser_str = serde_json::to_string(&config).unwrap();
+            #[cfg(feature = "human")]
+            assert_eq!(ser_str, "{\"slots\":[[480,720,\"6Mbps\"]]}");
+            #[cfg(not(feature = "human"))]
+            assert_eq!(
+                ser_str,
+                "{\"slots\":[[480,720,{\"gbps\":0,\"bps\":6000000}]]}"
+            );
+            let des: ScheduleBwConfig = serde_json::from_str(&ser_str).unwrap();
+            assert_eq!(des.slots, config.slots);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ScheduleBw: the minutes of slots must be within a day (0 to 1440)")]
+    fn test_schedule_bw_invalid_slot() {
+        ScheduleBwConfig::new()
+            .slots(vec![(0, 1441, Bandwidth::from_mbps(1))])
+            .build();
+    }
+
+    #[test]
+    fn test_iter_bw() {
+        let mut iter_bw = IterBw::new(
+            (0..5).map(|i| (Bandwidth::from_mbps(12 * i), Duration::from_millis(100 * i))),
+        );
+        for i in 0..5 {
+            assert_eq!(
+                iter_bw.next_bw(),
+                Some((Bandwidth::from_mbps(12 * i), Duration::from_millis(100 * i)))
+            );
+        }
+        assert_eq!(iter_bw.next_bw(), None);
+        assert_eq!(iter_bw.next_bw(), None);
+    }
+
+    #[test]
+    fn test_step_longer_than_duration() {
+        let config = NormalizedBwConfig::new()
+            .mean(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_secs(2));
+        assert_eq!(
+            config.lint(),
+            ["NormalizedBw: step 2s is longer than duration 1s, the trace will be a single segment of 1s"]
+        );
+        let mut normal_bw = config.build();
+        assert_eq!(
+            normal_bw.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+        );
+        assert_eq!(normal_bw.next_bw(), None);
+
+        let config = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(12))
+            .top(Bandwidth::from_mbps(16))
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_secs(2));
+        assert_eq!(
+            config.lint(),
+            ["SawtoothBw: step 2s is longer than duration 1s, the trace will be a single segment of 1s"]
+        );
+        let mut sawtooth_bw = config.build();
+        assert_eq!(
+            sawtooth_bw.next_bw(),
             Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
         );
+        assert_eq!(sawtooth_bw.next_bw(), None);
+
+        assert!(NormalizedBwConfig::new().lint().is_empty());
+        assert!(SawtoothBwConfig::new()
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_secs(1))
+            .lint()
+            .is_empty());
     }
 
     #[test]
-    fn test_forever() {
+    fn test_repeated_bw_infinite_sub_config() {
+        let finite = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1));
+        let infinite = RepeatedBwPatternConfig::new()
+            .pattern(vec![Box::new(finite.clone())])
+            .count(0);
+        assert!(!finite.is_infinite());
+        assert!(infinite.is_infinite());
+        assert!(finite.clone().forever().is_infinite());
+        assert!(!finite.clone().repeat(2).is_infinite());
+        assert!(!RepeatedBwPatternConfig::new().is_infinite());
+        assert!(ScheduleBwConfig::new().is_infinite());
+        assert!(TaggedBwConfig::new()
+            .inner(Box::new(infinite.clone()))
+            .is_infinite());
+        assert!(!TaggedBwConfig::new().is_infinite());
+
+        let config = RepeatedBwPatternConfig::new()
+            .pattern(vec![Box::new(infinite.clone())])
+            .count(2);
+        assert!(config.is_infinite());
+        assert_eq!(
+            config.lint(),
+            ["RepeatedBwPattern: sub-config 0 never ends, so the cycles after the first one are unreachable"]
+        );
+        // the outer pattern never gets to its second cycle, nor the sub-configs after the infinite one
+        let config = RepeatedBwPatternConfig::new()
+            .pattern(vec![
+                Box::new(finite.clone().bw(Bandwidth::from_mbps(24))),
+                Box::new(
+                    TaggedBwConfig::new()
+                        .tag("infinite")
+                        .inner(Box::new(infinite)),
+                ),
+                Box::new(finite.clone()),
+            ])
+            .count(2);
+        assert_eq!(
+            config.lint(),
+            [
+                "RepeatedBwPattern: sub-config 1 never ends, so the sub-configs after it are unreachable",
+                "RepeatedBwPattern: sub-config 1 never ends, so the cycles after the first one are unreachable"
+            ]
+        );
+        let mut model = config.build();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(24), Duration::from_secs(1)))
+        );
+        for _ in 0..10 {
+            assert_eq!(
+                model.next_bw_tagged(),
+                Some((
+                    Bandwidth::from_mbps(12),
+                    Duration::from_secs(1),
+                    Some("infinite")
+                ))
+            );
+        }
+
+        assert!(RepeatedBwPatternConfig::new()
+            .pattern(vec![Box::new(finite.clone())])
+            .count(2)
+            .lint()
+            .is_empty());
+        // a single cycle ending with an infinite sub-config is fine
+        assert!(RepeatedBwPatternConfig::new()
+            .pattern(vec![Box::new(finite.clone()), Box::new(finite.forever())])
+            .count(1)
+            .lint()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_empirical_bw() {
+        let config = EmpiricalBwConfig::new()
+            .cdf(vec![
+                (Bandwidth::from_mbps(10), 0.0),
+                (Bandwidth::from_mbps(20), 0.5),
+                (Bandwidth::from_mbps(30), 1.0),
+            ])
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(1))
+            .seed(42);
+        let mut empirical_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| empirical_bw.next_bw()).collect::<Vec<_>>();
+        assert_eq!(trace.len(), 1000);
+        assert!(trace.iter().all(|(bw, duration)| {
+            *bw >= Bandwidth::from_mbps(10)
+                && *bw <= Bandwidth::from_mbps(30)
+                && *duration == Duration::from_millis(1)
+        }));
+        // about half of the samples are below the median
+        let below = trace
+            .iter()
+            .filter(|(bw, _)| *bw < Bandwidth::from_mbps(20))
+            .count();
+        assert!((450..=550).contains(&below));
+
+        // the same seed gives the same trace
+        let mut empirical_bw = Box::new(config.clone()).into_model();
+        assert_eq!(
+            std::iter::from_fn(|| empirical_bw.next_bw()).collect::<Vec<_>>(),
+            trace
+        );
+        let mut empirical_bw = config.seed(43).build();
+        assert_ne!(
+            std::iter::from_fn(|| empirical_bw.next_bw()).collect::<Vec<_>>(),
+            trace
+        );
+
+        // a step CDF only yields the values on the CDF
+        let config = EmpiricalBwConfig::new()
+            .cdf(vec![
+                (Bandwidth::from_mbps(12), 0.5),
+                (Bandwidth::from_mbps(24), 0.5),
+                (Bandwidth::from_mbps(24), 1.0),
+            ])
+            .duration(Duration::from_millis(100))
+            .step(Duration::from_millis(1));
+        let mut empirical_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| empirical_bw.next_bw()).collect::<Vec<_>>();
+        assert!(trace
+            .iter()
+            .all(|(bw, _)| *bw == Bandwidth::from_mbps(12) || *bw == Bandwidth::from_mbps(24)));
+        assert!(trace.iter().any(|(bw, _)| *bw == Bandwidth::from_mbps(12)));
+        assert!(trace.iter().any(|(bw, _)| *bw == Bandwidth::from_mbps(24)));
+
+        #[cfg(feature = "human")]
+        {
+            let ser = Box::new(config) as Box<dyn BwTraceConfig>;
+            let ser_str = serde_json::to_string(&ser).unwrap();
+            assert_eq!(
+                ser_str,
+                "{\"EmpiricalBwConfig\":{\"cdf\":[[\"12Mbps\",0.5],[\"24Mbps\",0.5],[\"24Mbps\",1.0]],\"duration\":\"100ms\",\"step\":\"1ms\"}}"
+            );
+            let mut empirical_bw = serde_json::from_str::<Box<dyn BwTraceConfig>>(&ser_str)
+                .unwrap()
+                .into_model();
+            assert_eq!(
+                std::iter::from_fn(|| empirical_bw.next_bw()).collect::<Vec<_>>(),
+                trace
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "EmpiricalBw: cdf must end at cumulative probability 1.0")]
+    fn test_empirical_bw_invalid_cdf() {
+        EmpiricalBwConfig::new()
+            .cdf(vec![
+                (Bandwidth::from_mbps(12), 0.5),
+                (Bandwidth::from_mbps(24), 0.9),
+            ])
+            .build();
+    }
+
+    #[test]
+    fn test_markov_bw() {
+        use rand::SeedableRng;
+
+        let states = vec![
+            Bandwidth::from_mbps(1),
+            Bandwidth::from_mbps(2),
+            Bandwidth::from_mbps(3),
+        ];
+        let state_of = |bw: Bandwidth| states.iter().position(|state| *state == bw).unwrap();
+
+        // a deterministic cycle 0 -> 2 -> 1 -> 0, starting from state 1
+        let mut markov_bw = MarkovBwConfig::new()
+            .states(states.clone())
+            .transitions(vec![
+                vec![0.0, 0.0, 1.0],
+                vec![1.0, 0.0, 0.0],
+                vec![0.0, 1.0, 0.0],
+            ])
+            .initial_state(1)
+            .duration(Duration::from_millis(250))
+            .step(Duration::from_millis(50))
+            .build();
+        let trace = std::iter::from_fn(|| markov_bw.next_bw()).collect::<Vec<_>>();
+        assert_eq!(
+            trace
+                .iter()
+                .map(|(bw, _)| state_of(*bw))
+                .collect::<Vec<_>>(),
+            [1, 0, 2, 1, 0]
+        );
+        assert!(trace
+            .iter()
+            .all(|(_, duration)| *duration == Duration::from_millis(50)));
+
+        // a random chain only takes the transitions with a positive probability
+        let config = MarkovBwConfig::new()
+            .states(states.clone())
+            .transitions(vec![
+                vec![0.0, 1.0, 0.0],
+                vec![0.5, 0.0, 0.5],
+                vec![0.0, 0.3, 0.7],
+            ])
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(1))
+            .seed(42);
+        let mut markov_bw = config.clone().build();
+        let trace = std::iter::from_fn(|| markov_bw.next_bw())
+            .map(|(bw, _)| state_of(bw))
+            .collect::<Vec<_>>();
+        assert_eq!(trace.len(), 1000);
+        assert_eq!(trace[..2], [0, 1]);
+        for pair in trace.windows(2) {
+            assert!(
+                matches!(pair, [0, 1] | [1, 0] | [1, 2] | [2, 1] | [2, 2]),
+                "{:?}",
+                pair
+            );
+        }
+        // every state is visited
+        assert!((0..3).all(|state| trace.contains(&state)));
+
+        // the same seed gives the same sequence, also with build_with_rng
+        let run = |mut model: model::MarkovBw| {
+            std::iter::from_fn(|| model.next_bw())
+                .map(|(bw, _)| state_of(bw))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(config.clone().build()), trace);
+        assert_eq!(
+            run(config
+                .clone()
+                .build_with_rng(rand::rngs::StdRng::seed_from_u64(42))),
+            trace
+        );
+        assert_ne!(run(config.clone().seed(43).build()), trace);
+
+        #[cfg(feature = "serde")]
+        {
+            let config: Box<dyn BwTraceConfig> = Box::new(config);
+            let ser = serde_json::to_string(&config).unwrap();
+            #[cfg(feature = "human")]
+            assert!(ser.contains("\"states\":[\""), "{}", ser);
+            let des: Box<dyn BwTraceConfig> = serde_json::from_str(&ser).unwrap();
+            assert!(des.output_eq(
+                config.as_ref(),
+                Duration::from_millis(1),
+                Duration::from_secs(1)
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "MarkovBw: row 1 of the transition matrix must be non-negative and sum to 1.0"
+    )]
+    fn test_markov_bw_invalid_row() {
+        MarkovBwConfig::new()
+            .states(vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2)])
+            .transitions(vec![vec![0.5, 0.5], vec![0.5, 0.4]])
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "MarkovBw: transition matrix must be 2 x 2 for 2 states")]
+    fn test_markov_bw_invalid_matrix() {
+        MarkovBwConfig::new()
+            .states(vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2)])
+            .transitions(vec![vec![1.0]])
+            .build();
+    }
+
+    #[test]
+    fn test_bw_try_build() {
+        // an empty config yields an empty trace instead of panicking
+        let empty: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(SplineBwConfig::new()),
+            Box::new(MarkovBwConfig::new()),
+            Box::new(EmpiricalBwConfig::new()),
+            Box::new(SplineBwConfig::new().duration(Duration::from_secs(1))),
+            Box::new(MarkovBwConfig::new().duration(Duration::from_secs(1))),
+            Box::new(EmpiricalBwConfig::new().duration(Duration::from_secs(1))),
+        ];
+        for config in empty {
+            assert_eq!(config.total_duration(), Some(Duration::ZERO));
+            assert_eq!(config.into_model().next_bw(), None);
+        }
+        #[cfg(feature = "serde")]
+        for tag in ["SplineBwConfig", "MarkovBwConfig", "EmpiricalBwConfig"] {
+            let config: Box<dyn BwTraceConfig> =
+                serde_json::from_str(&format!("{{\"{}\":{{}}}}", tag)).unwrap();
+            assert_eq!(config.into_model().next_bw(), None);
+        }
+        assert!(SquareBwConfig::new().try_build().is_ok());
+        assert!(ScaledBwConfig::new().try_build().is_ok());
+        assert!(RampBwConfig::new().try_build().is_ok());
+        assert!(RandomPatternBwConfig::new().try_build().is_ok());
+        assert!(PoissonBwConfig::new().try_build().is_ok());
+        assert!(ScheduleBwConfig::new().try_build().is_ok());
+
+        // an invalid config returns an error
+        let message = |err: ModelBuildError| err.to_string();
+        let zero_step: Vec<(&str, Result<(), ModelBuildError>)> = vec![
+            (
+                "NormalizedBw",
+                NormalizedBwConfig::new()
+                    .mean(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1))
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "SawtoothBw",
+                SawtoothBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "SineBw",
+                SineBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "SquareBw",
+                SquareBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "MarkovBw",
+                MarkovBwConfig::new()
+                    .states(vec![Bandwidth::from_mbps(1)])
+                    .transitions(vec![vec![1.0]])
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "WeibullBw",
+                WeibullBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "GammaBw",
+                GammaBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "EmpiricalBw",
+                EmpiricalBwConfig::new()
+                    .cdf(vec![(Bandwidth::from_mbps(1), 1.0)])
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "RandomOutageBw",
+                RandomOutageBwConfig::new()
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+            (
+                "SplineBw",
+                SplineBwConfig::new()
+                    .points(vec![
+                        (Duration::ZERO, Bandwidth::from_mbps(12)),
+                        (Duration::from_secs(1), Bandwidth::from_mbps(24)),
+                    ])
+                    .step(Duration::ZERO)
+                    .try_build()
+                    .map(drop),
+            ),
+        ];
+        for (model, result) in zero_step {
+            assert_eq!(
+                result.map_err(message).err(),
+                Some(format!("{}: step must be positive", model))
+            );
+        }
+        assert_eq!(
+            SplineBwConfig::new()
+                .points(vec![
+                    (Duration::from_secs(1), Bandwidth::from_mbps(12)),
+                    (Duration::from_secs(1), Bandwidth::from_mbps(24)),
+                ])
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("SplineBw: points must be sorted by strictly increasing time".to_string())
+        );
+        assert_eq!(
+            MarkovBwConfig::new()
+                .states(vec![Bandwidth::from_mbps(1)])
+                .transitions(vec![vec![1.0]])
+                .initial_state(1)
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("MarkovBw: initial_state 1 is out of range for 1 states".to_string())
+        );
+        assert_eq!(
+            EmpiricalBwConfig::new()
+                .cdf(vec![(Bandwidth::from_mbps(1), 0.5)])
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("EmpiricalBw: cdf must end at cumulative probability 1.0".to_string())
+        );
+        assert_eq!(
+            ScheduleBwConfig::new()
+                .slots(vec![(0, 1441, Bandwidth::from_mbps(1))])
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("ScheduleBw: the minutes of slots must be within a day (0 to 1440)".to_string())
+        );
+        assert_eq!(
+            SquareBwConfig::new().duty_ratio(1.5).try_build().err(),
+            Some(ModelBuildError::InvalidParameter {
+                model: "SquareBw",
+                name: "duty_ratio",
+                expected: "between 0 and 1",
+                value: 1.5,
+            })
+        );
+        assert_eq!(
+            ScaledBwConfig::new()
+                .factor(-1.0)
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("ScaledBw: factor must be non-negative and finite, got -1".to_string())
+        );
+        assert_eq!(
+            RampBwConfig::new().step(Duration::ZERO).try_build().err(),
+            Some(ModelBuildError::ZeroDuration {
+                model: "RampBw",
+                name: "step",
+            })
+        );
+        assert_eq!(
+            PoissonBwConfig::new()
+                .step(Duration::ZERO)
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("PoissonBw: step must be positive".to_string())
+        );
+        assert_eq!(
+            RandomPatternBwConfig::new()
+                .pattern(vec![(0.0, Box::new(StaticBwConfig::new()))])
+                .try_build()
+                .map_err(message)
+                .err(),
+            Some("RandomPatternBw: at least one weight must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reset_with_seed() {
+        fn run(model: &mut impl BwTrace) -> Vec<(Bandwidth, Duration)> {
+            std::iter::from_fn(|| model.next_bw()).collect()
+        }
+
         let mut normal_bw = NormalizedBwConfig::new()
             .mean(Bandwidth::from_mbps(12))
             .std_dev(Bandwidth::from_mbps(1))
-            .duration(Duration::from_millis(200))
+            .duration(Duration::from_millis(500))
+            .step(Duration::from_millis(100))
+            .seed(42)
+            .build();
+        let original = run(&mut normal_bw);
+        assert_eq!(original.len(), 5);
+        normal_bw.reset_with_seed(43);
+        assert_eq!(normal_bw.seed, 43);
+        let reseeded = run(&mut normal_bw);
+        assert_eq!(reseeded.len(), 5);
+        assert_ne!(reseeded, original);
+        normal_bw.reset_with_seed(43);
+        assert_eq!(run(&mut normal_bw), reseeded);
+        normal_bw.reset_with_seed(42);
+        assert_eq!(run(&mut normal_bw), original);
+
+        let mut sawtooth_bw = SawtoothBwConfig::new()
+            .bottom(Bandwidth::from_mbps(12))
+            .top(Bandwidth::from_mbps(16))
+            .duration(Duration::from_millis(700))
+            .step(Duration::from_millis(100))
+            .interval(Duration::from_millis(500))
+            .std_dev(Bandwidth::from_mbps(1))
+            .seed(42)
+            .build();
+        let original = run(&mut sawtooth_bw);
+        sawtooth_bw.reset_with_seed(43);
+        let reseeded = run(&mut sawtooth_bw);
+        assert_eq!(reseeded.len(), original.len());
+        assert_ne!(reseeded, original);
+        sawtooth_bw.reset_with_seed(43);
+        assert_eq!(run(&mut sawtooth_bw), reseeded);
+        sawtooth_bw.reset_with_seed(42);
+        assert_eq!(run(&mut sawtooth_bw), original);
+
+        let mut empirical_bw = EmpiricalBwConfig::new()
+            .cdf(vec![
+                (Bandwidth::from_mbps(10), 0.0),
+                (Bandwidth::from_mbps(30), 1.0),
+            ])
+            .duration(Duration::from_millis(500))
             .step(Duration::from_millis(100))
             .seed(42)
             .build();
+        let original = run(&mut empirical_bw);
+        empirical_bw.reset_with_seed(43);
+        let reseeded = run(&mut empirical_bw);
+        assert_ne!(reseeded, original);
+        empirical_bw.reset_with_seed(43);
+        assert_eq!(run(&mut empirical_bw), reseeded);
+    }
+
+    #[test]
+    fn test_repeated_bw_cached_reset() {
+        let inner = RepeatedBwPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(1))
+                        .duration(Duration::from_millis(10)),
+                ) as Box<dyn BwTraceConfig>,
+                Box::new(TraceBwConfig::new().pattern(vec![(
+                    Duration::from_millis(5),
+                    vec![Bandwidth::from_mbps(2), Bandwidth::from_mbps(3)],
+                )])),
+            ])
+            .count(2);
+        let pattern: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_millis(100)),
+            ),
+            Box::new(
+                SquareBwConfig::new()
+                    .period(Duration::from_millis(70))
+                    .step(Duration::from_millis(30))
+                    .duration(Duration::from_millis(100)),
+            ),
+            // not resettable, rebuilt at each cycle
+            Box::new(
+                NormalizedBwConfig::new()
+                    .std_dev(Bandwidth::from_mbps(1))
+                    .duration(Duration::from_millis(30))
+                    .step(Duration::from_millis(10)),
+            ),
+            Box::new(inner),
+        ];
+
+        // the output of rebuilding every sub-model at each cycle
+        let mut expected = vec![];
+        for _ in 0..3 {
+            for config in &pattern {
+                let mut model = config.clone().into_model();
+                expected.extend(std::iter::from_fn(|| model.next_bw()));
+            }
+        }
+
+        let mut repeated_bw = RepeatedBwPatternConfig::new()
+            .pattern(pattern)
+            .count(3)
+            .build();
         assert_eq!(
-            normal_bw.next_bw(),
-            Some((Bandwidth::from_bps(12069427), Duration::from_millis(100)))
+            std::iter::from_fn(|| repeated_bw.next_bw()).collect::<Vec<_>>(),
+            expected
         );
+
+        // reset in the middle of a cycle
+        repeated_bw.reset();
+        for _ in 0..7 {
+            repeated_bw.next_bw();
+        }
+        repeated_bw.reset();
         assert_eq!(
-            normal_bw.next_bw(),
-            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+            std::iter::from_fn(|| repeated_bw.next_bw()).collect::<Vec<_>>(),
+            expected
         );
-        assert_eq!(normal_bw.next_bw(), None);
-        let normal_bw_config = NormalizedBwConfig::new()
-            .mean(Bandwidth::from_mbps(12))
-            .std_dev(Bandwidth::from_mbps(1))
-            .duration(Duration::from_millis(200))
-            .step(Duration::from_millis(100))
-            .seed(42);
-        let normal_bw_repeated = normal_bw_config.forever();
-        let mut model = Box::new(normal_bw_repeated).into_model();
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        fn nested(depth: usize) -> RepeatedBwPatternConfig {
+            let mut config = RepeatedBwPatternConfig::new()
+                .pattern(vec![Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(12))
+                        .duration(Duration::from_secs(1)),
+                )])
+                .count(1);
+            // the innermost static config and the pattern wrapping it
+            for _ in 2..depth {
+                config = RepeatedBwPatternConfig::new()
+                    .pattern(vec![Box::new(config)])
+                    .count(1);
+            }
+            config
+        }
+
+        let config = nested(DEFAULT_MAX_NESTING_DEPTH);
+        assert!(config.validate(DEFAULT_MAX_NESTING_DEPTH).is_ok());
+        let mut model = config
+            .build_with_max_depth(DEFAULT_MAX_NESTING_DEPTH)
+            .unwrap();
         assert_eq!(
             model.next_bw(),
-            Some((Bandwidth::from_bps(12069427), Duration::from_millis(100)))
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
         );
+        assert_eq!(model.next_bw(), None);
+
+        let config = nested(DEFAULT_MAX_NESTING_DEPTH + 1);
         assert_eq!(
-            model.next_bw(),
-            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+            config.validate(DEFAULT_MAX_NESTING_DEPTH),
+            Err("RepeatedBwPatternConfig: nesting depth exceeds the limit 64".to_string())
         );
+        assert!(config
+            .build_with_max_depth(DEFAULT_MAX_NESTING_DEPTH)
+            .is_err());
+
+        // wrappers count as a level as well
+        let config = RepeatedBwPatternConfig::new().pattern(vec![Box::new(
+            TaggedBwConfig::new()
+                .inner(Box::new(StaticBwConfig::new()))
+                .tag("tag"),
+        )]);
+        assert!(config.validate(3).is_ok());
+        assert!(config.validate(2).is_err());
+    }
+
+    #[test]
+    fn test_repeat() {
+        let config = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1));
+        let mut model = Box::new(config.clone().repeat(3)).into_model();
+        for _ in 0..3 {
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+            );
+        }
+        assert_eq!(model.next_bw(), None);
+
+        // Repeating a repeated pattern multiplies the count
+        let mut model = Box::new(config.clone().repeat(2).repeat(3)).into_model();
+        assert_eq!(std::iter::from_fn(|| model.next_bw()).take(10).count(), 6);
+
+        let mut model = Box::new(config.clone().forever()).into_model();
+        for _ in 0..10 {
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+            );
+        }
+
+        // `Forever` is still a `BwTraceConfig`, implemented on top of `Repeatable`
+        fn forever_model<T: model::bw::Forever + Clone>(
+            config: T,
+        ) -> (Box<dyn BwTrace>, Box<dyn BwTrace>) {
+            let once = Box::new(config.clone()).into_model();
+            (
+                once,
+                Box::new(model::bw::Forever::forever(config)).into_model(),
+            )
+        }
+        let (mut once, mut model) = forever_model(config);
         assert_eq!(
-            model.next_bw(),
-            Some((Bandwidth::from_bps(12069427), Duration::from_millis(100)))
+            once.next_bw(),
+            Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
         );
+        assert_eq!(once.next_bw(), None);
+        for _ in 0..10 {
+            assert_eq!(
+                model.next_bw(),
+                Some((Bandwidth::from_mbps(12), Duration::from_secs(1)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_once() {
+        let mut model = RepeatedBwPatternConfig::once(vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(SequenceBwConfig::from(vec![
+                (Bandwidth::from_mbps(24), Duration::from_millis(100)),
+                (Bandwidth::from_mbps(6), Duration::from_millis(200)),
+            ])),
+            Box::new(
+                TraceBwConfig::new()
+                    .pattern(vec![(Duration::from_millis(50), vec![Bandwidth::ZERO])]),
+            ),
+        ])
+        .build();
         assert_eq!(
-            model.next_bw(),
-            Some((Bandwidth::from_bps(12132938), Duration::from_millis(100)))
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            [
+                (Bandwidth::from_mbps(12), Duration::from_secs(1)),
+                (Bandwidth::from_mbps(24), Duration::from_millis(100)),
+                (Bandwidth::from_mbps(6), Duration::from_millis(200)),
+                (Bandwidth::ZERO, Duration::from_millis(50)),
+            ]
         );
+        assert_eq!(model.next_bw(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "logging")]
+    fn test_logging() {
+        use std::sync::Mutex;
+
+        static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct CaptureLogger;
+
+        impl log::Log for CaptureLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.level() <= log::Level::Debug
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    RECORDS.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CaptureLogger = CaptureLogger;
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let pattern = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(24))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn BwTraceConfig>,
+        ];
+        let mut model = model::RepeatedBwPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        while model.next_bw().is_some() {}
+
+        let records = RECORDS.lock().unwrap();
+        let expected = [
+            "RepeatedBwPattern built: 2 sub-configs, count 2",
+            "StaticBw built: ",
+            "RepeatedBwPattern completed cycle 1 (count: 2)",
+            "RepeatedBwPattern completed cycle 2 (count: 2)",
+            "RepeatedBwPattern exhausted after 2 cycles",
+        ];
+        for message in expected {
+            assert!(
+                records.iter().any(|record| record.starts_with(message)),
+                "missing log event: {}",
+                message
+            );
+        }
     }
 
     #[test]
@@ -0,0 +1,243 @@
+//! This module loads bandwidth trace configurations from files.
+//!
+//! Enable `loader` feature to use this module.
+//!
+//! The format of a file is detected by its extension:
+//!
+//! - `json`: A bandwidth trace configuration (i.e. a serialized `Box<dyn BwTraceConfig>`).
+//! - `mahi`, `mahimahi`, `trace`, `up` or `down`: A trace in mahimahi format, with one timestamp per line,
+//!   loaded by [`load_mahimahi_trace_streaming`]. Empty lines and comment lines starting with `#` (e.g. the header
+//!   emitted by [`MahimahiExt::mahimahi_to_string_with_header`](crate::MahimahiExt::mahimahi_to_string_with_header))
+//!   are skipped.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! # use netem_trace::loader::load_concatenated_bw;
+//! # use netem_trace::model::BwTraceConfig;
+//! # use netem_trace::Duration;
+//! let config = load_concatenated_bw(
+//!     &["hour-00.json", "hour-01.mahi", "hour-02.mahi"],
+//!     Duration::from_secs(1),
+//! )
+//! .unwrap();
+//! let mut model = Box::new(config).into_model();
+//! ```
+
+use crate::{
+    load_mahimahi_trace_streaming,
+    model::{BwTraceConfig, RepeatedBwPatternConfig, StaticBwConfig, DEFAULT_MAX_NESTING_DEPTH},
+    Bandwidth, Duration, MahimahiError,
+};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// The error returned when loading configurations from files.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Failed to read the file.
+    Io(PathBuf, std::io::Error),
+    /// The file is not a valid json configuration.
+    Json(PathBuf, serde_json::Error),
+    /// The file is not a valid mahimahi trace.
+    Mahimahi(PathBuf, String),
+    /// The format of the file cannot be detected from its extension.
+    UnknownFormat(PathBuf),
+    /// The configuration is nested deeper than the limit, see [`DEFAULT_MAX_NESTING_DEPTH`].
+    TooDeep(PathBuf, usize),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            ConfigError::Json(path, e) => {
+                write!(f, "invalid json configuration {}: {}", path.display(), e)
+            }
+            ConfigError::Mahimahi(path, e) => {
+                write!(f, "invalid mahimahi trace {}: {}", path.display(), e)
+            }
+            ConfigError::UnknownFormat(path) => {
+                write!(f, "unknown format of {}", path.display())
+            }
+            ConfigError::TooDeep(path, max_depth) => write!(
+                f,
+                "configuration {} is nested deeper than the limit {}",
+                path.display(),
+                max_depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(_, e) => Some(e),
+            ConfigError::Json(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Load a bandwidth trace configuration from a file, whose format is detected by its extension.
+///
+/// A mahimahi trace is loaded to play once (i.e. with `count` of `Some(1)`). A json configuration
+/// nested deeper than [`DEFAULT_MAX_NESTING_DEPTH`] is rejected, as building or running its model
+/// may overflow the stack.
+///
+/// See the [`loader`](crate::loader) module for the supported formats.
+pub fn load_bw<P: AsRef<Path>>(path: P) -> Result<Box<dyn BwTraceConfig>, ConfigError> {
+    load_bw_with_max_depth(path, DEFAULT_MAX_NESTING_DEPTH)
+}
+
+/// The same as [`load_bw`], but rejects json configurations nested deeper than `max_depth` levels
+/// instead of [`DEFAULT_MAX_NESTING_DEPTH`].
+pub fn load_bw_with_max_depth<P: AsRef<Path>>(
+    path: P,
+    max_depth: usize,
+) -> Result<Box<dyn BwTraceConfig>, ConfigError> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let is_json = match extension.as_deref() {
+        Some("json") => true,
+        Some("mahi" | "mahimahi" | "trace" | "up" | "down") => false,
+        _ => return Err(ConfigError::UnknownFormat(path.to_path_buf())),
+    };
+    if is_json {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        let config: Box<dyn BwTraceConfig> =
+            serde_json::from_str(&content).map_err(|e| ConfigError::Json(path.to_path_buf(), e))?;
+        if config.exceeds_depth(max_depth) {
+            return Err(ConfigError::TooDeep(path.to_path_buf(), max_depth));
+        }
+        Ok(config)
+    } else {
+        let file = File::open(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        let config =
+            load_mahimahi_trace_streaming(BufReader::new(file), Some(1)).map_err(|e| match e {
+                MahimahiError::Io(e) => ConfigError::Io(path.to_path_buf(), e),
+                e => ConfigError::Mahimahi(path.to_path_buf(), e.to_string()),
+            })?;
+        Ok(Box::new(config))
+    }
+}
+
+/// Load the bandwidth trace configurations from files and concatenate them into a single pattern.
+///
+/// Each file is loaded by [`load_bw`], and a zero bandwidth lasting for `gap` is inserted
+/// between every two consecutive files (no gap is inserted if `gap` is zero).
+///
+/// The returned pattern plays once (i.e. with `count` of 1). Note that if a configuration in the files
+/// repeats forever, the files after it will never be played.
+///
+/// Returns `Err` if any of the files fails to load.
+pub fn load_concatenated_bw<P: AsRef<Path>>(
+    paths: &[P],
+    gap: Duration,
+) -> Result<RepeatedBwPatternConfig, ConfigError> {
+    let mut pattern: Vec<Box<dyn BwTraceConfig>> = Vec::with_capacity(paths.len() * 2);
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 && !gap.is_zero() {
+            pattern.push(Box::new(
+                StaticBwConfig::new().bw(Bandwidth::ZERO).duration(gap),
+            ));
+        }
+        pattern.push(load_bw(path)?);
+    }
+    Ok(RepeatedBwPatternConfig::new().pattern(pattern).count(1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::Fixtures;
+    use crate::MahimahiExt;
+
+    #[test]
+    fn test_load_concatenated_bw() {
+        let fixtures = Fixtures::new("loader-concat");
+        #[cfg(feature = "human")]
+        let json = "{\"StaticBwConfig\":{\"bw\":\"24Mbps\",\"duration\":\"1s\"}}";
+        #[cfg(not(feature = "human"))]
+        let json = "{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}";
+        let json = fixtures.write("first.json", json);
+        let mahimahi = fixtures.write("second.mahi", "1\n2\n2\n");
+
+        let config = load_concatenated_bw(&[&json, &mahimahi], Duration::from_millis(500)).unwrap();
+        let mut model = Box::new(config).into_model();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            vec![
+                (Bandwidth::from_mbps(24), Duration::from_secs(1)),
+                (Bandwidth::ZERO, Duration::from_millis(500)),
+                (Bandwidth::from_mbps(12), Duration::from_millis(1)),
+                (Bandwidth::from_mbps(24), Duration::from_millis(1)),
+            ]
+        );
+
+        // the header of mahimahi traces is skipped
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(3))
+            .build();
+        let header = fixtures.write(
+            "header.mahi",
+            &static_bw.mahimahi_to_string_with_header(&Duration::MAX, "static"),
+        );
+        let mut model = load_bw(&header).unwrap().into_model();
+        assert_eq!(
+            model.next_bw(),
+            Some((Bandwidth::from_mbps(24), Duration::from_millis(3)))
+        );
+        assert_eq!(model.next_bw(), None);
+
+        // no gap for zero duration
+        let config = load_concatenated_bw(&[&mahimahi, &mahimahi], Duration::ZERO).unwrap();
+        assert_eq!(config.pattern.len(), 2);
+
+        let unknown = fixtures.write("third.csv", "1\n");
+        assert!(matches!(
+            load_concatenated_bw(&[&json, &unknown], Duration::ZERO),
+            Err(ConfigError::UnknownFormat(_))
+        ));
+        let invalid = fixtures.write("fourth.mahi", "2\n1\n");
+        let err = load_concatenated_bw(&[&invalid], Duration::ZERO)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ConfigError::Mahimahi(..)));
+        assert!(err
+            .to_string()
+            .ends_with("fourth.mahi: timestamps must be monotonically nondecreasing"));
+        let malformed = fixtures.write("fifth.mahi", "1\n# comment\n3ms\n");
+        let err = load_bw(&malformed).err().unwrap();
+        assert!(err
+            .to_string()
+            .ends_with("fifth.mahi: invalid timestamp on line 3: '3ms'"));
+        // the nesting depth of json configurations is limited
+        let nested = fixtures.write(
+            "nested.json",
+            &format!(
+                "{{\"RepeatedBwPatternConfig\":{{\"pattern\":[{}],\"count\":1}}}}",
+                std::fs::read_to_string(&json).unwrap()
+            ),
+        );
+        assert!(load_bw_with_max_depth(&nested, 2).is_ok());
+        let err = load_bw_with_max_depth(&nested, 1).err().unwrap();
+        assert!(matches!(err, ConfigError::TooDeep(_, 1)));
+        assert!(err
+            .to_string()
+            .ends_with("nested.json is nested deeper than the limit 1"));
+        assert!(load_bw(&nested).is_ok());
+        let missing = fixtures.path("missing.json");
+        assert!(matches!(
+            load_concatenated_bw(&[&missing], Duration::ZERO),
+            Err(ConfigError::Io(..))
+        ));
+    }
+}
@@ -1,6 +1,10 @@
 //! This module can generate traces in mahimahi format for struct implementing [`BwTrace`] and
 //! load traces in mahimahi format to [`RepeatedBwPatternConfig`].
 //!
+//! With `delay-model` feature also enabled, delay traces can be generated for struct implementing
+//! [`DelayTrace`](crate::DelayTrace) with [`DelayMahimahi`], and loaded to
+//! [`RepeatedDelayPatternConfig`](crate::model::RepeatedDelayPatternConfig) with [`load_mahimahi_delay_trace`].
+//!
 //! Enable `mahimahi` feature to use this module.
 //!
 //! ## Examples
@@ -57,53 +61,259 @@ pub trait Mahimahi: BwTrace {
     /// For example, if the bandwidth is 12Mbps (one packet per millisecond), then the sequence can be:
     /// \[1, 2, 3, 4, 5\]
     fn mahimahi(&mut self, total_dur: &Duration) -> Vec<u64> {
-        let mut timestamp = MAHIMAHI_TS_BIN;
-        let mut v = Vec::new();
-        let mut transfer = Bandwidth::from_bps(0);
-        let mut bin_rem = MAHIMAHI_TS_BIN;
-        while let Some((bw, mut dur)) = self.next_bw() {
-            if timestamp > *total_dur {
-                break;
+        self.mahimahi_iter(*total_dur).collect()
+    }
+
+    /// Lazily generate the timestamp sequence of [`mahimahi`](Mahimahi::mahimahi), without
+    /// allocating the whole sequence, e.g. to stream a long trace to a file.
+    ///
+    /// The segments of the model are consumed as the iterator advances.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::Mahimahi;
+    /// # use netem_trace::model::StaticBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let mut static_bw = StaticBwConfig::new()
+    ///     .bw(Bandwidth::from_mbps(24))
+    ///     .duration(Duration::from_secs(1))
+    ///     .build();
+    /// let mut timestamps = static_bw.mahimahi_iter(Duration::from_secs(1));
+    /// assert_eq!(timestamps.next(), Some(1));
+    /// assert_eq!(timestamps.count(), 1999);
+    /// ```
+    fn mahimahi_iter(&mut self, total_dur: Duration) -> impl Iterator<Item = u64> + '_ {
+        MahimahiIter {
+            trace: self,
+            total_dur,
+            timestamp: MAHIMAHI_TS_BIN,
+            transfer: Bandwidth::from_bps(0),
+            bin_rem: MAHIMAHI_TS_BIN,
+            segment: None,
+            draining: false,
+            done: false,
+        }
+    }
+
+    /// Compute the number of timestamps [`mahimahi`](Mahimahi::mahimahi) would generate for the same
+    /// `total_dur`, without generating them, e.g. to pre-size buffers.
+    ///
+    /// The bandwidth is integrated over the whole milliseconds within `total_dur` and divided by the
+    /// MTU (1500 bytes), rounding down. As [`mahimahi`](Mahimahi::mahimahi) accumulates the fractional
+    /// packets with floating-point arithmetic, the actual count may differ by one packet from the
+    /// expected count.
+    ///
+    /// Like [`mahimahi`](Mahimahi::mahimahi), this consumes the segments of the model.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::Mahimahi;
+    /// # use netem_trace::model::StaticBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let mut static_bw = StaticBwConfig::new()
+    ///     .bw(Bandwidth::from_mbps(24))
+    ///     .duration(Duration::from_secs(1))
+    ///     .build();
+    /// assert_eq!(static_bw.mahimahi_expected_count(&Duration::from_millis(5)), 10);
+    /// ```
+    fn mahimahi_expected_count(&mut self, total_dur: &Duration) -> u64 {
+        let total_dur = Duration::from_millis(saturating_duration_as_millis_u64!(total_dur));
+        crate::measure::total_bytes(self, total_dur) / MTU_IN_BYTES
+    }
+}
+
+impl<T: BwTrace + ?Sized> Mahimahi for T {}
+
+/// Tracks the time covered by the segments taken from a trace.
+struct Elapsed<'a, T: ?Sized> {
+    trace: &'a mut T,
+    elapsed: Duration,
+}
+
+impl<T: BwTrace + ?Sized> BwTrace for Elapsed<'_, T> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let (bw, duration) = self.trace.next_bw()?;
+        self.elapsed = self.elapsed.saturating_add(duration);
+        Some((bw, duration))
+    }
+}
+
+/// The iterator returned by [`Mahimahi::mahimahi_iter`].
+struct MahimahiIter<'a, T: ?Sized> {
+    trace: &'a mut T,
+    total_dur: Duration,
+    timestamp: Duration,
+    transfer: Bandwidth,
+    bin_rem: Duration,
+    /// The rest of the current segment.
+    segment: Option<(Bandwidth, Duration)>,
+    /// Whether the packets transferred within the current bin are being yielded.
+    draining: bool,
+    done: bool,
+}
+
+impl<T: BwTrace + ?Sized> Iterator for MahimahiIter<'_, T> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if self.draining {
+                if self.transfer >= MTU_PER_MILLIS {
+                    self.transfer -= MTU_PER_MILLIS;
+                    return Some(saturating_duration_as_millis_u64!(self.timestamp));
+                }
+                self.draining = false;
+                if self.bin_rem.is_zero() {
+                    self.bin_rem = MAHIMAHI_TS_BIN;
+                    self.timestamp += MAHIMAHI_TS_BIN;
+                }
+            }
+            if self.done {
+                return None;
             }
-            while (timestamp <= *total_dur) && !dur.is_zero() {
-                let bin = bin_rem.min(dur);
-                bin_rem -= bin;
-                dur -= bin;
-                let bin_factor = bin.as_secs_f64() / MAHIMAHI_TS_BIN.as_secs_f64();
-                transfer += bw.mul_f64(bin_factor);
-                while transfer >= MTU_PER_MILLIS {
-                    v.push(saturating_duration_as_millis_u64!(timestamp));
-                    transfer -= MTU_PER_MILLIS;
+            match self.segment.as_mut() {
+                Some((bw, dur)) if self.timestamp <= self.total_dur && !dur.is_zero() => {
+                    let bin = self.bin_rem.min(*dur);
+                    self.bin_rem -= bin;
+                    *dur -= bin;
+                    let bin_factor = bin.as_secs_f64() / MAHIMAHI_TS_BIN.as_secs_f64();
+                    self.transfer += bw.mul_f64(bin_factor);
+                    self.draining = true;
                 }
-                if bin_rem.is_zero() {
-                    bin_rem = MAHIMAHI_TS_BIN;
-                    timestamp += MAHIMAHI_TS_BIN;
+                _ => {
+                    self.segment = self.trace.next_bw();
+                    if self.segment.is_none() || self.timestamp > self.total_dur {
+                        self.done = true;
+                    }
                 }
             }
         }
-        v
     }
 }
 
-impl<T: BwTrace + ?Sized> Mahimahi for T {}
-
 /// The `MahimahiExt` trait provides some convenient methods to generate a trace in mahimahi format.
 pub trait MahimahiExt: Mahimahi {
     /// Join the mahimahi timestamp sequence to a string.
     fn mahimahi_to_string(&mut self, total_dur: &Duration) -> String {
-        let ts = self.mahimahi(total_dur);
-        itertools::join(ts, "\n")
+        itertools::join(self.mahimahi_iter(*total_dur), "\n")
+    }
+
+    /// Join the mahimahi timestamp sequence to a string, prefixed with a comment header.
+    ///
+    /// The header consists of lines starting with `#`, containing the given `metadata` (one comment
+    /// line per line of `metadata`, omitted if empty) and the statistics of the generated trace:
+    /// the duration, the packet count and the mean bandwidth. The duration is the time the trace
+    /// lasts for, i.e. `total_dur` or less if the model ends earlier. For example:
+    ///
+    /// ```text
+    /// # generated from static.json
+    /// # duration: 5ms
+    /// # packets: 5
+    /// # mean bandwidth: 12.000Mbps
+    /// 1
+    /// 2
+    /// ...
+    /// ```
+    ///
+    /// The header lines are skipped when loading the trace with [`loader`](crate::loader).
+    /// Note that `mm-link` of mahimahi does not accept such a header.
+    fn mahimahi_to_string_with_header(&mut self, total_dur: &Duration, metadata: &str) -> String {
+        let mut trace = Elapsed {
+            trace: self,
+            elapsed: Duration::ZERO,
+        };
+        let ts = trace.mahimahi(total_dur);
+        let duration = trace.elapsed.min(*total_dur);
+        let mut header: String = metadata
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    "#\n".to_string()
+                } else {
+                    format!("# {}\n", line)
+                }
+            })
+            .collect();
+        let mean_mbps = if duration.is_zero() {
+            0.0
+        } else {
+            ts.len() as f64 * MTU_IN_BITS as f64 / duration.as_secs_f64() / 1e6
+        };
+        header.push_str(&format!(
+            "# duration: {:?}\n# packets: {}\n# mean bandwidth: {:.3}Mbps\n",
+            duration,
+            ts.len(),
+            mean_mbps
+        ));
+        header + &itertools::join(ts, "\n")
     }
 
-    /// Write the mahimahi timestamp sequence to a file.
-    fn mahimahi_to_file<P: AsRef<std::path::Path>>(&mut self, total_dur: &Duration, path: P) {
-        let content = self.mahimahi_to_string(total_dur);
-        std::fs::write(path, content).unwrap();
+    /// Write the mahimahi timestamp sequence to a file, in the same format as
+    /// [`mahimahi_to_string`](MahimahiExt::mahimahi_to_string).
+    ///
+    /// The timestamps are streamed through a [`BufWriter`](std::io::BufWriter) as they are
+    /// generated, without holding the whole trace in memory.
+    fn mahimahi_to_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        total_dur: &Duration,
+        path: P,
+    ) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for (i, ts) in self.mahimahi_iter(*total_dur).enumerate() {
+            if i == 0 {
+                write!(writer, "{}", ts)?;
+            } else {
+                write!(writer, "\n{}", ts)?;
+            }
+        }
+        writer.flush()
     }
 }
 
 impl<T: Mahimahi + ?Sized> MahimahiExt for T {}
 
+/// The error returned when loading a mahimahi trace.
+#[derive(Debug)]
+pub enum MahimahiError {
+    /// Failed to read the trace.
+    Io(std::io::Error),
+    /// The line (starting from 1) is not a valid timestamp.
+    Parse(usize, String),
+    /// The timestamps are not a valid mahimahi trace, e.g. not monotonically nondecreasing.
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for MahimahiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MahimahiError::Io(e) => write!(f, "io error: {}", e),
+            MahimahiError::Parse(line, content) => {
+                write!(f, "invalid timestamp on line {}: '{}'", line, content)
+            }
+            MahimahiError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MahimahiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MahimahiError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MahimahiError {
+    fn from(e: std::io::Error) -> Self {
+        MahimahiError::Io(e)
+    }
+}
+
 /// Load a mahimahi trace to a [`RepeatedBwPatternConfig`].
 ///
 /// The `trace` is a sequence of timestamps, each timestamp represents an opportunity
@@ -118,90 +328,391 @@ impl<T: Mahimahi + ?Sized> MahimahiExt for T {}
 ///
 /// **Warning:** In some cases, this trace may slightly deviate from the behavior of mahimahi.
 ///
-/// Returns `Err` string if the mahimahi trace is invalid.
+/// A trace without zero timestamps is regenerated exactly by [`Mahimahi::mahimahi`] within its
+/// last timestamp. Zero timestamps are merged into the last millisecond of the pattern (as mahimahi
+/// sends them at the end of each period), so they are regenerated as copies of the last timestamp.
+///
+/// Returns [`MahimahiError::Invalid`] if the mahimahi trace is invalid.
 pub fn load_mahimahi_trace(
     trace: Vec<u64>,
     count: Option<usize>,
-) -> Result<RepeatedBwPatternConfig, &'static str> {
-    let mut pattern: Vec<StaticBwConfig> = vec![];
-    // The closure inserts a bandwidth config into the pattern.
-    let mut insert_into_pattern = |config| {
-        if pattern.is_empty() {
-            pattern.push(config);
-        } else {
-            let last_config = pattern.last_mut().unwrap();
-            if last_config.bw.unwrap() == config.bw.unwrap() {
+) -> Result<RepeatedBwPatternConfig, MahimahiError> {
+    load_mahimahi_trace_with_bin(trace, MAHIMAHI_TS_BIN, count)
+}
+
+/// Load a mahimahi-style trace whose timestamps are in units of `bin` to a
+/// [`RepeatedBwPatternConfig`], e.g. a microsecond-resolution capture with a `bin` of 1us.
+///
+/// This generalizes [`load_mahimahi_trace`], which is the same as a `bin` of 1ms. Each timestamp
+/// is converted into a bandwidth of one MTU (1500 bytes) per `bin` lasting for `bin`, rounded to
+/// the nearest bps, so a shorter `bin` gives a higher bandwidth per timestamp.
+///
+/// Returns [`MahimahiError::Invalid`] if `bin` is zero or the trace is invalid.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::load_mahimahi_trace_with_bin;
+/// # use netem_trace::{Bandwidth, BwTrace, Duration};
+/// let config = load_mahimahi_trace_with_bin(vec![1, 1, 3], Duration::from_micros(500), Some(1));
+/// let mut model = config.unwrap().build();
+/// assert_eq!(model.next_bw(), Some((Bandwidth::from_mbps(48), Duration::from_micros(500))));
+/// assert_eq!(model.next_bw(), Some((Bandwidth::ZERO, Duration::from_micros(500))));
+/// assert_eq!(model.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_micros(500))));
+/// assert_eq!(model.next_bw(), None);
+/// ```
+pub fn load_mahimahi_trace_with_bin(
+    trace: Vec<u64>,
+    bin: Duration,
+    count: Option<usize>,
+) -> Result<RepeatedBwPatternConfig, MahimahiError> {
+    let mut loader = MahimahiLoader::new(bin).map_err(MahimahiError::Invalid)?;
+    for ts in trace {
+        loader.push(ts).map_err(MahimahiError::Invalid)?;
+    }
+    loader.finish(count).map_err(MahimahiError::Invalid)
+}
+
+/// Load a mahimahi trace to a [`RepeatedBwPatternConfig`], reading the timestamps line by line.
+///
+/// This is the streaming version of [`load_mahimahi_trace`], which produces the same pattern, but
+/// never holds the whole trace in memory, so the memory usage is proportional to the size of the
+/// resulting pattern instead of the size of the trace. This is useful for very large trace files.
+///
+/// Each line holds one timestamp. Leading and trailing whitespace is trimmed, and empty lines and
+/// comment lines starting with `#` (e.g. the header emitted by
+/// [`MahimahiExt::mahimahi_to_string_with_header`]) are skipped.
+///
+/// Returns [`MahimahiError::Io`] if reading fails, [`MahimahiError::Parse`] if a line is not a
+/// valid timestamp, or [`MahimahiError::Invalid`] if the mahimahi trace is invalid.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::load_mahimahi_trace_streaming;
+/// # use netem_trace::{Bandwidth, BwTrace, Duration};
+/// let config = load_mahimahi_trace_streaming("1\n1\n\n3\n".as_bytes(), Some(1)).unwrap();
+/// let mut model = config.build();
+/// assert_eq!(model.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(1))));
+/// assert_eq!(model.next_bw(), Some((Bandwidth::ZERO, Duration::from_millis(1))));
+/// assert_eq!(model.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(1))));
+/// assert_eq!(model.next_bw(), None);
+/// ```
+pub fn load_mahimahi_trace_streaming<R: std::io::BufRead>(
+    reader: R,
+    count: Option<usize>,
+) -> Result<RepeatedBwPatternConfig, MahimahiError> {
+    let mut loader = MahimahiLoader::new(MAHIMAHI_TS_BIN).map_err(MahimahiError::Invalid)?;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let ts = line
+            .parse::<u64>()
+            .map_err(|_| MahimahiError::Parse(index + 1, line.to_string()))?;
+        loader.push(ts).map_err(MahimahiError::Invalid)?;
+    }
+    loader.finish(count).map_err(MahimahiError::Invalid)
+}
+
+/// Load a mahimahi trace file to a [`RepeatedBwPatternConfig`].
+///
+/// The file holds one timestamp per line, and is read by [`load_mahimahi_trace_streaming`], so
+/// leading and trailing whitespace, empty lines and comment lines starting with `#` are skipped.
+///
+/// Returns [`MahimahiError::Io`] if the file cannot be read, [`MahimahiError::Parse`] if a line is
+/// not a valid timestamp, or [`MahimahiError::Invalid`] if the mahimahi trace is invalid.
+pub fn load_mahimahi_trace_from_file<P: AsRef<std::path::Path>>(
+    path: P,
+    count: Option<usize>,
+) -> Result<RepeatedBwPatternConfig, MahimahiError> {
+    let file = std::fs::File::open(path)?;
+    load_mahimahi_trace_streaming(std::io::BufReader::new(file), count)
+}
+
+/// The state of loading a mahimahi trace, which coalesces the timestamps into a bandwidth pattern
+/// incrementally.
+struct MahimahiLoader {
+    pattern: Vec<StaticBwConfig>,
+    bin: Duration,          // duration of each timestamp
+    mtu_per_bin: Bandwidth, // bandwidth of one timestamp
+    zero_ts_cnt: u32,       // count of zero timestamps
+    last_ts: u64,           // last non-zero timestamp
+    last_cnt: u32,          // count of last non-zero timestamp
+}
+
+impl MahimahiLoader {
+    fn new(bin: Duration) -> Result<Self, &'static str> {
+        if bin.is_zero() {
+            return Err("bin must be nonzero");
+        }
+        let nanos = bin.as_nanos();
+        let bps = (MTU_IN_BITS as u128 * 1_000_000_000 + nanos / 2) / nanos;
+        Ok(Self {
+            pattern: Vec::new(),
+            bin,
+            mtu_per_bin: Bandwidth::from_bps(bps as u64),
+            zero_ts_cnt: 0,
+            last_ts: 0,
+            last_cnt: 0,
+        })
+    }
+
+    /// Insert a bandwidth config into the pattern, merging it with the last one if they have the
+    /// same bandwidth.
+    fn insert_into_pattern(&mut self, config: StaticBwConfig) {
+        match self.pattern.last_mut() {
+            Some(last_config) if last_config.bw.unwrap() == config.bw.unwrap() => {
                 last_config.duration =
                     Some(last_config.duration.unwrap() + config.duration.unwrap());
-            } else {
-                pattern.push(config);
             }
+            _ => self.pattern.push(config),
         }
-    };
+    }
 
-    let mut zeor_ts_cnt = 0; // count of zero timestamps
-    let mut last_ts = 0; // last non-zero timestamp
-    let mut last_cnt = 0; // count of last non-zero timestamp
-    for ts in trace {
+    fn push(&mut self, ts: u64) -> Result<(), &'static str> {
         // count zero timestamps
         if ts == 0 {
-            zeor_ts_cnt += 1;
-            continue;
+            self.zero_ts_cnt += 1;
+            return Ok(());
         }
         // non-zero timestamps
-        match ts.cmp(&last_ts) {
+        match ts.cmp(&self.last_ts) {
             std::cmp::Ordering::Less => {
                 return Err("timestamps must be monotonically nondecreasing");
             }
             std::cmp::Ordering::Equal => {
-                last_cnt += 1;
+                self.last_cnt += 1;
             }
             std::cmp::Ordering::Greater => {
-                if last_ts > 0 {
+                if self.last_ts > 0 {
                     // insert new bandwidth config
-                    insert_into_pattern(
+                    self.insert_into_pattern(
                         StaticBwConfig::new()
-                            .bw(MTU_PER_MILLIS * last_cnt)
-                            .duration(MAHIMAHI_TS_BIN),
+                            .bw(self.mtu_per_bin * self.last_cnt)
+                            .duration(self.bin),
                     );
                 }
-                if ts - last_ts > 1 {
+                if ts - self.last_ts > 1 {
                     // insert zero bandwidth
-                    insert_into_pattern(
+                    self.insert_into_pattern(
                         StaticBwConfig::new()
                             .bw(Bandwidth::ZERO)
-                            .duration(MAHIMAHI_TS_BIN * ((ts - last_ts - 1) as u32)),
+                            .duration(self.bin * ((ts - self.last_ts - 1) as u32)),
                     );
                 }
-                last_cnt = 1;
-                last_ts = ts;
+                self.last_cnt = 1;
+                self.last_ts = ts;
             }
         }
+        Ok(())
     }
-    if last_cnt == 0 {
-        // no non-zero timestamps
-        return Err("trace must last for a nonzero amount of time");
-    } else {
-        // merge final timestamps and zero timestamps
-        insert_into_pattern(
-            StaticBwConfig::new()
-                .bw(MTU_PER_MILLIS * (last_cnt + zeor_ts_cnt))
-                .duration(MAHIMAHI_TS_BIN),
-        );
+
+    fn finish(mut self, count: Option<usize>) -> Result<RepeatedBwPatternConfig, &'static str> {
+        if self.last_cnt == 0 {
+            // no non-zero timestamps
+            return Err("trace must last for a nonzero amount of time");
+        } else {
+            // merge final timestamps and zero timestamps
+            self.insert_into_pattern(
+                StaticBwConfig::new()
+                    .bw(self.mtu_per_bin * (self.last_cnt + self.zero_ts_cnt))
+                    .duration(self.bin),
+            );
+        }
+        Ok(RepeatedBwPatternConfig::new()
+            .count(count.unwrap_or(0))
+            .pattern(
+                self.pattern
+                    .drain(..)
+                    .map(|config| Box::new(config) as Box<dyn BwTraceConfig>)
+                    .collect(),
+            ))
     }
-    Ok(RepeatedBwPatternConfig::new()
+}
+
+/// The `DelayMahimahi` trait provides methods to generate a delay trace in a mahimahi-like format.
+///
+/// The trace is a sequence of delays in milliseconds, one for each millisecond (the same time bin
+/// as the timestamps of [`Mahimahi`]). Each value is the delay at the start of its millisecond,
+/// truncated to milliseconds.
+///
+/// This trait is automatically implemented for all types that implement `DelayTrace`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::mahimahi::DelayMahimahi;
+/// # use netem_trace::model::StaticDelayConfig;
+/// # use netem_trace::{Delay, Duration};
+/// let mut static_delay = StaticDelayConfig::new()
+///     .delay(Delay::from_millis(10))
+///     .duration(Duration::from_millis(3))
+///     .build();
+/// assert_eq!(static_delay.mahimahi_delay_to_string(&Duration::MAX), "10\n10\n10");
+/// ```
+#[cfg(feature = "delay-model")]
+pub trait DelayMahimahi: crate::DelayTrace {
+    /// Generate a delay sequence (in milliseconds) with one value per millisecond.
+    ///
+    /// The sequence ends when the trace goes to end, or the next millisecond exceeds `total_dur`.
+    fn mahimahi_delay(&mut self, total_dur: &Duration) -> Vec<u64> {
+        let mut v = Vec::new();
+        let mut bin_end = MAHIMAHI_TS_BIN;
+        let mut delay = crate::Delay::ZERO;
+        // the end of the current delay segment
+        let mut end = Duration::ZERO;
+        while bin_end <= *total_dur {
+            let bin_start = bin_end - MAHIMAHI_TS_BIN;
+            while end <= bin_start {
+                match self.next_delay() {
+                    Some((next_delay, dur)) => {
+                        delay = next_delay;
+                        end = end.saturating_add(dur);
+                    }
+                    None => return v,
+                }
+            }
+            v.push(saturating_duration_as_millis_u64!(delay));
+            bin_end += MAHIMAHI_TS_BIN;
+        }
+        v
+    }
+
+    /// Join the delay sequence to a string, one value per line.
+    fn mahimahi_delay_to_string(&mut self, total_dur: &Duration) -> String {
+        let delays = self.mahimahi_delay(total_dur);
+        itertools::join(delays, "\n")
+    }
+
+    /// Write the delay sequence to a file, in the same format as
+    /// [`mahimahi_delay_to_string`](DelayMahimahi::mahimahi_delay_to_string).
+    fn mahimahi_delay_to_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        total_dur: &Duration,
+        path: P,
+    ) -> std::io::Result<()> {
+        let content = self.mahimahi_delay_to_string(total_dur);
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(feature = "delay-model")]
+impl<T: crate::DelayTrace + ?Sized> DelayMahimahi for T {}
+
+/// Load a delay trace generated by [`DelayMahimahi`] to a [`RepeatedDelayPatternConfig`](crate::model::RepeatedDelayPatternConfig).
+///
+/// The `trace` is a sequence of delays in milliseconds, each of which lasts for 1 millisecond.
+/// Consecutive equal delays are merged. For example, if the trace is \[10, 10, 20\], then the delay
+/// pattern will be \[10ms for 2ms, 20ms for 1ms\].
+///
+/// The `count` is the number of times the trace repeats. If `count` is `None` or `Some(0)`,
+/// then the trace will repeat forever.
+///
+/// Returns [`MahimahiError::Invalid`] if the trace is empty.
+#[cfg(feature = "delay-model")]
+pub fn load_mahimahi_delay_trace(
+    trace: Vec<u64>,
+    count: Option<usize>,
+) -> Result<crate::model::RepeatedDelayPatternConfig, MahimahiError> {
+    use crate::model::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+
+    if trace.is_empty() {
+        return Err(MahimahiError::Invalid(
+            "trace must last for a nonzero amount of time",
+        ));
+    }
+    let mut pattern: Vec<(u64, u32)> = vec![];
+    for delay in trace {
+        match pattern.last_mut() {
+            Some((last_delay, bins)) if *last_delay == delay => *bins += 1,
+            _ => pattern.push((delay, 1)),
+        }
+    }
+    Ok(RepeatedDelayPatternConfig::new()
         .count(count.unwrap_or(0))
         .pattern(
             pattern
-                .drain(..)
-                .map(|config| Box::new(config) as Box<dyn BwTraceConfig>)
+                .into_iter()
+                .map(|(delay, bins)| {
+                    Box::new(
+                        StaticDelayConfig::new()
+                            .delay(crate::Delay::from_millis(delay))
+                            .duration(MAHIMAHI_TS_BIN * bins),
+                    ) as Box<dyn DelayTraceConfig>
+                })
                 .collect(),
         ))
 }
 
+/// Generate traces for [Pantheon](https://github.com/StanfordSNR/pantheon).
+///
+/// Pantheon feeds the trace files directly to `mm-link` of mahimahi, so the format is the
+/// mahimahi format with the following differences to [`MahimahiExt::mahimahi_to_string`]:
+///
+/// - Every timestamp (including the last one) is terminated by a newline (`\n`), as the trace
+///   files shipped with Pantheon are.
+/// - No header or metadata comment is emitted. `mm-link` parses every line as a timestamp and
+///   rejects anything else, so metadata must be kept outside of the trace file.
+/// - A trace without any delivery opportunity is rejected, as `mm-link` refuses it. This includes
+///   both a trace lasting for no time and a trace whose bandwidth is too low to deliver a packet.
+///
+/// The time unit is the same as mahimahi (milliseconds).
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::mahimahi::pantheon;
+/// # use netem_trace::model::StaticBwConfig;
+/// # use netem_trace::{Bandwidth, Duration};
+/// let mut static_bw = StaticBwConfig::new()
+///     .bw(Bandwidth::from_mbps(12))
+///     .duration(Duration::from_secs(1))
+///     .build();
+/// assert_eq!(
+///     pantheon::to_string(&mut static_bw, &Duration::from_millis(3)),
+///     Ok("1\n2\n3\n".to_string())
+/// );
+/// ```
+pub mod pantheon {
+    use super::{Elapsed, Mahimahi};
+    use crate::display::fmt_delay;
+    use crate::Duration;
+
+    /// Generate a trace in the format expected by Pantheon.
+    ///
+    /// Returns `Err` string if the trace contains no timestamp, telling whether the trace lasts for
+    /// no time or has no packet delivery opportunity within its duration (e.g. zero bandwidth).
+    pub fn to_string<T: Mahimahi + ?Sized>(
+        trace: &mut T,
+        total_dur: &Duration,
+    ) -> Result<String, String> {
+        let mut trace = Elapsed {
+            trace,
+            elapsed: Duration::ZERO,
+        };
+        let ts = trace.mahimahi(total_dur);
+        if ts.is_empty() {
+            let elapsed = trace.elapsed.min(*total_dur);
+            return Err(if elapsed.is_zero() {
+                "trace must last for a nonzero amount of time".to_string()
+            } else {
+                format!(
+                    "trace has no packet delivery opportunities within {}",
+                    fmt_delay(elapsed)
+                )
+            });
+        }
+        Ok(ts.iter().map(|t| format!("{}\n", t)).collect())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::fixtures::Fixtures;
     use crate::model::StaticBwConfig;
     use crate::Bandwidth;
 
@@ -242,15 +753,217 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_iter() {
+        use crate::model::{NormalizedBwConfig, SawtoothBwConfig};
+
+        // the eager generation before `mahimahi_iter`
+        fn mahimahi_vec(trace: &mut dyn BwTrace, total_dur: &Duration) -> Vec<u64> {
+            let mut timestamp = MAHIMAHI_TS_BIN;
+            let mut v = Vec::new();
+            let mut transfer = Bandwidth::from_bps(0);
+            let mut bin_rem = MAHIMAHI_TS_BIN;
+            while let Some((bw, mut dur)) = trace.next_bw() {
+                if timestamp > *total_dur {
+                    break;
+                }
+                while (timestamp <= *total_dur) && !dur.is_zero() {
+                    let bin = bin_rem.min(dur);
+                    bin_rem -= bin;
+                    dur -= bin;
+                    let bin_factor = bin.as_secs_f64() / MAHIMAHI_TS_BIN.as_secs_f64();
+                    transfer += bw.mul_f64(bin_factor);
+                    while transfer >= MTU_PER_MILLIS {
+                        v.push(saturating_duration_as_millis_u64!(timestamp));
+                        transfer -= MTU_PER_MILLIS;
+                    }
+                    if bin_rem.is_zero() {
+                        bin_rem = MAHIMAHI_TS_BIN;
+                        timestamp += MAHIMAHI_TS_BIN;
+                    }
+                }
+            }
+            v
+        }
+
+        let configs: Vec<Box<dyn BwTraceConfig>> = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(18))
+                    .duration(Duration::from_millis(50)),
+            ),
+            Box::new(
+                NormalizedBwConfig::new()
+                    .mean(Bandwidth::from_mbps(12))
+                    .std_dev(Bandwidth::from_mbps(4))
+                    .duration(Duration::from_millis(200))
+                    .step(Duration::from_micros(300))
+                    .seed(42),
+            ),
+            Box::new(
+                SawtoothBwConfig::new()
+                    .bottom(Bandwidth::from_kbps(500))
+                    .top(Bandwidth::from_mbps(36))
+                    .interval(Duration::from_millis(70))
+                    .duration(Duration::from_millis(300))
+                    .step(Duration::from_micros(1700)),
+            ),
+        ];
+        for config in configs {
+            for total in [
+                Duration::ZERO,
+                Duration::from_micros(1500),
+                Duration::from_millis(120),
+                Duration::MAX,
+            ] {
+                let expected = mahimahi_vec(config.clone().into_model().as_mut(), &total);
+                let mut model = config.clone().into_model();
+                assert_eq!(model.mahimahi_iter(total).collect::<Vec<_>>(), expected);
+                // the model is consumed as far as by the eager generation
+                let mut reference = config.clone().into_model();
+                mahimahi_vec(reference.as_mut(), &total);
+                assert_eq!(model.next_bw(), reference.next_bw());
+            }
+        }
+    }
+
+    #[test]
+    fn test_expected_count() {
+        for (kbps, millis) in [
+            (12_000, 5),
+            (24_000, 1000),
+            (1_500, 1000),
+            (7_000, 333),
+            (100_000, 2500),
+            (123_456, 777),
+            (0, 100),
+        ] {
+            let config = StaticBwConfig::new()
+                .bw(Bandwidth::from_kbps(kbps))
+                .duration(Duration::from_secs(1));
+            let total_dur = Duration::from_millis(millis);
+            let expected = config.clone().build().mahimahi_expected_count(&total_dur);
+            let actual = config.build().mahimahi(&total_dur).len() as u64;
+            assert!(
+                expected.abs_diff(actual) <= 1,
+                "{}kbps for {}ms: expected {}, actual {}",
+                kbps,
+                millis,
+                expected,
+                actual
+            );
+        }
+
+        // whole packets per millisecond are exact
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(36))
+            .duration(Duration::from_secs(1))
+            .build();
+        assert_eq!(
+            static_bw.mahimahi_expected_count(&Duration::from_millis(2500)),
+            3000
+        );
+        // the partial millisecond at the end is not counted, as by `mahimahi`
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .build();
+        assert_eq!(
+            static_bw.mahimahi_expected_count(&Duration::from_micros(5500)),
+            5
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(3))
+            .build();
+        let content = static_bw.mahimahi_to_string_with_header(
+            &Duration::from_millis(4),
+            "config: static\n\nseed: 42",
+        );
+        assert_eq!(
+            content,
+            "# config: static\n#\n# seed: 42\n# duration: 3ms\n# packets: 6\n# mean bandwidth: 24.000Mbps\n1\n1\n2\n2\n3\n3"
+        );
+
+        // the statistics cover the trace only, not an unbounded `total_dur`
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(3))
+            .build();
+        assert_eq!(
+            static_bw.mahimahi_to_string_with_header(&Duration::MAX, ""),
+            "# duration: 3ms\n# packets: 6\n# mean bandwidth: 24.000Mbps\n1\n1\n2\n2\n3\n3"
+        );
+
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1))
+            .build();
+        assert_eq!(
+            static_bw.mahimahi_to_string_with_header(&Duration::from_millis(2), ""),
+            "# duration: 2ms\n# packets: 2\n# mean bandwidth: 12.000Mbps\n1\n2"
+        );
+    }
+
+    #[test]
+    fn test_pantheon() {
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(24))
+            .duration(Duration::from_millis(3))
+            .build();
+        let content = pantheon::to_string(&mut static_bw, &Duration::MAX).unwrap();
+        assert_eq!(content, "1\n1\n2\n2\n3\n3\n");
+        // every line is a timestamp in milliseconds, readable by mm-link
+        let trace = content
+            .lines()
+            .map(|line| line.parse::<u64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(trace, [1, 1, 2, 2, 3, 3]);
+        assert!(load_mahimahi_trace(trace, None).is_ok());
+
+        let mut zero_bw = StaticBwConfig::new()
+            .bw(Bandwidth::ZERO)
+            .duration(Duration::from_millis(3))
+            .build();
+        assert_eq!(
+            pantheon::to_string(&mut zero_bw, &Duration::MAX),
+            Err("trace has no packet delivery opportunities within 3 ms".to_string())
+        );
+        let mut zero_bw = StaticBwConfig::new()
+            .bw(Bandwidth::ZERO)
+            .duration(Duration::from_secs(10))
+            .build();
+        assert_eq!(
+            pantheon::to_string(&mut zero_bw, &Duration::from_millis(500)),
+            Err("trace has no packet delivery opportunities within 500 ms".to_string())
+        );
+        let mut empty = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::ZERO)
+            .build();
+        assert_eq!(
+            pantheon::to_string(&mut empty, &Duration::MAX),
+            Err("trace must last for a nonzero amount of time".to_string())
+        );
+    }
+
     #[test]
     fn test_load() {
         assert!(matches!(
             load_mahimahi_trace(vec![0, 2, 4, 3], None),
-            Err("timestamps must be monotonically nondecreasing")
+            Err(MahimahiError::Invalid(
+                "timestamps must be monotonically nondecreasing"
+            ))
         ));
         assert!(matches!(
             load_mahimahi_trace(vec![0, 0, 0], None),
-            Err("trace must last for a nonzero amount of time")
+            Err(MahimahiError::Invalid(
+                "trace must last for a nonzero amount of time"
+            ))
         ));
 
         let trace = vec![1, 1, 5, 6];
@@ -339,6 +1052,256 @@ mod test {
         assert_eq!(bw.next_bw(), None);
     }
 
+    #[test]
+    fn test_load_with_bin() {
+        let collect = |config: RepeatedBwPatternConfig| {
+            let mut model = config.count(1).build();
+            std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>()
+        };
+        for trace in [vec![1, 1, 5, 6], vec![0, 0, 2, 2, 3, 3, 6, 6]] {
+            let expected = collect(load_mahimahi_trace(trace.clone(), None).unwrap());
+            // a 1000us bin reproduces the 1ms results
+            let micros =
+                load_mahimahi_trace_with_bin(trace.clone(), Duration::from_micros(1000), None)
+                    .unwrap();
+            assert_eq!(micros.count, 0);
+            assert_eq!(collect(micros), expected);
+            // a 500us bin doubles the bandwidth of each timestamp within half the time
+            let halved = collect(
+                load_mahimahi_trace_with_bin(trace, Duration::from_micros(500), None).unwrap(),
+            );
+            assert_eq!(
+                halved,
+                expected
+                    .iter()
+                    .map(|(bw, duration)| (*bw * 2, *duration / 2))
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert!(matches!(
+            load_mahimahi_trace_with_bin(vec![1], Duration::ZERO, None),
+            Err(MahimahiError::Invalid("bin must be nonzero"))
+        ));
+    }
+
+    #[test]
+    fn test_load_streaming() {
+        let collect = |config: RepeatedBwPatternConfig| {
+            let count = config.count;
+            let mut model = config.count(1).build();
+            (
+                count,
+                std::iter::from_fn(|| model.next_bw()).collect::<Vec<_>>(),
+            )
+        };
+        let mut static_bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(18))
+            .duration(Duration::from_millis(50))
+            .build();
+        for trace in [
+            vec![1, 1, 5, 6],
+            vec![0, 0, 2, 2, 3, 3, 6, 6],
+            vec![3],
+            static_bw.mahimahi(&Duration::MAX),
+        ] {
+            let content = itertools::join(&trace, "\n");
+            for count in [None, Some(1), Some(3)] {
+                let expected = load_mahimahi_trace(trace.clone(), count).unwrap();
+                let streamed = load_mahimahi_trace_streaming(content.as_bytes(), count).unwrap();
+                assert_eq!(collect(streamed), collect(expected));
+            }
+        }
+
+        // whitespace, empty lines and comments are skipped
+        let streamed =
+            load_mahimahi_trace_streaming("# header\n 1 \n\n1\r\n5\n6\n".as_bytes(), None).unwrap();
+        assert_eq!(
+            collect(streamed),
+            collect(load_mahimahi_trace(vec![1, 1, 5, 6], None).unwrap())
+        );
+
+        let err = load_mahimahi_trace_streaming("1\n\nx\n".as_bytes(), None)
+            .err()
+            .unwrap();
+        assert!(matches!(err, MahimahiError::Parse(3, ref line) if line == "x"));
+        assert_eq!(err.to_string(), "invalid timestamp on line 3: 'x'");
+        for (content, message) in [
+            (
+                "0\n2\n4\n3",
+                "timestamps must be monotonically nondecreasing",
+            ),
+            ("0\n0\n", "trace must last for a nonzero amount of time"),
+            ("", "trace must last for a nonzero amount of time"),
+        ] {
+            let err = load_mahimahi_trace_streaming(content.as_bytes(), None)
+                .err()
+                .unwrap();
+            assert!(matches!(err, MahimahiError::Invalid(e) if e == message));
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let fixtures = Fixtures::new("mahimahi-load");
+
+        let path = fixtures.write("valid.mahi", "1\n\n1  \n5\t\n\n6\n\n");
+        let mut bw = load_mahimahi_trace_from_file(&path, Some(1))
+            .unwrap()
+            .build();
+        let mut expected = load_mahimahi_trace(vec![1, 1, 5, 6], Some(1))
+            .unwrap()
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| bw.next_bw()).collect::<Vec<_>>(),
+            std::iter::from_fn(|| expected.next_bw()).collect::<Vec<_>>()
+        );
+
+        let path = fixtures.write("malformed.mahi", "1\n2\n\n3ms\n4\n");
+        assert!(matches!(
+            load_mahimahi_trace_from_file(&path, None),
+            Err(MahimahiError::Parse(4, line)) if line == "3ms"
+        ));
+
+        let path = fixtures.write("invalid.mahi", "2\n1\n");
+        assert!(matches!(
+            load_mahimahi_trace_from_file(&path, None),
+            Err(MahimahiError::Invalid(
+                "timestamps must be monotonically nondecreasing"
+            ))
+        ));
+
+        let err = load_mahimahi_trace_from_file(fixtures.path("missing.mahi"), None)
+            .err()
+            .unwrap();
+        assert!(matches!(&err, MahimahiError::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_to_file() {
+        let fixtures = Fixtures::new("mahimahi-to-file");
+        let config = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(18))
+            .duration(Duration::from_millis(50));
+
+        let path = fixtures.path("static.mahi");
+        for total in [Duration::ZERO, Duration::from_millis(30)] {
+            config
+                .clone()
+                .build()
+                .mahimahi_to_file(&total, &path)
+                .unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&path).unwrap(),
+                config.clone().build().mahimahi_to_string(&total)
+            );
+        }
+
+        let err = config
+            .build()
+            .mahimahi_to_file(
+                &Duration::from_millis(30),
+                fixtures.path("missing").join("static.mahi"),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(feature = "delay-model")]
+    fn test_delay_to_file() {
+        use crate::model::StaticDelayConfig;
+        use crate::Delay;
+
+        let fixtures = Fixtures::new("mahimahi-delay-to-file");
+        let config = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_millis(5));
+
+        let path = fixtures.path("static.delay");
+        config
+            .clone()
+            .build()
+            .mahimahi_delay_to_file(&Duration::MAX, &path)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "10\n10\n10\n10\n10"
+        );
+
+        let err = config
+            .build()
+            .mahimahi_delay_to_file(
+                &Duration::MAX,
+                fixtures.path("missing").join("static.delay"),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(feature = "delay-model")]
+    fn test_delay_interoperability() {
+        use crate::model::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+        use crate::{Delay, DelayTrace};
+
+        let mut static_delay = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_millis(5))
+            .build();
+        let trace = static_delay.mahimahi_delay(&Duration::MAX);
+        assert_eq!(trace, [10, 10, 10, 10, 10]);
+        let mut delay = load_mahimahi_delay_trace(trace, Some(1)).unwrap().build();
+        assert_eq!(
+            delay.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_millis(5)))
+        );
+        assert_eq!(delay.next_delay(), None);
+
+        let config = RepeatedDelayPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    StaticDelayConfig::new()
+                        .delay(Delay::from_millis(10))
+                        .duration(Duration::from_millis(2)),
+                ) as Box<dyn DelayTraceConfig>,
+                Box::new(
+                    StaticDelayConfig::new()
+                        .delay(Delay::from_micros(20500))
+                        .duration(Duration::from_micros(1500)),
+                ) as Box<dyn DelayTraceConfig>,
+            ])
+            .count(0);
+        // the delay at the start of each millisecond, truncated to milliseconds
+        let trace = config
+            .clone()
+            .build()
+            .mahimahi_delay(&Duration::from_millis(8));
+        assert_eq!(trace, [10, 10, 20, 20, 10, 10, 20, 10]);
+        assert_eq!(
+            config
+                .build()
+                .mahimahi_delay_to_string(&Duration::from_millis(3)),
+            "10\n10\n20"
+        );
+        let mut loaded = load_mahimahi_delay_trace(trace.clone(), None)
+            .unwrap()
+            .build();
+        assert_eq!(loaded.mahimahi_delay(&Duration::from_millis(8)), trace);
+        assert_eq!(
+            loaded.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_millis(2)))
+        );
+
+        assert!(matches!(
+            load_mahimahi_delay_trace(vec![], None),
+            Err(MahimahiError::Invalid(
+                "trace must last for a nonzero amount of time"
+            ))
+        ));
+    }
+
     #[test]
     fn test_interoperability() {
         // this check only works on non-zero timestamps trace, which has full interoperability
@@ -374,4 +1337,77 @@ mod test {
             vec![1, 1, 2, 2, 3, 3, 4, 5, 6, 7, 8, 8, 9, 9, 10, 10, 11, 12, 13, 14]
         );
     }
+
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::test_runner::RngSeed;
+
+        /// Generate a trace of non-zero timestamps from the gaps between consecutive timestamps, so
+        /// that it shrinks towards short and dense traces. A zero gap repeats the last timestamp.
+        fn nonzero_trace() -> impl Strategy<Value = Vec<u64>> {
+            let gap = prop_oneof![8 => 0..3u64, 1 => 3..100u64];
+            prop::collection::vec(gap, 1..200).prop_map(|gaps| {
+                gaps.into_iter()
+                    .scan(1, |ts, gap| {
+                        *ts += gap;
+                        Some(*ts)
+                    })
+                    .collect()
+            })
+        }
+
+        proptest! {
+            // a fixed seed keeps the test deterministic, so a failure is reproduced by rerunning it
+            #![proptest_config(ProptestConfig {
+                cases: 256,
+                rng_seed: RngSeed::Fixed(42),
+                failure_persistence: None,
+                ..ProptestConfig::default()
+            })]
+
+            #[test]
+            fn test_roundtrip(trace in nonzero_trace()) {
+                let last = *trace.last().unwrap();
+                let config = load_mahimahi_trace(trace.clone(), None).unwrap();
+                prop_assert_eq!(
+                    &config.clone().build().mahimahi(&Duration::from_millis(last)),
+                    &trace
+                );
+                // the trace repeats with a period of the last timestamp
+                let repeated: Vec<_> = trace
+                    .iter()
+                    .copied()
+                    .chain(trace.iter().map(|ts| ts + last))
+                    .collect();
+                prop_assert_eq!(
+                    config.build().mahimahi(&Duration::from_millis(2 * last)),
+                    repeated
+                );
+                let mut once = load_mahimahi_trace(trace.clone(), Some(1)).unwrap().build();
+                prop_assert_eq!(once.mahimahi(&Duration::MAX), trace);
+            }
+
+            #[test]
+            fn test_roundtrip_zero_leading(zeros in 1..4usize, trace in nonzero_trace()) {
+                // mahimahi sends the packets of zero timestamps at the end of each period, i.e. at
+                // the last timestamp, so they are regenerated as copies of the last timestamp
+                let last = *trace.last().unwrap();
+                let mut expected = trace.clone();
+                expected.extend(std::iter::repeat_n(last, zeros));
+                let zero_leading: Vec<_> = std::iter::repeat_n(0, zeros).chain(trace).collect();
+                let generated = load_mahimahi_trace(zero_leading, None)
+                    .unwrap()
+                    .build()
+                    .mahimahi(&Duration::from_millis(last));
+                prop_assert_eq!(&generated, &expected);
+                // the regenerated trace has no zero timestamps, so it is stable from then on
+                let regenerated = load_mahimahi_trace(generated.clone(), None)
+                    .unwrap()
+                    .build()
+                    .mahimahi(&Duration::from_millis(last));
+                prop_assert_eq!(regenerated, generated);
+            }
+        }
+    }
 }
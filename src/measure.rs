@@ -0,0 +1,86 @@
+//! Sampling and integration of bandwidth traces, shared by [`crate::analysis`], [`crate::mahimahi`]
+//! and the bandwidth model configurations.
+
+#[cfg(any(feature = "analysis", feature = "mahimahi"))]
+use crate::units::bps_u128;
+use crate::{Bandwidth, BwTrace, Duration};
+
+/// Sample the bandwidth of a trace at nondecreasing time points.
+///
+/// The bandwidth of an exhausted trace is regarded as [`Bandwidth::ZERO`].
+#[cfg(any(feature = "analysis", feature = "bw-model"))]
+pub(crate) struct BwSampler<'a, T: BwTrace + ?Sized> {
+    trace: &'a mut T,
+    current: Bandwidth,
+    /// The end time of the current segment.
+    end: Duration,
+    exhausted: bool,
+}
+
+#[cfg(any(feature = "analysis", feature = "bw-model"))]
+impl<'a, T: BwTrace + ?Sized> BwSampler<'a, T> {
+    pub(crate) fn new(trace: &'a mut T) -> Self {
+        Self {
+            trace,
+            current: Bandwidth::ZERO,
+            end: Duration::ZERO,
+            exhausted: false,
+        }
+    }
+
+    /// Returns the bandwidth at time `t`. `t` must not be smaller than that of the last call.
+    pub(crate) fn sample(&mut self, t: Duration) -> Bandwidth {
+        while !self.exhausted && self.end <= t {
+            match self.trace.next_bw() {
+                Some((bw, duration)) => {
+                    self.current = bw;
+                    self.end = self.end.saturating_add(duration);
+                }
+                None => {
+                    self.current = Bandwidth::ZERO;
+                    self.exhausted = true;
+                }
+            }
+        }
+        self.current
+    }
+}
+
+/// Accumulates the bytes transferred over a sequence of bandwidth segments.
+///
+/// The products are kept in bit-nanoseconds to avoid rounding errors on each segment.
+#[cfg(any(feature = "analysis", feature = "mahimahi"))]
+#[derive(Default)]
+pub(crate) struct ByteCounter {
+    pub(crate) bit_nanos: u128,
+}
+
+#[cfg(any(feature = "analysis", feature = "mahimahi"))]
+impl ByteCounter {
+    pub(crate) fn add(&mut self, bw: Bandwidth, duration: Duration) {
+        self.bit_nanos = self
+            .bit_nanos
+            .saturating_add(bps_u128(bw).saturating_mul(duration.as_nanos()));
+    }
+
+    pub(crate) fn bytes(&self) -> u64 {
+        u64::try_from(self.bit_nanos / 8_000_000_000).unwrap_or(u64::MAX)
+    }
+}
+
+/// Compute the total bytes transferable over the first `total` of a trace, see
+/// [`crate::analysis::total_bytes`].
+#[cfg(any(feature = "analysis", feature = "mahimahi"))]
+pub(crate) fn total_bytes(trace: &mut (impl BwTrace + ?Sized), total: Duration) -> u64 {
+    let mut counter = ByteCounter::default();
+    let mut elapsed = Duration::ZERO;
+    while elapsed < total {
+        let Some((bw, duration)) = trace.next_bw() else {
+            break;
+        };
+        let duration = duration.min(total - elapsed);
+        counter.add(bw, duration);
+        elapsed += duration;
+    }
+    counter.bytes()
+}
@@ -5,9 +5,46 @@
 //! ## Predefined models
 //!
 //! - [`StaticBw`]: A trace model with static bandwidth.
+//! - [`EnvBwConfig`]: A configuration of [`StaticBw`] reading the bandwidth from an environment variable, e.g. for tuning in CI.
 //! - [`NormalizedBw`]: A trace model whose bandwidth subjects to a normal distribution (can set upper and lower bounds, and can configure it to be truncated with `truncated-normal` feature enabled).
 //! - [`RepeatedBwPattern`]: A trace model with a repeated bandwidth pattern.
 //! - [`TraceBw`]: A trace model to replay compact bandwidth changes from file, especially useful for online sampled records.
+//! - [`SequenceBw`]: A trace model to replay an explicit sequence of bandwidth segments, especially useful for hand-written short traces.
+//! - [`TaggedBw`]: A wrapper model attaching a tag to every segment of the inner model.
+//! - [`RandomOutageBw`]: A wrapper model overlaying random brief drops to zero bandwidth on the inner model.
+//! - [`EmpiricalBw`]: A trace model whose bandwidth subjects to an empirical distribution given by its CDF.
+//! - [`WindowMaxBw`]: A wrapper model emitting the maximum bandwidth of the inner model over a trailing window.
+//! - [`SplineBw`]: A trace model smoothly interpolating sparse bandwidth points with a natural cubic spline.
+//! - [`SineBw`]: A trace model whose bandwidth oscillates smoothly as a sine wave.
+//! - [`SquareBw`]: A trace model whose bandwidth alternates between two fixed levels as a square wave.
+//! - [`ScheduleBw`]: A trace model following a schedule of bandwidth keyed to the wall-clock time of day.
+//! - [`FloorPlusVariableBw`]: A wrapper model adding a static floor to the bandwidth of a variable model.
+//! - [`ScaledBw`]: A wrapper model scaling the bandwidth of the inner model by a factor.
+//! - [`ClampedBw`]: A wrapper model clamping the bandwidth of the inner model within bounds.
+//! - [`RandomPhaseBw`]: A wrapper model looping the inner model forever from a random offset.
+//! - [`MarkovBw`]: A trace model whose bandwidth follows a discrete Markov chain over a set of states.
+//! - [`HysteresisBw`]: A wrapper model suppressing the bandwidth changes of the inner model within a threshold.
+//! - [`RampBw`]: A trace model whose bandwidth changes linearly from a start value to an end value.
+//! - [`RandomPatternBw`]: A trace model with a pattern of bandwidth traces picked at random by their weights.
+//! - [`ReverseBw`]: A wrapper model playing a finite inner model backwards.
+//! - [`PoissonBw`]: A trace model whose bandwidth is driven by Poisson-distributed packet arrivals.
+//! - [`SumBw`]: A wrapper model summing the bandwidth of multiple inner models aligned in time.
+//! - [`WeibullBw`]: A trace model whose bandwidth subjects to a Weibull distribution, e.g. for heavy-tailed cellular throughput.
+//! - [`GammaBw`]: A trace model whose bandwidth subjects to a Gamma distribution, e.g. for right-skewed aggregate traffic demand.
+//! - [`SpliceBw`]: A wrapper model overriding the bandwidth of a base model within a time window.
+//! - [`QuantizedBw`]: A wrapper model snapping the bandwidth of the inner model to the nearest of discrete levels.
+//! - [`StepBw`]: A trace model emitting a staircase of bandwidths, each held for a fixed duration.
+//! - [`ParetoBw`]: A trace model alternating between on and off periods with Pareto distributed durations.
+//! - [`DelayedStartBw`]: A wrapper model prepending a period of zero bandwidth to the inner model.
+//! - [`ExponentialDwellBw`]: A trace model switching between random bandwidth levels after exponentially distributed dwell times.
+//! - [`LossAsGapsBw`]: A model zeroing the segments of a bandwidth model at random by the rate of a loss model (with feature `loss-model` enabled).
+//!
+//! ## Lints
+//!
+//! The configs of some models provide `lint` to check for likely mistakes, e.g.
+//! [`NormalizedBwConfig::lint`], which returns a warning message for each of them. The check is
+//! also performed on build, where the warnings are emitted through the `log` crate if `logging`
+//! feature is enabled.
 //!
 //! ## Examples
 //!
@@ -55,13 +92,24 @@
 //! );
 //! assert_eq!(model.next_bw(), None);
 //! ```
-use crate::{Bandwidth, BwTrace, Duration};
+use super::Repeatable;
+#[cfg(feature = "logging")]
+use crate::display::{fmt_bandwidth, fmt_delay};
+use crate::measure::BwSampler;
+use crate::{Bandwidth, BandwidthExt, BwTrace, Duration, Resettable};
 use dyn_clone::DynClone;
 use rand::rngs::StdRng;
-use rand::SeedableRng;
-use rand_distr::{Distribution, Normal};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_distr::{Distribution, Exp, Gamma, Normal, Pareto, Poisson, Weibull};
+use std::collections::VecDeque;
 
-const DEFAULT_RNG_SEED: u64 = 42;
+use super::{EmptyPatternGuard, ModelBuildError, Rewind, DEFAULT_RNG_SEED};
+#[cfg(feature = "loss-model")]
+use super::{LossTraceConfig, SegmentMerger};
+#[cfg(feature = "rng-state")]
+use super::{RngState, RngStateError};
+#[cfg(feature = "loss-model")]
+use crate::{LossPattern, LossTrace};
 
 /// This trait is used to convert a bandwidth trace configuration into a bandwidth trace model.
 ///
@@ -72,8 +120,123 @@ const DEFAULT_RNG_SEED: u64 = 42;
 #[cfg_attr(feature = "serde", typetag::serde)]
 pub trait BwTraceConfig: DynClone + Send {
     fn into_model(self: Box<Self>) -> Box<dyn BwTrace>;
+
+    /// Returns whether the configuration is nested deeper than `max_depth` levels.
+    ///
+    /// A configuration without any inner configuration has a depth of 1, and each level of
+    /// wrapping (e.g. with [`RepeatedBwPatternConfig`]) adds 1 to the depth.
+    ///
+    /// Configurations containing inner configurations should override this method. The check
+    /// never recurses deeper than `max_depth`, so it is safe for arbitrarily deep configurations.
+    fn exceeds_depth(&self, max_depth: usize) -> bool {
+        max_depth == 0
+    }
+
+    /// Returns whether the model built from this configuration never ends.
+    ///
+    /// Configurations containing inner configurations (e.g. [`RepeatedBwPatternConfig`]) should
+    /// override this method according to their inner configurations.
+    ///
+    /// The default implementation returns `false`.
+    ///
+    /// Check this before draining a model with no time limit, e.g. generating a mahimahi trace up to
+    /// [`Duration::MAX`], which never returns for an infinite model.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{BwTraceConfig, Forever, RepeatedBwPatternConfig, StaticBwConfig};
+    /// # use netem_trace::Duration;
+    /// let config = StaticBwConfig::new().duration(Duration::from_secs(1));
+    /// assert!(!config.is_infinite());
+    /// assert!(config.clone().forever().is_infinite());
+    /// let pattern = RepeatedBwPatternConfig::new().pattern(vec![Box::new(config)]);
+    /// assert!(!pattern.clone().count(2).is_infinite());
+    /// assert!(pattern.count(0).is_infinite());
+    /// ```
+    fn is_infinite(&self) -> bool {
+        false
+    }
+
+    /// Returns the total duration of the model built from this configuration, without building it.
+    ///
+    /// This is known in advance for every model whose total duration is fixed by its configuration,
+    /// even if its bandwidth is random, e.g. [`StaticBwConfig`], [`TraceBwConfig`],
+    /// [`NormalizedBwConfig`] or [`PoissonBwConfig`]. It is also known for some configurations
+    /// containing inner configurations, e.g. [`RepeatedBwPatternConfig`] with a nonzero `count` whose
+    /// inner configurations all have a known total duration. The total duration saturates at
+    /// [`Duration::MAX`].
+    ///
+    /// The default implementation returns `None`, i.e. the model is infinite or its duration is not
+    /// known in advance.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{BwTraceConfig, RepeatedBwPatternConfig, StaticBwConfig};
+    /// # use netem_trace::Duration;
+    /// let config = RepeatedBwPatternConfig::new()
+    ///     .pattern(vec![
+    ///         Box::new(StaticBwConfig::new().duration(Duration::from_secs(1))),
+    ///         Box::new(StaticBwConfig::new().duration(Duration::from_millis(500))),
+    ///     ])
+    ///     .count(2);
+    /// assert_eq!(config.total_duration(), Some(Duration::from_secs(3)));
+    /// assert_eq!(config.count(0).total_duration(), None);
+    /// ```
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns whether the models built from this and `other` configuration produce the same bandwidth,
+    /// sampled every `sample_step` within `[0, sample_total)`.
+    ///
+    /// This is a probabilistic equivalence check, e.g. for caching: two configurations may be regarded
+    /// as equal even if they differ between the sample points or after `sample_total`. Returns `false`
+    /// on the first difference. The bandwidth of an exhausted model is regarded as [`Bandwidth::ZERO`].
+    /// If `sample_step` is zero, only time zero is sampled.
+    ///
+    /// Both configurations are cloned, so this does not consume them.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{BwTraceConfig, StaticBwConfig, TraceBwConfig};
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let static_bw = StaticBwConfig::new()
+    ///     .bw(Bandwidth::from_mbps(12))
+    ///     .duration(Duration::from_secs(1));
+    /// let trace_bw = TraceBwConfig::new()
+    ///     .pattern(vec![(Duration::from_millis(500), vec![Bandwidth::from_mbps(12); 2])]);
+    /// assert!(static_bw.output_eq(&trace_bw, Duration::from_millis(100), Duration::from_secs(2)));
+    /// ```
+    fn output_eq(
+        &self,
+        other: &dyn BwTraceConfig,
+        sample_step: Duration,
+        sample_total: Duration,
+    ) -> bool {
+        let mut this = dyn_clone::clone_box(self).into_model();
+        let mut other = dyn_clone::clone_box(other).into_model();
+        let mut this = BwSampler::new(&mut *this);
+        let mut other = BwSampler::new(&mut *other);
+        let mut t = Duration::ZERO;
+        while t < sample_total {
+            if this.sample(t) != other.sample(t) {
+                return false;
+            }
+            t = match t.checked_add(sample_step) {
+                Some(next) if next > t => next,
+                _ => break,
+            };
+        }
+        true
+    }
 }
 
+/// The default maximum nesting depth of configurations, see [`RepeatedBwPatternConfig::validate`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
 dyn_clone::clone_trait_object!(BwTraceConfig);
 
 #[cfg(feature = "serde")]
@@ -117,7 +280,56 @@ pub struct StaticBwConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+}
+
+/// The configuration of a [`StaticBw`] whose bandwidth is read from an environment variable when
+/// built, e.g. to tune the emulated bandwidth in CI without editing the configuration files.
+///
+/// With feature `human` enabled, the variable is parsed in human-readable format (e.g. `"12Mbps"`),
+/// otherwise as an integer number of bps. If the variable is not configured, unset or unparseable,
+/// the `default_bw` bandwidth is used.
+///
+/// Use [`EnvBwConfig::build_with_lookup`] to read the variable from somewhere other than the process
+/// environment, e.g. in tests.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::EnvBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut env_bw = EnvBwConfig::new()
+///     .var("NETEM_TRACE_DOC_EXAMPLE_BW")
+///     .default_bw(Bandwidth::from_mbps(24))
+///     .duration(Duration::from_secs(1))
+///     .build();
+/// assert_eq!(env_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_secs(1))));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct EnvBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub var: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub default_bw: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub duration: Option<Duration>,
 }
@@ -126,6 +338,17 @@ pub struct StaticBwConfig {
 ///
 /// The bandwidth will subject to N(mean, std_dev), but bounded within [lower_bound, upper_bound] (optional)
 ///
+/// The `step` describes how long between two consecutive bandwidth samples. Each segment lasts for
+/// `min(step, remaining duration)`, so a `step` longer than `duration` results in a single segment of
+/// `duration`, which is reported by [`NormalizedBwConfig::lint`].
+///
+/// If `duration` is not a multiple of `step`, the last segment is shorter than `step` while its
+/// bandwidth is sampled just like the others. A mean computed over the samples (rather than weighted
+/// by their durations) then gives this partial step the same weight as a full one, which skews it. Set
+/// `exact_duration` to `false` to drop the trailing partial step instead, so that every segment lasts
+/// exactly `step` and the trace may be shorter than `duration`. It defaults to `true`, i.e. the trace
+/// always lasts `duration`.
+///
 /// ## Examples
 ///
 /// A simple example without any bound on bandwidth:
@@ -140,7 +363,7 @@ pub struct StaticBwConfig {
 ///     .step(Duration::from_millis(100))
 ///     .seed(42)
 ///     .build();
-/// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12069427), Duration::from_millis(100))));
+/// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12069428), Duration::from_millis(100))));
 /// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12132938), Duration::from_millis(100))));
 /// ```
 ///
@@ -158,19 +381,21 @@ pub struct StaticBwConfig {
 ///     .upper_bound(Bandwidth::from_kbps(12100))
 ///     .lower_bound(Bandwidth::from_kbps(11900))
 ///     .build();
-/// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12069427), Duration::from_millis(100))));
+/// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12069428), Duration::from_millis(100))));
 /// assert_eq!(normal_bw.next_bw(), Some((Bandwidth::from_bps(12100000), Duration::from_millis(100))));
 /// ```
 #[derive(Debug, Clone)]
-pub struct NormalizedBw {
+pub struct NormalizedBw<R = StdRng> {
     pub mean: Bandwidth,
     pub std_dev: Bandwidth,
     pub upper_bound: Option<Bandwidth>,
     pub lower_bound: Option<Bandwidth>,
     pub duration: Duration,
     pub step: Duration,
+    pub exact_duration: bool,
     pub seed: u64,
-    rng: StdRng,
+    total_duration: Duration,
+    rng: R,
     normal: Normal<f64>,
 }
 
@@ -207,16 +432,26 @@ pub struct NormalizedBwConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub duration: Option<Duration>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub step: Option<Duration>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub exact_duration: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub seed: Option<u64>,
 }
 
@@ -224,9 +459,11 @@ pub struct NormalizedBwConfig {
 ///
 /// The lowest value of the sawtooth is set by `bottom` while the highest value is set by `top`.
 /// The `interval` describes how long a sawtooth lasts. The `duty_ratio` describes how much the rising time of a sawtooth
-/// occupies the `interval`.
+/// occupies the `interval`. It must be within `[0, 1]`: `1.0` yields a pure rising ramp and `0.0` a pure falling ramp.
 ///
-/// The `step` describes how long between two consecutive bandwidth samples.
+/// The `step` describes how long between two consecutive bandwidth samples. Each segment lasts for
+/// `min(step, remaining duration)`, so a `step` longer than `duration` results in a single segment of
+/// `duration`, which is reported by [`SawtoothBwConfig::lint`].
 ///
 /// The noise of the sawtooth bandwidth will subject to N(0, std_dev), but bounded within [-lower_noise_bound, upper_noise_bound] (optional)
 ///
@@ -301,11 +538,11 @@ pub struct NormalizedBwConfig {
 ///     .build();
 /// assert_eq!(
 ///     sawtooth_bw.next_bw(),
-///     Some((Bandwidth::from_bps(12347139), Duration::from_millis(100)))
+///     Some((Bandwidth::from_bps(12347140), Duration::from_millis(100)))
 /// );
 /// assert_eq!(
 ///     sawtooth_bw.next_bw(),
-///     Some((Bandwidth::from_bps(13664690), Duration::from_millis(100)))
+///     Some((Bandwidth::from_bps(13664691), Duration::from_millis(100)))
 /// );
 /// assert_eq!(
 ///     sawtooth_bw.next_bw(),
@@ -317,7 +554,7 @@ pub struct NormalizedBwConfig {
 /// );
 /// ```
 #[derive(Debug, Clone)]
-pub struct SawtoothBw {
+pub struct SawtoothBw<R = StdRng> {
     pub bottom: Bandwidth,
     pub top: Bandwidth,
     pub interval: Duration,
@@ -329,7 +566,8 @@ pub struct SawtoothBw {
     pub upper_noise_bound: Option<Bandwidth>,
     pub lower_noise_bound: Option<Bandwidth>,
     current: Duration,
-    rng: StdRng,
+    total_duration: Duration,
+    rng: R,
     noise: Normal<f64>,
 }
 
@@ -354,7 +592,11 @@ pub struct SawtoothBwConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub interval: Option<Duration>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -362,13 +604,134 @@ pub struct SawtoothBwConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub std_dev: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub upper_noise_bound: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub lower_noise_bound: Option<Bandwidth>,
+}
+
+/// The model of a bandwidth trace oscillating smoothly as a sine wave.
+///
+/// The bandwidth is `mean + amplitude * sin(2π * current / period)`, where `current` is the time
+/// since the start of the model, sampled at the start of each `step`. It lasts for `duration`.
+///
+/// Like [`SawtoothBw`], a gaussian noise with `std_dev` can be added to the bandwidth, which can be
+/// bounded by `upper_noise_bound` and `lower_noise_bound`. The resulting bandwidth is clamped to be
+/// non-negative, so an `amplitude` larger than `mean` cuts off the troughs at zero.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::SineBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut sine_bw = SineBwConfig::new()
+///     .mean(Bandwidth::from_mbps(12))
+///     .amplitude(Bandwidth::from_mbps(4))
+///     .period(Duration::from_millis(400))
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .build();
+/// assert_eq!(sine_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(sine_bw.next_bw(), Some((Bandwidth::from_mbps(16), Duration::from_millis(100))));
+/// assert_eq!(sine_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(sine_bw.next_bw(), Some((Bandwidth::from_mbps(8), Duration::from_millis(100))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SineBw<R = StdRng> {
+    pub mean: Bandwidth,
+    pub amplitude: Bandwidth,
+    pub period: Duration,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    pub std_dev: Bandwidth,
+    pub upper_noise_bound: Option<Bandwidth>,
+    pub lower_noise_bound: Option<Bandwidth>,
+    current: Duration,
+    total_duration: Duration,
+    rng: R,
+    noise: Normal<f64>,
+}
+
+/// The configuration struct for [`SineBw`].
+///
+/// See [`SineBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct SineBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub mean: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub amplitude: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub period: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub duration: Option<Duration>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub step: Option<Duration>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -393,12 +756,105 @@ pub struct SawtoothBwConfig {
     pub lower_noise_bound: Option<Bandwidth>,
 }
 
+/// The model of a bandwidth trace alternating between two fixed levels as a square wave.
+///
+/// In each cycle of `period`, the bandwidth is `high` for the first `duty_ratio * period`,
+/// and `low` for the remainder. Unlike [`SawtoothBw`], there is no interpolation between the levels.
+///
+/// The bandwidth is emitted in segments of at most `step`, and a segment is cut short at the duty
+/// boundary and at the end of each cycle, so the level changes exactly on time even if `period`
+/// is not an integer multiple of `step`. It lasts for `duration`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::SquareBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut square_bw = SquareBwConfig::new()
+///     .low(Bandwidth::from_mbps(4))
+///     .high(Bandwidth::from_mbps(12))
+///     .period(Duration::from_millis(300))
+///     .duty_ratio(0.5)
+///     .step(Duration::from_millis(100))
+///     .duration(Duration::from_millis(600))
+///     .build();
+/// assert_eq!(square_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(square_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(50))));
+/// assert_eq!(square_bw.next_bw(), Some((Bandwidth::from_mbps(4), Duration::from_millis(100))));
+/// assert_eq!(square_bw.next_bw(), Some((Bandwidth::from_mbps(4), Duration::from_millis(50))));
+/// assert_eq!(square_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SquareBw {
+    pub low: Bandwidth,
+    pub high: Bandwidth,
+    pub period: Duration,
+    pub duty_ratio: f64,
+    pub step: Duration,
+    pub duration: Duration,
+    current: Duration,
+    total_duration: Duration,
+}
+
+/// The configuration struct for [`SquareBw`].
+///
+/// See [`SquareBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct SquareBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub low: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub high: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub period: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub duty_ratio: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+}
+
 /// The model contains an array of bandwidth trace models.
 ///
 /// Combine multiple bandwidth trace models into one bandwidth pattern,
 /// and repeat the pattern for `count` times.
 ///
-/// If `count` is 0, the pattern will be repeated forever.
+/// If `count` is 0, the pattern will be repeated forever. To play each model once in sequence,
+/// use [`RepeatedBwPatternConfig::once`].
 ///
 /// ## Examples
 ///
@@ -412,8 +868,10 @@ pub struct SawtoothBwConfig {
 /// # let config_file_content = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":\"12Mbps\",\"duration\":\"1s\"}},{\"StaticBwConfig\":{\"bw\":\"24Mbps\",\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The content would be "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let config_file_content = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let config_file_content = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":1000000000}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":1000000000}}],\"count\":2}}";
 /// let des: Box<dyn BwTraceConfig> = serde_json::from_str(config_file_content).unwrap();
 /// let mut model = des.into_model();
 /// assert_eq!(
@@ -458,8 +916,10 @@ pub struct SawtoothBwConfig {
 /// # let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":\"12Mbps\",\"duration\":\"1s\"}},{\"StaticBwConfig\":{\"bw\":\"24Mbps\",\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The json string would be "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":12000000},\"duration\":1000000000}},{\"StaticBwConfig\":{\"bw\":{\"gbps\":0,\"bps\":24000000},\"duration\":1000000000}}],\"count\":2}}";
 /// assert_eq!(ser_str, json_str);
 /// ```
 pub struct RepeatedBwPattern {
@@ -468,6 +928,9 @@ pub struct RepeatedBwPattern {
     current_model: Option<Box<dyn BwTrace>>,
     current_cycle: usize,
     current_pattern: usize,
+    current_tag: Option<String>,
+    cached_models: Vec<Option<Box<dyn BwTrace>>>,
+    guard: EmptyPatternGuard,
 }
 
 /// The configuration struct for [`RepeatedBwPattern`].
@@ -484,6 +947,10 @@ pub struct RepeatedBwPatternConfig {
 /// It replays the bandwidth changes according to a trace file,
 /// and is necessary for replaying sampled traces from Internet or production.
 ///
+/// By default, each bandwidth of the pattern is held for its whole duration. With a sub-sample `step`
+/// configured, each segment is split into sub-samples of at most `step`, whose bandwidth is computed
+/// by the [`Interpolation`] between the bandwidth of the segment and the one of the next segment.
+///
 /// ## Examples
 ///
 /// ```
@@ -534,8 +1001,10 @@ pub struct RepeatedBwPatternConfig {
 /// let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"TraceBwConfig\":{\"pattern\":[[\"1ms\",[\"2Mbps\",\"4Mbps\"]],[\"2ms\",[\"1Mbps\"]]]}}],\"count\":0}}";
 /// // The json string would be "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"TraceBwConfig\":{\"pattern\":[[{\"secs\":0,\"nanos\":1000000},[{\"gbps\":0,\"bps\":2000000},{\"gbps\":0,\"bps\":4000000}]],[{\"secs\":0,\"nanos\":2000000},[{\"gbps\":0,\"bps\":1000000}]]]}}],\"count\":0}}"
 /// // if the `human` feature is not enabled.
-/// #[cfg(not(feature = "human"))]
+/// #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"TraceBwConfig\":{\"pattern\":[[{\"secs\":0,\"nanos\":1000000},[{\"gbps\":0,\"bps\":2000000},{\"gbps\":0,\"bps\":4000000}]],[{\"secs\":0,\"nanos\":2000000},[{\"gbps\":0,\"bps\":1000000}]]]}}],\"count\":0}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let json_str = "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"TraceBwConfig\":{\"pattern\":[[1000000,[{\"gbps\":0,\"bps\":2000000},{\"gbps\":0,\"bps\":4000000}]],[2000000,[{\"gbps\":0,\"bps\":1000000}]]]}}],\"count\":0}}";
 /// assert_eq!(ser_str, json_str);
 ///
 /// let des: Box<dyn BwTraceConfig> = serde_json::from_str(json_str).unwrap();
@@ -553,6 +1022,44 @@ pub struct TraceBw {
     pub pattern: Vec<(Duration, Vec<Bandwidth>)>, // inner vector is never empty
     pub outer_index: usize,
     pub inner_index: usize,
+    pub interpolation: Interpolation,
+    pub step: Option<Duration>,
+    current: Duration,
+}
+
+/// How [`TraceBw`] computes the bandwidth of the sub-samples within a segment, when a sub-sample
+/// `step` is configured.
+///
+/// The bandwidth of a segment is considered to be sampled at the start of the segment, and each
+/// sub-sample is computed at its own start. The last segment is always held.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{Interpolation, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let config = TraceBwConfig::new()
+///     .pattern(vec![(
+///         Duration::from_secs(1),
+///         vec![Bandwidth::from_mbps(10), Bandwidth::from_mbps(20)],
+///     )])
+///     .step(Duration::from_millis(500));
+/// let mut linear = config.interpolation(Interpolation::Linear).build();
+/// assert_eq!(linear.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_millis(500))));
+/// assert_eq!(linear.next_bw(), Some((Bandwidth::from_mbps(15), Duration::from_millis(500))));
+/// assert_eq!(linear.next_bw(), Some((Bandwidth::from_mbps(20), Duration::from_millis(500))));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Hold the bandwidth of the segment, i.e. a step function.
+    #[default]
+    Hold,
+    /// Interpolate linearly from the bandwidth of the segment to the one of the next segment.
+    Linear,
+    /// Take the bandwidth of the nearer one of the segment start and the next segment start, rounding
+    /// half up to the next segment.
+    Nearest,
 }
 
 /// The configuration struct for [`TraceBw`].
@@ -566,12 +1073,32 @@ pub struct TraceBwConfig {
         all(feature = "serde", feature = "human"),
         serde(with = "tracebw_serde")
     )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration::trace_pattern")
+    )]
     pub pattern: Vec<(Duration, Vec<Bandwidth>)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub interpolation: Option<Interpolation>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
 }
 
 impl TraceBwConfig {
     pub fn new() -> Self {
-        Self { pattern: vec![] }
+        Self {
+            pattern: vec![],
+            interpolation: None,
+            step: None,
+        }
     }
 
     pub fn pattern(mut self, pattern: Vec<(Duration, Vec<Bandwidth>)>) -> Self {
@@ -579,26 +1106,320 @@ impl TraceBwConfig {
         self
     }
 
-    pub fn build(self) -> TraceBw {
-        TraceBw {
-            pattern: self
-                .pattern
-                .into_iter()
-                .filter(|(_, bandwidths)| !bandwidths.is_empty())
-                .collect(),
-            outer_index: 0,
-            inner_index: 0,
-        }
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = Some(interpolation);
+        self
     }
-}
 
-#[cfg(all(feature = "serde", feature = "human"))]
-mod tracebw_serde {
-    use super::*;
-    use serde::ser::SerializeSeq;
-    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
-    use std::fmt;
-    use std::ops::{Deref, DerefMut};
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Build the pattern from points gathered out of order, each of which is an absolute offset from
+    /// the start of the trace and the bandwidth sampled over the interval ending at the offset.
+    ///
+    /// Like the rows of the CSV format (with feature `csv`), a point lasts from the offset of the
+    /// previous point (or 0 for the first point) to its own offset. The points are sorted by offset,
+    /// and consecutive points lasting for the same duration are grouped into one entry of the pattern.
+    /// A point at offset 0 covers no time and is skipped.
+    ///
+    /// Returns [`ModelBuildError::InvalidConfig`] if two points have the same offset.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::TraceBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let config = TraceBwConfig::from_unsorted(vec![
+    ///     (Duration::from_millis(300), Bandwidth::from_mbps(3)),
+    ///     (Duration::from_millis(100), Bandwidth::from_mbps(1)),
+    ///     (Duration::from_millis(200), Bandwidth::from_mbps(2)),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(
+    ///     config.pattern,
+    ///     vec![(
+    ///         Duration::from_millis(100),
+    ///         vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(2), Bandwidth::from_mbps(3)]
+    ///     )]
+    /// );
+    /// ```
+    pub fn from_unsorted(mut points: Vec<(Duration, Bandwidth)>) -> Result<Self, ModelBuildError> {
+        points.sort_by_key(|(offset, _)| *offset);
+        let mut pattern: Vec<(Duration, Vec<Bandwidth>)> = vec![];
+        let mut last_offset = None;
+        for (offset, bw) in points {
+            if last_offset == Some(offset) {
+                return Err(ModelBuildError::InvalidConfig {
+                    model: "TraceBw",
+                    reason: format!("duplicate offset {:?}", offset),
+                });
+            }
+            let duration = offset - last_offset.unwrap_or(Duration::ZERO);
+            last_offset = Some(offset);
+            if duration.is_zero() {
+                continue;
+            }
+            match pattern.last_mut() {
+                Some((last_duration, bws)) if *last_duration == duration => bws.push(bw),
+                _ => pattern.push((duration, vec![bw])),
+            }
+        }
+        Ok(Self::new().pattern(pattern))
+    }
+
+    /// Build the pattern from entries keyed by absolute timestamps instead of durations, each of
+    /// which is an offset from the start of the trace and the bandwidths over the interval ending at
+    /// the offset.
+    ///
+    /// Like the points of [`from_unsorted`](Self::from_unsorted), an entry lasts from the timestamp
+    /// of the previous entry (or 0 for the first entry) to its own timestamp, and the bandwidths of
+    /// the entry share the interval evenly. If the interval cannot be split evenly (in nanoseconds),
+    /// the remainder goes to the last bandwidth of the entry. A first entry at timestamp 0 covers
+    /// no time and is skipped.
+    ///
+    /// Returns [`ModelBuildError::InvalidConfig`] if the timestamps are not strictly increasing or an
+    /// entry has no bandwidths.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::TraceBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration};
+    /// let config = TraceBwConfig::from_timestamps(vec![
+    ///     (Duration::from_millis(100), vec![Bandwidth::from_mbps(1)]),
+    ///     (Duration::from_millis(300), vec![Bandwidth::from_mbps(2), Bandwidth::from_mbps(3)]),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(
+    ///     config.pattern,
+    ///     vec![
+    ///         (Duration::from_millis(100), vec![Bandwidth::from_mbps(1)]),
+    ///         (Duration::from_millis(100), vec![Bandwidth::from_mbps(2), Bandwidth::from_mbps(3)]),
+    ///     ]
+    /// );
+    /// ```
+    pub fn from_timestamps(
+        entries: Vec<(Duration, Vec<Bandwidth>)>,
+    ) -> Result<Self, ModelBuildError> {
+        let invalid = |reason: String| ModelBuildError::InvalidConfig {
+            model: "TraceBw",
+            reason,
+        };
+        let mut pattern = Vec::with_capacity(entries.len());
+        let mut last_timestamp = None;
+        for (timestamp, mut bws) in entries {
+            if let Some(last_timestamp) = last_timestamp.filter(|last| timestamp <= *last) {
+                return Err(invalid(format!(
+                    "timestamp {:?} must be larger than the previous one {:?}",
+                    timestamp, last_timestamp
+                )));
+            }
+            if bws.is_empty() {
+                return Err(invalid(format!(
+                    "entry at timestamp {:?} has no bandwidths",
+                    timestamp
+                )));
+            }
+            let span = timestamp - last_timestamp.unwrap_or(Duration::ZERO);
+            last_timestamp = Some(timestamp);
+            if span.is_zero() {
+                continue;
+            }
+            let len = bws.len() as u128;
+            // the quotient and the remainder are not larger than the span, which fits in a duration
+            let duration = Duration::from_nanos((span.as_nanos() / len) as u64);
+            let remainder = Duration::from_nanos((span.as_nanos() % len) as u64);
+            if remainder.is_zero() {
+                pattern.push((duration, bws));
+            } else {
+                let last = bws.pop().unwrap();
+                if !bws.is_empty() {
+                    pattern.push((duration, bws));
+                }
+                pattern.push((duration + remainder, vec![last]));
+            }
+        }
+        Ok(Self::new().pattern(pattern))
+    }
+
+    /// The total duration of the pattern, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.pattern
+            .iter()
+            .fold(Duration::ZERO, |total, (duration, bandwidths)| {
+                let entry = u32::try_from(bandwidths.len())
+                    .ok()
+                    .and_then(|len| duration.checked_mul(len))
+                    .unwrap_or(Duration::MAX);
+                total.saturating_add(entry)
+            })
+    }
+
+    pub fn build(self) -> TraceBw {
+        let pattern: Vec<_> = self
+            .pattern
+            .into_iter()
+            .filter(|(_, bandwidths)| !bandwidths.is_empty())
+            .collect();
+        let interpolation = self.interpolation.unwrap_or_default();
+        if self.step.is_some_and(|step| step.is_zero()) {
+            panic!("TraceBw: step must be positive");
+        }
+        debug_log!(
+            "TraceBw built: {} non-empty entries, interpolation {:?}, step {:?}",
+            pattern.len(),
+            interpolation,
+            self.step.map(fmt_delay)
+        );
+        TraceBw {
+            pattern,
+            outer_index: 0,
+            inner_index: 0,
+            interpolation,
+            step: self.step,
+            current: Duration::ZERO,
+        }
+    }
+}
+
+/// Convert a [`StaticBwConfig`] into a [`TraceBwConfig`] with a single entry, which is lossless.
+///
+/// The defaults of [`StaticBwConfig`] are applied to the unset fields. A zero duration results
+/// in an empty pattern, as [`StaticBw`] produces nothing in that case.
+impl From<StaticBwConfig> for TraceBwConfig {
+    fn from(config: StaticBwConfig) -> Self {
+        let StaticBw { bw, duration } = config.build();
+        let pattern = match duration {
+            Some(duration) if !duration.is_zero() => vec![(duration, vec![bw])],
+            _ => vec![],
+        };
+        TraceBwConfig::new().pattern(pattern)
+    }
+}
+
+/// Convert a [`TraceBwConfig`] into a [`StaticBwConfig`].
+///
+/// The conversion succeeds only if the pattern produces at most one segment (i.e. one bandwidth in
+/// total) with a non-zero duration, since a [`StaticBw`] cannot change its bandwidth. An empty pattern
+/// is converted to a zero duration. Otherwise, [`ModelBuildError::InvalidConfig`] is returned.
+impl TryFrom<TraceBwConfig> for StaticBwConfig {
+    type Error = ModelBuildError;
+
+    fn try_from(config: TraceBwConfig) -> Result<Self, Self::Error> {
+        let mut segments = config
+            .pattern
+            .iter()
+            .flat_map(|(duration, bandwidths)| bandwidths.iter().map(move |bw| (*bw, *duration)));
+        match (segments.next(), segments.next()) {
+            (None, _) => Ok(StaticBwConfig::new().duration(Duration::ZERO)),
+            (Some((bw, duration)), None) if !duration.is_zero() => {
+                Ok(StaticBwConfig::new().bw(bw).duration(duration))
+            }
+            (Some(_), None) => Err(ModelBuildError::InvalidConfig {
+                model: "StaticBw",
+                reason: "the segment of the TraceBwConfig has a zero duration".to_string(),
+            }),
+            (Some(_), Some(_)) => Err(ModelBuildError::InvalidConfig {
+                model: "StaticBw",
+                reason: format!(
+                    "can only have one segment, but the TraceBwConfig has {}",
+                    config
+                        .pattern
+                        .iter()
+                        .map(|(_, bandwidths)| bandwidths.len())
+                        .sum::<usize>()
+                ),
+            }),
+        }
+    }
+}
+
+/// The model replays an explicit sequence of `(Bandwidth, Duration)` segments in order.
+///
+/// Unlike [`TraceBw`], which groups consecutive segments of the same duration, the sequence is kept
+/// flat, which is the most natural representation for hand-written short traces. Segments with a
+/// zero duration are skipped.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::SequenceBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut sequence_bw = SequenceBwConfig::from(vec![
+///     (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+///     (Bandwidth::from_mbps(24), Duration::ZERO),
+///     (Bandwidth::from_mbps(6), Duration::from_millis(50)),
+/// ])
+/// .build();
+/// assert_eq!(sequence_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(sequence_bw.next_bw(), Some((Bandwidth::from_mbps(6), Duration::from_millis(50))));
+/// assert_eq!(sequence_bw.next_bw(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequenceBw {
+    pub sequence: Vec<(Bandwidth, Duration)>, // durations are never zero
+    pub index: usize,
+}
+
+/// The configuration struct for [`SequenceBw`].
+///
+/// See [`SequenceBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct SequenceBwConfig {
+    #[cfg_attr(all(feature = "serde", feature = "human"), serde(with = "pairs_serde"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration::sequence")
+    )]
+    pub sequence: Vec<(Bandwidth, Duration)>,
+}
+
+impl SequenceBwConfig {
+    pub fn new() -> Self {
+        Self { sequence: vec![] }
+    }
+
+    pub fn sequence(mut self, sequence: Vec<(Bandwidth, Duration)>) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// The total duration of the sequence, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.sequence
+            .iter()
+            .fold(Duration::ZERO, |total, (_, duration)| {
+                total.saturating_add(*duration)
+            })
+    }
+
+    pub fn build(self) -> SequenceBw {
+        let sequence: Vec<_> = self
+            .sequence
+            .into_iter()
+            .filter(|(_, duration)| !duration.is_zero())
+            .collect();
+        debug_log!("SequenceBw built: {} non-empty segments", sequence.len());
+        SequenceBw { sequence, index: 0 }
+    }
+}
+
+impl From<Vec<(Bandwidth, Duration)>> for SequenceBwConfig {
+    fn from(sequence: Vec<(Bandwidth, Duration)>) -> Self {
+        SequenceBwConfig::new().sequence(sequence)
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "human"))]
+mod tracebw_serde {
+    use super::*;
+    use serde::ser::SerializeSeq;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+    use std::ops::{Deref, DerefMut};
     /// Deserializes a `Bandwidth` in human-readable format.
     ///
     /// This function can be used with `serde_derive`'s `with` and
@@ -688,13 +1509,13 @@ mod tracebw_serde {
                         seq.next_element::<(String, Vec<String>)>()?
                     {
                         let duration =
-                            humantime_serde::re::humantime::parse_duration(duration_str.as_str())
+                            crate::model::human_duration::parse_duration(duration_str.as_str())
                                 .map_err(|e| {
-                                serde::de::Error::custom(format!(
-                                    "Failed to parse duration '{}': {}",
-                                    duration_str, e
-                                ))
-                            })?;
+                                    serde::de::Error::custom(format!(
+                                        "Failed to parse duration '{}': {}",
+                                        duration_str, e
+                                    ))
+                                })?;
 
                         let bandwidths = bandwidths_str
                             .into_iter()
@@ -754,193 +1575,5504 @@ mod tracebw_serde {
     }
 }
 
-impl BwTrace for StaticBw {
-    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
-        if let Some(duration) = self.duration.take() {
-            if duration.is_zero() {
-                None
-            } else {
-                Some((self.bw, duration))
-            }
-        } else {
-            None
-        }
-    }
+/// The model attaches a tag to every segment of the inner bandwidth trace model.
+///
+/// The tag can be read with [`BwTrace::next_bw_tagged`] and survives composition,
+/// e.g. with [`RepeatedBwPattern`], which helps to track which source a segment comes from.
+/// If the inner model already carries a tag, the inner tag is kept.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{BwTraceConfig, RepeatedBwPatternConfig, StaticBwConfig, TaggedBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut tagged_bw = RepeatedBwPatternConfig::new()
+///     .pattern(vec![
+///         Box::new(
+///             TaggedBwConfig::new()
+///                 .inner(Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))))
+///                 .tag("wifi"),
+///         ) as Box<dyn BwTraceConfig>,
+///         Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(24))) as Box<dyn BwTraceConfig>,
+///     ])
+///     .count(1)
+///     .build();
+/// assert_eq!(
+///     tagged_bw.next_bw_tagged(),
+///     Some((Bandwidth::from_mbps(12), Duration::from_secs(1), Some("wifi")))
+/// );
+/// assert_eq!(
+///     tagged_bw.next_bw_tagged(),
+///     Some((Bandwidth::from_mbps(24), Duration::from_secs(1), None))
+/// );
+/// assert_eq!(tagged_bw.next_bw_tagged(), None);
+/// ```
+pub struct TaggedBw {
+    pub tag: String,
+    inner: Option<Box<dyn BwTrace>>,
 }
 
-impl BwTrace for NormalizedBw {
-    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
-        if self.duration.is_zero() {
-            None
-        } else {
-            let bw = self.sample() as u64;
-            let mut bw = Bandwidth::from_bps(bw);
-            if let Some(lower_bound) = self.lower_bound {
-                bw = bw.max(lower_bound);
-            }
-            if let Some(upper_bound) = self.upper_bound {
-                bw = bw.min(upper_bound);
-            }
-            let duration = self.step.min(self.duration);
-            self.duration -= duration;
-            Some((bw, duration))
-        }
-    }
+/// The configuration struct for [`TaggedBw`].
+///
+/// See [`TaggedBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct TaggedBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    pub tag: String,
 }
 
-impl BwTrace for SawtoothBw {
-    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
-        if self.duration.is_zero() {
-            None
-        } else {
-            let current = self.current.as_secs_f64();
-            let change_point = self.interval.as_secs_f64() * self.duty_ratio;
-            let base_bw = if current < change_point {
-                let ratio = current / change_point;
-                self.bottom + (self.top - self.bottom).mul_f64(ratio)
-            } else {
-                let ratio = (current - change_point) / (self.interval.as_secs_f64() - change_point);
-                self.top - (self.top - self.bottom).mul_f64(ratio)
-            };
-            let mut offset = self.noise.sample(&mut self.rng);
-            if let Some(upper_noise_bound) = self.upper_noise_bound {
-                offset = offset.min(upper_noise_bound.as_bps() as f64);
-            }
-            if let Some(lower_noise_bound) = self.lower_noise_bound {
-                offset = offset.max(-(lower_noise_bound.as_bps() as f64));
-            }
-            let bw = Bandwidth::from_bps((base_bw.as_bps() as f64 + offset) as u64);
-            let duration = self.step.min(self.duration);
-            self.duration -= duration;
-            self.current += duration;
-            if self.current >= self.interval {
-                self.current -= self.interval;
-            }
-            Some((bw, duration))
-        }
-    }
+/// The model adds a static `floor` to the bandwidth of the `variable` model, e.g. to model a link
+/// split into a guaranteed portion and a best-effort portion.
+///
+/// Each segment of the `variable` model is emitted with the bandwidth of `floor + variable`
+/// (saturating) and the same duration, so the bandwidth is never lower than `floor`.
+/// The model ends when the `variable` model ends, and produces nothing without a `variable` model.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{FloorPlusVariableBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut floor_plus_variable_bw = FloorPlusVariableBwConfig::new()
+///     .floor(Bandwidth::from_mbps(5))
+///     .variable(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![Bandwidth::from_mbps(3), Bandwidth::ZERO],
+///     )])))
+///     .build();
+/// assert_eq!(
+///     floor_plus_variable_bw.next_bw(),
+///     Some((Bandwidth::from_mbps(8), Duration::from_millis(100)))
+/// );
+/// assert_eq!(
+///     floor_plus_variable_bw.next_bw(),
+///     Some((Bandwidth::from_mbps(5), Duration::from_millis(100)))
+/// );
+/// assert_eq!(floor_plus_variable_bw.next_bw(), None);
+/// ```
+pub struct FloorPlusVariableBw {
+    pub floor: Bandwidth,
+    variable: Option<Box<dyn BwTrace>>,
 }
 
-impl BwTrace for RepeatedBwPattern {
-    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
-        if self.pattern.is_empty() || (self.count != 0 && self.current_cycle >= self.count) {
-            None
-        } else {
-            if self.current_model.is_none() {
-                self.current_model = Some(self.pattern[self.current_pattern].clone().into_model());
-            }
-            match self.current_model.as_mut().unwrap().next_bw() {
-                Some(bw) => Some(bw),
-                None => {
-                    self.current_model = None;
-                    self.current_pattern += 1;
-                    if self.current_pattern >= self.pattern.len() {
-                        self.current_pattern = 0;
-                        self.current_cycle += 1;
-                        if self.count != 0 && self.current_cycle >= self.count {
-                            return None;
-                        }
-                    }
-                    self.next_bw()
-                }
-            }
-        }
-    }
+/// The configuration struct for [`FloorPlusVariableBw`].
+///
+/// See [`FloorPlusVariableBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct FloorPlusVariableBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub floor: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub variable: Option<Box<dyn BwTraceConfig>>,
 }
 
-impl BwTrace for TraceBw {
-    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
-        let result = self
-            .pattern
-            .get(self.outer_index)
-            .and_then(|(duration, bandwidth)| {
-                bandwidth
-                    .get(self.inner_index)
-                    .map(|bandwidth| (*bandwidth, *duration))
-            });
-        if result.is_some() {
-            if self.pattern[self.outer_index].1.len() > self.inner_index + 1 {
-                self.inner_index += 1;
-            } else {
-                self.outer_index += 1;
-                self.inner_index = 0;
-            }
-        }
-        result
-    }
+/// The model scales the bandwidth of the inner model by `factor`, e.g. to reuse a recorded trace
+/// at a different link capacity.
+///
+/// Each segment of the inner model is emitted with its bandwidth multiplied by `factor` and the
+/// same duration, saturating at [`Bandwidth::MAX`]. The model ends when the inner model ends, and
+/// produces nothing without an inner model.
+///
+/// The `factor` must be non-negative and finite, otherwise the model panics on build.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{ScaledBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut scaled_bw = ScaledBwConfig::new()
+///     .inner(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(6)],
+///     )])))
+///     .factor(0.5)
+///     .build();
+/// assert_eq!(scaled_bw.next_bw(), Some((Bandwidth::from_mbps(6), Duration::from_millis(100))));
+/// assert_eq!(scaled_bw.next_bw(), Some((Bandwidth::from_mbps(3), Duration::from_millis(100))));
+/// assert_eq!(scaled_bw.next_bw(), None);
+/// ```
+pub struct ScaledBw {
+    pub factor: f64,
+    inner: Option<Box<dyn BwTrace>>,
 }
 
-impl NormalizedBw {
-    pub fn sample(&mut self) -> f64 {
-        self.normal.sample(&mut self.rng)
-    }
+/// The configuration struct for [`ScaledBw`].
+///
+/// See [`ScaledBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct ScaledBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub factor: Option<f64>,
 }
 
-impl StaticBwConfig {
-    pub fn new() -> Self {
-        Self {
-            bw: None,
-            duration: None,
-        }
-    }
-
-    pub fn bw(mut self, bw: Bandwidth) -> Self {
-        self.bw = Some(bw);
-        self
-    }
-
-    pub fn duration(mut self, duration: Duration) -> Self {
-        self.duration = Some(duration);
-        self
-    }
+/// The model clamps the bandwidth of the inner model within `[min, max]`, e.g. to keep a noisy
+/// model within the hardware limits.
+///
+/// Each segment of the inner model is emitted with its bandwidth clamped and the same duration.
+/// Both bounds are optional. Like the bounds of [`NormalizedBw`], the lower bound is applied first,
+/// so `max` wins if `min` is larger than `max`. The model ends when the inner model ends, and
+/// produces nothing without an inner model.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{ClampedBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut clamped_bw = ClampedBwConfig::new()
+///     .inner(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(6), Bandwidth::from_mbps(24)],
+///     )])))
+///     .min(Bandwidth::from_mbps(2))
+///     .max(Bandwidth::from_mbps(12))
+///     .build();
+/// assert_eq!(clamped_bw.next_bw(), Some((Bandwidth::from_mbps(2), Duration::from_millis(100))));
+/// assert_eq!(clamped_bw.next_bw(), Some((Bandwidth::from_mbps(6), Duration::from_millis(100))));
+/// assert_eq!(clamped_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(clamped_bw.next_bw(), None);
+/// ```
+pub struct ClampedBw {
+    pub min: Option<Bandwidth>,
+    pub max: Option<Bandwidth>,
+    inner: Option<Box<dyn BwTrace>>,
+}
 
-    pub fn build(self) -> StaticBw {
+/// The configuration struct for [`ClampedBw`].
+///
+/// See [`ClampedBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct ClampedBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub min: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub max: Option<Bandwidth>,
+}
+
+/// The model loops the inner model forever, starting from a random offset (phase) in `[0, period)`,
+/// e.g. so that runs of an ensemble experiment replaying the same trace are not perfectly aligned.
+///
+/// The offset is drawn deterministically from `seed`, so the same seed always gives the same offset,
+/// and is exposed as `offset`. The part of the looped inner model before the offset is skipped, and
+/// the segment containing the offset is cut at it. `period` is usually the duration of one loop of
+/// the inner model. It defaults to zero, i.e. no offset. The model produces nothing without an inner
+/// model, or with an inner model producing nothing (e.g. of zero duration), instead of looping it
+/// forever.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{RandomPhaseBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut phase_bw = RandomPhaseBwConfig::new()
+///     .inner(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)],
+///     )])))
+///     .period(Duration::from_millis(200))
+///     .seed(42)
+///     .build();
+/// assert!(phase_bw.offset < Duration::from_millis(200));
+/// // the first segment is cut at the offset
+/// let (bw, duration) = phase_bw.next_bw().unwrap();
+/// if phase_bw.offset < Duration::from_millis(100) {
+///     assert_eq!((bw, duration), (Bandwidth::from_mbps(12), Duration::from_millis(100) - phase_bw.offset));
+/// } else {
+///     assert_eq!((bw, duration), (Bandwidth::from_mbps(24), Duration::from_millis(200) - phase_bw.offset));
+/// }
+/// ```
+pub struct RandomPhaseBw {
+    pub period: Duration,
+    pub seed: u64,
+    pub offset: Duration,
+    inner: Option<Box<dyn BwTrace>>,
+    // the part of the looped inner model still to be skipped
+    skip: Duration,
+    current_tag: Option<String>,
+}
+
+/// The configuration struct for [`RandomPhaseBw`].
+///
+/// See [`RandomPhaseBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct RandomPhaseBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub period: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model of a bandwidth trace following a discrete Markov chain, e.g. for scenarios switching
+/// between a few typical network conditions.
+///
+/// Each state is a bandwidth, and `transitions[i][j]` is the probability of moving from state `i`
+/// to state `j`, so the transition matrix must be N x N for N states, and each row must sum to 1.0.
+/// Starting from `initial_state` (defaults to the first state), every `step` the bandwidth of the
+/// current state is emitted, and the next state is drawn from the row of the current state.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::MarkovBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut markov_bw = MarkovBwConfig::new()
+///     .states(vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)])
+///     .transitions(vec![vec![0.0, 1.0], vec![0.5, 0.5]])
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .seed(42)
+///     .build();
+/// assert_eq!(markov_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// // the first state always moves to the second one
+/// assert_eq!(markov_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(100))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkovBw<R = StdRng> {
+    pub states: Vec<Bandwidth>,
+    pub transitions: Vec<Vec<f64>>,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    current_state: usize,
+    rng: R,
+}
+
+/// The configuration struct for [`MarkovBw`].
+///
+/// See [`MarkovBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct MarkovBwConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "bandwidths_serde")
+    )]
+    pub states: Vec<Bandwidth>,
+    pub transitions: Vec<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub initial_state: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model applies hysteresis to the bandwidth changes of the inner model, e.g. to avoid rapid
+/// oscillation in reactive consumers.
+///
+/// A new bandwidth level is emitted only when a segment of the inner model differs from the current
+/// level by more than `threshold`. Otherwise the segment is absorbed, i.e. the duration of the current
+/// level is extended by the duration of the segment. The segments of a level carry the tag of its first
+/// segment. The model ends when the inner model ends, and produces nothing without an inner model.
+///
+/// A level is emitted once it ends or once it has been held for `max_hold` (1 minute by default),
+/// whichever comes first, so that an inner model which never changes by more than `threshold` (e.g.
+/// an infinite one) neither stalls the model nor is buffered as a whole. A level cut by `max_hold`
+/// is held on, i.e. the following segments are still compared with it.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{HysteresisBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut hysteresis_bw = HysteresisBwConfig::new()
+///     .inner(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![
+///             Bandwidth::from_mbps(12),
+///             Bandwidth::from_mbps(13),
+///             Bandwidth::from_mbps(11),
+///             Bandwidth::from_mbps(24),
+///         ],
+///     )])))
+///     .threshold(Bandwidth::from_mbps(2))
+///     .build();
+/// assert_eq!(hysteresis_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(300))));
+/// assert_eq!(hysteresis_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(100))));
+/// assert_eq!(hysteresis_bw.next_bw(), None);
+/// ```
+pub struct HysteresisBw {
+    pub threshold: Bandwidth,
+    pub max_hold: Duration,
+    inner: Option<Box<dyn BwTrace>>,
+    /// The held level with the tag of its first segment.
+    level: Option<(Bandwidth, Option<String>)>,
+    /// The duration of the held level not emitted yet.
+    pending: Duration,
+    current_tag: Option<String>,
+}
+
+/// The configuration struct for [`HysteresisBw`].
+///
+/// See [`HysteresisBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct HysteresisBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub threshold: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub max_hold: Option<Duration>,
+}
+
+/// The model of a bandwidth trace changing linearly from `start` to `end` over `duration`.
+///
+/// The bandwidth is `start + (end - start) * current / last`, where `current` is the time since the
+/// start of the model, sampled at the start of each `step`, and `last` is the start of the final
+/// step (which is cut at the end of `duration`), so that the first step is at `start` and the final
+/// step is at `end`. A ramp of a single step is at `end`. Unlike [`SawtoothBw`], the ramp happens
+/// only once: the model ends when `duration` is exhausted, and `end` can be lower than `start` for
+/// a dropping bandwidth.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::RampBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut ramp_bw = RampBwConfig::new()
+///     .start(Bandwidth::from_mbps(12))
+///     .end(Bandwidth::from_mbps(4))
+///     .duration(Duration::from_millis(500))
+///     .step(Duration::from_millis(100))
+///     .build();
+/// assert_eq!(ramp_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(ramp_bw.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_millis(100))));
+/// assert_eq!(ramp_bw.next_bw(), Some((Bandwidth::from_mbps(8), Duration::from_millis(100))));
+/// assert_eq!(ramp_bw.next_bw(), Some((Bandwidth::from_mbps(6), Duration::from_millis(100))));
+/// assert_eq!(ramp_bw.next_bw(), Some((Bandwidth::from_mbps(4), Duration::from_millis(100))));
+/// assert_eq!(ramp_bw.next_bw(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RampBw {
+    pub start: Bandwidth,
+    pub end: Bandwidth,
+    pub duration: Duration,
+    pub step: Duration,
+    current: Duration,
+}
+
+/// The configuration struct for [`RampBw`].
+///
+/// See [`RampBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct RampBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub start: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub end: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+}
+
+/// This model plays a pattern of bandwidth traces in random order.
+///
+/// Instead of cycling through the pattern like [`RepeatedBwPattern`], each cycle picks one of the
+/// configs in `pattern` at random with a probability proportional to its weight, builds it and plays
+/// it until it ends, then picks again. The model ends after `count` cycles, or never if `count` is 0.
+///
+/// The picks are drawn from an RNG seeded by `seed`, so that the sequence of picked configs is
+/// reproducible.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{BwTraceConfig, RandomPatternBwConfig, StaticBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let static_bw = |mbps| {
+///     Box::new(
+///         StaticBwConfig::new()
+///             .bw(Bandwidth::from_mbps(mbps))
+///             .duration(Duration::from_secs(1)),
+///     ) as Box<dyn BwTraceConfig>
+/// };
+/// let mut random_bw = RandomPatternBwConfig::new()
+///     .pattern(vec![(3.0, static_bw(12)), (1.0, static_bw(24)), (0.0, static_bw(36))])
+///     .count(10)
+///     .seed(42)
+///     .build();
+/// let mut segments = 0;
+/// while let Some((bw, duration)) = random_bw.next_bw() {
+///     // a config with zero weight is never picked
+///     assert!(bw == Bandwidth::from_mbps(12) || bw == Bandwidth::from_mbps(24));
+///     assert_eq!(duration, Duration::from_secs(1));
+///     segments += 1;
+/// }
+/// assert_eq!(segments, 10);
+/// ```
+pub struct RandomPatternBw<R = StdRng> {
+    pub pattern: Vec<(f64, Box<dyn BwTraceConfig>)>,
+    pub count: usize,
+    pub seed: u64,
+    current_model: Option<Box<dyn BwTrace>>,
+    current_cycle: usize,
+    current_index: usize,
+    current_tag: Option<String>,
+    rng: R,
+    guard: EmptyPatternGuard,
+}
+
+/// The configuration struct for [`RandomPatternBw`].
+///
+/// See [`RandomPatternBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct RandomPatternBwConfig {
+    pub pattern: Vec<(f64, Box<dyn BwTraceConfig>)>,
+    pub count: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model plays the segments of a finite inner model in reverse order, e.g. for symmetric
+/// up/down experiments with a recorded trace.
+///
+/// On the first call of `next_bw`, the inner model is drained into a buffer, whose segments are then
+/// played from the last one to the first one, each with its own bandwidth, duration and tag.
+///
+/// An inner model which [never ends](BwTraceConfig::is_infinite) cannot be drained, so the model
+/// produces nothing in that case, as well as without an inner model.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{ReverseBwConfig, SequenceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut reverse_bw = ReverseBwConfig::new()
+///     .inner(Box::new(SequenceBwConfig::new().sequence(vec![
+///         (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+///         (Bandwidth::from_mbps(24), Duration::from_millis(200)),
+///     ])))
+///     .build();
+/// assert_eq!(reverse_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(200))));
+/// assert_eq!(reverse_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(100))));
+/// assert_eq!(reverse_bw.next_bw(), None);
+/// ```
+pub struct ReverseBw {
+    inner: Option<Box<dyn BwTrace>>,
+    segments: Vec<(Bandwidth, Duration, Option<String>)>,
+    current_tag: Option<String>,
+}
+
+/// The configuration struct for [`ReverseBw`].
+///
+/// See [`ReverseBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct ReverseBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+}
+
+/// The model of a packetized link whose throughput is driven by Poisson-distributed packet bursts.
+///
+/// The trace is split into steps of `step` (the last one is truncated to the remaining `duration`).
+/// For each step, the number of packets arriving is drawn from a Poisson distribution with mean
+/// `rate_pps` times the length of the step, and the bandwidth of the step is the one carrying
+/// these packets of `packet_size` bytes within the step, i.e. `count * packet_size * 8 / step`.
+/// The mean bandwidth is thus `rate_pps * packet_size * 8`.
+///
+/// With `rate_pps` 0, no packet ever arrives and the bandwidth is always zero.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::PoissonBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut poisson_bw = PoissonBwConfig::new()
+///     .rate_pps(1000.0)
+///     .packet_size(1500)
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(10))
+///     .seed(42)
+///     .build();
+/// // each step carries a whole number of 1500-byte packets within 10ms, i.e. multiples of 1.2Mbps
+/// let (bw, duration) = poisson_bw.next_bw().unwrap();
+/// assert_eq!(bw.as_bps() % 1_200_000, 0);
+/// assert_eq!(duration, Duration::from_millis(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoissonBw<R = StdRng> {
+    pub rate_pps: f64,
+    pub packet_size: u64,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+}
+
+/// The configuration struct for [`PoissonBw`].
+///
+/// See [`PoissonBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct PoissonBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rate_pps: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub packet_size: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model sums the bandwidth of multiple inner models aligned in time, e.g. to model multiple
+/// flows sharing a path.
+///
+/// A segment ends whenever a segment of any of the `inputs` ends, i.e. its duration is the minimum
+/// remaining duration of the current segments of the inputs, and its bandwidth is the (saturating)
+/// sum of their bandwidths. An input which has ended contributes zero bandwidth, and the model ends
+/// when all the inputs end. It produces nothing without any input.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{BwTraceConfig, StaticBwConfig, SumBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut sum_bw = SumBwConfig::new()
+///     .inputs(vec![
+///         Box::new(
+///             StaticBwConfig::new()
+///                 .bw(Bandwidth::from_mbps(12))
+///                 .duration(Duration::from_secs(1)),
+///         ) as Box<dyn BwTraceConfig>,
+///         Box::new(
+///             StaticBwConfig::new()
+///                 .bw(Bandwidth::from_mbps(24))
+///                 .duration(Duration::from_secs(3)),
+///         ) as Box<dyn BwTraceConfig>,
+///     ])
+///     .build();
+/// assert_eq!(sum_bw.next_bw(), Some((Bandwidth::from_mbps(36), Duration::from_secs(1))));
+/// assert_eq!(sum_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_secs(2))));
+/// assert_eq!(sum_bw.next_bw(), None);
+/// ```
+pub struct SumBw {
+    inputs: Vec<Box<dyn BwTrace>>,
+    /// The bandwidth and remaining duration of the current segment of each input, `None` if ended.
+    current: Vec<Option<(Bandwidth, Duration)>>,
+}
+
+/// The configuration struct for [`SumBw`].
+///
+/// See [`SumBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct SumBwConfig {
+    pub inputs: Vec<Box<dyn BwTraceConfig>>,
+}
+
+/// The model of a bandwidth trace subjecting to a Weibull distribution.
+///
+/// The bandwidth of each step is sampled from a Weibull distribution with the given `scale` and
+/// `shape`, and then clamped within `lower_bound` and `upper_bound` if they are set. A `shape`
+/// below 1 gives a heavy tail, which often fits the throughput of cellular links better than a
+/// normal distribution, 1 gives an exponential distribution, and a larger one concentrates the
+/// bandwidth around `scale`.
+///
+/// The `step` describes how long between two consecutive bandwidth samples. Each segment lasts for
+/// `min(step, remaining duration)`, like [`NormalizedBw`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::WeibullBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut weibull_bw = WeibullBwConfig::new()
+///     .scale(Bandwidth::from_mbps(12))
+///     .shape(0.8)
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .upper_bound(Bandwidth::from_mbps(100))
+///     .seed(42)
+///     .build();
+/// let (bw, duration) = weibull_bw.next_bw().unwrap();
+/// assert!(bw <= Bandwidth::from_mbps(100));
+/// assert_eq!(duration, Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WeibullBw<R = StdRng> {
+    pub scale: Bandwidth,
+    pub shape: f64,
+    pub upper_bound: Option<Bandwidth>,
+    pub lower_bound: Option<Bandwidth>,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+    /// The Weibull distribution with a unit scale, so that a zero `scale` is allowed.
+    weibull: Weibull<f64>,
+}
+
+/// The configuration struct for [`WeibullBw`].
+///
+/// See [`WeibullBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct WeibullBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub scale: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub shape: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub upper_bound: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub lower_bound: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model of a bandwidth trace subjecting to a Gamma distribution.
+///
+/// The bandwidth of each step is sampled from a Gamma distribution with the given `shape` and
+/// `scale`, and then clamped within `lower_bound` and `upper_bound` if they are set. The mean of
+/// the distribution is `shape * scale` and it is skewed to the right, which often fits aggregate
+/// traffic demand better than a normal distribution. The skew lessens as `shape` grows.
+///
+/// The `step` describes how long between two consecutive bandwidth samples. Each segment lasts for
+/// `min(step, remaining duration)`, like [`NormalizedBw`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::GammaBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut gamma_bw = GammaBwConfig::new()
+///     .shape(2.0)
+///     .scale(Bandwidth::from_mbps(6))
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .upper_bound(Bandwidth::from_mbps(100))
+///     .seed(42)
+///     .build();
+/// let (bw, duration) = gamma_bw.next_bw().unwrap();
+/// assert!(bw <= Bandwidth::from_mbps(100));
+/// assert_eq!(duration, Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GammaBw<R = StdRng> {
+    pub shape: f64,
+    pub scale: Bandwidth,
+    pub upper_bound: Option<Bandwidth>,
+    pub lower_bound: Option<Bandwidth>,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+    gamma: Gamma<f64>,
+}
+
+/// The configuration struct for [`GammaBw`].
+///
+/// See [`GammaBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct GammaBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub shape: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub scale: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub upper_bound: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub lower_bound: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model overrides the bandwidth of a base model with `override_bw` within the window
+/// `[start, end)`, e.g. for what-if experiments on a recorded trace.
+///
+/// The segments of the base model are passed through outside of the window, and emitted with
+/// `override_bw` inside of it. A segment straddling a boundary of the window is split at the
+/// boundary. The window does not extend the base model: the model ends when the base model ends,
+/// and produces nothing without a base model. The window is empty if `start` is not before `end`,
+/// which is the default.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{SpliceBwConfig, StaticBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut splice_bw = SpliceBwConfig::new()
+///     .base(Box::new(
+///         StaticBwConfig::new()
+///             .bw(Bandwidth::from_mbps(12))
+///             .duration(Duration::from_secs(1)),
+///     ))
+///     .start(Duration::from_millis(200))
+///     .end(Duration::from_millis(500))
+///     .override_bw(Bandwidth::from_mbps(1))
+///     .build();
+/// assert_eq!(splice_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(200))));
+/// assert_eq!(splice_bw.next_bw(), Some((Bandwidth::from_mbps(1), Duration::from_millis(300))));
+/// assert_eq!(splice_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(500))));
+/// assert_eq!(splice_bw.next_bw(), None);
+/// ```
+pub struct SpliceBw {
+    pub start: Duration,
+    pub end: Duration,
+    pub override_bw: Bandwidth,
+    base: Option<Box<dyn BwTrace>>,
+    // the rest of the base segment which is not yet emitted
+    pending: Option<(Bandwidth, Duration)>,
+    now: Duration,
+}
+
+/// The configuration struct for [`SpliceBw`].
+///
+/// See [`SpliceBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct SpliceBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub base: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub start: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub end: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub override_bw: Option<Bandwidth>,
+}
+
+/// The model snaps the bandwidth of the inner model to the nearest of the discrete `levels`, e.g.
+/// for shaping hardware which only supports a set of rates.
+///
+/// A bandwidth halfway between two levels is snapped to the lower one. The durations of the inner
+/// model are kept. Without any level, the bandwidth is passed through unchanged. The model ends
+/// when the inner model ends, and produces nothing without an inner model.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{QuantizedBwConfig, TraceBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut quantized_bw = QuantizedBwConfig::new()
+///     .inner(Box::new(TraceBwConfig::new().pattern(vec![(
+///         Duration::from_millis(100),
+///         vec![Bandwidth::from_mbps(3), Bandwidth::from_mbps(15), Bandwidth::from_mbps(40)],
+///     )])))
+///     .levels(vec![Bandwidth::from_mbps(10), Bandwidth::from_mbps(20)])
+///     .build();
+/// assert_eq!(quantized_bw.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_millis(100))));
+/// assert_eq!(quantized_bw.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_millis(100))));
+/// assert_eq!(quantized_bw.next_bw(), Some((Bandwidth::from_mbps(20), Duration::from_millis(100))));
+/// assert_eq!(quantized_bw.next_bw(), None);
+/// ```
+pub struct QuantizedBw {
+    pub levels: Vec<Bandwidth>, // sorted ascending
+    inner: Option<Box<dyn BwTrace>>,
+}
+
+/// The configuration struct for [`QuantizedBw`].
+///
+/// See [`QuantizedBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct QuantizedBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "bandwidths_serde")
+    )]
+    pub levels: Vec<Bandwidth>,
+}
+
+/// The model applying a loss model to a bandwidth model by removing bandwidth instead of dropping
+/// packets, e.g. for replay pipelines which do not model the loss probability.
+///
+/// Each segment of the `bw` model keeps its bandwidth, or is zeroed as a whole with probability equal
+/// to the loss rate at its start, i.e. the first element of the [`LossPattern`] of the `loss` model
+/// (clamped to `[0, 1]`, and zero for an empty pattern). The random draws are deterministic from
+/// `seed`, and segments starting without loss are passed through unchanged without a draw.
+///
+/// The two models are merged lazily at the boundaries of their segments, so either of them may go on
+/// forever, and a bandwidth segment spanning several loss segments is split at their boundaries. The
+/// model ends when either of them ends, and produces nothing without both of them.
+///
+/// Enabled with feature `loss-model` in addition.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{LossAsGapsBwConfig, StaticBwConfig, StaticLossConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut model = LossAsGapsBwConfig::new()
+///     .bw(Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))))
+///     .loss(Box::new(
+///         StaticLossConfig::new()
+///             .loss(vec![1.0])
+///             .duration(Duration::from_millis(500)),
+///     ))
+///     .seed(42)
+///     .build();
+/// assert_eq!(model.next_bw(), Some((Bandwidth::ZERO, Duration::from_millis(500))));
+/// assert_eq!(model.next_bw(), None);
+/// ```
+#[cfg(feature = "loss-model")]
+pub struct LossAsGapsBw<R = StdRng> {
+    pub seed: u64,
+    bw: Option<Box<dyn BwTrace>>,
+    loss: Option<Box<dyn LossTrace>>,
+    merger: SegmentMerger<Bandwidth, LossPattern>,
+    /// Whether the current bandwidth segment is zeroed.
+    zeroed: bool,
+    rng: R,
+}
+
+/// The configuration struct for [`LossAsGapsBw`].
+///
+/// See [`LossAsGapsBw`] for more details.
+#[cfg(feature = "loss-model")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct LossAsGapsBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bw: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub loss: Option<Box<dyn LossTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model of a bandwidth trace emitting a staircase of bandwidths, e.g. for capacity-step
+/// experiments.
+///
+/// Each of `steps` is held for `hold`, in order, and the staircase is cycled `repeat` times, or
+/// forever if `repeat` is zero. This is a shorthand for a [`RepeatedBwPattern`] of [`StaticBw`]s
+/// of the same duration. The model produces nothing if `steps` is empty or `hold` is zero.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::StepBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut step_bw = StepBwConfig::new()
+///     .steps(vec![Bandwidth::from_mbps(10), Bandwidth::from_mbps(20)])
+///     .hold(Duration::from_secs(5))
+///     .repeat(2)
+///     .build();
+/// assert_eq!(step_bw.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_secs(5))));
+/// assert_eq!(step_bw.next_bw(), Some((Bandwidth::from_mbps(20), Duration::from_secs(5))));
+/// assert_eq!(step_bw.next_bw(), Some((Bandwidth::from_mbps(10), Duration::from_secs(5))));
+/// assert_eq!(step_bw.next_bw(), Some((Bandwidth::from_mbps(20), Duration::from_secs(5))));
+/// assert_eq!(step_bw.next_bw(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StepBw {
+    pub steps: Vec<Bandwidth>,
+    pub hold: Duration,
+    pub repeat: usize,
+    index: usize,
+    cycle: usize,
+}
+
+/// The configuration struct for [`StepBw`].
+///
+/// See [`StepBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct StepBwConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "bandwidths_serde")
+    )]
+    pub steps: Vec<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub hold: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub repeat: Option<usize>,
+}
+
+/// The model of a bandwidth trace alternating between on and off periods whose durations are
+/// Pareto distributed, e.g. to emulate self-similar traffic.
+///
+/// The model starts with an on period, emitting `on_bw`, followed by an off period, emitting
+/// `off_bw`, and so on. The duration of each period (in seconds) is drawn from a Pareto
+/// distribution with `scale` (the minimum duration, in seconds) and `shape`. A `shape` within
+/// `(1, 2)` gives heavy-tailed periods with a finite mean of `scale * shape / (shape - 1)` seconds,
+/// which is the classic setting for self-similar traffic. The last period is truncated to the
+/// remaining `duration`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::ParetoBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut pareto_bw = ParetoBwConfig::new()
+///     .scale(0.01)
+///     .shape(1.5)
+///     .on_bw(Bandwidth::from_mbps(24))
+///     .off_bw(Bandwidth::from_mbps(1))
+///     .duration(Duration::from_secs(1))
+///     .seed(42)
+///     .build();
+/// let (bw, duration) = pareto_bw.next_bw().unwrap();
+/// assert_eq!(bw, Bandwidth::from_mbps(24));
+/// assert!(duration >= Duration::from_millis(10));
+/// assert_eq!(pareto_bw.next_bw().unwrap().0, Bandwidth::from_mbps(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParetoBw<R = StdRng> {
+    pub scale: f64,
+    pub shape: f64,
+    pub on_bw: Bandwidth,
+    pub off_bw: Bandwidth,
+    pub duration: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    on: bool,
+    rng: R,
+    pareto: Pareto<f64>,
+}
+
+/// The configuration struct for [`ParetoBw`].
+///
+/// See [`ParetoBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct ParetoBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub scale: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub shape: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub on_bw: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub off_bw: Option<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model delays the start of the inner model with a leading period of [`Bandwidth::ZERO`]
+/// lasting `offset`, e.g. to align multiple traces in time.
+///
+/// The zero bandwidth segment is emitted first (unless `offset` is zero), followed by the segments
+/// of the inner model. Without an inner model, only the leading segment is produced.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{DelayedStartBwConfig, StaticBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut delayed_bw = DelayedStartBwConfig::new()
+///     .inner(Box::new(
+///         StaticBwConfig::new()
+///             .bw(Bandwidth::from_mbps(12))
+///             .duration(Duration::from_secs(1)),
+///     ))
+///     .offset(Duration::from_millis(200))
+///     .build();
+/// assert_eq!(delayed_bw.next_bw(), Some((Bandwidth::ZERO, Duration::from_millis(200))));
+/// assert_eq!(delayed_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_secs(1))));
+/// assert_eq!(delayed_bw.next_bw(), None);
+/// ```
+pub struct DelayedStartBw {
+    pub offset: Duration,
+    inner: Option<Box<dyn BwTrace>>,
+    started: bool,
+}
+
+/// The configuration struct for [`DelayedStartBw`].
+///
+/// See [`DelayedStartBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct DelayedStartBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub offset: Option<Duration>,
+}
+
+/// The model of a bandwidth trace switching between bandwidth levels after exponentially
+/// distributed (memoryless) dwell times.
+///
+/// Each segment picks one of `levels` uniformly at random, and holds it for a dwell time drawn from
+/// an exponential distribution of `rate` (per second), i.e. `1 / rate` seconds on average, which
+/// is more realistic than a fixed `step` for e.g. handovers between cells. The same level may be
+/// picked again for the next segment. The last segment is truncated to the remaining `duration`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::ExponentialDwellBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut dwell_bw = ExponentialDwellBwConfig::new()
+///     .levels(vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)])
+///     .rate(10.0)
+///     .duration(Duration::from_secs(1))
+///     .seed(42)
+///     .build();
+/// let (bw, _) = dwell_bw.next_bw().unwrap();
+/// assert!(bw == Bandwidth::from_mbps(12) || bw == Bandwidth::from_mbps(24));
+/// let total: Duration = std::iter::from_fn(|| dwell_bw.next_bw()).map(|(_, d)| d).sum();
+/// assert!(total < Duration::from_secs(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExponentialDwellBw<R = StdRng> {
+    pub levels: Vec<Bandwidth>,
+    pub rate: f64,
+    pub duration: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+    exp: Exp<f64>,
+}
+
+/// The configuration struct for [`ExponentialDwellBw`].
+///
+/// See [`ExponentialDwellBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct ExponentialDwellBwConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "bandwidths_serde")
+    )]
+    pub levels: Vec<Bandwidth>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rate: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model overlays random brief drops to zero (micro-outages) on the inner bandwidth trace model.
+///
+/// At every `step` (starting from time zero), an outage starts with probability `drop_prob`,
+/// during which the bandwidth is forced to zero for `drop_dur`. The inner model keeps running
+/// during the outage, i.e. the outage replaces the inner bandwidth instead of delaying it.
+/// Overlapping outages are merged.
+///
+/// If `drop_prob` is zero, the inner model is passed through unchanged.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{RandomOutageBwConfig, StaticBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut outage_bw = RandomOutageBwConfig::new()
+///     .inner(Box::new(
+///         StaticBwConfig::new()
+///             .bw(Bandwidth::from_mbps(12))
+///             .duration(Duration::from_millis(300)),
+///     ))
+///     .drop_prob(1.0)
+///     .drop_dur(Duration::from_millis(50))
+///     .step(Duration::from_millis(100))
+///     .build();
+/// assert_eq!(outage_bw.next_bw(), Some((Bandwidth::ZERO, Duration::from_millis(50))));
+/// assert_eq!(outage_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(50))));
+/// assert_eq!(outage_bw.next_bw(), Some((Bandwidth::ZERO, Duration::from_millis(50))));
+/// ```
+pub struct RandomOutageBw<R = StdRng> {
+    pub drop_prob: f64,
+    pub drop_dur: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    inner: Option<Box<dyn BwTrace>>,
+    // the rest of the inner segment which is not yet emitted
+    pending: Option<(Bandwidth, Duration)>,
+    now: Duration,
+    next_roll: Duration,
+    outage_end: Duration,
+    rng: R,
+}
+
+/// The configuration struct for [`RandomOutageBw`].
+///
+/// See [`RandomOutageBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct RandomOutageBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub drop_prob: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub drop_dur: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model of a bandwidth trace subjects to an empirical distribution.
+///
+/// The distribution is given by an empirical CDF as a sequence of `(bandwidth, cumulative_probability)` points,
+/// which must be sorted (by both bandwidth and probability) and end at probability 1.0. The CDF is linearly
+/// interpolated between the points, and the probability below the first point is assigned to the first bandwidth.
+///
+/// Every `step`, a uniform sample in `[0, 1)` is drawn and the bandwidth is obtained by inverting the CDF.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::EmpiricalBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut empirical_bw = EmpiricalBwConfig::new()
+///     .cdf(vec![
+///         (Bandwidth::from_mbps(12), 0.5),
+///         (Bandwidth::from_mbps(24), 0.5),
+///         (Bandwidth::from_mbps(24), 1.0),
+///     ])
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .seed(42)
+///     .build();
+/// let (bw, duration) = empirical_bw.next_bw().unwrap();
+/// assert!(bw == Bandwidth::from_mbps(12) || bw == Bandwidth::from_mbps(24));
+/// assert_eq!(duration, Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EmpiricalBw<R = StdRng> {
+    pub cdf: Vec<(Bandwidth, f64)>,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+}
+
+/// The configuration struct for [`EmpiricalBw`].
+///
+/// See [`EmpiricalBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct EmpiricalBwConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "empiricalbw_serde")
+    )]
+    pub cdf: Vec<(Bandwidth, f64)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model emits the maximum bandwidth of the inner bandwidth trace model over a trailing window.
+///
+/// At any time `t`, the bandwidth is the maximum bandwidth the inner model has in `(t - window, t]`,
+/// i.e. a peak is held for `window` after the inner model drops from it. The segments of the inner
+/// model within the window are buffered, and a segment is split where a buffered peak leaves the window.
+///
+/// The trace ends when the inner model ends. If `window` is zero, the inner model is passed through.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{RepeatedBwPatternConfig, StaticBwConfig, WindowMaxBwConfig};
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut window_max_bw = WindowMaxBwConfig::new()
+///     .inner(Box::new(
+///         RepeatedBwPatternConfig::new()
+///             .pattern(vec![
+///                 Box::new(
+///                     StaticBwConfig::new()
+///                         .bw(Bandwidth::from_mbps(24))
+///                         .duration(Duration::from_secs(1)),
+///                 ),
+///                 Box::new(
+///                     StaticBwConfig::new()
+///                         .bw(Bandwidth::from_mbps(12))
+///                         .duration(Duration::from_secs(1)),
+///                 ),
+///             ])
+///             .count(1),
+///     ))
+///     .window(Duration::from_millis(300))
+///     .build();
+/// assert_eq!(window_max_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_secs(1))));
+/// assert_eq!(window_max_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(300))));
+/// assert_eq!(window_max_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(700))));
+/// assert_eq!(window_max_bw.next_bw(), None);
+/// ```
+pub struct WindowMaxBw {
+    pub window: Duration,
+    inner: Option<Box<dyn BwTrace>>,
+    // the inner segment being emitted, as (bandwidth, end time)
+    current: Option<(Bandwidth, Duration)>,
+    // the past inner segments which may still be the maximum within the window, as (bandwidth, end time),
+    // with strictly decreasing bandwidth and increasing end time
+    history: VecDeque<(Bandwidth, Duration)>,
+    now: Duration,
+}
+
+/// The configuration struct for [`WindowMaxBw`].
+///
+/// See [`WindowMaxBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct WindowMaxBwConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub window: Option<Duration>,
+}
+
+/// The model of a bandwidth trace interpolating sparse `(time, bandwidth)` points with a natural cubic spline.
+///
+/// The spline passes through all the `points`, which must be sorted by strictly increasing time. It is sampled
+/// at the start of each `step` (from time zero) and lasts for `duration`, which defaults to the time of the last point.
+/// Before the first point or after the last point, the bandwidth of that point is held. The interpolated bandwidth
+/// is clamped to be non-negative.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::SplineBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// let mut spline_bw = SplineBwConfig::new()
+///     .points(vec![
+///         (Duration::ZERO, Bandwidth::from_mbps(12)),
+///         (Duration::from_secs(1), Bandwidth::from_mbps(24)),
+///         (Duration::from_secs(2), Bandwidth::from_mbps(12)),
+///     ])
+///     .step(Duration::from_millis(500))
+///     .build();
+/// assert_eq!(spline_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_millis(500))));
+/// // above the linear interpolation (18Mbps) as the spline is smooth at the peak
+/// assert_eq!(spline_bw.next_bw(), Some((Bandwidth::from_kbps(20250), Duration::from_millis(500))));
+/// assert_eq!(spline_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_millis(500))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplineBw {
+    pub points: Vec<(Duration, Bandwidth)>,
+    pub step: Duration,
+    pub duration: Duration,
+    // the second derivatives of the spline at the points, in bps/s^2
+    second_derivatives: Vec<f64>,
+    current: Duration,
+}
+
+/// The configuration struct for [`SplineBw`].
+///
+/// See [`SplineBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct SplineBwConfig {
+    #[cfg_attr(all(feature = "serde", feature = "human"), serde(with = "pairs_serde"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration::points")
+    )]
+    pub points: Vec<(Duration, Bandwidth)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+}
+
+/// The model of a bandwidth trace following a schedule keyed to the wall-clock time of day.
+///
+/// Each slot `(start_minute, end_minute, bandwidth)` sets the bandwidth from `start_minute` (inclusive)
+/// to `end_minute` (exclusive), counted in minutes since midnight **UTC** (from 0 to 1440). A slot with
+/// `start_minute` greater than `end_minute` wraps around midnight, e.g. `(1320, 120, bw)` lasts from
+/// 22:00 to 02:00. If slots overlap, the first one wins. The bandwidth is `default_bw` outside of all slots.
+///
+/// At each call of `next_bw`, the current time of day is queried, and the returned segment lasts until
+/// the next slot boundary (or midnight). The trace never ends.
+///
+/// The time is queried from [`SystemTime::now`](std::time::SystemTime::now) by default, and can be
+/// replaced with [`ScheduleBwConfig::build_with_clock`], e.g. for testing.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::ScheduleBwConfig;
+/// # use netem_trace::{Bandwidth, Duration, BwTrace};
+/// # use std::time::{SystemTime, UNIX_EPOCH};
+/// // lower bandwidth during peak hours from 18:00 to 23:00
+/// let mut schedule_bw = ScheduleBwConfig::new()
+///     .slots(vec![(18 * 60, 23 * 60, Bandwidth::from_mbps(2))])
+///     .default_bw(Bandwidth::from_mbps(12))
+///     // 17:30 UTC
+///     .build_with_clock(|| UNIX_EPOCH + Duration::from_secs(17 * 3600 + 30 * 60));
+/// assert_eq!(schedule_bw.next_bw(), Some((Bandwidth::from_mbps(12), Duration::from_secs(30 * 60))));
+/// ```
+#[derive(Clone)]
+pub struct ScheduleBw {
+    pub slots: Vec<(u32, u32, Bandwidth)>,
+    pub default_bw: Bandwidth,
+    clock: std::sync::Arc<dyn Fn() -> std::time::SystemTime + Send + Sync>,
+}
+
+/// The configuration struct for [`ScheduleBw`].
+///
+/// See [`ScheduleBw`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleBwConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "schedulebw_serde")
+    )]
+    pub slots: Vec<(u32, u32, Bandwidth)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "human_bandwidth::serde")
+    )]
+    pub default_bw: Option<Bandwidth>,
+}
+
+impl BwTrace for StaticBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if let Some(duration) = self.duration.take() {
+            if duration.is_zero() {
+                None
+            } else {
+                Some((self.bw, duration))
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl BwTrace for Rewind<StaticBw> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.model.next_bw()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for NormalizedBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() || (!self.exact_duration && self.duration < self.step) {
+            None
+        } else {
+            let mut bw = Bandwidth::from_bps_f64(self.sample());
+            if let Some(lower_bound) = self.lower_bound {
+                bw = bw.max(lower_bound);
+            }
+            if let Some(upper_bound) = self.upper_bound {
+                bw = bw.min(upper_bound);
+            }
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            Some((bw, duration))
+        }
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for SawtoothBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let current = self.current.as_secs_f64();
+            let interval = self.interval.as_secs_f64();
+            let change_point = interval * self.duty_ratio;
+            // a zero rising (duty_ratio 0.0) or falling (duty_ratio 1.0) time must not be divided by
+            let base_bw = if self.duty_ratio >= 1.0 || current < change_point {
+                let ratio = if change_point > 0.0 {
+                    (current / change_point).min(1.0)
+                } else {
+                    0.0
+                };
+                self.bottom + (self.top - self.bottom).mul_f64(ratio)
+            } else {
+                let falling = interval - change_point;
+                let ratio = if falling > 0.0 {
+                    ((current - change_point) / falling).min(1.0)
+                } else {
+                    0.0
+                };
+                self.top - (self.top - self.bottom).mul_f64(ratio)
+            };
+            let mut offset = self.noise.sample(&mut self.rng);
+            if let Some(upper_noise_bound) = self.upper_noise_bound {
+                offset = offset.min(upper_noise_bound.as_bps() as f64);
+            }
+            if let Some(lower_noise_bound) = self.lower_noise_bound {
+                offset = offset.max(-(lower_noise_bound.as_bps() as f64));
+            }
+            let bw = Bandwidth::from_bps_f64(base_bw.as_bps() as f64 + offset);
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            self.current += duration;
+            if self.current >= self.interval {
+                self.current -= self.interval;
+            }
+            Some((bw, duration))
+        }
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for SineBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let phase = if self.period.is_zero() {
+                0.0
+            } else {
+                self.current.as_secs_f64() / self.period.as_secs_f64()
+            };
+            let base_bps = self.mean.as_bps() as f64
+                + self.amplitude.as_bps() as f64 * (2.0 * std::f64::consts::PI * phase).sin();
+            let mut offset = self.noise.sample(&mut self.rng);
+            if let Some(upper_noise_bound) = self.upper_noise_bound {
+                offset = offset.min(upper_noise_bound.as_bps() as f64);
+            }
+            if let Some(lower_noise_bound) = self.lower_noise_bound {
+                offset = offset.max(-(lower_noise_bound.as_bps() as f64));
+            }
+            let bw = Bandwidth::from_bps_f64((base_bps + offset).max(0.0));
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            self.current += duration;
+            if !self.period.is_zero() && self.current >= self.period {
+                self.current -= self.period;
+            }
+            Some((bw, duration))
+        }
+    }
+}
+
+impl BwTrace for SquareBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            // rounded to nanoseconds, so that e.g. 3/7 of 7ms is exactly 3ms
+            let boundary = Duration::from_nanos(
+                (self.period.as_nanos() as f64 * self.duty_ratio).round() as u64,
+            );
+            let (bw, level_end) = if self.current < boundary {
+                (self.high, boundary)
+            } else {
+                (self.low, self.period)
+            };
+            let mut duration = self.step.min(self.duration);
+            if !self.period.is_zero() {
+                duration = duration.min(level_end - self.current);
+            }
+            self.duration -= duration;
+            self.current += duration;
+            if !self.period.is_zero() && self.current >= self.period {
+                self.current -= self.period;
+            }
+            Some((bw, duration))
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for RepeatedBwPattern {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        // iterate instead of recursing, so that a long run of sub-models producing nothing
+        // cannot overflow the stack
+        while self.count == 0 || self.current_cycle < self.count {
+            if self.current_model.is_none() {
+                self.current_model = Some(self.take_model(self.current_pattern));
+            }
+            if let Some((bw, duration, tag)) = self.current_model.as_mut().unwrap().next_bw_tagged()
+            {
+                self.guard.produced();
+                self.current_tag = tag.map(str::to_owned);
+                return Some((bw, duration, self.current_tag.as_deref()));
+            }
+            self.cache_current_model();
+            if !self.guard.ended(self.current_pattern) {
+                debug_log!("RepeatedBwPattern stopped as no sub-model produces anything");
+                return None;
+            }
+            self.current_pattern += 1;
+            if self.current_pattern >= self.pattern.len() {
+                self.current_pattern = 0;
+                self.current_cycle += 1;
+                debug_log!(
+                    "RepeatedBwPattern completed cycle {} (count: {})",
+                    self.current_cycle,
+                    self.count
+                );
+                if self.count != 0 && self.current_cycle >= self.count {
+                    debug_log!(
+                        "RepeatedBwPattern exhausted after {} cycles",
+                        self.current_cycle
+                    );
+                }
+            }
+        }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl RepeatedBwPattern {
+    /// Take the model of the `index`-th config in the pattern, which is either the cached one
+    /// being reset, or a newly built one.
+    fn take_model(&mut self, index: usize) -> Box<dyn BwTrace> {
+        match self.cached_models.get_mut(index).and_then(Option::take) {
+            Some(mut model) => {
+                model.as_resettable().unwrap().reset();
+                model
+            }
+            None => self.pattern[index].clone().into_model(),
+        }
+    }
+
+    /// Cache the current model for the next cycle if it is resettable, otherwise drop it.
+    fn cache_current_model(&mut self) {
+        if let Some(mut model) = self.current_model.take() {
+            if model.as_resettable().is_some() {
+                if self.cached_models.len() < self.pattern.len() {
+                    self.cached_models.resize_with(self.pattern.len(), || None);
+                }
+                self.cached_models[self.current_pattern] = Some(model);
+            }
+        }
+    }
+}
+
+impl TraceBw {
+    /// The bandwidth of the segment following the current one, if any.
+    fn next_segment_bw(&self) -> Option<Bandwidth> {
+        let bandwidths = &self.pattern[self.outer_index].1;
+        bandwidths.get(self.inner_index + 1).copied().or_else(|| {
+            self.pattern
+                .get(self.outer_index + 1)
+                .map(|(_, bandwidths)| bandwidths[0])
+        })
+    }
+}
+
+impl BwTrace for TraceBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let (bandwidth, duration) =
+            self.pattern
+                .get(self.outer_index)
+                .and_then(|(duration, bandwidth)| {
+                    bandwidth
+                        .get(self.inner_index)
+                        .map(|bandwidth| (*bandwidth, *duration))
+                })?;
+        let remaining = duration - self.current;
+        let sample = self.step.map_or(remaining, |step| step.min(remaining));
+        let bandwidth = match (self.interpolation, self.next_segment_bw()) {
+            _ if duration.is_zero() => bandwidth,
+            (Interpolation::Linear, Some(next)) => {
+                let ratio = self.current.as_secs_f64() / duration.as_secs_f64();
+                let (from, to) = (bandwidth.as_gbps_f64(), next.as_gbps_f64());
+                let bps = (from + (to - from) * ratio) * 1e9;
+                Bandwidth::from_bps(bps.round() as u64)
+            }
+            (Interpolation::Nearest, Some(next)) if self.current >= duration / 2 => next,
+            _ => bandwidth,
+        };
+        self.current += sample;
+        if self.current >= duration {
+            self.current = Duration::ZERO;
+            if self.pattern[self.outer_index].1.len() > self.inner_index + 1 {
+                self.inner_index += 1;
+            } else {
+                self.outer_index += 1;
+                self.inner_index = 0;
+            }
+        }
+        Some((bandwidth, sample))
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for SequenceBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let result = self.sequence.get(self.index).copied();
+        if result.is_some() {
+            self.index += 1;
+        }
+        result
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for TaggedBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.inner.as_mut()?.next_bw()
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        self.inner
+            .as_mut()?
+            .next_bw_tagged()
+            .map(|(bw, duration, tag)| (bw, duration, Some(tag.unwrap_or(&self.tag))))
+    }
+}
+
+impl BwTrace for FloorPlusVariableBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let floor = self.floor;
+        self.variable
+            .as_mut()?
+            .next_bw_tagged()
+            .map(|(bw, duration, tag)| (floor.saturating_add(bw), duration, tag))
+    }
+}
+
+impl BwTrace for ScaledBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let factor = self.factor;
+        self.inner
+            .as_mut()?
+            .next_bw_tagged()
+            .map(|(bw, duration, tag)| {
+                // saturates instead of panicking like `Bandwidth::mul_f64` on overflow
+                let bw = Bandwidth::try_from_gbps_f64(bw.as_gbps_f64() * factor)
+                    .unwrap_or(Bandwidth::MAX);
+                (bw, duration, tag)
+            })
+    }
+}
+
+impl BwTrace for ClampedBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let (min, max) = (self.min, self.max);
+        self.inner
+            .as_mut()?
+            .next_bw_tagged()
+            .map(|(mut bw, duration, tag)| {
+                if let Some(min) = min {
+                    bw = bw.max(min);
+                }
+                if let Some(max) = max {
+                    bw = bw.min(max);
+                }
+                (bw, duration, tag)
+            })
+    }
+}
+
+impl BwTrace for RandomPhaseBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let inner = self.inner.as_mut()?;
+        while !self.skip.is_zero() {
+            let (bw, duration, tag) = inner.next_bw_tagged()?;
+            if duration > self.skip {
+                // cut the segment containing the offset
+                let duration = duration - std::mem::take(&mut self.skip);
+                self.current_tag = tag.map(str::to_owned);
+                return Some((bw, duration, self.current_tag.as_deref()));
+            }
+            self.skip -= duration;
+        }
+        inner.next_bw_tagged()
+    }
+}
+
+impl<R: RngCore> MarkovBw<R> {
+    /// Draw the next state from the row of the current state.
+    fn transition(&mut self) {
+        let row = &self.transitions[self.current_state];
+        let p = self.rng.random::<f64>();
+        let mut cumulative = 0.0;
+        for (state, prob) in row.iter().enumerate() {
+            cumulative += prob;
+            if p < cumulative {
+                self.current_state = state;
+                return;
+            }
+        }
+        // the row may sum to slightly less than 1.0, fall back to the last reachable state
+        if let Some(state) = row.iter().rposition(|prob| *prob > 0.0) {
+            self.current_state = state;
+        }
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for MarkovBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let bw = self.states[self.current_state];
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            self.transition();
+            Some((bw, duration))
+        }
+    }
+}
+
+impl BwTrace for HysteresisBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let inner = self.inner.as_mut()?;
+        loop {
+            if self.pending >= self.max_hold {
+                // the held level is cut but still held
+                let (level, tag) = self.level.as_ref()?;
+                self.current_tag = tag.clone();
+                return Some((
+                    *level,
+                    std::mem::take(&mut self.pending),
+                    self.current_tag.as_deref(),
+                ));
+            }
+            let Some((bw, duration, tag)) = inner.next_bw_tagged() else {
+                let (level, tag) = self.level.as_ref()?;
+                if self.pending.is_zero() {
+                    return None;
+                }
+                self.current_tag = tag.clone();
+                return Some((
+                    *level,
+                    std::mem::take(&mut self.pending),
+                    self.current_tag.as_deref(),
+                ));
+            };
+            let held = self.level.as_ref().is_some_and(|(level, _)| {
+                let change = if bw > *level {
+                    bw - *level
+                } else {
+                    *level - bw
+                };
+                change <= self.threshold
+            });
+            if held {
+                self.pending = self.pending.saturating_add(duration);
+                continue;
+            }
+            let old = self.level.replace((bw, tag.map(str::to_owned)));
+            let pending = std::mem::replace(&mut self.pending, duration);
+            if let Some((level, tag)) = old.filter(|_| !pending.is_zero()) {
+                self.current_tag = tag;
+                return Some((level, pending, self.current_tag.as_deref()));
+            }
+        }
+    }
+}
+
+impl BwTrace for RampBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.current >= self.duration {
+            None
+        } else {
+            let duration = self.step.min(self.duration - self.current);
+            let bw = if self.current + duration >= self.duration {
+                self.end
+            } else {
+                // the final step starts at the last multiple of `step` before the end
+                let cut = (self.duration.as_nanos() - 1) % self.step.as_nanos() + 1;
+                let last = self.duration.as_nanos() - cut;
+                let ratio = self.current.as_nanos() as f64 / last as f64;
+                let start = self.start.as_bps() as f64;
+                let end = self.end.as_bps() as f64;
+                Bandwidth::from_bps_f64((start + (end - start) * ratio).round())
+            };
+            self.current += duration;
+            Some((bw, duration))
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore> RandomPatternBw<R> {
+    /// Pick the index of a config in the pattern with a probability proportional to its weight.
+    fn pick(&mut self) -> usize {
+        let total: f64 = self.pattern.iter().map(|(weight, _)| weight).sum();
+        let p = self.rng.random::<f64>() * total;
+        let mut cumulative = 0.0;
+        for (index, (weight, _)) in self.pattern.iter().enumerate() {
+            cumulative += weight;
+            if p < cumulative {
+                return index;
+            }
+        }
+        // the rounding error may leave `p` beyond the sum, fall back to the last pickable config
+        self.pattern
+            .iter()
+            .rposition(|(weight, _)| *weight > 0.0)
+            .unwrap_or(0)
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for RandomPatternBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        while self.count == 0 || self.current_cycle < self.count {
+            if self.current_model.is_none() {
+                let index = self.pick();
+                debug_log!(
+                    "RandomPatternBw picked config {} for cycle {}",
+                    index,
+                    self.current_cycle
+                );
+                self.current_index = index;
+                self.current_model = Some(self.pattern[index].1.clone().into_model());
+            }
+            if let Some((bw, duration, tag)) = self.current_model.as_mut().unwrap().next_bw_tagged()
+            {
+                self.guard.produced();
+                self.current_tag = tag.map(str::to_owned);
+                return Some((bw, duration, self.current_tag.as_deref()));
+            }
+            self.current_model = None;
+            if !self.guard.ended(self.current_index) {
+                debug_log!("RandomPatternBw stopped as no config produces anything");
+                return None;
+            }
+            self.current_cycle += 1;
+        }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for ReverseBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        if let Some(mut inner) = self.inner.take() {
+            while let Some((bw, duration, tag)) = inner.next_bw_tagged() {
+                self.segments.push((bw, duration, tag.map(str::to_owned)));
+            }
+            debug_log!("ReverseBw drained {} segments", self.segments.len());
+        }
+        // the buffer is in the order of the inner model, so popping plays it backwards
+        let (bw, duration, tag) = self.segments.pop()?;
+        self.current_tag = tag;
+        Some((bw, duration, self.current_tag.as_deref()))
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for PoissonBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            let secs = duration.as_secs_f64();
+            // a zero rate is rejected by `Poisson::new`, and means no packet at all
+            let count = Poisson::new(self.rate_pps * secs)
+                .map(|poisson| poisson.sample(&mut self.rng))
+                .unwrap_or(0.0);
+            let bw = Bandwidth::from_bps_f64(count * self.packet_size as f64 * 8.0 / secs);
+            Some((bw, duration))
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for SumBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        // refill the inputs whose current segment is used up
+        for (input, current) in self.inputs.iter_mut().zip(self.current.iter_mut()) {
+            if current.is_none_or(|(_, remaining)| remaining.is_zero()) {
+                *current = std::iter::from_fn(|| input.next_bw())
+                    .find(|(_, duration)| !duration.is_zero());
+            }
+        }
+        let duration = self
+            .current
+            .iter()
+            .filter_map(|current| current.map(|(_, remaining)| remaining))
+            .min()?;
+        let mut sum = Bandwidth::ZERO;
+        for (bw, remaining) in self.current.iter_mut().flatten() {
+            sum = sum.saturating_add(*bw);
+            *remaining -= duration;
+        }
+        Some((sum, duration))
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for WeibullBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let sample = self.weibull.sample(&mut self.rng);
+            let mut bw = Bandwidth::from_bps_f64(sample * self.scale.as_bps() as f64);
+            if let Some(lower_bound) = self.lower_bound {
+                bw = bw.max(lower_bound);
+            }
+            if let Some(upper_bound) = self.upper_bound {
+                bw = bw.min(upper_bound);
+            }
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            Some((bw, duration))
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for GammaBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let mut bw = Bandwidth::from_bps_f64(self.gamma.sample(&mut self.rng));
+            if let Some(lower_bound) = self.lower_bound {
+                bw = bw.max(lower_bound);
+            }
+            if let Some(upper_bound) = self.upper_bound {
+                bw = bw.min(upper_bound);
+            }
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            Some((bw, duration))
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for SpliceBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let (bw, remaining) = match self.pending.take() {
+            Some(segment) => segment,
+            None => loop {
+                let (bw, duration) = self.base.as_mut()?.next_bw()?;
+                if !duration.is_zero() {
+                    break (bw, duration);
+                }
+            },
+        };
+        let in_window = self.start <= self.now && self.now < self.end;
+        let boundary = if self.now < self.start {
+            self.start
+        } else if in_window {
+            self.end
+        } else {
+            Duration::MAX
+        };
+        let duration = remaining.min(boundary - self.now);
+        if remaining > duration {
+            self.pending = Some((bw, remaining - duration));
+        }
+        self.now = self.now.saturating_add(duration);
+        if in_window {
+            Some((self.override_bw, duration))
+        } else {
+            Some((bw, duration))
+        }
+    }
+}
+
+/// The nearest of the sorted `levels` to `bw`, or the lower one of a tie, or `bw` without levels.
+fn quantize(levels: &[Bandwidth], bw: Bandwidth) -> Bandwidth {
+    let index = levels.partition_point(|level| *level < bw);
+    match (index.checked_sub(1).map(|i| levels[i]), levels.get(index)) {
+        (Some(lower), Some(&upper)) if upper - bw < bw - lower => upper,
+        (Some(lower), _) => lower,
+        (None, Some(&upper)) => upper,
+        (None, None) => bw,
+    }
+}
+
+#[cfg(feature = "loss-model")]
+impl<R: RngCore + Send> BwTrace for LossAsGapsBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let (Some(bw), Some(loss)) = (self.bw.as_mut(), self.loss.as_mut()) else {
+            return None;
+        };
+        let mut new_segment = false;
+        let (bandwidth, pattern, duration) = self.merger.next(
+            || {
+                new_segment = true;
+                bw.next_bw()
+            },
+            || loss.next_loss(),
+        )?;
+        if new_segment {
+            // the draw is made once for the whole bandwidth segment, by the loss rate at its start
+            let loss_rate = pattern.first().map_or(0.0, |rate| rate.clamp(0.0, 1.0));
+            self.zeroed = loss_rate > 0.0 && self.rng.random::<f64>() < loss_rate;
+        }
+        let bandwidth = if self.zeroed {
+            Bandwidth::ZERO
+        } else {
+            *bandwidth
+        };
+        Some((bandwidth, duration))
+    }
+}
+
+impl BwTrace for QuantizedBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        self.next_bw_tagged()
+            .map(|(bw, duration, _)| (bw, duration))
+    }
+
+    fn next_bw_tagged(&mut self) -> Option<(Bandwidth, Duration, Option<&str>)> {
+        let levels = &self.levels;
+        self.inner
+            .as_mut()?
+            .next_bw_tagged()
+            .map(|(bw, duration, tag)| (quantize(levels, bw), duration, tag))
+    }
+}
+
+impl BwTrace for StepBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.steps.is_empty() || self.hold.is_zero() {
+            return None;
+        }
+        if self.index == self.steps.len() {
+            self.index = 0;
+            self.cycle += 1;
+        }
+        if self.repeat != 0 && self.cycle >= self.repeat {
+            return None;
+        }
+        let bw = self.steps[self.index];
+        self.index += 1;
+        Some((bw, self.hold))
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for ParetoBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            return None;
+        }
+        let bw = if self.on { self.on_bw } else { self.off_bw };
+        self.on = !self.on;
+        let period = Duration::try_from_secs_f64(self.pareto.sample(&mut self.rng))
+            .unwrap_or(Duration::MAX)
+            // a period below 1ns would be an empty segment
+            .max(Duration::from_nanos(1));
+        let duration = period.min(self.duration);
+        self.duration -= duration;
+        Some((bw, duration))
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl BwTrace for DelayedStartBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if !self.started {
+            self.started = true;
+            if !self.offset.is_zero() {
+                return Some((Bandwidth::ZERO, self.offset));
+            }
+        }
+        self.inner.as_mut()?.next_bw()
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> BwTrace for ExponentialDwellBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            return None;
+        }
+        let bw = self.levels[self.rng.random_range(0..self.levels.len())];
+        let dwell = Duration::try_from_secs_f64(self.exp.sample(&mut self.rng))
+            .unwrap_or(Duration::MAX)
+            // a dwell time below 1ns would be an empty segment
+            .max(Duration::from_nanos(1));
+        let duration = dwell.min(self.duration);
+        self.duration -= duration;
+        Some((bw, duration))
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for RandomOutageBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.drop_prob <= 0.0 || self.step.is_zero() {
+            return self.inner.as_mut()?.next_bw();
+        }
+        let (bw, remaining) = match self.pending.take() {
+            Some(segment) => segment,
+            None => loop {
+                let (bw, duration) = self.inner.as_mut()?.next_bw()?;
+                if !duration.is_zero() {
+                    break (bw, duration);
+                }
+            },
+        };
+        while self.next_roll <= self.now && self.next_roll < Duration::MAX {
+            if self.rng.random::<f64>() < self.drop_prob {
+                self.outage_end = self
+                    .outage_end
+                    .max(self.next_roll.saturating_add(self.drop_dur));
+            }
+            self.next_roll = self.next_roll.saturating_add(self.step);
+        }
+        let in_outage = self.now < self.outage_end;
+        let boundary = if in_outage {
+            self.outage_end.min(self.next_roll)
+        } else {
+            self.next_roll
+        };
+        let duration = remaining.min(boundary.saturating_sub(self.now));
+        if remaining > duration {
+            self.pending = Some((bw, remaining - duration));
+        }
+        self.now = self.now.saturating_add(duration);
+        if in_outage {
+            Some((Bandwidth::ZERO, duration))
+        } else {
+            Some((bw, duration))
+        }
+    }
+}
+
+impl<R: SeedableRng> EmpiricalBw<R> {
+    /// Rerun the model from the beginning under a new seed.
+    ///
+    /// The RNG is re-seeded with `seed`, the remaining duration is restored to the one
+    /// the model was built with, and the stored `seed` is updated.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed);
+        self.duration = self.total_duration;
+    }
+}
+
+impl<R> EmpiricalBw<R> {
+    /// Invert the linearly interpolated CDF at probability `p`.
+    fn inverse_cdf(&self, p: f64) -> Bandwidth {
+        let idx = self.cdf.partition_point(|(_, prob)| *prob < p);
+        if idx == 0 {
+            return self.cdf[0].0;
+        }
+        let Some(&(high_bw, high_prob)) = self.cdf.get(idx) else {
+            return self.cdf[self.cdf.len() - 1].0;
+        };
+        let (low_bw, low_prob) = self.cdf[idx - 1];
+        let ratio = (p - low_prob) / (high_prob - low_prob);
+        low_bw + (high_bw - low_bw).mul_f64(ratio)
+    }
+}
+
+impl<R: RngCore + Send> BwTrace for EmpiricalBw<R> {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let prob = self.rng.random::<f64>();
+            let bw = self.inverse_cdf(prob);
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            Some((bw, duration))
+        }
+    }
+}
+
+impl WindowMaxBw {
+    fn push_history(&mut self, bw: Bandwidth, end: Duration) {
+        if self.window.is_zero() {
+            return;
+        }
+        // a past segment with no larger bandwidth leaves the window no later than this one
+        while self
+            .history
+            .back()
+            .is_some_and(|&(past_bw, _)| past_bw <= bw)
+        {
+            self.history.pop_back();
+        }
+        self.history.push_back((bw, end));
+    }
+}
+
+impl BwTrace for WindowMaxBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let (bw, end) = match self.current {
+            Some((bw, end)) if end > self.now => (bw, end),
+            _ => {
+                if let Some((bw, end)) = self.current.take() {
+                    self.push_history(bw, end);
+                }
+                let (bw, end) = loop {
+                    let (bw, duration) = self.inner.as_mut()?.next_bw()?;
+                    if !duration.is_zero() {
+                        break (bw, self.now.saturating_add(duration));
+                    }
+                };
+                self.current = Some((bw, end));
+                (bw, end)
+            }
+        };
+        while self
+            .history
+            .front()
+            .is_some_and(|&(_, past_end)| past_end.saturating_add(self.window) <= self.now)
+        {
+            self.history.pop_front();
+        }
+        let (max_bw, boundary) = match self.history.front() {
+            Some(&(past_bw, past_end)) if past_bw > bw => {
+                (past_bw, end.min(past_end.saturating_add(self.window)))
+            }
+            _ => (bw, end),
+        };
+        let duration = boundary - self.now;
+        self.now = boundary;
+        Some((max_bw, duration))
+    }
+}
+
+impl SplineBw {
+    /// Evaluate the spline at time `t` (in seconds), in bps.
+    fn interpolate(&self, t: f64) -> f64 {
+        let x = |i: usize| self.points[i].0.as_secs_f64();
+        let y = |i: usize| self.points[i].1.as_bps() as f64;
+        let last = self.points.len() - 1;
+        if t <= x(0) {
+            return y(0);
+        }
+        if t >= x(last) {
+            return y(last);
+        }
+        // the segment [x(i), x(i + 1)] containing t
+        let i = self
+            .points
+            .partition_point(|(time, _)| time.as_secs_f64() <= t)
+            - 1;
+        let h = x(i + 1) - x(i);
+        let (a, b) = (x(i + 1) - t, t - x(i));
+        let (m0, m1) = (self.second_derivatives[i], self.second_derivatives[i + 1]);
+        m0 * a.powi(3) / (6.0 * h)
+            + m1 * b.powi(3) / (6.0 * h)
+            + (y(i) / h - m0 * h / 6.0) * a
+            + (y(i + 1) / h - m1 * h / 6.0) * b
+    }
+}
+
+impl BwTrace for SplineBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            let bps = self.interpolate(self.current.as_secs_f64()).max(0.0);
+            let bw = Bandwidth::from_bps(bps.round() as u64);
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            self.current += duration;
+            Some((bw, duration))
+        }
+    }
+}
+
+impl std::fmt::Debug for ScheduleBw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduleBw")
+            .field("slots", &self.slots)
+            .field("default_bw", &self.default_bw)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScheduleBw {
+    const MINUTES_PER_DAY: u32 = 24 * 60;
+
+    /// Returns the bandwidth of the `minute` of day, i.e. the one of the first slot containing it.
+    fn bw_at(&self, minute: u32) -> Bandwidth {
+        self.slots
+            .iter()
+            .find(|(start, end, _)| {
+                if start <= end {
+                    (*start..*end).contains(&minute)
+                } else {
+                    minute >= *start || minute < *end
+                }
+            })
+            .map_or(self.default_bw, |(_, _, bw)| *bw)
+    }
+}
+
+impl BwTrace for ScheduleBw {
+    fn next_bw(&mut self) -> Option<(Bandwidth, Duration)> {
+        let since_epoch = (self.clock)()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let day = Duration::from_secs(Self::MINUTES_PER_DAY as u64 * 60);
+        let time_of_day = Duration::new(
+            since_epoch.as_secs() % day.as_secs(),
+            since_epoch.subsec_nanos(),
+        );
+        let minute = (time_of_day.as_secs() / 60) as u32;
+        let next_boundary = self
+            .slots
+            .iter()
+            .flat_map(|(start, end, _)| [*start, *end])
+            .filter(|boundary| *boundary > minute)
+            .min()
+            .unwrap_or(Self::MINUTES_PER_DAY)
+            .min(Self::MINUTES_PER_DAY);
+        let duration = Duration::from_secs(next_boundary as u64 * 60) - time_of_day;
+        Some((self.bw_at(minute), duration))
+    }
+}
+
+impl<R: RngCore> NormalizedBw<R> {
+    pub fn sample(&mut self) -> f64 {
+        self.normal.sample(&mut self.rng)
+    }
+}
+
+impl<R: SeedableRng> NormalizedBw<R> {
+    /// Rerun the model from the beginning under a new seed.
+    ///
+    /// The RNG is re-seeded with `seed`, the remaining duration is restored to the one
+    /// the model was built with, and the stored `seed` is updated.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::NormalizedBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration, BwTrace};
+    /// let mut normal_bw = NormalizedBwConfig::new()
+    ///     .mean(Bandwidth::from_mbps(12))
+    ///     .std_dev(Bandwidth::from_mbps(1))
+    ///     .duration(Duration::from_millis(200))
+    ///     .step(Duration::from_millis(100))
+    ///     .seed(42)
+    ///     .build();
+    /// let first = normal_bw.next_bw();
+    /// normal_bw.reset_with_seed(42);
+    /// assert_eq!(normal_bw.next_bw(), first);
+    /// ```
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed);
+        self.duration = self.total_duration;
+    }
+}
+
+#[cfg(feature = "rng-state")]
+impl<R: RngState> NormalizedBw<R> {
+    /// Save the state of the RNG, e.g. to checkpoint a long-running emulation.
+    ///
+    /// The model must be built with a RNG implementing [`RngState`], e.g. with
+    /// [`build_with_rng`](NormalizedBwConfig::build_with_rng) and a `ChaCha20Rng`, since the state
+    /// of the default [`StdRng`] is not exposed. Only the RNG is saved: the remaining `duration`
+    /// must be saved and restored alongside it.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::NormalizedBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration, BwTrace};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let config = NormalizedBwConfig::new()
+    ///     .mean(Bandwidth::from_mbps(12))
+    ///     .std_dev(Bandwidth::from_mbps(1))
+    ///     .duration(Duration::from_secs(1))
+    ///     .step(Duration::from_millis(100));
+    /// let mut normal_bw = config.clone().build_with_rng(ChaCha20Rng::seed_from_u64(42));
+    /// normal_bw.next_bw();
+    /// let (state, duration) = (normal_bw.rng_state(), normal_bw.duration);
+    ///
+    /// let mut resumed = config.build_with_rng(ChaCha20Rng::seed_from_u64(0));
+    /// resumed.restore_rng_state(&state).unwrap();
+    /// resumed.duration = duration;
+    /// assert_eq!(resumed.next_bw(), normal_bw.next_bw());
+    /// ```
+    pub fn rng_state(&self) -> Vec<u8> {
+        self.rng.rng_state()
+    }
+
+    /// Restore the state of the RNG saved by [`rng_state`](Self::rng_state).
+    ///
+    /// Returns `Err` and leaves the model untouched if the state is invalid.
+    pub fn restore_rng_state(&mut self, state: &[u8]) -> Result<(), RngStateError> {
+        self.rng.restore_rng_state(state)
+    }
+}
+
+impl Resettable for SquareBw {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.current = Duration::ZERO;
+    }
+}
+
+impl Resettable for RampBw {
+    fn reset(&mut self) {
+        self.current = Duration::ZERO;
+    }
+}
+
+impl<R: SeedableRng> Resettable for RandomPatternBw<R> {
+    fn reset(&mut self) {
+        self.current_model = None;
+        self.current_cycle = 0;
+        self.current_tag = None;
+        self.rng = R::seed_from_u64(self.seed);
+        self.guard.reset();
+    }
+}
+
+impl<R: SeedableRng> Resettable for PoissonBw<R> {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl<R: SeedableRng> Resettable for WeibullBw<R> {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl<R: SeedableRng> Resettable for GammaBw<R> {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl<R: SeedableRng> Resettable for ExponentialDwellBw<R> {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl Resettable for StepBw {
+    fn reset(&mut self) {
+        self.index = 0;
+        self.cycle = 0;
+    }
+}
+
+impl<R: SeedableRng> Resettable for ParetoBw<R> {
+    fn reset(&mut self) {
+        self.duration = self.total_duration;
+        self.on = true;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl Resettable for TraceBw {
+    fn reset(&mut self) {
+        self.outer_index = 0;
+        self.inner_index = 0;
+        self.current = Duration::ZERO;
+    }
+}
+
+impl Resettable for SequenceBw {
+    fn reset(&mut self) {
+        self.index = 0;
+    }
+}
+
+impl Resettable for RepeatedBwPattern {
+    fn reset(&mut self) {
+        self.cache_current_model();
+        self.current_cycle = 0;
+        self.current_pattern = 0;
+        self.current_tag = None;
+        self.guard.reset();
+    }
+}
+
+impl<R: SeedableRng> SawtoothBw<R> {
+    /// Rerun the model from the beginning under a new seed.
+    ///
+    /// The RNG is re-seeded with `seed`, the remaining duration and the position in the sawtooth
+    /// are restored to the ones the model was built with, and the stored `seed` is updated.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed);
+        self.duration = self.total_duration;
+        self.current = Duration::ZERO;
+    }
+}
+
+#[cfg(feature = "rng-state")]
+impl<R: RngState> SawtoothBw<R> {
+    /// Save the state of the RNG of the noise, e.g. to checkpoint a long-running emulation.
+    ///
+    /// The model must be built with a RNG implementing [`RngState`], e.g. with
+    /// [`build_with_rng`](SawtoothBwConfig::build_with_rng) and a `ChaCha20Rng`, since the state
+    /// of the default [`StdRng`] is not exposed. Only the RNG is saved, not the remaining duration
+    /// or the position in the sawtooth.
+    pub fn rng_state(&self) -> Vec<u8> {
+        self.rng.rng_state()
+    }
+
+    /// Restore the state of the RNG of the noise saved by [`rng_state`](Self::rng_state).
+    ///
+    /// Returns `Err` and leaves the model untouched if the state is invalid.
+    pub fn restore_rng_state(&mut self, state: &[u8]) -> Result<(), RngStateError> {
+        self.rng.restore_rng_state(state)
+    }
+}
+
+impl<R: SeedableRng> SineBw<R> {
+    /// Rerun the model from the beginning under a new seed.
+    ///
+    /// The RNG is re-seeded with `seed`, the remaining duration and the phase of the sine wave
+    /// are restored to the ones the model was built with, and the stored `seed` is updated.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed);
+        self.duration = self.total_duration;
+        self.current = Duration::ZERO;
+    }
+}
+
+impl StaticBwConfig {
+    pub fn new() -> Self {
+        Self {
+            bw: None,
+            duration: None,
+        }
+    }
+
+    pub fn bw(mut self, bw: Bandwidth) -> Self {
+        self.bw = Some(bw);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    pub(crate) fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// The bandwidth of the model, defaulting to 12Mbps.
+    pub(crate) fn bw_or_default(&self) -> Bandwidth {
+        self.bw.unwrap_or_else(|| Bandwidth::from_mbps(12))
+    }
+
+    pub fn build(self) -> StaticBw {
+        let bw = self.bw_or_default();
+        let duration = self.finite_duration();
+        debug_log!(
+            "StaticBw built: bw {}, duration {}",
+            fmt_bandwidth(bw),
+            fmt_delay(duration)
+        );
         StaticBw {
-            bw: self.bw.unwrap_or_else(|| Bandwidth::from_mbps(12)),
-            duration: Some(self.duration.unwrap_or_else(|| Duration::from_secs(1))),
+            bw,
+            duration: Some(duration),
+        }
+    }
+}
+
+impl EnvBwConfig {
+    pub fn new() -> Self {
+        Self {
+            var: None,
+            default_bw: None,
+            duration: None,
+        }
+    }
+
+    pub fn var(mut self, var: impl Into<String>) -> Self {
+        self.var = Some(var.into());
+        self
+    }
+
+    pub fn default_bw(mut self, default_bw: Bandwidth) -> Self {
+        self.default_bw = Some(default_bw);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// The bandwidth read from the variable with `lookup`, if it is configured, set and parseable.
+    fn env_bw(&self, lookup: impl FnOnce(&str) -> Option<String>) -> Option<Bandwidth> {
+        let value = lookup(self.var.as_ref()?)?;
+        #[cfg(feature = "human")]
+        let bw = human_bandwidth::parse_bandwidth(value.trim()).ok();
+        #[cfg(not(feature = "human"))]
+        let bw = value.trim().parse().ok().map(Bandwidth::from_bps);
+        bw
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Build the model, reading the variable from the process environment.
+    pub fn build(self) -> StaticBw {
+        self.build_with_lookup(|var| std::env::var(var).ok())
+    }
+
+    /// Build the model, reading the variable with `lookup` instead of from the process environment.
+    ///
+    /// `lookup` is called with the name of the variable, and returns its value if it is set.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::EnvBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration, BwTrace};
+    /// let mut env_bw = EnvBwConfig::new()
+    ///     .var("BW")
+    ///     .duration(Duration::from_secs(1))
+    ///     .build_with_lookup(|var| (var == "BW").then(|| "24000000".to_string()));
+    /// # #[cfg(feature = "human")]
+    /// # let mut env_bw = EnvBwConfig::new()
+    /// #     .var("BW")
+    /// #     .duration(Duration::from_secs(1))
+    /// #     .build_with_lookup(|var| (var == "BW").then(|| "24Mbps".to_string()));
+    /// assert_eq!(env_bw.next_bw(), Some((Bandwidth::from_mbps(24), Duration::from_secs(1))));
+    /// ```
+    pub fn build_with_lookup(self, lookup: impl FnOnce(&str) -> Option<String>) -> StaticBw {
+        let default = self.default_bw.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let bw = self.env_bw(lookup).unwrap_or(default);
+        debug_log!("EnvBw built: var {:?}, bw {}", self.var, fmt_bandwidth(bw));
+        StaticBwConfig::new()
+            .bw(bw)
+            .duration(self.finite_duration())
+            .build()
+    }
+}
+
+/// Returns a warning if `step` is longer than `duration`, which is usually a configuration mistake.
+fn lint_step(model: &str, step: Duration, duration: Duration) -> Option<String> {
+    (step > duration).then(|| {
+        format!(
+            "{}: step {:?} is longer than duration {:?}, the trace will be a single segment of {:?}",
+            model, step, duration, duration
+        )
+    })
+}
+
+/// Convert a bandwidth to bps as u64 with saturating operation.
+macro_rules! saturating_bandwidth_as_bps_u64 {
+    ($bw:expr) => {
+        $bw.as_gbps()
+            .saturating_mul(1_000_000_000)
+            .saturating_add($bw.subgbps_bps() as u64)
+    };
+}
+
+impl NormalizedBwConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            std_dev: None,
+            upper_bound: None,
+            lower_bound: None,
+            duration: None,
+            step: None,
+            exact_duration: None,
+            seed: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: Bandwidth) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Bandwidth) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Bandwidth) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Bandwidth) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn exact_duration(mut self, exact_duration: bool) -> Self {
+        self.exact_duration = Some(exact_duration);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`]. Without `exact_duration`,
+    /// the last partial step is dropped.
+    fn finite_duration(&self) -> Duration {
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if self.exact_duration.unwrap_or(true) || step.is_zero() {
+            duration
+        } else {
+            duration - Duration::from_nanos((duration.as_nanos() % step.as_nanos()) as u64)
+        }
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::NormalizedBwConfig;
+    /// # use netem_trace::Duration;
+    /// let config = NormalizedBwConfig::new()
+    ///     .duration(Duration::from_secs(1))
+    ///     .step(Duration::from_secs(2));
+    /// assert_eq!(
+    ///     config.lint(),
+    ///     ["NormalizedBw: step 2s is longer than duration 1s, the trace will be a single segment of 1s"]
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<String> {
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if self.exact_duration.unwrap_or(true) {
+            lint_step("NormalizedBw", step, duration)
+                .into_iter()
+                .collect()
+        } else {
+            (step > duration)
+                .then(|| {
+                    format!(
+                        "NormalizedBw: step {:?} is longer than duration {:?}, the trace will be empty without exact_duration",
+                        step, duration
+                    )
+                })
+                .into_iter()
+                .collect()
+        }
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, panicking if the configuration is
+    /// invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> NormalizedBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> NormalizedBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `lower_bound` is
+    /// larger than `upper_bound`, or `step` is zero.
+    pub fn try_build(self) -> Result<NormalizedBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<NormalizedBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let mean = self.mean.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let std_dev = self.std_dev.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        let upper_bound = self.upper_bound;
+        let lower_bound = self.lower_bound;
+        if let (Some(lower), Some(upper)) = (lower_bound, upper_bound) {
+            if lower > upper {
+                return Err(ModelBuildError::InvalidBounds {
+                    model: "NormalizedBw",
+                    lower: "lower_bound",
+                    upper: "upper_bound",
+                });
+            }
+        }
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "NormalizedBw",
+                name: "step",
+            });
+        }
+        let exact_duration = self.exact_duration.unwrap_or(true);
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        let bw_mean = saturating_bandwidth_as_bps_u64!(mean) as f64;
+        let bw_std_dev = saturating_bandwidth_as_bps_u64!(std_dev) as f64;
+        let normal: Normal<f64> = Normal::new(bw_mean, bw_std_dev).unwrap();
+        debug_log!(
+            "NormalizedBw built: mean {}, std_dev {}, upper_bound {:?}, lower_bound {:?}, duration {}, step {}, exact_duration {}, seed {}",
+            fmt_bandwidth(mean),
+            fmt_bandwidth(std_dev),
+            upper_bound.map(fmt_bandwidth),
+            lower_bound.map(fmt_bandwidth),
+            fmt_delay(duration),
+            fmt_delay(step),
+            exact_duration,
+            seed
+        );
+        Ok(NormalizedBw {
+            mean,
+            std_dev,
+            upper_bound,
+            lower_bound,
+            duration,
+            step,
+            exact_duration,
+            seed,
+            total_duration: duration,
+            rng,
+            normal,
+        })
+    }
+}
+
+#[cfg(feature = "truncated-normal")]
+impl NormalizedBwConfig {
+    /// This is another implementation for converting NormalizedBwConfig into NormalizedBw, where the impact
+    /// of truncation (`lower_bound` and `upper_bound` field) on the mathematical expectation of the distribution
+    /// is taking account by modifing the center of the distribution.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    ///
+    /// # use netem_trace::model::NormalizedBwConfig;
+    /// # use netem_trace::{Bandwidth, Duration, BwTrace};
+    /// # use crate::netem_trace::model::Forever;
+    /// let normal_bw = NormalizedBwConfig::new()
+    ///     .mean(Bandwidth::from_mbps(12))
+    ///     .std_dev(Bandwidth::from_mbps(12))
+    ///     .duration(Duration::from_secs(100))
+    ///     .step(Duration::from_millis(1))
+    ///     .seed(42);
+    ///
+    /// let mut default_build = normal_bw.clone().build();
+    /// let mut truncate_build = normal_bw.clone().build_truncated();
+    ///
+    /// fn avg_mbps(mut model: impl BwTrace) -> f64{
+    ///     let mut count = 0;
+    ///     std::iter::from_fn( move ||{
+    ///         model.next_bw().map(|b| b.0.as_gbps_f64() * 1000.0)
+    ///     }).inspect(|_| count += 1).sum::<f64>() / count as f64
+    /// }
+    ///
+    /// assert_eq!(avg_mbps(default_build), 12.974758499450035); // significantly higher than the expected mean
+    /// assert_eq!(avg_mbps(truncate_build), 11.976424973269959);
+    ///
+    /// let normal_bw = NormalizedBwConfig::new()
+    ///     .mean(Bandwidth::from_mbps(12))
+    ///     .std_dev(Bandwidth::from_mbps(12))
+    ///     .duration(Duration::from_secs(100))
+    ///     .lower_bound(Bandwidth::from_mbps(8))
+    ///     .upper_bound(Bandwidth::from_mbps(20))
+    ///     .step(Duration::from_millis(1))
+    ///     .seed(42);
+    ///
+    /// let mut default_build = normal_bw.clone().build();
+    /// let mut truncate_build = normal_bw.clone().build_truncated();
+    ///
+    /// assert_eq!(avg_mbps(default_build),  13.221356661659886); // significantly higher than the expected mean
+    /// assert_eq!(avg_mbps(truncate_build), 11.978819601529935);
+    ///
+    /// ```
+    pub fn build_truncated(mut self) -> NormalizedBw {
+        let mean = self
+            .mean
+            .unwrap_or_else(|| Bandwidth::from_mbps(12))
+            .as_gbps_f64();
+        let sigma = self
+            .std_dev
+            .unwrap_or_else(|| Bandwidth::from_mbps(0))
+            .as_gbps_f64()
+            / mean;
+        let lower = self
+            .lower_bound
+            .unwrap_or_else(|| Bandwidth::from_mbps(0))
+            .as_gbps_f64()
+            / mean;
+        let upper = self.upper_bound.map(|upper| upper.as_gbps_f64() / mean);
+        let new_mean = mean * solve(1f64, sigma, Some(lower), upper).unwrap_or(1f64);
+        self.mean = Some(Bandwidth::from_gbps_f64(new_mean));
+        self.build()
+    }
+}
+
+impl SawtoothBwConfig {
+    pub fn new() -> Self {
+        Self {
+            bottom: None,
+            top: None,
+            interval: None,
+            duty_ratio: None,
+            duration: None,
+            step: None,
+            seed: None,
+            std_dev: None,
+            upper_noise_bound: None,
+            lower_noise_bound: None,
+        }
+    }
+
+    pub fn bottom(mut self, bottom: Bandwidth) -> Self {
+        self.bottom = Some(bottom);
+        self
+    }
+
+    pub fn top(mut self, top: Bandwidth) -> Self {
+        self.top = Some(top);
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    pub fn duty_ratio(mut self, duty_ratio: f64) -> Self {
+        self.duty_ratio = Some(duty_ratio);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Bandwidth) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_noise_bound(mut self, upper_noise_bound: Bandwidth) -> Self {
+        self.upper_noise_bound = Some(upper_noise_bound);
+        self
+    }
+
+    pub fn lower_noise_bound(mut self, lower_noise_bound: Bandwidth) -> Self {
+        self.lower_noise_bound = Some(lower_noise_bound);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("SawtoothBw", step, duration)
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, panicking if the configuration is
+    /// invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> SawtoothBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> SawtoothBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `bottom` is larger
+    /// than `top`, `duty_ratio` is not within [0, 1], or `step` is zero.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{ModelBuildError, SawtoothBwConfig};
+    /// # use netem_trace::Bandwidth;
+    /// let err = SawtoothBwConfig::new()
+    ///     .bottom(Bandwidth::from_mbps(24))
+    ///     .top(Bandwidth::from_mbps(12))
+    ///     .try_build()
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "SawtoothBw: bottom must not be larger than top");
+    /// assert!(SawtoothBwConfig::new().try_build().is_ok());
+    /// ```
+    pub fn try_build(self) -> Result<SawtoothBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<SawtoothBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let bottom = self.bottom.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        let top = self.top.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        if bottom > top {
+            return Err(ModelBuildError::InvalidBounds {
+                model: "SawtoothBw",
+                lower: "bottom",
+                upper: "top",
+            });
+        }
+        let interval = self.interval.unwrap_or_else(|| Duration::from_secs(1));
+        let duty_ratio = self.duty_ratio.unwrap_or(0.5);
+        if !(0.0..=1.0).contains(&duty_ratio) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "SawtoothBw",
+                name: "duty_ratio",
+                expected: "within [0, 1]",
+                value: duty_ratio,
+            });
+        }
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "SawtoothBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        let std_dev = self.std_dev.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        let upper_noise_bound = self.upper_noise_bound;
+        let lower_noise_bound = self.lower_noise_bound;
+        let current = Duration::ZERO;
+        let bw_std_dev = saturating_bandwidth_as_bps_u64!(std_dev) as f64;
+        let noise: Normal<f64> = Normal::new(0.0, bw_std_dev).unwrap();
+        debug_log!(
+            "SawtoothBw built: bottom {}, top {}, interval {}, duty_ratio {}, duration {}, step {}, seed {}, std_dev {}, upper_noise_bound {:?}, lower_noise_bound {:?}",
+            fmt_bandwidth(bottom),
+            fmt_bandwidth(top),
+            fmt_delay(interval),
+            duty_ratio,
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed,
+            fmt_bandwidth(std_dev),
+            upper_noise_bound.map(fmt_bandwidth),
+            lower_noise_bound.map(fmt_bandwidth)
+        );
+        Ok(SawtoothBw {
+            bottom,
+            top,
+            interval,
+            duty_ratio,
+            duration,
+            step,
+            seed,
+            std_dev,
+            upper_noise_bound,
+            lower_noise_bound,
+            current,
+            total_duration: duration,
+            rng,
+            noise,
+        })
+    }
+}
+
+impl SineBwConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            amplitude: None,
+            period: None,
+            duration: None,
+            step: None,
+            seed: None,
+            std_dev: None,
+            upper_noise_bound: None,
+            lower_noise_bound: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: Bandwidth) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn amplitude(mut self, amplitude: Bandwidth) -> Self {
+        self.amplitude = Some(amplitude);
+        self
+    }
+
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Bandwidth) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_noise_bound(mut self, upper_noise_bound: Bandwidth) -> Self {
+        self.upper_noise_bound = Some(upper_noise_bound);
+        self
+    }
+
+    pub fn lower_noise_bound(mut self, lower_noise_bound: Bandwidth) -> Self {
+        self.lower_noise_bound = Some(lower_noise_bound);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("SineBw", step, duration).into_iter().collect()
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, panicking if the configuration is
+    /// invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> SineBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> SineBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `step` is zero.
+    pub fn try_build(self) -> Result<SineBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<SineBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let mean = self.mean.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let amplitude = self.amplitude.unwrap_or_else(|| Bandwidth::from_mbps(6));
+        let period = self.period.unwrap_or_else(|| Duration::from_secs(1));
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "SineBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        let std_dev = self.std_dev.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        let upper_noise_bound = self.upper_noise_bound;
+        let lower_noise_bound = self.lower_noise_bound;
+        let bw_std_dev = saturating_bandwidth_as_bps_u64!(std_dev) as f64;
+        let noise: Normal<f64> = Normal::new(0.0, bw_std_dev).unwrap();
+        debug_log!(
+            "SineBw built: mean {}, amplitude {}, period {}, duration {}, step {}, seed {}, std_dev {}, upper_noise_bound {:?}, lower_noise_bound {:?}",
+            fmt_bandwidth(mean),
+            fmt_bandwidth(amplitude),
+            fmt_delay(period),
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed,
+            fmt_bandwidth(std_dev),
+            upper_noise_bound.map(fmt_bandwidth),
+            lower_noise_bound.map(fmt_bandwidth)
+        );
+        Ok(SineBw {
+            mean,
+            amplitude,
+            period,
+            duration,
+            step,
+            seed,
+            std_dev,
+            upper_noise_bound,
+            lower_noise_bound,
+            current: Duration::ZERO,
+            total_duration: duration,
+            rng,
+            noise,
+        })
+    }
+}
+
+impl SquareBwConfig {
+    pub fn new() -> Self {
+        Self {
+            low: None,
+            high: None,
+            period: None,
+            duty_ratio: None,
+            step: None,
+            duration: None,
+        }
+    }
+
+    pub fn low(mut self, low: Bandwidth) -> Self {
+        self.low = Some(low);
+        self
+    }
+
+    pub fn high(mut self, high: Bandwidth) -> Self {
+        self.high = Some(high);
+        self
+    }
+
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn duty_ratio(mut self, duty_ratio: f64) -> Self {
+        self.duty_ratio = Some(duty_ratio);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("SquareBw", step, duration).into_iter().collect()
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> SquareBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `duty_ratio` is not within [0, 1], or `step` is zero.
+    pub fn try_build(self) -> Result<SquareBw, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let low = self.low.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        let high = self.high.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let period = self.period.unwrap_or_else(|| Duration::from_secs(1));
+        let duty_ratio = self.duty_ratio.unwrap_or(0.5);
+        if !(0.0..=1.0).contains(&duty_ratio) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "SquareBw",
+                name: "duty_ratio",
+                expected: "between 0 and 1",
+                value: duty_ratio,
+            });
+        }
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "SquareBw",
+                name: "step",
+            });
+        }
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        debug_log!(
+            "SquareBw built: low {}, high {}, period {}, duty_ratio {}, step {}, duration {}",
+            fmt_bandwidth(low),
+            fmt_bandwidth(high),
+            fmt_delay(period),
+            duty_ratio,
+            fmt_delay(step),
+            fmt_delay(duration)
+        );
+        Ok(SquareBw {
+            low,
+            high,
+            period,
+            duty_ratio,
+            step,
+            duration,
+            current: Duration::ZERO,
+            total_duration: duration,
+        })
+    }
+}
+
+impl RepeatedBwPatternConfig {
+    pub fn new() -> Self {
+        Self {
+            pattern: vec![],
+            count: 0,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: Vec<Box<dyn BwTraceConfig>>) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Chain the bandwidth traces in `pattern` to play each of them once in sequence, i.e. a pattern
+    /// with `count` 1, which ends after the last trace ends.
+    pub fn once(pattern: Vec<Box<dyn BwTraceConfig>>) -> Self {
+        Self::new().pattern(pattern).count(1)
+    }
+
+    /// Check that the configuration is nested no deeper than `max_depth` levels.
+    ///
+    /// Both building and running a model recurse once per nesting level, so a deeply nested
+    /// configuration (e.g. patterns containing patterns containing patterns...) may overflow
+    /// the stack. It is recommended to validate configurations from untrusted sources, e.g.
+    /// with [`DEFAULT_MAX_NESTING_DEPTH`].
+    ///
+    /// Returns `Err` string if the configuration is nested too deep.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{RepeatedBwPatternConfig, StaticBwConfig};
+    /// let config = RepeatedBwPatternConfig::new()
+    ///     .pattern(vec![Box::new(StaticBwConfig::new())]);
+    /// assert!(config.validate(2).is_ok());
+    /// assert!(config.validate(1).is_err());
+    /// ```
+    pub fn validate(&self, max_depth: usize) -> Result<(), String> {
+        if self.exceeds_depth(max_depth) {
+            Err(format!(
+                "RepeatedBwPatternConfig: nesting depth exceeds the limit {}",
+                max_depth
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The same as [`build`](Self::build), but [validates](Self::validate) the nesting depth first.
+    ///
+    /// Returns `Err` string if the configuration is nested deeper than `max_depth` levels.
+    pub fn build_with_max_depth(self, max_depth: usize) -> Result<RepeatedBwPattern, String> {
+        self.validate(max_depth)?;
+        Ok(self.build())
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    ///
+    /// A sub-config which [never ends](BwTraceConfig::is_infinite) makes the sub-configs after it,
+    /// as well as the later cycles if `count` is larger than 1, unreachable.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use netem_trace::model::{Forever, RepeatedBwPatternConfig, StaticBwConfig};
+    /// let config = RepeatedBwPatternConfig::new()
+    ///     .pattern(vec![Box::new(StaticBwConfig::new().forever())])
+    ///     .count(2);
+    /// assert_eq!(
+    ///     config.lint(),
+    ///     ["RepeatedBwPattern: sub-config 0 never ends, so the cycles after the first one are unreachable"]
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<String> {
+        let Some(index) = self.pattern.iter().position(|config| config.is_infinite()) else {
+            return vec![];
+        };
+        let mut warnings = vec![];
+        if index + 1 < self.pattern.len() {
+            warnings.push(format!(
+                "RepeatedBwPattern: sub-config {} never ends, so the sub-configs after it are unreachable",
+                index
+            ));
+        }
+        if self.count > 1 {
+            warnings.push(format!(
+                "RepeatedBwPattern: sub-config {} never ends, so the cycles after the first one are unreachable",
+                index
+            ));
+        }
+        warnings
+    }
+
+    pub fn build(self) -> RepeatedBwPattern {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        debug_log!(
+            "RepeatedBwPattern built: {} sub-configs, count {}",
+            self.pattern.len(),
+            self.count
+        );
+        RepeatedBwPattern {
+            guard: EmptyPatternGuard::new(self.pattern.len()),
+            pattern: self.pattern,
+            count: self.count,
+            current_model: None,
+            current_cycle: 0,
+            current_pattern: 0,
+            current_tag: None,
+            cached_models: vec![],
+        }
+    }
+}
+
+impl TaggedBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            tag: String::new(),
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    pub fn build(self) -> TaggedBw {
+        debug_log!("TaggedBw built: tag {:?}", self.tag);
+        TaggedBw {
+            tag: self.tag,
+            inner: self.inner.map(|inner| inner.into_model()),
+        }
+    }
+}
+
+impl FloorPlusVariableBwConfig {
+    pub fn new() -> Self {
+        Self {
+            floor: None,
+            variable: None,
+        }
+    }
+
+    pub fn floor(mut self, floor: Bandwidth) -> Self {
+        self.floor = Some(floor);
+        self
+    }
+
+    pub fn variable(mut self, variable: Box<dyn BwTraceConfig>) -> Self {
+        self.variable = Some(variable);
+        self
+    }
+
+    pub fn build(self) -> FloorPlusVariableBw {
+        let floor = self.floor.unwrap_or_else(|| Bandwidth::from_mbps(0));
+        debug_log!("FloorPlusVariableBw built: floor {}", fmt_bandwidth(floor));
+        FloorPlusVariableBw {
+            floor,
+            variable: self.variable.map(|variable| variable.into_model()),
+        }
+    }
+}
+
+impl ScaledBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            factor: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn factor(mut self, factor: f64) -> Self {
+        self.factor = Some(factor);
+        self
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> ScaledBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `factor` is negative or not finite.
+    pub fn try_build(self) -> Result<ScaledBw, ModelBuildError> {
+        let factor = self.factor.unwrap_or(1.0);
+        if !(factor.is_finite() && factor >= 0.0) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "ScaledBw",
+                name: "factor",
+                expected: "non-negative and finite",
+                value: factor,
+            });
+        }
+        debug_log!("ScaledBw built: factor {:?}", factor);
+        Ok(ScaledBw {
+            factor,
+            inner: self.inner.map(|inner| inner.into_model()),
+        })
+    }
+}
+
+impl ClampedBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn min(mut self, min: Bandwidth) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: Bandwidth) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn build(self) -> ClampedBw {
+        debug_log!(
+            "ClampedBw built: min {:?}, max {:?}",
+            self.min.map(fmt_bandwidth),
+            self.max.map(fmt_bandwidth)
+        );
+        ClampedBw {
+            min: self.min,
+            max: self.max,
+            inner: self.inner.map(|inner| inner.into_model()),
+        }
+    }
+}
+
+impl RandomPhaseBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            period: None,
+            seed: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, drawing the phase offset from a [`StdRng`] seeded by `seed`.
+    pub fn build(self) -> RandomPhaseBw {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.build_with_rng(rng)
+    }
+
+    /// Build the model, drawing the phase offset from the given RNG instead of a [`StdRng`] seeded
+    /// by `seed`.
+    pub fn build_with_rng<R: RngCore>(self, mut rng: R) -> RandomPhaseBw {
+        let period = self.period.unwrap_or(Duration::ZERO);
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        let offset = if period.is_zero() {
+            Duration::ZERO
+        } else {
+            let period_nanos = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX);
+            Duration::from_nanos(rng.random_range(0..period_nanos))
+        };
+        debug_log!(
+            "RandomPhaseBw built: period {}, seed {}, offset {}",
+            fmt_delay(period),
+            seed,
+            fmt_delay(offset)
+        );
+        RandomPhaseBw {
+            period,
+            seed,
+            offset,
+            // an inner model known to be empty is dropped up front, and the looped pattern ends by
+            // itself if the inner model turns out to produce nothing
+            inner: self
+                .inner
+                .filter(|inner| inner.total_duration() != Some(Duration::ZERO))
+                .map(|inner| {
+                    Box::new(
+                        RepeatedBwPatternConfig::new()
+                            .pattern(vec![inner])
+                            .count(0)
+                            .build(),
+                    ) as Box<dyn BwTrace>
+                }),
+            skip: offset,
+            current_tag: None,
+        }
+    }
+}
+
+impl MarkovBwConfig {
+    pub fn new() -> Self {
+        Self {
+            states: vec![],
+            transitions: vec![],
+            initial_state: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn states(mut self, states: Vec<Bandwidth>) -> Self {
+        self.states = states;
+        self
+    }
+
+    pub fn transitions(mut self, transitions: Vec<Vec<f64>>) -> Self {
+        self.transitions = transitions;
+        self
+    }
+
+    pub fn initial_state(mut self, initial_state: usize) -> Self {
+        self.initial_state = Some(initial_state);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        if self.states.is_empty() {
+            return Duration::ZERO;
+        }
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, panicking if the configuration is invalid.
+    /// See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> MarkovBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> MarkovBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, or return an error if the transition matrix
+    /// is not N x N for N states, a row has a negative probability or does not sum to 1.0 (within
+    /// 1e-6), `initial_state` is out of range, or `step` is zero.
+    ///
+    /// Without any state, the model produces nothing.
+    pub fn try_build(self) -> Result<MarkovBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<MarkovBw<R>, ModelBuildError> {
+        let n = self.states.len();
+        if self.transitions.len() != n || self.transitions.iter().any(|row| row.len() != n) {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "MarkovBw",
+                reason: format!("transition matrix must be {} x {} for {} states", n, n, n),
+            });
+        }
+        for (state, row) in self.transitions.iter().enumerate() {
+            if row.iter().any(|prob| !prob.is_finite() || *prob < 0.0)
+                || (row.iter().sum::<f64>() - 1.0).abs() > 1e-6
+            {
+                return Err(ModelBuildError::InvalidConfig {
+                    model: "MarkovBw",
+                    reason: format!(
+                        "row {} of the transition matrix must be non-negative and sum to 1.0",
+                        state
+                    ),
+                });
+            }
+        }
+        let initial_state = self.initial_state.unwrap_or(0);
+        if n > 0 && initial_state >= n {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "MarkovBw",
+                reason: format!(
+                    "initial_state {} is out of range for {} states",
+                    initial_state, n
+                ),
+            });
+        }
+        let duration = self.finite_duration();
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "MarkovBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "MarkovBw built: {} states, initial_state {}, duration {}, step {}, seed {}",
+            n,
+            initial_state,
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed
+        );
+        Ok(MarkovBw {
+            states: self.states,
+            transitions: self.transitions,
+            duration,
+            step,
+            seed,
+            current_state: initial_state,
+            rng,
+        })
+    }
+}
+
+impl HysteresisBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            threshold: None,
+            max_hold: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: Bandwidth) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn max_hold(mut self, max_hold: Duration) -> Self {
+        self.max_hold = Some(max_hold);
+        self
+    }
+
+    pub fn build(self) -> HysteresisBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// The same as [`build`](Self::build), but returns an error instead of panicking.
+    ///
+    /// Returns [`ModelBuildError::ZeroDuration`] if `max_hold` is zero.
+    pub fn try_build(self) -> Result<HysteresisBw, ModelBuildError> {
+        let threshold = self.threshold.unwrap_or(Bandwidth::ZERO);
+        let max_hold = self.max_hold.unwrap_or(Duration::from_secs(60));
+        if max_hold.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "HysteresisBw",
+                name: "max_hold",
+            });
+        }
+        debug_log!(
+            "HysteresisBw built: threshold {}, max_hold {}",
+            fmt_bandwidth(threshold),
+            fmt_delay(max_hold)
+        );
+        Ok(HysteresisBw {
+            threshold,
+            max_hold,
+            inner: self.inner.map(|inner| inner.into_model()),
+            level: None,
+            pending: Duration::ZERO,
+            current_tag: None,
+        })
+    }
+}
+
+impl RampBwConfig {
+    pub fn new() -> Self {
+        Self {
+            start: None,
+            end: None,
+            duration: None,
+            step: None,
+        }
+    }
+
+    pub fn start(mut self, start: Bandwidth) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: Bandwidth) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// The total duration of the ramp, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("RampBw", step, self.finite_duration())
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> RampBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `step` is zero.
+    pub fn try_build(self) -> Result<RampBw, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let start = self.start.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let end = self.end.unwrap_or_else(|| Bandwidth::from_mbps(24));
+        let duration = self.finite_duration();
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "RampBw",
+                name: "step",
+            });
+        }
+        debug_log!(
+            "RampBw built: start {}, end {}, duration {}, step {}",
+            fmt_bandwidth(start),
+            fmt_bandwidth(end),
+            fmt_delay(duration),
+            fmt_delay(step)
+        );
+        Ok(RampBw {
+            start,
+            end,
+            duration,
+            step,
+            current: Duration::ZERO,
+        })
+    }
+}
+
+impl RandomPatternBwConfig {
+    pub fn new() -> Self {
+        Self {
+            pattern: vec![],
+            count: 0,
+            seed: None,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: Vec<(f64, Box<dyn BwTraceConfig>)>) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, panicking if the configuration is invalid.
+    /// See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> RandomPatternBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> RandomPatternBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, or return an error if a weight is negative
+    /// or not finite, or if the pattern is not empty but all its weights are zero.
+    pub fn try_build(self) -> Result<RandomPatternBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<RandomPatternBw<R>, ModelBuildError> {
+        if let Some((weight, _)) = self
+            .pattern
+            .iter()
+            .find(|(weight, _)| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "RandomPatternBw",
+                name: "weight",
+                expected: "non-negative and finite",
+                value: *weight,
+            });
+        }
+        if !self.pattern.is_empty() && self.pattern.iter().all(|(weight, _)| *weight == 0.0) {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "RandomPatternBw",
+                reason: "at least one weight must be positive".to_string(),
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "RandomPatternBw built: {} configs, count {}, seed {}",
+            self.pattern.len(),
+            self.count,
+            seed
+        );
+        let mut guard = EmptyPatternGuard::new(self.pattern.len());
+        for (index, (weight, _)) in self.pattern.iter().enumerate() {
+            // a config with zero weight is never picked
+            if *weight == 0.0 {
+                guard.exclude(index);
+            }
+        }
+        Ok(RandomPatternBw {
+            pattern: self.pattern,
+            count: self.count,
+            seed,
+            current_model: None,
+            current_cycle: 0,
+            current_index: 0,
+            current_tag: None,
+            rng,
+            guard,
+        })
+    }
+}
+
+impl ReverseBwConfig {
+    pub fn new() -> Self {
+        Self { inner: None }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    ///
+    /// An inner config which [never ends](BwTraceConfig::is_infinite) makes the model produce nothing.
+    pub fn lint(&self) -> Vec<String> {
+        if self.inner.as_ref().is_some_and(|inner| inner.is_infinite()) {
+            vec![
+                "ReverseBw: the inner config never ends, so the model produces nothing".to_string(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn build(self) -> ReverseBw {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        debug_log!("ReverseBw built");
+        ReverseBw {
+            inner: self
+                .inner
+                .filter(|inner| !inner.is_infinite())
+                .map(|inner| inner.into_model()),
+            segments: vec![],
+            current_tag: None,
+        }
+    }
+}
+
+impl PoissonBwConfig {
+    pub fn new() -> Self {
+        Self {
+            rate_pps: None,
+            packet_size: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn rate_pps(mut self, rate_pps: f64) -> Self {
+        self.rate_pps = Some(rate_pps);
+        self
+    }
+
+    pub fn packet_size(mut self, packet_size: u64) -> Self {
+        self.packet_size = Some(packet_size);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("PoissonBw", step, self.finite_duration())
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, panicking if the configuration is invalid.
+    /// See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> PoissonBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> PoissonBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with the RNG seeded by `seed`, or return an error if `rate_pps` is negative
+    /// or not finite, `step` is zero, or the mean number of packets in a step exceeds
+    /// [`Poisson::MAX_LAMBDA`].
+    pub fn try_build(self) -> Result<PoissonBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<PoissonBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let rate_pps = self.rate_pps.unwrap_or(1000.0);
+        if !(rate_pps.is_finite() && rate_pps >= 0.0) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "PoissonBw",
+                name: "rate_pps",
+                expected: "non-negative and finite",
+                value: rate_pps,
+            });
+        }
+        let packet_size = self.packet_size.unwrap_or(1500);
+        let duration = self.finite_duration();
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "PoissonBw",
+                name: "step",
+            });
+        }
+        if rate_pps * step.as_secs_f64() > Poisson::<f64>::MAX_LAMBDA {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "PoissonBw",
+                reason: format!("rate_pps is too large for a step of {:?}", step),
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "PoissonBw built: rate_pps {}, packet_size {}, duration {}, step {}, seed {}",
+            rate_pps,
+            packet_size,
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed
+        );
+        Ok(PoissonBw {
+            rate_pps,
+            packet_size,
+            duration,
+            step,
+            seed,
+            total_duration: duration,
+            rng,
+        })
+    }
+}
+
+impl SumBwConfig {
+    pub fn new() -> Self {
+        Self { inputs: vec![] }
+    }
+
+    pub fn inputs(mut self, inputs: Vec<Box<dyn BwTraceConfig>>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    pub fn build(self) -> SumBw {
+        debug_log!("SumBw built: {} inputs", self.inputs.len());
+        SumBw {
+            current: vec![None; self.inputs.len()],
+            inputs: self
+                .inputs
+                .into_iter()
+                .map(|input| input.into_model())
+                .collect(),
+        }
+    }
+}
+
+impl WeibullBwConfig {
+    pub fn new() -> Self {
+        Self {
+            scale: None,
+            shape: None,
+            upper_bound: None,
+            lower_bound: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn scale(mut self, scale: Bandwidth) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn shape(mut self, shape: f64) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Bandwidth) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Bandwidth) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("WeibullBw", step, self.finite_duration())
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> WeibullBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> WeibullBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `shape` is not positive (or NaN), `lower_bound` is
+    /// larger than `upper_bound`, or `step` is zero.
+    pub fn try_build(self) -> Result<WeibullBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<WeibullBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let scale = self.scale.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let shape = self.shape.unwrap_or(1.0);
+        let weibull = Weibull::new(1.0, shape).map_err(|_| ModelBuildError::InvalidParameter {
+            model: "WeibullBw",
+            name: "shape",
+            expected: "positive",
+            value: shape,
+        })?;
+        let upper_bound = self.upper_bound;
+        let lower_bound = self.lower_bound;
+        if let (Some(lower), Some(upper)) = (lower_bound, upper_bound) {
+            if lower > upper {
+                return Err(ModelBuildError::InvalidBounds {
+                    model: "WeibullBw",
+                    lower: "lower_bound",
+                    upper: "upper_bound",
+                });
+            }
+        }
+        let duration = self.finite_duration();
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "WeibullBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "WeibullBw built: scale {}, shape {}, upper_bound {:?}, lower_bound {:?}, duration {}, step {}, seed {}",
+            fmt_bandwidth(scale),
+            shape,
+            upper_bound.map(fmt_bandwidth),
+            lower_bound.map(fmt_bandwidth),
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed
+        );
+        Ok(WeibullBw {
+            scale,
+            shape,
+            upper_bound,
+            lower_bound,
+            duration,
+            step,
+            seed,
+            total_duration: duration,
+            rng,
+            weibull,
+        })
+    }
+}
+
+impl GammaBwConfig {
+    pub fn new() -> Self {
+        Self {
+            shape: None,
+            scale: None,
+            upper_bound: None,
+            lower_bound: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn shape(mut self, shape: f64) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
+    pub fn scale(mut self, scale: Bandwidth) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Bandwidth) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Bandwidth) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Check the configuration for likely mistakes, returning a warning message for each of them,
+    /// see [lints](crate::model::bw#lints).
+    pub fn lint(&self) -> Vec<String> {
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        lint_step("GammaBw", step, self.finite_duration())
+            .into_iter()
+            .collect()
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> GammaBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> GammaBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `shape` or `scale` is not positive (or `shape` is
+    /// NaN), `lower_bound` is larger than `upper_bound`, or `step` is zero.
+    pub fn try_build(self) -> Result<GammaBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<GammaBw<R>, ModelBuildError> {
+        #[cfg(feature = "logging")]
+        for warning in self.lint() {
+            log::warn!("{}", warning);
+        }
+        let shape = self.shape.unwrap_or(1.0);
+        let scale = self.scale.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        if scale.is_zero() {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "GammaBw",
+                name: "scale",
+                expected: "positive",
+                value: 0.0,
+            });
+        }
+        let gamma = Gamma::new(shape, scale.as_bps() as f64).map_err(|_| {
+            ModelBuildError::InvalidParameter {
+                model: "GammaBw",
+                name: "shape",
+                expected: "positive",
+                value: shape,
+            }
+        })?;
+        let upper_bound = self.upper_bound;
+        let lower_bound = self.lower_bound;
+        if let (Some(lower), Some(upper)) = (lower_bound, upper_bound) {
+            if lower > upper {
+                return Err(ModelBuildError::InvalidBounds {
+                    model: "GammaBw",
+                    lower: "lower_bound",
+                    upper: "upper_bound",
+                });
+            }
+        }
+        let duration = self.finite_duration();
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "GammaBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "GammaBw built: shape {}, scale {}, upper_bound {:?}, lower_bound {:?}, duration {}, step {}, seed {}",
+            shape,
+            fmt_bandwidth(scale),
+            upper_bound.map(fmt_bandwidth),
+            lower_bound.map(fmt_bandwidth),
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed
+        );
+        Ok(GammaBw {
+            shape,
+            scale,
+            upper_bound,
+            lower_bound,
+            duration,
+            step,
+            seed,
+            total_duration: duration,
+            rng,
+            gamma,
+        })
+    }
+}
+
+impl SpliceBwConfig {
+    pub fn new() -> Self {
+        Self {
+            base: None,
+            start: None,
+            end: None,
+            override_bw: None,
+        }
+    }
+
+    pub fn base(mut self, base: Box<dyn BwTraceConfig>) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    pub fn start(mut self, start: Duration) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: Duration) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn override_bw(mut self, override_bw: Bandwidth) -> Self {
+        self.override_bw = Some(override_bw);
+        self
+    }
+
+    pub fn build(self) -> SpliceBw {
+        let start = self.start.unwrap_or(Duration::ZERO);
+        let end = self.end.unwrap_or(Duration::ZERO);
+        let override_bw = self.override_bw.unwrap_or(Bandwidth::ZERO);
+        debug_log!(
+            "SpliceBw built: start {}, end {}, override_bw {}",
+            fmt_delay(start),
+            fmt_delay(end),
+            fmt_bandwidth(override_bw)
+        );
+        SpliceBw {
+            start,
+            end,
+            override_bw,
+            base: self.base.map(|base| base.into_model()),
+            pending: None,
+            now: Duration::ZERO,
         }
     }
 }
 
-/// Convert a bandwidth to bps as u64 with saturating operation.
-macro_rules! saturating_bandwidth_as_bps_u64 {
-    ($bw:expr) => {
-        $bw.as_gbps()
-            .saturating_mul(1_000_000_000)
-            .saturating_add($bw.subgbps_bps() as u64)
-    };
+#[cfg(feature = "loss-model")]
+impl LossAsGapsBwConfig {
+    pub fn new() -> Self {
+        Self {
+            bw: None,
+            loss: None,
+            seed: None,
+        }
+    }
+
+    pub fn bw(mut self, bw: Box<dyn BwTraceConfig>) -> Self {
+        self.bw = Some(bw);
+        self
+    }
+
+    pub fn loss(mut self, loss: Box<dyn LossTraceConfig>) -> Self {
+        self.loss = Some(loss);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`.
+    pub fn build(self) -> LossAsGapsBw {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore>(self, rng: R) -> LossAsGapsBw<R> {
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!("LossAsGapsBw built: seed {}", seed);
+        LossAsGapsBw {
+            seed,
+            bw: self.bw.map(|bw| bw.into_model()),
+            loss: self.loss.map(|loss| loss.into_model()),
+            merger: SegmentMerger::new(),
+            zeroed: false,
+            rng,
+        }
+    }
+}
+
+impl QuantizedBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            levels: vec![],
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn levels(mut self, levels: Vec<Bandwidth>) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn build(self) -> QuantizedBw {
+        let mut levels = self.levels;
+        levels.sort();
+        levels.dedup();
+        debug_log!("QuantizedBw built: {} levels", levels.len());
+        QuantizedBw {
+            levels,
+            inner: self.inner.map(|inner| inner.into_model()),
+        }
+    }
+}
+
+impl StepBwConfig {
+    pub fn new() -> Self {
+        Self {
+            steps: vec![],
+            hold: None,
+            repeat: None,
+        }
+    }
+
+    pub fn steps(mut self, steps: Vec<Bandwidth>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    pub fn hold(mut self, hold: Duration) -> Self {
+        self.hold = Some(hold);
+        self
+    }
+
+    pub fn repeat(mut self, repeat: usize) -> Self {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// The total duration of the staircase, or `None` if it repeats forever, see
+    /// [`BwTraceConfig::total_duration`].
+    fn repeated_duration(&self) -> Option<Duration> {
+        let hold = self.hold.unwrap_or_else(|| Duration::from_secs(1));
+        if self.steps.is_empty() || hold.is_zero() {
+            return Some(Duration::ZERO);
+        }
+        let repeat = self.repeat.unwrap_or(1);
+        if repeat == 0 {
+            return None;
+        }
+        Some(
+            u32::try_from(self.steps.len().saturating_mul(repeat))
+                .ok()
+                .and_then(|count| hold.checked_mul(count))
+                .unwrap_or(Duration::MAX),
+        )
+    }
+
+    /// Build the model. `hold` defaults to 1s and `repeat` to 1.
+    pub fn build(self) -> StepBw {
+        let hold = self.hold.unwrap_or_else(|| Duration::from_secs(1));
+        let repeat = self.repeat.unwrap_or(1);
+        debug_log!(
+            "StepBw built: {} steps, hold {}, repeat {}",
+            self.steps.len(),
+            fmt_delay(hold),
+            repeat
+        );
+        StepBw {
+            steps: self.steps,
+            hold,
+            repeat,
+            index: 0,
+            cycle: 0,
+        }
+    }
+}
+
+impl ParetoBwConfig {
+    pub fn new() -> Self {
+        Self {
+            scale: None,
+            shape: None,
+            on_bw: None,
+            off_bw: None,
+            duration: None,
+            seed: None,
+        }
+    }
+
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn shape(mut self, shape: f64) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
+    pub fn on_bw(mut self, on_bw: Bandwidth) -> Self {
+        self.on_bw = Some(on_bw);
+        self
+    }
+
+    pub fn off_bw(mut self, off_bw: Bandwidth) -> Self {
+        self.off_bw = Some(off_bw);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> ParetoBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> ParetoBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `scale` or `shape` is not positive and finite.
+    ///
+    /// `scale` defaults to 0.01 (10ms), `shape` to 1.5, `on_bw` to 12Mbps and `off_bw` to zero.
+    pub fn try_build(self) -> Result<ParetoBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<ParetoBw<R>, ModelBuildError> {
+        let scale = self.scale.unwrap_or(0.01);
+        let shape = self.shape.unwrap_or(1.5);
+        for (name, value) in [("scale", scale), ("shape", shape)] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(ModelBuildError::InvalidParameter {
+                    model: "ParetoBw",
+                    name,
+                    expected: "positive and finite",
+                    value,
+                });
+            }
+        }
+        let pareto = Pareto::new(scale, shape).unwrap();
+        let on_bw = self.on_bw.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        let off_bw = self.off_bw.unwrap_or(Bandwidth::ZERO);
+        let duration = self.finite_duration();
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "ParetoBw built: scale {}, shape {}, on_bw {}, off_bw {}, duration {}, seed {}",
+            scale,
+            shape,
+            fmt_bandwidth(on_bw),
+            fmt_bandwidth(off_bw),
+            fmt_delay(duration),
+            seed
+        );
+        Ok(ParetoBw {
+            scale,
+            shape,
+            on_bw,
+            off_bw,
+            duration,
+            seed,
+            total_duration: duration,
+            on: true,
+            rng,
+            pareto,
+        })
+    }
+}
+
+impl DelayedStartBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            offset: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn build(self) -> DelayedStartBw {
+        let offset = self.offset.unwrap_or(Duration::ZERO);
+        debug_log!("DelayedStartBw built: offset {}", fmt_delay(offset));
+        DelayedStartBw {
+            offset,
+            inner: self.inner.map(|inner| inner.into_model()),
+            started: false,
+        }
+    }
+}
+
+impl ExponentialDwellBwConfig {
+    pub fn new() -> Self {
+        Self {
+            levels: vec![],
+            rate: None,
+            duration: None,
+            seed: None,
+        }
+    }
+
+    pub fn levels(mut self, levels: Vec<Bandwidth>) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        if self.levels.is_empty() {
+            return Duration::ZERO;
+        }
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> ExponentialDwellBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> ExponentialDwellBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, or return an error if `rate` is not positive and finite.
+    ///
+    /// Without any level, the model produces nothing.
+    pub fn try_build(self) -> Result<ExponentialDwellBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<ExponentialDwellBw<R>, ModelBuildError> {
+        let rate = self.rate.unwrap_or(1.0);
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "ExponentialDwellBw",
+                name: "rate",
+                expected: "positive and finite",
+                value: rate,
+            });
+        }
+        let exp = Exp::new(rate).unwrap();
+        let duration = self.finite_duration();
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "ExponentialDwellBw built: {} levels, rate {}, duration {}, seed {}",
+            self.levels.len(),
+            rate,
+            fmt_delay(duration),
+            seed
+        );
+        Ok(ExponentialDwellBw {
+            levels: self.levels,
+            rate,
+            duration,
+            seed,
+            total_duration: duration,
+            rng,
+            exp,
+        })
+    }
 }
 
-impl NormalizedBwConfig {
+impl RandomOutageBwConfig {
     pub fn new() -> Self {
         Self {
-            mean: None,
-            std_dev: None,
-            upper_bound: None,
-            lower_bound: None,
-            duration: None,
+            inner: None,
+            drop_prob: None,
+            drop_dur: None,
             step: None,
             seed: None,
         }
     }
 
-    pub fn mean(mut self, mean: Bandwidth) -> Self {
-        self.mean = Some(mean);
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
         self
     }
 
-    pub fn std_dev(mut self, std_dev: Bandwidth) -> Self {
-        self.std_dev = Some(std_dev);
+    pub fn drop_prob(mut self, drop_prob: f64) -> Self {
+        self.drop_prob = Some(drop_prob);
         self
     }
 
-    pub fn upper_bound(mut self, upper_bound: Bandwidth) -> Self {
-        self.upper_bound = Some(upper_bound);
+    pub fn drop_dur(mut self, drop_dur: Duration) -> Self {
+        self.drop_dur = Some(drop_dur);
         self
     }
 
-    pub fn lower_bound(mut self, lower_bound: Bandwidth) -> Self {
-        self.lower_bound = Some(lower_bound);
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, panicking if the configuration is
+    /// invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> RandomOutageBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore>(self, rng: R) -> RandomOutageBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `drop_prob` is
+    /// not within `[0, 1]` or `step` is zero.
+    pub fn try_build(self) -> Result<RandomOutageBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore>(
+        self,
+        rng: R,
+    ) -> Result<RandomOutageBw<R>, ModelBuildError> {
+        let drop_prob = self.drop_prob.unwrap_or(0.01);
+        if !(0.0..=1.0).contains(&drop_prob) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "RandomOutageBw",
+                name: "drop_prob",
+                expected: "within [0, 1]",
+                value: drop_prob,
+            });
+        }
+        let drop_dur = self.drop_dur.unwrap_or_else(|| Duration::from_millis(100));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(100));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "RandomOutageBw",
+                name: "step",
+            });
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "RandomOutageBw built: drop_prob {}, drop_dur {}, step {}, seed {}",
+            drop_prob,
+            fmt_delay(drop_dur),
+            fmt_delay(step),
+            seed
+        );
+        Ok(RandomOutageBw {
+            drop_prob,
+            drop_dur,
+            step,
+            seed,
+            inner: self.inner.map(|inner| inner.into_model()),
+            pending: None,
+            now: Duration::ZERO,
+            next_roll: Duration::ZERO,
+            outage_end: Duration::ZERO,
+            rng,
+        })
+    }
+}
+
+impl EmpiricalBwConfig {
+    pub fn new() -> Self {
+        Self {
+            cdf: vec![],
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn cdf(mut self, cdf: Vec<(Bandwidth, f64)>) -> Self {
+        self.cdf = cdf;
         self
     }
 
@@ -959,277 +7091,870 @@ impl NormalizedBwConfig {
         self
     }
 
-    pub fn build(self) -> NormalizedBw {
-        let mean = self.mean.unwrap_or_else(|| Bandwidth::from_mbps(12));
-        let std_dev = self.std_dev.unwrap_or_else(|| Bandwidth::from_mbps(0));
-        let upper_bound = self.upper_bound;
-        let lower_bound = self.lower_bound;
-        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        if self.cdf.is_empty() {
+            return Duration::ZERO;
+        }
+        self.duration.unwrap_or_else(|| Duration::from_secs(1))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, panicking if the configuration is
+    /// invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> EmpiricalBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> EmpiricalBw<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if the CDF is not
+    /// sorted, has probabilities out of `[0, 1]`, or does not end at 1.0, or if `step` is zero.
+    ///
+    /// With an empty CDF, the model produces nothing.
+    pub fn try_build(self) -> Result<EmpiricalBw, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<EmpiricalBw<R>, ModelBuildError> {
+        let invalid = |reason: &str| {
+            Err(ModelBuildError::InvalidConfig {
+                model: "EmpiricalBw",
+                reason: reason.to_string(),
+            })
+        };
+        if self.cdf.iter().any(|(_, prob)| !(0.0..=1.0).contains(prob)) {
+            return invalid("cumulative probabilities must be within [0, 1]");
+        }
+        if self
+            .cdf
+            .windows(2)
+            .any(|w| w[0].0 > w[1].0 || w[0].1 > w[1].1)
+        {
+            return invalid("cdf must be sorted by both bandwidth and cumulative probability");
+        }
+        if self.cdf.last().is_some_and(|(_, prob)| *prob != 1.0) {
+            return invalid("cdf must end at cumulative probability 1.0");
+        }
+        let duration = self.finite_duration();
         let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "EmpiricalBw",
+                name: "step",
+            });
+        }
         let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
-        let rng = StdRng::seed_from_u64(seed);
-        let bw_mean = saturating_bandwidth_as_bps_u64!(mean) as f64;
-        let bw_std_dev = saturating_bandwidth_as_bps_u64!(std_dev) as f64;
-        let normal: Normal<f64> = Normal::new(bw_mean, bw_std_dev).unwrap();
-        NormalizedBw {
-            mean,
-            std_dev,
-            upper_bound,
-            lower_bound,
+        debug_log!(
+            "EmpiricalBw built: {} cdf points, duration {}, step {}, seed {}",
+            self.cdf.len(),
+            fmt_delay(duration),
+            fmt_delay(step),
+            seed
+        );
+        Ok(EmpiricalBw {
+            cdf: self.cdf,
             duration,
             step,
             seed,
+            total_duration: duration,
             rng,
-            normal,
+        })
+    }
+}
+
+impl WindowMaxBwConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            window: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn BwTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    pub fn build(self) -> WindowMaxBw {
+        let window = self.window.unwrap_or_else(|| Duration::from_secs(1));
+        debug_log!("WindowMaxBw built: window {}", fmt_delay(window));
+        WindowMaxBw {
+            window,
+            inner: self.inner.map(|inner| inner.into_model()),
+            current: None,
+            history: VecDeque::new(),
+            now: Duration::ZERO,
         }
     }
 }
 
-#[cfg(feature = "truncated-normal")]
-impl NormalizedBwConfig {
-    /// This is another implementation for converting NormalizedBwConfig into NormalizedBw, where the impact
-    /// of truncation (`lower_bound` and `upper_bound` field) on the mathematical expectation of the distribution
-    /// is taking account by modifing the center of the distribution.
-    ///
-    /// ## Examples
-    ///
-    /// ```
-    ///
-    /// # use netem_trace::model::NormalizedBwConfig;
-    /// # use netem_trace::{Bandwidth, Duration, BwTrace};
-    /// # use crate::netem_trace::model::Forever;
-    /// let normal_bw = NormalizedBwConfig::new()
-    ///     .mean(Bandwidth::from_mbps(12))
-    ///     .std_dev(Bandwidth::from_mbps(12))
-    ///     .duration(Duration::from_secs(100))
-    ///     .step(Duration::from_millis(1))
-    ///     .seed(42);
-    ///
-    /// let mut default_build = normal_bw.clone().build();
-    /// let mut truncate_build = normal_bw.clone().build_truncated();
-    ///
-    /// fn avg_mbps(mut model: impl BwTrace) -> f64{
-    ///     let mut count = 0;
-    ///     std::iter::from_fn( move ||{
-    ///         model.next_bw().map(|b| b.0.as_gbps_f64() * 1000.0)
-    ///     }).inspect(|_| count += 1).sum::<f64>() / count as f64
-    /// }
+impl SplineBwConfig {
+    pub fn new() -> Self {
+        Self {
+            points: vec![],
+            step: None,
+            duration: None,
+        }
+    }
+
+    pub fn points(mut self, points: Vec<(Duration, Bandwidth)>) -> Self {
+        self.points = points;
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// The duration of the model, see [`BwTraceConfig::total_duration`].
+    fn finite_duration(&self) -> Duration {
+        match self.points.last() {
+            Some((time, _)) => self.duration.unwrap_or(*time),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Build the model, fitting a natural cubic spline through the points, and panicking if the
+    /// configuration is invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> SplineBw {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, fitting a natural cubic spline through the points, or return an error if
+    /// the points are not sorted by strictly increasing time, or `step` is zero.
     ///
-    /// assert_eq!(avg_mbps(default_build), 12.974758080079994); // significantly higher than the expected mean
-    /// assert_eq!(avg_mbps(truncate_build), 11.97642456625989);
+    /// Without any point, the model produces nothing.
+    pub fn try_build(self) -> Result<SplineBw, ModelBuildError> {
+        if self.points.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "SplineBw",
+                reason: "points must be sorted by strictly increasing time".to_string(),
+            });
+        }
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(100));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "SplineBw",
+                name: "step",
+            });
+        }
+        let duration = self.finite_duration();
+        debug_log!(
+            "SplineBw built: {} points, step {}, duration {}",
+            self.points.len(),
+            fmt_delay(step),
+            fmt_delay(duration)
+        );
+        let second_derivatives = natural_spline_second_derivatives(&self.points);
+        Ok(SplineBw {
+            points: self.points,
+            step,
+            duration,
+            second_derivatives,
+            current: Duration::ZERO,
+        })
+    }
+}
+
+/// Solve the second derivatives of the natural cubic spline through the points (in bps/s^2).
+///
+/// The second derivatives at both ends are zero, and the interior ones satisfy a tridiagonal
+/// system, which is solved with the Thomas algorithm.
+fn natural_spline_second_derivatives(points: &[(Duration, Bandwidth)]) -> Vec<f64> {
+    let n = points.len();
+    let mut m = vec![0.0; n];
+    if n < 3 {
+        return m;
+    }
+    let x = |i: usize| points[i].0.as_secs_f64();
+    let y = |i: usize| points[i].1.as_bps() as f64;
+    let h = |i: usize| x(i + 1) - x(i);
+    // forward elimination over the interior points 1..n-1, where the equation of point i is
+    // h(i-1) * m(i-1) + 2 * (h(i-1) + h(i)) * m(i) + h(i) * m(i+1) = rhs(i)
+    let mut diag = vec![0.0; n];
+    let mut rhs = vec![0.0; n];
+    for i in 1..n - 1 {
+        diag[i] = 2.0 * (h(i - 1) + h(i));
+        rhs[i] = 6.0 * ((y(i + 1) - y(i)) / h(i) - (y(i) - y(i - 1)) / h(i - 1));
+        if i > 1 {
+            let factor = h(i - 1) / diag[i - 1];
+            diag[i] -= factor * h(i - 1);
+            rhs[i] -= factor * rhs[i - 1];
+        }
+    }
+    // back substitution
+    for i in (1..n - 1).rev() {
+        m[i] = (rhs[i] - h(i) * m[i + 1]) / diag[i];
+    }
+    m
+}
+
+impl ScheduleBwConfig {
+    pub fn new() -> Self {
+        Self {
+            slots: vec![],
+            default_bw: None,
+        }
+    }
+
+    pub fn slots(mut self, slots: Vec<(u32, u32, Bandwidth)>) -> Self {
+        self.slots = slots;
+        self
+    }
+
+    pub fn default_bw(mut self, default_bw: Bandwidth) -> Self {
+        self.default_bw = Some(default_bw);
+        self
+    }
+
+    /// Build the model querying the time from [`SystemTime::now`](std::time::SystemTime::now).
     ///
-    /// let normal_bw = NormalizedBwConfig::new()
-    ///     .mean(Bandwidth::from_mbps(12))
-    ///     .std_dev(Bandwidth::from_mbps(12))
-    ///     .duration(Duration::from_secs(100))
-    ///     .lower_bound(Bandwidth::from_mbps(8))
-    ///     .upper_bound(Bandwidth::from_mbps(20))
-    ///     .step(Duration::from_millis(1))
-    ///     .seed(42);
+    /// ## Panics
     ///
-    /// let mut default_build = normal_bw.clone().build();
-    /// let mut truncate_build = normal_bw.clone().build_truncated();
+    /// Panics if the configuration is invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build(self) -> ScheduleBw {
+        self.build_with_clock(std::time::SystemTime::now)
+    }
+
+    /// Build the model querying the time from `clock` instead of the system clock.
     ///
-    /// assert_eq!(avg_mbps(default_build),  13.221356471729928); // significantly higher than the expected mean
-    /// assert_eq!(avg_mbps(truncate_build), 11.978819427569897);
+    /// ## Panics
     ///
-    /// ```
-    pub fn build_truncated(mut self) -> NormalizedBw {
-        let mean = self
-            .mean
-            .unwrap_or_else(|| Bandwidth::from_mbps(12))
-            .as_gbps_f64();
-        let sigma = self
-            .std_dev
-            .unwrap_or_else(|| Bandwidth::from_mbps(0))
-            .as_gbps_f64()
-            / mean;
-        let lower = self
-            .lower_bound
-            .unwrap_or_else(|| Bandwidth::from_mbps(0))
-            .as_gbps_f64()
-            / mean;
-        let upper = self.upper_bound.map(|upper| upper.as_gbps_f64() / mean);
-        let new_mean = mean * solve(1f64, sigma, Some(lower), upper).unwrap_or(1f64);
-        self.mean = Some(Bandwidth::from_gbps_f64(new_mean));
-        self.build()
+    /// Panics if the configuration is invalid. See [`try_build`](Self::try_build) for the conditions.
+    pub fn build_with_clock<F>(self, clock: F) -> ScheduleBw
+    where
+        F: Fn() -> std::time::SystemTime + Send + Sync + 'static,
+    {
+        self.try_build_with_clock(clock)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
-}
 
-impl SawtoothBwConfig {
-    pub fn new() -> Self {
-        Self {
-            bottom: None,
-            top: None,
-            interval: None,
-            duty_ratio: None,
-            duration: None,
-            step: None,
-            seed: None,
-            std_dev: None,
-            upper_noise_bound: None,
-            lower_noise_bound: None,
+    /// Build the model querying the time from [`SystemTime::now`](std::time::SystemTime::now), or
+    /// return an error if the minute of any slot is greater than 1440 (i.e. the end of a day).
+    pub fn try_build(self) -> Result<ScheduleBw, ModelBuildError> {
+        self.try_build_with_clock(std::time::SystemTime::now)
+    }
+
+    /// Build the model querying the time from `clock`, or return an error if the configuration is
+    /// invalid. See [`build_with_clock`](Self::build_with_clock) and [`try_build`](Self::try_build).
+    pub fn try_build_with_clock<F>(self, clock: F) -> Result<ScheduleBw, ModelBuildError>
+    where
+        F: Fn() -> std::time::SystemTime + Send + Sync + 'static,
+    {
+        if self.slots.iter().any(|(start, end, _)| {
+            *start > ScheduleBw::MINUTES_PER_DAY || *end > ScheduleBw::MINUTES_PER_DAY
+        }) {
+            return Err(ModelBuildError::InvalidConfig {
+                model: "ScheduleBw",
+                reason: "the minutes of slots must be within a day (0 to 1440)".to_string(),
+            });
         }
+        let default_bw = self.default_bw.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        debug_log!(
+            "ScheduleBw built: {} slots, default_bw {}",
+            self.slots.len(),
+            fmt_bandwidth(default_bw)
+        );
+        Ok(ScheduleBw {
+            slots: self.slots,
+            default_bw,
+            clock: std::sync::Arc::new(clock),
+        })
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "human"))]
+mod empiricalbw_serde {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    /// Deserializes the CDF points with bandwidth in human-readable format.
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Bandwidth, f64)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<(String, f64)>::deserialize(d)?
+            .into_iter()
+            .map(|(bw, prob)| {
+                human_bandwidth::parse_bandwidth(&bw)
+                    .map(|bw| (bw, prob))
+                    .map_err(|e| {
+                        de::Error::custom(format!("Failed to parse bandwidth '{}': {}", bw, e))
+                    })
+            })
+            .collect()
+    }
+
+    /// Serializes the CDF points with bandwidth in human-readable format.
+    pub fn serialize<S>(cdf: &[(Bandwidth, f64)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            cdf.iter()
+                .map(|(bw, prob)| (human_bandwidth::format_bandwidth(*bw).to_string(), *prob)),
+        )
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "human"))]
+mod bandwidths_serde {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    /// Deserializes a list of bandwidths in human-readable format, e.g. the states of
+    /// [`MarkovBwConfig`] or the levels of [`QuantizedBwConfig`].
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<Bandwidth>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(d)?
+            .into_iter()
+            .map(|bw| {
+                human_bandwidth::parse_bandwidth(&bw).map_err(|e| {
+                    de::Error::custom(format!("Failed to parse bandwidth '{}': {}", bw, e))
+                })
+            })
+            .collect()
     }
 
-    pub fn bottom(mut self, bottom: Bandwidth) -> Self {
-        self.bottom = Some(bottom);
-        self
+    /// Serializes a list of bandwidths in human-readable format.
+    pub fn serialize<S>(bandwidths: &[Bandwidth], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            bandwidths
+                .iter()
+                .map(|bw| human_bandwidth::format_bandwidth(*bw).to_string()),
+        )
     }
+}
 
-    pub fn top(mut self, top: Bandwidth) -> Self {
-        self.top = Some(top);
-        self
-    }
+#[cfg(all(feature = "serde", feature = "human"))]
+mod pairs_serde {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
 
-    pub fn interval(mut self, interval: Duration) -> Self {
-        self.interval = Some(interval);
-        self
+    /// A value of a pair written in human-readable format, i.e. a bandwidth or a duration.
+    pub trait Human: Sized + Copy {
+        const NAME: &'static str;
+
+        fn parse(s: &str) -> Result<Self, String>;
+
+        fn format(self) -> String;
     }
 
-    pub fn duty_ratio(mut self, duty_ratio: f64) -> Self {
-        self.duty_ratio = Some(duty_ratio);
-        self
+    impl Human for Bandwidth {
+        const NAME: &'static str = "bandwidth";
+
+        fn parse(s: &str) -> Result<Self, String> {
+            human_bandwidth::parse_bandwidth(s).map_err(|e| e.to_string())
+        }
+
+        fn format(self) -> String {
+            human_bandwidth::format_bandwidth(self).to_string()
+        }
     }
 
-    pub fn duration(mut self, duration: Duration) -> Self {
-        self.duration = Some(duration);
-        self
+    impl Human for Duration {
+        const NAME: &'static str = "duration";
+
+        fn parse(s: &str) -> Result<Self, String> {
+            crate::model::human_duration::parse_duration(s)
+        }
+
+        fn format(self) -> String {
+            humantime_serde::re::humantime::format_duration(self).to_string()
+        }
     }
 
-    pub fn step(mut self, step: Duration) -> Self {
-        self.step = Some(step);
-        self
+    fn parse<T: Human, E: de::Error>(s: &str) -> Result<T, E> {
+        T::parse(s).map_err(|e| E::custom(format!("Failed to parse {} '{}': {}", T::NAME, s, e)))
     }
 
-    pub fn seed(mut self, seed: u64) -> Self {
-        self.seed = Some(seed);
-        self
+    /// Deserializes the pairs of bandwidth and time (in either order) in human-readable format,
+    /// e.g. the points of [`SplineBwConfig`] or the sequence of [`SequenceBwConfig`].
+    pub fn deserialize<'de, D, A, B>(d: D) -> Result<Vec<(A, B)>, D::Error>
+    where
+        D: Deserializer<'de>,
+        A: Human,
+        B: Human,
+    {
+        Vec::<(String, String)>::deserialize(d)?
+            .into_iter()
+            .map(|(a, b)| Ok((parse(&a)?, parse(&b)?)))
+            .collect()
     }
 
-    pub fn std_dev(mut self, std_dev: Bandwidth) -> Self {
-        self.std_dev = Some(std_dev);
-        self
+    /// Serializes the pairs of bandwidth and time (in either order) in human-readable format.
+    pub fn serialize<S, A, B>(pairs: &[(A, B)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        A: Human,
+        B: Human,
+    {
+        s.collect_seq(pairs.iter().map(|(a, b)| (a.format(), b.format())))
     }
+}
 
-    pub fn upper_noise_bound(mut self, upper_noise_bound: Bandwidth) -> Self {
-        self.upper_noise_bound = Some(upper_noise_bound);
-        self
+#[cfg(all(feature = "serde", feature = "human"))]
+mod schedulebw_serde {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    /// Deserializes the slots with bandwidth in human-readable format.
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(u32, u32, Bandwidth)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<(u32, u32, String)>::deserialize(d)?
+            .into_iter()
+            .map(|(start, end, bw)| {
+                let bw = human_bandwidth::parse_bandwidth(&bw).map_err(|e| {
+                    de::Error::custom(format!("Failed to parse bandwidth '{}': {}", bw, e))
+                })?;
+                Ok((start, end, bw))
+            })
+            .collect()
     }
 
-    pub fn lower_noise_bound(mut self, lower_noise_bound: Bandwidth) -> Self {
-        self.lower_noise_bound = Some(lower_noise_bound);
-        self
+    /// Serializes the slots with bandwidth in human-readable format.
+    pub fn serialize<S>(slots: &[(u32, u32, Bandwidth)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(slots.iter().map(|(start, end, bw)| {
+            (
+                start,
+                end,
+                human_bandwidth::format_bandwidth(*bw).to_string(),
+            )
+        }))
     }
+}
 
-    pub fn build(self) -> SawtoothBw {
-        let bottom = self.bottom.unwrap_or_else(|| Bandwidth::from_mbps(0));
-        let top = self.top.unwrap_or_else(|| Bandwidth::from_mbps(12));
-        if bottom > top {
-            panic!("SawtoothBw: bottom bw must be less than top bw");
+/// Implement [`BwTraceConfig`] for the built-in bandwidth trace model configs, and register their tags.
+///
+/// A config containing inner configs is followed by the field holding them in parentheses, either
+/// `(pattern)` for `Vec<Box<dyn BwTraceConfig>>`, `(weighted)` for a `pattern` of
+/// `Vec<(f64, Box<dyn BwTraceConfig>)>`, or the name of an `Option<Box<dyn BwTraceConfig>>`
+/// field (e.g. `(inner)`), so that its nesting depth is checked. A config whose model never ends
+/// by itself is followed by `(forever)`, or by `(inner forever)` if it loops its inner config forever.
+/// A config which buffers its whole inner config, and thus produces nothing if the inner config never
+/// ends, is followed by `(inner buffered)`. A config playing a `Vec<Box<dyn BwTraceConfig>>` field in
+/// parallel until all of them end is followed by the name of the field and `parallel`, e.g.
+/// `(inputs parallel)`. A config merging an `Option<Box<dyn BwTraceConfig>>` field with an input of
+/// another kind (e.g. a loss trace), and thus ending when either of them ends, is followed by the name
+/// of the field and `merged`, e.g. `(bw merged)`.
+/// The `exceeds_depth` override of every config with inner configs is generated by the `@depth` rules.
+/// A config whose total duration is known in advance is followed by `(finite)`, and implements
+/// `finite_duration`. A finite config whose model is only resettable through [`Rewind`] is followed
+/// by `(rewind finite)` instead.
+macro_rules! impl_bw_trace_config {
+    (@depth option $field:ident) => {
+        fn exceeds_depth(&self, max_depth: usize) -> bool {
+            max_depth == 0
+                || self
+                    .$field
+                    .as_ref()
+                    .is_some_and(|inner| inner.exceeds_depth(max_depth - 1))
         }
-        let interval = self.interval.unwrap_or_else(|| Duration::from_secs(1));
-        let duty_ratio = self.duty_ratio.unwrap_or(0.5);
-        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
-        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
-        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
-        let rng = StdRng::seed_from_u64(seed);
-        let std_dev = self.std_dev.unwrap_or_else(|| Bandwidth::from_mbps(0));
-        let upper_noise_bound = self.upper_noise_bound;
-        let lower_noise_bound = self.lower_noise_bound;
-        let current = Duration::ZERO;
-        let bw_std_dev = saturating_bandwidth_as_bps_u64!(std_dev) as f64;
-        let noise: Normal<f64> = Normal::new(0.0, bw_std_dev).unwrap();
-        SawtoothBw {
-            bottom,
-            top,
-            interval,
-            duty_ratio,
-            duration,
-            step,
-            seed,
-            std_dev,
-            upper_noise_bound,
-            lower_noise_bound,
-            current,
-            rng,
-            noise,
+    };
+    (@depth vec $field:ident) => {
+        fn exceeds_depth(&self, max_depth: usize) -> bool {
+            max_depth == 0
+                || self
+                    .$field
+                    .iter()
+                    .any(|config| config.exceeds_depth(max_depth - 1))
         }
-    }
-}
+    };
+    (@depth weighted $field:ident) => {
+        fn exceeds_depth(&self, max_depth: usize) -> bool {
+            max_depth == 0
+                || self
+                    .$field
+                    .iter()
+                    .any(|(_, config)| config.exceeds_depth(max_depth - 1))
+        }
+    };
+    (@impl $name:ident) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+        }
+    };
+    (@impl $name:ident (rewind finite)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(Rewind::new(self.build()))
+            }
 
-impl RepeatedBwPatternConfig {
-    pub fn new() -> Self {
-        Self {
-            pattern: vec![],
-            count: 0,
+            fn total_duration(&self) -> Option<Duration> {
+                Some(self.finite_duration())
+            }
         }
-    }
+    };
+    (@impl $name:ident (finite)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
 
-    pub fn pattern(mut self, pattern: Vec<Box<dyn BwTraceConfig>>) -> Self {
-        self.pattern = pattern;
-        self
-    }
+            fn total_duration(&self) -> Option<Duration> {
+                Some(self.finite_duration())
+            }
+        }
+    };
+    (@impl $name:ident (repeated)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
 
-    pub fn count(mut self, count: usize) -> Self {
-        self.count = count;
-        self
-    }
+            fn is_infinite(&self) -> bool {
+                self.repeated_duration().is_none()
+            }
 
-    pub fn build(self) -> RepeatedBwPattern {
-        RepeatedBwPattern {
-            pattern: self.pattern,
-            count: self.count,
-            current_model: None,
-            current_cycle: 0,
-            current_pattern: 0,
+            fn total_duration(&self) -> Option<Duration> {
+                self.repeated_duration()
+            }
         }
-    }
-}
+    };
+    (@impl $name:ident (forever)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
 
-macro_rules! impl_bw_trace_config {
-    ($name:ident) => {
+            fn is_infinite(&self) -> bool {
+                true
+            }
+        }
+    };
+    (@impl $name:ident (pattern)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth vec pattern);
+
+            fn is_infinite(&self) -> bool {
+                !self.pattern.is_empty()
+                    && (self.count == 0 || self.pattern.iter().any(|config| config.is_infinite()))
+            }
+
+            fn total_duration(&self) -> Option<Duration> {
+                if self.pattern.is_empty() {
+                    return Some(Duration::ZERO);
+                }
+                if self.count == 0 {
+                    return None;
+                }
+                let cycle = self.pattern.iter().try_fold(Duration::ZERO, |total, config| {
+                    Some(total.saturating_add(config.total_duration()?))
+                })?;
+                Some(
+                    u32::try_from(self.count)
+                        .ok()
+                        .and_then(|count| cycle.checked_mul(count))
+                        .unwrap_or(Duration::MAX),
+                )
+            }
+        }
+    };
+    (@impl $name:ident (weighted)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth weighted pattern);
+
+            fn is_infinite(&self) -> bool {
+                !self.pattern.is_empty()
+                    && (self.count == 0
+                        || self
+                            .pattern
+                            .iter()
+                            .any(|(weight, config)| *weight > 0.0 && config.is_infinite()))
+            }
+
+            fn total_duration(&self) -> Option<Duration> {
+                if self.pattern.is_empty() {
+                    return Some(Duration::ZERO);
+                }
+                if self.count == 0 {
+                    return None;
+                }
+                // only known in advance if every config which may be picked lasts the same
+                let mut durations = self
+                    .pattern
+                    .iter()
+                    .filter(|(weight, _)| *weight > 0.0)
+                    .map(|(_, config)| config.total_duration());
+                let cycle = durations.next()??;
+                if !durations.all(|duration| duration == Some(cycle)) {
+                    return None;
+                }
+                Some(
+                    u32::try_from(self.count)
+                        .ok()
+                        .and_then(|count| cycle.checked_mul(count))
+                        .unwrap_or(Duration::MAX),
+                )
+            }
+        }
+    };
+    (@impl $name:ident ($field:ident)) => {
         #[cfg_attr(feature = "serde", typetag::serde)]
         impl BwTraceConfig for $name {
             fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
                 Box::new(self.build())
             }
+
+            impl_bw_trace_config!(@depth option $field);
+
+            fn is_infinite(&self) -> bool {
+                self.$field.as_ref().is_some_and(|inner| inner.is_infinite())
+            }
+        }
+    };
+    (@impl $name:ident ($field:ident forever)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth option $field);
+
+            fn is_infinite(&self) -> bool {
+                self.$field.is_some()
+            }
+        }
+    };
+    (@impl $name:ident ($field:ident buffered)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth option $field);
+
+            fn total_duration(&self) -> Option<Duration> {
+                match self.$field.as_ref() {
+                    Some(inner) if !inner.is_infinite() => inner.total_duration(),
+                    _ => Some(Duration::ZERO),
+                }
+            }
+        }
+    };
+    (@impl $name:ident ($field:ident merged)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth option $field);
         }
     };
+    (@impl $name:ident ($field:ident parallel)) => {
+        #[cfg_attr(feature = "serde", typetag::serde)]
+        impl BwTraceConfig for $name {
+            fn into_model(self: Box<$name>) -> Box<dyn BwTrace> {
+                Box::new(self.build())
+            }
+
+            impl_bw_trace_config!(@depth vec $field);
+
+            fn is_infinite(&self) -> bool {
+                self.$field.iter().any(|config| config.is_infinite())
+            }
+
+            fn total_duration(&self) -> Option<Duration> {
+                self.$field
+                    .iter()
+                    .try_fold(Duration::ZERO, |longest, config| {
+                        Some(longest.max(config.total_duration()?))
+                    })
+            }
+        }
+    };
+    ($($(#[$attr:meta])* $name:ident $(($($arg:ident)+))?),+ $(,)?) => {
+        $($(#[$attr])* impl_bw_trace_config!(@impl $name $(($($arg)+))?);)+
+
+        static REGISTERED_BW_CONFIG_TAGS: &[&str] = &[$($(#[$attr])* stringify!($name)),+];
+    };
 }
 
-impl_bw_trace_config!(StaticBwConfig);
-impl_bw_trace_config!(NormalizedBwConfig);
-impl_bw_trace_config!(SawtoothBwConfig);
-impl_bw_trace_config!(RepeatedBwPatternConfig);
-impl_bw_trace_config!(TraceBwConfig);
+impl_bw_trace_config!(
+    StaticBwConfig(rewind finite),
+    NormalizedBwConfig(finite),
+    SawtoothBwConfig(finite),
+    TraceBwConfig(finite),
+    SequenceBwConfig(finite),
+    RepeatedBwPatternConfig(pattern),
+    TaggedBwConfig(inner),
+    RandomOutageBwConfig(inner),
+    EmpiricalBwConfig(finite),
+    WindowMaxBwConfig(inner),
+    SplineBwConfig(finite),
+    SineBwConfig(finite),
+    SquareBwConfig(finite),
+    ScheduleBwConfig(forever),
+    FloorPlusVariableBwConfig(variable),
+    ScaledBwConfig(inner),
+    ClampedBwConfig(inner),
+    RandomPhaseBwConfig(inner forever),
+    MarkovBwConfig(finite),
+    EnvBwConfig(finite),
+    HysteresisBwConfig(inner),
+    RampBwConfig(finite),
+    RandomPatternBwConfig(weighted),
+    ReverseBwConfig(inner buffered),
+    PoissonBwConfig(finite),
+    SumBwConfig(inputs parallel),
+    WeibullBwConfig(finite),
+    GammaBwConfig(finite),
+    SpliceBwConfig(base),
+    ExponentialDwellBwConfig(finite),
+    DelayedStartBwConfig(inner),
+    ParetoBwConfig(finite),
+    StepBwConfig(repeated),
+    QuantizedBwConfig(inner),
+    #[cfg(feature = "loss-model")]
+    LossAsGapsBwConfig(bw merged),
+);
+
+/// Returns the tags of the built-in bandwidth trace model configs, i.e. the names used to identify them
+/// in configuration files (e.g. `"StaticBwConfig"`).
+///
+/// Configs implementing [`BwTraceConfig`] outside of this crate are not included.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::registered_bw_config_tags;
+/// assert!(registered_bw_config_tags().contains(&"StaticBwConfig"));
+/// ```
+pub fn registered_bw_config_tags() -> &'static [&'static str] {
+    REGISTERED_BW_CONFIG_TAGS
+}
 
 /// Turn a [`BwTraceConfig`] into a forever repeated [`RepeatedBwPatternConfig`].
+///
+/// It is implemented for every [`BwTraceConfig`] repeated with [`RepeatedBwPatternConfig`] by
+/// [`Repeatable`]. With both traits in scope, call it as `Forever::forever(config)` to avoid the
+/// ambiguity with [`Repeatable::forever`].
 pub trait Forever: BwTraceConfig {
     fn forever(self) -> RepeatedBwPatternConfig;
 }
 
-/// Implement the [`Forever`] trait for the bandwidth trace model config (any struct implements [`BwTraceConfig`]).
+impl<T> Forever for T
+where
+    T: BwTraceConfig + Repeatable<Repeated = RepeatedBwPatternConfig>,
+{
+    fn forever(self) -> RepeatedBwPatternConfig {
+        self.repeat(0)
+    }
+}
+
+/// Implement the [`Repeatable`] (and thus [`Forever`]) trait for the bandwidth trace model config
+/// (any struct implements [`BwTraceConfig`]).
+///
+/// The config will be repeated with [`RepeatedBwPatternConfig`].
 #[macro_export]
 macro_rules! impl_forever {
     ($name:ident) => {
-        impl Forever for $name {
-            fn forever(self) -> RepeatedBwPatternConfig {
-                RepeatedBwPatternConfig::new()
+        impl $crate::model::Repeatable for $name {
+            type Repeated = $crate::model::RepeatedBwPatternConfig;
+
+            fn repeat(self, count: usize) -> $crate::model::RepeatedBwPatternConfig {
+                $crate::model::RepeatedBwPatternConfig::new()
                     .pattern(vec![Box::new(self)])
-                    .count(0)
+                    .count(count)
             }
         }
     };
 }
 
 impl_forever!(StaticBwConfig);
+impl_forever!(EnvBwConfig);
+impl_forever!(HysteresisBwConfig);
 impl_forever!(NormalizedBwConfig);
 impl_forever!(SawtoothBwConfig);
 impl_forever!(TraceBwConfig);
+impl_forever!(SequenceBwConfig);
+impl_forever!(TaggedBwConfig);
+impl_forever!(RandomOutageBwConfig);
+impl_forever!(EmpiricalBwConfig);
+impl_forever!(WindowMaxBwConfig);
+impl_forever!(SplineBwConfig);
+impl_forever!(SineBwConfig);
+impl_forever!(SquareBwConfig);
+impl_forever!(ScheduleBwConfig);
+impl_forever!(FloorPlusVariableBwConfig);
+impl_forever!(ScaledBwConfig);
+impl_forever!(ClampedBwConfig);
+impl_forever!(RandomPhaseBwConfig);
+impl_forever!(MarkovBwConfig);
+impl_forever!(RampBwConfig);
+impl_forever!(RandomPatternBwConfig);
+impl_forever!(ReverseBwConfig);
+impl_forever!(PoissonBwConfig);
+impl_forever!(SumBwConfig);
+impl_forever!(WeibullBwConfig);
+impl_forever!(GammaBwConfig);
+impl_forever!(SpliceBwConfig);
+impl_forever!(ExponentialDwellBwConfig);
+impl_forever!(DelayedStartBwConfig);
+impl_forever!(ParetoBwConfig);
+impl_forever!(StepBwConfig);
+impl_forever!(QuantizedBwConfig);
+#[cfg(feature = "loss-model")]
+impl_forever!(LossAsGapsBwConfig);
 
-impl Forever for RepeatedBwPatternConfig {
-    fn forever(self) -> RepeatedBwPatternConfig {
-        self.count(0)
+impl Repeatable for RepeatedBwPatternConfig {
+    type Repeated = RepeatedBwPatternConfig;
+
+    /// Repeat the whole pattern for `count` times, by multiplying the `count` of the pattern.
+    fn repeat(self, count: usize) -> RepeatedBwPatternConfig {
+        let count = self.count.saturating_mul(count);
+        self.count(count)
     }
 }
@@ -0,0 +1,121 @@
+//! This module provides a wrapper attaching a SHA-256 checksum to a bandwidth trace configuration,
+//! which helps to detect corrupted configuration files when they are distributed across machines.
+//!
+//! Enabled with feature `checksum`.
+//!
+//! The checksum is computed over the canonical serialization of the inner configuration,
+//! i.e. its compact JSON representation (the same as `serde_json::to_string`).
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::{ChecksummedConfig, StaticBwConfig};
+//! # use netem_trace::{Bandwidth, Duration};
+//! let checksummed = ChecksummedConfig::wrap(Box::new(
+//!     StaticBwConfig::new()
+//!         .bw(Bandwidth::from_mbps(12))
+//!         .duration(Duration::from_secs(1)),
+//! ));
+//! let content = serde_json::to_string(&checksummed).unwrap();
+//!
+//! let des: ChecksummedConfig = serde_json::from_str(&content).unwrap();
+//! assert!(des.verify());
+//! let model = des.into_inner().into_model();
+//! ```
+use super::BwTraceConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A bandwidth trace configuration with the SHA-256 checksum of its canonical serialization.
+///
+/// The checksum is stored as a lowercase hex string in `sha256`.
+///
+/// See the [`checksum`](crate::model::checksum) module for more details.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChecksummedConfig {
+    pub config: Box<dyn BwTraceConfig>,
+    pub sha256: String,
+}
+
+impl ChecksummedConfig {
+    /// Wrap a configuration, computing its checksum.
+    pub fn wrap(config: Box<dyn BwTraceConfig>) -> Self {
+        let sha256 = checksum(config.as_ref());
+        Self { config, sha256 }
+    }
+
+    /// Recompute the checksum of the inner configuration and compare it with the stored one.
+    ///
+    /// Returns `false` if the inner configuration or the stored checksum is altered.
+    pub fn verify(&self) -> bool {
+        checksum(self.config.as_ref()).eq_ignore_ascii_case(self.sha256.trim())
+    }
+
+    /// Unwrap the inner configuration (without verification).
+    pub fn into_inner(self) -> Box<dyn BwTraceConfig> {
+        self.config
+    }
+}
+
+/// Compute the SHA-256 checksum of the canonical serialization of a configuration as a hex string.
+fn checksum(config: &dyn BwTraceConfig) -> String {
+    let canonical = serde_json::to_string(config).unwrap();
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{RepeatedBwPatternConfig, StaticBwConfig};
+    use crate::{Bandwidth, Duration};
+
+    #[test]
+    fn test_checksum() {
+        let config = RepeatedBwPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(12))
+                        .duration(Duration::from_secs(1)),
+                ) as Box<dyn BwTraceConfig>,
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(24))
+                        .duration(Duration::from_secs(1)),
+                ) as Box<dyn BwTraceConfig>,
+            ])
+            .count(2);
+        let checksummed = ChecksummedConfig::wrap(Box::new(config));
+        assert_eq!(checksummed.sha256.len(), 64);
+        assert!(checksummed.verify());
+
+        let content = serde_json::to_string(&checksummed).unwrap();
+        let des: ChecksummedConfig = serde_json::from_str(&content).unwrap();
+        assert_eq!(des.sha256, checksummed.sha256);
+        assert!(des.verify());
+
+        // alter the inner config in the file
+        #[cfg(feature = "human")]
+        let tampered = content.replace("24Mbps", "25Mbps");
+        #[cfg(not(feature = "human"))]
+        let tampered = content.replace("24000000", "25000000");
+        assert_ne!(tampered, content);
+        let des: ChecksummedConfig = serde_json::from_str(&tampered).unwrap();
+        assert!(!des.verify());
+
+        // alter the checksum
+        let mut des: ChecksummedConfig = serde_json::from_str(&content).unwrap();
+        des.sha256.replace_range(
+            0..1,
+            if des.sha256.starts_with('0') {
+                "1"
+            } else {
+                "0"
+            },
+        );
+        assert!(!des.verify());
+    }
+}
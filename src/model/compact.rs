@@ -0,0 +1,336 @@
+//! This module implements a compact single-line form of some bandwidth trace configurations, e.g.
+//! for command line tools, with [`FromStr`] and [`Display`].
+//!
+//! Enabled with feature `bw-model` and `human`.
+//!
+//! The supported forms are:
+//!
+//! - [`StaticBwConfig`]: `static:<bw>[@<duration>]`, e.g. `static:24Mbps@1s`.
+//! - [`SawtoothBwConfig`]: `sawtooth:<bottom>-<top>/<interval>[@<duration>][,<key>=<value>...]`, e.g.
+//!   `sawtooth:12Mbps-16Mbps/500ms@10s,step=10ms`. The optional keys are `duty_ratio`, `step`,
+//!   `seed`, `std_dev`, `upper_noise_bound` and `lower_noise_bound`.
+//!
+//! Bandwidths and durations are written in the human-readable format of the configuration files,
+//! e.g. `12Mbps` or `1s 500ms`. Fields which are part of the form, but not set in the configuration,
+//! are displayed with their default values, and other fields which are not set are omitted.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::{SawtoothBwConfig, StaticBwConfig};
+//! # use netem_trace::{Bandwidth, Duration};
+//! let config: StaticBwConfig = "static:24Mbps@1s".parse().unwrap();
+//! assert_eq!(config.bw, Some(Bandwidth::from_mbps(24)));
+//! assert_eq!(config.duration, Some(Duration::from_secs(1)));
+//! assert_eq!(config.to_string(), "static:24Mbps@1s");
+//!
+//! let config: SawtoothBwConfig = "sawtooth:12Mbps-16Mbps/500ms".parse().unwrap();
+//! assert_eq!(config.top, Some(Bandwidth::from_mbps(16)));
+//! assert_eq!(config.interval, Some(Duration::from_millis(500)));
+//! ```
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use super::bw::{SawtoothBwConfig, StaticBwConfig};
+use crate::{Bandwidth, Duration};
+use human_bandwidth::{format_bandwidth, parse_bandwidth};
+use humantime_serde::re::humantime::{format_duration, parse_duration};
+
+const STATIC_FORM: &str = "static:<bw>[@<duration>]";
+const SAWTOOTH_FORM: &str = "sawtooth:<bottom>-<top>/<interval>[@<duration>][,<key>=<value>...]";
+
+/// The error returned when parsing a configuration from its compact form fails.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseConfigError {
+    /// The input `input` does not match the form `expected`.
+    InvalidFormat {
+        expected: &'static str,
+        input: String,
+    },
+    /// The value `value` of the field `field` is invalid.
+    InvalidValue {
+        field: &'static str,
+        value: String,
+        reason: String,
+    },
+    /// The key of the option is not a known field.
+    UnknownOption(String),
+}
+
+impl Display for ParseConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseConfigError::InvalidFormat { expected, input } => {
+                write!(f, "invalid config '{}', expected '{}'", input, expected)
+            }
+            ParseConfigError::InvalidValue {
+                field,
+                value,
+                reason,
+            } => write!(f, "invalid {} '{}': {}", field, value, reason),
+            ParseConfigError::UnknownOption(key) => write!(f, "unknown option '{}'", key),
+        }
+    }
+}
+
+impl std::error::Error for ParseConfigError {}
+
+fn bandwidth(field: &'static str, value: &str) -> Result<Bandwidth, ParseConfigError> {
+    parse_bandwidth(value.trim()).map_err(|e| ParseConfigError::InvalidValue {
+        field,
+        value: value.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn duration(field: &'static str, value: &str) -> Result<Duration, ParseConfigError> {
+    parse_duration(value.trim()).map_err(|e| ParseConfigError::InvalidValue {
+        field,
+        value: value.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn number<T: FromStr>(field: &'static str, value: &str) -> Result<T, ParseConfigError>
+where
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e: T::Err| ParseConfigError::InvalidValue {
+            field,
+            value: value.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// Strip the `kind:` prefix, and split the rest into the body and the optional `@<duration>`.
+fn split_form<'a>(
+    s: &'a str,
+    kind: &str,
+    expected: &'static str,
+) -> Result<(&'a str, Option<Duration>), ParseConfigError> {
+    let body = s
+        .trim()
+        .strip_prefix(kind)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or_else(|| ParseConfigError::InvalidFormat {
+            expected,
+            input: s.to_string(),
+        })?;
+    match body.split_once('@') {
+        Some((body, time)) => Ok((body, Some(duration("duration", time)?))),
+        None => Ok((body, None)),
+    }
+}
+
+impl FromStr for StaticBwConfig {
+    type Err = ParseConfigError;
+
+    /// Parse the compact form `static:<bw>[@<duration>]`, see the [`compact`](super::compact)
+    /// module.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bw, time) = split_form(s, "static", STATIC_FORM)?;
+        Ok(StaticBwConfig {
+            bw: Some(bandwidth("bw", bw)?),
+            duration: time,
+        })
+    }
+}
+
+impl Display for StaticBwConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bw = self.bw.unwrap_or_else(|| Bandwidth::from_mbps(12));
+        write!(f, "static:{}", format_bandwidth(bw))?;
+        if let Some(time) = self.duration {
+            write!(f, "@{}", format_duration(time))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SawtoothBwConfig {
+    type Err = ParseConfigError;
+
+    /// Parse the compact form `sawtooth:<bottom>-<top>/<interval>[@<duration>][,<key>=<value>...]`,
+    /// see the [`compact`](super::compact) module.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseConfigError::InvalidFormat {
+            expected: SAWTOOTH_FORM,
+            input: s.to_string(),
+        };
+        let (head, options) = match s.split_once(',') {
+            Some((head, options)) => (head, Some(options)),
+            None => (s, None),
+        };
+        let (body, time) = split_form(head, "sawtooth", SAWTOOTH_FORM)?;
+        let (range, interval) = body.split_once('/').ok_or_else(invalid)?;
+        let (bottom, top) = range.split_once('-').ok_or_else(invalid)?;
+        let mut config = SawtoothBwConfig::new()
+            .bottom(bandwidth("bottom", bottom)?)
+            .top(bandwidth("top", top)?)
+            .interval(duration("interval", interval)?);
+        config.duration = time;
+        for option in options.into_iter().flat_map(|options| options.split(',')) {
+            let (key, value) = option.split_once('=').ok_or_else(invalid)?;
+            match key.trim() {
+                "duty_ratio" => config.duty_ratio = Some(number("duty_ratio", value)?),
+                "step" => config.step = Some(duration("step", value)?),
+                "seed" => config.seed = Some(number("seed", value)?),
+                "std_dev" => config.std_dev = Some(bandwidth("std_dev", value)?),
+                "upper_noise_bound" => {
+                    config.upper_noise_bound = Some(bandwidth("upper_noise_bound", value)?)
+                }
+                "lower_noise_bound" => {
+                    config.lower_noise_bound = Some(bandwidth("lower_noise_bound", value)?)
+                }
+                key => return Err(ParseConfigError::UnknownOption(key.to_string())),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl Display for SawtoothBwConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sawtooth:{}-{}/{}",
+            format_bandwidth(self.bottom.unwrap_or(Bandwidth::ZERO)),
+            format_bandwidth(self.top.unwrap_or_else(|| Bandwidth::from_mbps(12))),
+            format_duration(self.interval.unwrap_or_else(|| Duration::from_secs(1)))
+        )?;
+        if let Some(time) = self.duration {
+            write!(f, "@{}", format_duration(time))?;
+        }
+        if let Some(duty_ratio) = self.duty_ratio {
+            write!(f, ",duty_ratio={}", duty_ratio)?;
+        }
+        if let Some(step) = self.step {
+            write!(f, ",step={}", format_duration(step))?;
+        }
+        if let Some(seed) = self.seed {
+            write!(f, ",seed={}", seed)?;
+        }
+        for (key, value) in [
+            ("std_dev", self.std_dev),
+            ("upper_noise_bound", self.upper_noise_bound),
+            ("lower_noise_bound", self.lower_noise_bound),
+        ] {
+            if let Some(value) = value {
+                write!(f, ",{}={}", key, format_bandwidth(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_static_roundtrip() {
+        for (s, bw, time) in [
+            (
+                "static:24Mbps@1s",
+                Bandwidth::from_mbps(24),
+                Some(Duration::from_secs(1)),
+            ),
+            (
+                "static:1Gbps 500Mbps@1m 30s",
+                Bandwidth::from_mbps(1500),
+                Some(Duration::from_secs(90)),
+            ),
+            ("static:12kbps", Bandwidth::from_kbps(12), None),
+        ] {
+            let config: StaticBwConfig = s.parse().unwrap();
+            assert_eq!((config.bw, config.duration), (Some(bw), time), "{}", s);
+            assert_eq!(config.to_string(), s);
+        }
+        // whitespace around the values is allowed
+        let config: StaticBwConfig = " static: 24Mbps @ 1s ".parse().unwrap();
+        assert_eq!(config.to_string(), "static:24Mbps@1s");
+        // the default bandwidth is displayed
+        assert_eq!(StaticBwConfig::new().to_string(), "static:12Mbps");
+    }
+
+    #[test]
+    fn test_sawtooth_roundtrip() {
+        let config: SawtoothBwConfig = "sawtooth:12Mbps-16Mbps/500ms".parse().unwrap();
+        assert_eq!(config.bottom, Some(Bandwidth::from_mbps(12)));
+        assert_eq!(config.top, Some(Bandwidth::from_mbps(16)));
+        assert_eq!(config.interval, Some(Duration::from_millis(500)));
+        assert_eq!(config.duration, None);
+        assert_eq!(config.to_string(), "sawtooth:12Mbps-16Mbps/500ms");
+
+        let s = "sawtooth:0bps-12Mbps/1s@10s,duty_ratio=0.8,step=10ms,seed=7,std_dev=1Mbps,\
+                 upper_noise_bound=2Mbps,lower_noise_bound=500kbps";
+        let config: SawtoothBwConfig = s.parse().unwrap();
+        assert_eq!(config.duration, Some(Duration::from_secs(10)));
+        assert_eq!(config.duty_ratio, Some(0.8));
+        assert_eq!(config.step, Some(Duration::from_millis(10)));
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.std_dev, Some(Bandwidth::from_mbps(1)));
+        assert_eq!(config.upper_noise_bound, Some(Bandwidth::from_mbps(2)));
+        assert_eq!(config.lower_noise_bound, Some(Bandwidth::from_kbps(500)));
+        assert_eq!(config.to_string(), s);
+
+        assert_eq!(
+            SawtoothBwConfig::new().to_string(),
+            "sawtooth:0bps-12Mbps/1s"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = "sawtooth:12Mbps-16Mbps/500ms".parse::<StaticBwConfig>();
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "invalid config 'sawtooth:12Mbps-16Mbps/500ms', expected 'static:<bw>[@<duration>]'"
+        );
+        assert!(matches!(
+            "static24Mbps".parse::<StaticBwConfig>(),
+            Err(ParseConfigError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "static:24Mbps@1x".parse::<StaticBwConfig>(),
+            Err(ParseConfigError::InvalidValue {
+                field: "duration",
+                ..
+            })
+        ));
+        assert!(matches!(
+            "static:fast".parse::<StaticBwConfig>(),
+            Err(ParseConfigError::InvalidValue { field: "bw", .. })
+        ));
+
+        assert!(matches!(
+            "sawtooth:12Mbps/500ms".parse::<SawtoothBwConfig>(),
+            Err(ParseConfigError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "sawtooth:12Mbps-16Mbps".parse::<SawtoothBwConfig>(),
+            Err(ParseConfigError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "sawtooth:12Mbps-16Mbps/500ms,step".parse::<SawtoothBwConfig>(),
+            Err(ParseConfigError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "sawtooth:12Mbps-16Mbps/500ms,duty_ratio=half".parse::<SawtoothBwConfig>(),
+            Err(ParseConfigError::InvalidValue {
+                field: "duty_ratio",
+                ..
+            })
+        ));
+        assert_eq!(
+            "sawtooth:12Mbps-16Mbps/500ms,speed=1"
+                .parse::<SawtoothBwConfig>()
+                .unwrap_err()
+                .to_string(),
+            "unknown option 'speed'"
+        );
+    }
+}
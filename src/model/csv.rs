@@ -0,0 +1,296 @@
+//! This module imports and exports [`TraceBwConfig`] in CSV format.
+//!
+//! Enabled with feature `csv`.
+//!
+//! Each row is `timestamp_ms,bw_mbps`, where the bandwidth is sampled over the interval ending at
+//! the timestamp, i.e. a row lasts from the timestamp of the previous row (or 0 for the first row)
+//! to its own timestamp. The timestamps must be strictly increasing from 0.
+//!
+//! Both columns are decimal numbers, with at most 6 fractional digits (i.e. nanoseconds and bps),
+//! so that a trace survives the round trip exactly. An optional header `timestamp_ms,bw_mbps`
+//! and empty lines are skipped.
+//!
+//! Consecutive rows lasting for the same duration are grouped into one entry of the pattern.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::TraceBwConfig;
+//! # use netem_trace::{Bandwidth, Duration};
+//! let csv = "timestamp_ms,bw_mbps\n100,12\n200,24\n350,1.5\n";
+//! let config = TraceBwConfig::from_csv_reader(csv.as_bytes()).unwrap();
+//! assert_eq!(
+//!     config.pattern,
+//!     vec![
+//!         (Duration::from_millis(100), vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(24)]),
+//!         (Duration::from_millis(150), vec![Bandwidth::from_kbps(1500)]),
+//!     ]
+//! );
+//!
+//! let mut output = vec![];
+//! config.to_csv_writer(&mut output).unwrap();
+//! assert_eq!(String::from_utf8(output).unwrap(), "timestamp_ms,bw_mbps\n100,12\n200,24\n350,1.5\n");
+//! ```
+use super::TraceBwConfig;
+use crate::units::bps_u128;
+use crate::{Bandwidth, Duration};
+use std::io::{BufRead, BufReader, Read, Write};
+
+const HEADER: &str = "timestamp_ms,bw_mbps";
+
+/// The error returned when importing or exporting a [`TraceBwConfig`] in CSV format.
+#[derive(Debug)]
+pub enum TraceBwError {
+    /// Failed to read or write.
+    Io(std::io::Error),
+    /// The row on the line (starting from 1) is not a valid `timestamp_ms,bw_mbps` pair.
+    Parse(usize, String),
+    /// The timestamp on the line (starting from 1) is not larger than the previous one.
+    NonMonotonic(usize),
+}
+
+impl std::fmt::Display for TraceBwError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceBwError::Io(e) => write!(f, "io error: {}", e),
+            TraceBwError::Parse(line, row) => write!(f, "invalid row on line {}: '{}'", line, row),
+            TraceBwError::NonMonotonic(line) => {
+                write!(
+                    f,
+                    "timestamp on line {} must be larger than the previous one",
+                    line
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceBwError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TraceBwError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TraceBwError {
+    fn from(e: std::io::Error) -> Self {
+        TraceBwError::Io(e)
+    }
+}
+
+/// Parse a non-negative decimal number with at most 6 fractional digits, scaled by 10^6.
+fn parse_micro_units(s: &str) -> Option<u128> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    if int.is_empty() || frac.len() > 6 {
+        return None;
+    }
+    if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let int: u128 = int.parse().ok()?;
+    let frac: u128 = format!("{:0<6}", frac).parse().ok()?;
+    int.checked_mul(1_000_000)?.checked_add(frac)
+}
+
+/// Format a number scaled by 10^6 as a decimal number, without trailing zeros.
+fn format_micro_units(units: u128) -> String {
+    let (int, frac) = (units / 1_000_000, units % 1_000_000);
+    if frac == 0 {
+        int.to_string()
+    } else {
+        format!("{}.{}", int, format!("{:06}", frac).trim_end_matches('0'))
+    }
+}
+
+impl TraceBwConfig {
+    /// Import the pattern from CSV rows of `timestamp_ms,bw_mbps`.
+    ///
+    /// See the [`csv`](crate::model::csv) module for the format.
+    pub fn from_csv_reader<R: Read>(r: R) -> Result<Self, TraceBwError> {
+        let mut pattern: Vec<(Duration, Vec<Bandwidth>)> = vec![];
+        let mut last_timestamp = Duration::ZERO;
+        let mut first_row = true;
+        for (index, line) in BufReader::new(r).lines().enumerate() {
+            let line = line?;
+            let row = line.trim();
+            if row.is_empty() {
+                continue;
+            }
+            if std::mem::take(&mut first_row) && row == HEADER {
+                continue;
+            }
+            let parse_error = || TraceBwError::Parse(index + 1, row.to_string());
+            let (timestamp, bw) = row.split_once(',').ok_or_else(parse_error)?;
+            let timestamp = parse_micro_units(timestamp.trim())
+                .and_then(|nanos| u64::try_from(nanos).ok())
+                .map(Duration::from_nanos)
+                .ok_or_else(parse_error)?;
+            let bw = parse_micro_units(bw.trim())
+                .and_then(|bps| u64::try_from(bps).ok())
+                .map(Bandwidth::from_bps)
+                .ok_or_else(parse_error)?;
+            if timestamp <= last_timestamp {
+                return Err(TraceBwError::NonMonotonic(index + 1));
+            }
+            let duration = timestamp - last_timestamp;
+            last_timestamp = timestamp;
+            match pattern.last_mut() {
+                Some((last_duration, bws)) if *last_duration == duration => bws.push(bw),
+                _ => pattern.push((duration, vec![bw])),
+            }
+        }
+        Ok(TraceBwConfig::new().pattern(pattern))
+    }
+
+    /// Export the pattern as CSV rows of `timestamp_ms,bw_mbps`, with the header.
+    ///
+    /// Entries with an empty bandwidth list are skipped, as they are when building the model.
+    /// Zero-duration segments are skipped as well, as they take no time in the model and would
+    /// otherwise repeat the timestamp of the previous row, which is rejected when reading back.
+    ///
+    /// See the [`csv`](crate::model::csv) module for the format.
+    pub fn to_csv_writer<W: Write>(&self, mut w: W) -> Result<(), TraceBwError> {
+        writeln!(w, "{}", HEADER)?;
+        let mut timestamp = Duration::ZERO;
+        for (duration, bws) in &self.pattern {
+            for bw in bws {
+                let next = timestamp.saturating_add(*duration);
+                if next == timestamp {
+                    continue;
+                }
+                timestamp = next;
+                let bps = bps_u128(*bw);
+                writeln!(
+                    w,
+                    "{},{}",
+                    format_micro_units(timestamp.as_nanos()),
+                    format_micro_units(bps)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::BwTraceConfig;
+
+    #[test]
+    fn test_csv() {
+        // empty file
+        let config = TraceBwConfig::from_csv_reader("".as_bytes()).unwrap();
+        assert!(config.pattern.is_empty());
+        let config = TraceBwConfig::from_csv_reader("timestamp_ms,bw_mbps\n\n".as_bytes()).unwrap();
+        assert!(config.pattern.is_empty());
+        let mut output = vec![];
+        config.to_csv_writer(&mut output).unwrap();
+        assert_eq!(output, b"timestamp_ms,bw_mbps\n");
+
+        // single row
+        let config = TraceBwConfig::from_csv_reader("1.5,0.25".as_bytes()).unwrap();
+        assert_eq!(
+            config.pattern,
+            vec![(Duration::from_micros(1500), vec![Bandwidth::from_kbps(250)])]
+        );
+
+        // grouping of equal-duration rows
+        let csv = "timestamp_ms,bw_mbps\n10,1\n20,2\n30,3\n50,4\n70,5\n71,6\n";
+        let config = TraceBwConfig::from_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(
+            config.pattern,
+            vec![
+                (
+                    Duration::from_millis(10),
+                    vec![
+                        Bandwidth::from_mbps(1),
+                        Bandwidth::from_mbps(2),
+                        Bandwidth::from_mbps(3)
+                    ]
+                ),
+                (
+                    Duration::from_millis(20),
+                    vec![Bandwidth::from_mbps(4), Bandwidth::from_mbps(5)]
+                ),
+                (Duration::from_millis(1), vec![Bandwidth::from_mbps(6)]),
+            ]
+        );
+        let mut output = vec![];
+        config.to_csv_writer(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), csv);
+
+        // round trip with nanoseconds and bps
+        let config = TraceBwConfig::new().pattern(vec![(
+            Duration::from_nanos(1_000_001),
+            vec![Bandwidth::from_bps(1), Bandwidth::from_gbps(10)],
+        )]);
+        let mut output = vec![];
+        config.to_csv_writer(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "timestamp_ms,bw_mbps\n1.000001,0.000001\n2.000002,10000\n"
+        );
+        assert_eq!(
+            TraceBwConfig::from_csv_reader(output.as_slice())
+                .unwrap()
+                .pattern,
+            config.pattern
+        );
+
+        // zero-duration segments are skipped
+        let config = TraceBwConfig::new().pattern(vec![
+            (Duration::from_millis(10), vec![Bandwidth::from_mbps(1)]),
+            (Duration::ZERO, vec![Bandwidth::from_mbps(2)]),
+            (Duration::from_millis(10), vec![Bandwidth::from_mbps(3)]),
+        ]);
+        let mut output = vec![];
+        config.to_csv_writer(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "timestamp_ms,bw_mbps\n10,1\n20,3\n"
+        );
+        let read = TraceBwConfig::from_csv_reader(output.as_slice()).unwrap();
+        assert_eq!(
+            read.pattern,
+            vec![(
+                Duration::from_millis(10),
+                vec![Bandwidth::from_mbps(1), Bandwidth::from_mbps(3)]
+            )]
+        );
+        assert!(read.output_eq(&config, Duration::from_millis(1), Duration::from_millis(30)));
+    }
+
+    #[test]
+    fn test_csv_error() {
+        for row in ["10", "10,abc", "-1,12", "10,1.0000001", "10,", "a,b,c"] {
+            assert!(
+                matches!(
+                    TraceBwConfig::from_csv_reader(format!("5,1\n{}", row).as_bytes()),
+                    Err(TraceBwError::Parse(2, _))
+                ),
+                "{}",
+                row
+            );
+        }
+        let err = TraceBwConfig::from_csv_reader("10,1\n\n10,2".as_bytes())
+            .err()
+            .unwrap();
+        assert!(matches!(err, TraceBwError::NonMonotonic(3)));
+        assert_eq!(
+            err.to_string(),
+            "timestamp on line 3 must be larger than the previous one"
+        );
+        assert!(matches!(
+            TraceBwConfig::from_csv_reader("0,1".as_bytes()),
+            Err(TraceBwError::NonMonotonic(1))
+        ));
+        // the header is only allowed on the first line
+        assert!(matches!(
+            TraceBwConfig::from_csv_reader("10,1\ntimestamp_ms,bw_mbps".as_bytes()),
+            Err(TraceBwError::Parse(2, _))
+        ));
+    }
+}
@@ -5,7 +5,12 @@
 //! ## Predefined models
 //!
 //! - [`StaticDelay`]: A trace model with static delay.
+//! - [`NormalizedDelay`]: A trace model whose delay subjects to a normal distribution, changing at fixed intervals.
 //! - [`RepeatedDelayPattern`]: A trace model with a repeated delay pattern.
+//! - [`TraceDelay`]: A trace model to replay a recorded delay timeline, e.g. captured alongside a bandwidth trace.
+//! - [`DelayedStartDelay`]: A wrapper model prepending a period of static delay to the inner model.
+//! - [`PerPacketAsDelay`]: A wrapper model sampling a per-packet delay model at a fixed packet interval (with feature `delay-per-packet-model` enabled).
+//! - [`SerializationPlusPropDelay`]: A model adding the serialization delay over a bandwidth model to a propagation delay model (with feature `bw-model` enabled).
 //!
 //! ## Examples
 //!
@@ -53,8 +58,22 @@
 //! );
 //! assert_eq!(model.next_delay(), None);
 //! ```
-use crate::{Delay, DelayTrace, Duration};
+#[cfg(feature = "delay-per-packet-model")]
+use super::DelayPerPacketTraceConfig;
+#[cfg(feature = "bw-model")]
+use super::{BwTraceConfig, SegmentMerger};
+use super::{EmptyPatternGuard, Repeatable, Rewind, DEFAULT_RNG_SEED};
+#[cfg(feature = "bw-model")]
+use crate::units::bps_u128;
+#[cfg(feature = "delay-per-packet-model")]
+use crate::DelayPerPacketTrace;
+#[cfg(feature = "bw-model")]
+use crate::{Bandwidth, BwTrace};
+use crate::{Delay, DelayTrace, Duration, Resettable};
 use dyn_clone::DynClone;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rand_distr::{Distribution, Normal};
 
 /// This trait is used to convert a delay trace configuration into a delay trace model.
 ///
@@ -101,23 +120,142 @@ pub struct StaticDelayConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub delay: Option<Delay>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub duration: Option<Duration>,
 }
 
+/// The model of a delay trace subjects to a normal distribution.
+///
+/// The delay will subject to N(mean, std_dev), but bounded within [lower_bound, upper_bound] (optional),
+/// and never negative. This is the time-driven counterpart of [`NormalizedBw`](crate::model::NormalizedBw),
+/// e.g. for emulators changing the delay at fixed intervals regardless of packet arrivals.
+///
+/// The `step` describes how long between two consecutive delay samples. Each segment lasts for
+/// `min(step, remaining duration)`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::NormalizedDelayConfig;
+/// # use netem_trace::{Delay, Duration, DelayTrace};
+/// let mut normal_delay = NormalizedDelayConfig::new()
+///     .mean(Delay::from_millis(12))
+///     .std_dev(Delay::from_millis(1))
+///     .upper_bound(Delay::from_micros(12100))
+///     .lower_bound(Delay::from_micros(11900))
+///     .duration(Duration::from_secs(1))
+///     .step(Duration::from_millis(100))
+///     .seed(42)
+///     .build();
+/// let (delay, duration) = normal_delay.next_delay().unwrap();
+/// assert!(delay >= Delay::from_micros(11900) && delay <= Delay::from_micros(12100));
+/// assert_eq!(duration, Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalizedDelay<R = StdRng> {
+    pub mean: Delay,
+    pub std_dev: Delay,
+    pub upper_bound: Option<Delay>,
+    pub lower_bound: Option<Delay>,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    rng: R,
+    normal: Normal<f64>,
+}
+
+/// The configuration struct for [`NormalizedDelay`].
+///
+/// See [`NormalizedDelay`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct NormalizedDelayConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub mean: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub std_dev: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub upper_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub lower_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
 /// The model contains an array of delay trace models.
 ///
 /// Combine multiple delay trace models into one delay pattern,
 /// and repeat the pattern for `count` times.
 ///
-/// If `count` is 0, the pattern will be repeated forever.
+/// If `count` is 0, the pattern will be repeated forever. To play each model once in sequence,
+/// use [`RepeatedDelayPatternConfig::once`].
 ///
 /// ## Examples
 ///
@@ -131,8 +269,10 @@ pub struct StaticDelayConfig {
 /// # let config_file_content = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":\"10ms\",\"duration\":\"1s\"}},{\"StaticDelayConfig\":{\"delay\":\"20ms\",\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The content would be "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":10000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":20000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let config_file_content = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":10000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":20000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let config_file_content = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":10000000,\"duration\":1000000000}},{\"StaticDelayConfig\":{\"delay\":20000000,\"duration\":1000000000}}],\"count\":2}}";
 /// let des: Box<dyn DelayTraceConfig> = serde_json::from_str(config_file_content).unwrap();
 /// let mut model = des.into_model();
 /// assert_eq!(
@@ -177,8 +317,10 @@ pub struct StaticDelayConfig {
 /// # let json_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":\"10ms\",\"duration\":\"1s\"}},{\"StaticDelayConfig\":{\"delay\":\"20ms\",\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The json string would be "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":10000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":20000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let json_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":10000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":20000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let json_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":10000000,\"duration\":1000000000}},{\"StaticDelayConfig\":{\"delay\":20000000,\"duration\":1000000000}}],\"count\":2}}";
 /// assert_eq!(ser_str, json_str);
 /// ```
 pub struct RepeatedDelayPattern {
@@ -187,6 +329,7 @@ pub struct RepeatedDelayPattern {
     current_model: Option<Box<dyn DelayTrace>>,
     current_cycle: usize,
     current_pattern: usize,
+    guard: EmptyPatternGuard,
 }
 
 /// The configuration struct for [`RepeatedDelayPattern`].
@@ -199,6 +342,224 @@ pub struct RepeatedDelayPatternConfig {
     pub count: usize,
 }
 
+/// The model of a delay trace replaying a recorded delay timeline, like
+/// [`TraceBw`](crate::model::TraceBw) for bandwidth.
+///
+/// Each `(duration, delay)` entry of `pattern` is a segment, returned in order by `next_delay` as
+/// `(delay, duration)`. Segments with a zero duration are skipped. The trace ends after the last
+/// segment, and can be repeated with [`Repeatable`].
+///
+/// With `human` feature enabled, the pattern is (de)serialized in a compact format like
+/// `[["1s","10ms"],["500ms","20ms"]]`.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::TraceDelayConfig;
+/// # use netem_trace::{Delay, Duration, DelayTrace};
+/// let mut trace_delay = TraceDelayConfig::new()
+///     .pattern(vec![
+///         (Duration::from_secs(1), Delay::from_millis(10)),
+///         (Duration::from_millis(500), Delay::from_millis(20)),
+///     ])
+///     .build();
+/// assert_eq!(trace_delay.next_delay(), Some((Delay::from_millis(10), Duration::from_secs(1))));
+/// assert_eq!(trace_delay.next_delay(), Some((Delay::from_millis(20), Duration::from_millis(500))));
+/// assert_eq!(trace_delay.next_delay(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TraceDelay {
+    pub pattern: Vec<(Duration, Delay)>,
+    index: usize,
+}
+
+/// The configuration struct for [`TraceDelay`].
+///
+/// See [`TraceDelay`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct TraceDelayConfig {
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "tracedelay_serde")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration::delay_pattern")
+    )]
+    pub pattern: Vec<(Duration, Delay)>,
+}
+
+/// The model delays the start of the inner model with a leading period of static `delay` lasting
+/// `offset`, e.g. to align multiple traces in time.
+///
+/// This is the delay counterpart of [`DelayedStartBw`](crate::model::DelayedStartBw). The leading
+/// segment is emitted first (unless `offset` is zero), followed by the segments of the inner model.
+/// Without an inner model, only the leading segment is produced. The `delay` defaults to zero.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{DelayedStartDelayConfig, StaticDelayConfig};
+/// # use netem_trace::{Delay, Duration, DelayTrace};
+/// let mut delayed_delay = DelayedStartDelayConfig::new()
+///     .inner(Box::new(
+///         StaticDelayConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .duration(Duration::from_secs(1)),
+///     ))
+///     .offset(Duration::from_millis(200))
+///     .delay(Delay::from_millis(5))
+///     .build();
+/// assert_eq!(delayed_delay.next_delay(), Some((Delay::from_millis(5), Duration::from_millis(200))));
+/// assert_eq!(delayed_delay.next_delay(), Some((Delay::from_millis(10), Duration::from_secs(1))));
+/// assert_eq!(delayed_delay.next_delay(), None);
+/// ```
+pub struct DelayedStartDelay {
+    pub offset: Duration,
+    pub delay: Delay,
+    inner: Option<Box<dyn DelayTrace>>,
+    started: bool,
+}
+
+/// The configuration struct for [`DelayedStartDelay`].
+///
+/// See [`DelayedStartDelay`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct DelayedStartDelayConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn DelayTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub offset: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub delay: Option<Delay>,
+}
+
+/// The model converts a per-packet delay trace into a time-based delay trace.
+///
+/// The `inner` per-packet model is sampled once every `packet_interval`, as if a packet was sent at
+/// the start of each interval, and each delay lasts for `packet_interval`. This allows to drive a
+/// per-packet model (e.g. with per-packet jitter) in a time-stepped emulator.
+///
+/// The model ends when the `inner` model ends, and produces nothing without an `inner` model.
+///
+/// Enabled with feature `delay-per-packet-model` in addition.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{PerPacketAsDelayConfig, StaticDelayPerPacketConfig};
+/// # use netem_trace::{Delay, Duration, DelayTrace};
+/// let mut model = PerPacketAsDelayConfig::new()
+///     .inner(Box::new(
+///         StaticDelayPerPacketConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .count(2),
+///     ))
+///     .packet_interval(Duration::from_millis(5))
+///     .build();
+/// assert_eq!(model.next_delay(), Some((Delay::from_millis(10), Duration::from_millis(5))));
+/// assert_eq!(model.next_delay(), Some((Delay::from_millis(10), Duration::from_millis(5))));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+#[cfg(feature = "delay-per-packet-model")]
+pub struct PerPacketAsDelay {
+    pub packet_interval: Duration,
+    inner: Option<Box<dyn DelayPerPacketTrace>>,
+}
+
+/// The configuration struct for [`PerPacketAsDelay`].
+///
+/// See [`PerPacketAsDelay`] for more details.
+#[cfg(feature = "delay-per-packet-model")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct PerPacketAsDelayConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inner: Option<Box<dyn DelayPerPacketTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub packet_interval: Option<Duration>,
+}
+
+/// The model adding the serialization delay of packets over a bandwidth model to a propagation
+/// delay model, i.e. the total delay of a packet.
+///
+/// The total delay of a packet of `packet_bytes` bytes is its propagation delay plus its serialization
+/// (transmission) delay, i.e. `prop_delay + packet_bytes * 8 / bw`. The serialization delay is computed
+/// in nanoseconds (rounded up), and is regarded as [`Delay::MAX`] when the bandwidth is zero.
+///
+/// The `bw` and `prop` models are merged lazily at the boundaries of their segments, so either of
+/// them may go on forever, and consecutive segments are not joined even if their total delays are the
+/// same. The model ends when either of them ends, and produces nothing without both of them.
+///
+/// Enabled with feature `bw-model` in addition.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{SerializationPlusPropDelayConfig, StaticBwConfig, StaticDelayConfig};
+/// # use netem_trace::{Bandwidth, Delay, Duration, DelayTrace};
+/// // 12Mbps takes 1ms to send a 1500-byte packet
+/// let mut model = SerializationPlusPropDelayConfig::new()
+///     .bw(Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))))
+///     .prop(Box::new(
+///         StaticDelayConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .duration(Duration::from_millis(500)),
+///     ))
+///     .packet_bytes(1500)
+///     .build();
+/// assert_eq!(model.next_delay(), Some((Delay::from_millis(11), Duration::from_millis(500))));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+#[cfg(feature = "bw-model")]
+pub struct SerializationPlusPropDelay {
+    pub packet_bytes: u64,
+    bw: Option<Box<dyn BwTrace>>,
+    prop: Option<Box<dyn DelayTrace>>,
+    merger: SegmentMerger<Bandwidth, Delay>,
+}
+
+/// The configuration struct for [`SerializationPlusPropDelay`].
+///
+/// See [`SerializationPlusPropDelay`] for more details.
+#[cfg(feature = "bw-model")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct SerializationPlusPropDelayConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bw: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub prop: Option<Box<dyn DelayTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub packet_bytes: Option<u64>,
+}
+
 impl DelayTrace for StaticDelay {
     fn next_delay(&mut self) -> Option<(Delay, Duration)> {
         if let Some(duration) = self.duration.take() {
@@ -213,30 +574,126 @@ impl DelayTrace for StaticDelay {
     }
 }
 
-impl DelayTrace for RepeatedDelayPattern {
+impl DelayTrace for Rewind<StaticDelay> {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        self.model.next_delay()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + Send> DelayTrace for NormalizedDelay<R> {
     fn next_delay(&mut self) -> Option<(Delay, Duration)> {
-        if self.pattern.is_empty() || (self.count != 0 && self.current_cycle >= self.count) {
+        if self.duration.is_zero() {
             None
         } else {
+            // negative samples saturate to zero
+            let mut delay = Delay::from_nanos(self.normal.sample(&mut self.rng) as u64);
+            if let Some(lower_bound) = self.lower_bound {
+                delay = delay.max(lower_bound);
+            }
+            if let Some(upper_bound) = self.upper_bound {
+                delay = delay.min(upper_bound);
+            }
+            let duration = self.step.min(self.duration);
+            self.duration -= duration;
+            Some((delay, duration))
+        }
+    }
+}
+
+impl DelayTrace for RepeatedDelayPattern {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        // iterate instead of recursing, so that a long run of sub-models producing nothing
+        // cannot overflow the stack
+        while self.count == 0 || self.current_cycle < self.count {
             if self.current_model.is_none() {
                 self.current_model = Some(self.pattern[self.current_pattern].clone().into_model());
             }
-            match self.current_model.as_mut().unwrap().next_delay() {
-                Some(bw) => Some(bw),
-                None => {
-                    self.current_model = None;
-                    self.current_pattern += 1;
-                    if self.current_pattern >= self.pattern.len() {
-                        self.current_pattern = 0;
-                        self.current_cycle += 1;
-                        if self.count != 0 && self.current_cycle >= self.count {
-                            return None;
-                        }
-                    }
-                    self.next_delay()
-                }
+            if let Some(delay) = self.current_model.as_mut().unwrap().next_delay() {
+                self.guard.produced();
+                return Some(delay);
+            }
+            self.current_model = None;
+            if !self.guard.ended(self.current_pattern) {
+                return None;
+            }
+            self.current_pattern += 1;
+            if self.current_pattern >= self.pattern.len() {
+                self.current_pattern = 0;
+                self.current_cycle += 1;
             }
         }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl DelayTrace for TraceDelay {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        while let Some((duration, delay)) = self.pattern.get(self.index) {
+            self.index += 1;
+            if !duration.is_zero() {
+                return Some((*delay, *duration));
+            }
+        }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl DelayTrace for DelayedStartDelay {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        if !self.started {
+            self.started = true;
+            if !self.offset.is_zero() {
+                return Some((self.delay, self.offset));
+            }
+        }
+        self.inner.as_mut()?.next_delay()
+    }
+}
+
+#[cfg(feature = "delay-per-packet-model")]
+impl DelayTrace for PerPacketAsDelay {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        let delay = self.inner.as_mut()?.next_delay()?;
+        Some((delay, self.packet_interval))
+    }
+}
+
+#[cfg(feature = "bw-model")]
+impl DelayTrace for SerializationPlusPropDelay {
+    fn next_delay(&mut self) -> Option<(Delay, Duration)> {
+        // The amount of data is measured in bit-nanoseconds to keep the computation exact.
+        let packet_bit_nanos = (self.packet_bytes as u128)
+            .saturating_mul(8)
+            .saturating_mul(1_000_000_000);
+        let (Some(bw), Some(prop)) = (self.bw.as_mut(), self.prop.as_mut()) else {
+            return None;
+        };
+        let (bandwidth, prop_delay, duration) =
+            self.merger.next(|| bw.next_bw(), || prop.next_delay())?;
+        let bps = bps_u128(*bandwidth);
+        let serialization = if packet_bit_nanos == 0 {
+            Delay::ZERO
+        } else if bps == 0 {
+            Delay::MAX
+        } else {
+            Delay::from_nanos(u64::try_from(packet_bit_nanos.div_ceil(bps)).unwrap_or(u64::MAX))
+        };
+        Some((prop_delay.saturating_add(serialization), duration))
     }
 }
 
@@ -266,6 +723,91 @@ impl StaticDelayConfig {
     }
 }
 
+impl NormalizedDelayConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            std_dev: None,
+            upper_bound: None,
+            lower_bound: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: Delay) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Delay) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Delay) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Delay) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, with the RNG seeded by `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    pub fn build(self) -> NormalizedDelay {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> NormalizedDelay<R> {
+        let mean = self.mean.unwrap_or_else(|| Delay::from_millis(10));
+        let std_dev = self.std_dev.unwrap_or(Delay::ZERO);
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            panic!("NormalizedDelay: step must be positive");
+        }
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        let normal: Normal<f64> =
+            Normal::new(mean.as_nanos() as f64, std_dev.as_nanos() as f64).unwrap();
+        NormalizedDelay {
+            mean,
+            std_dev,
+            upper_bound: self.upper_bound,
+            lower_bound: self.lower_bound,
+            duration,
+            step,
+            seed,
+            rng,
+            normal,
+        }
+    }
+}
+
 impl RepeatedDelayPatternConfig {
     pub fn new() -> Self {
         Self {
@@ -284,8 +826,15 @@ impl RepeatedDelayPatternConfig {
         self
     }
 
+    /// Chain the delay traces in `pattern` to play each of them once in sequence, i.e. a pattern
+    /// with `count` 1, which ends after the last trace ends.
+    pub fn once(pattern: Vec<Box<dyn DelayTraceConfig>>) -> Self {
+        Self::new().pattern(pattern).count(1)
+    }
+
     pub fn build(self) -> RepeatedDelayPattern {
         RepeatedDelayPattern {
+            guard: EmptyPatternGuard::new(self.pattern.len()),
             pattern: self.pattern,
             count: self.count,
             current_model: None,
@@ -295,19 +844,296 @@ impl RepeatedDelayPatternConfig {
     }
 }
 
+impl TraceDelayConfig {
+    pub fn new() -> Self {
+        Self { pattern: vec![] }
+    }
+
+    pub fn pattern(mut self, pattern: Vec<(Duration, Delay)>) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn build(self) -> TraceDelay {
+        TraceDelay {
+            pattern: self.pattern,
+            index: 0,
+        }
+    }
+}
+
+impl DelayedStartDelayConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            offset: None,
+            delay: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn DelayTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn delay(mut self, delay: Delay) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn build(self) -> DelayedStartDelay {
+        DelayedStartDelay {
+            offset: self.offset.unwrap_or(Duration::ZERO),
+            delay: self.delay.unwrap_or(Delay::ZERO),
+            inner: self.inner.map(|inner| inner.into_model()),
+            started: false,
+        }
+    }
+}
+
+#[cfg(feature = "delay-per-packet-model")]
+impl PerPacketAsDelayConfig {
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            packet_interval: None,
+        }
+    }
+
+    pub fn inner(mut self, inner: Box<dyn DelayPerPacketTraceConfig>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    pub fn packet_interval(mut self, packet_interval: Duration) -> Self {
+        self.packet_interval = Some(packet_interval);
+        self
+    }
+
+    pub fn build(self) -> PerPacketAsDelay {
+        let packet_interval = self
+            .packet_interval
+            .unwrap_or_else(|| Duration::from_millis(1));
+        if packet_interval.is_zero() {
+            panic!("PerPacketAsDelay: packet_interval must be positive");
+        }
+        PerPacketAsDelay {
+            packet_interval,
+            inner: self.inner.map(|inner| inner.into_model()),
+        }
+    }
+}
+
+#[cfg(feature = "bw-model")]
+impl SerializationPlusPropDelayConfig {
+    pub fn new() -> Self {
+        Self {
+            bw: None,
+            prop: None,
+            packet_bytes: None,
+        }
+    }
+
+    pub fn bw(mut self, bw: Box<dyn BwTraceConfig>) -> Self {
+        self.bw = Some(bw);
+        self
+    }
+
+    pub fn prop(mut self, prop: Box<dyn DelayTraceConfig>) -> Self {
+        self.prop = Some(prop);
+        self
+    }
+
+    pub fn packet_bytes(mut self, packet_bytes: u64) -> Self {
+        self.packet_bytes = Some(packet_bytes);
+        self
+    }
+
+    /// Build the model, where `packet_bytes` defaults to 1500.
+    pub fn build(self) -> SerializationPlusPropDelay {
+        SerializationPlusPropDelay {
+            packet_bytes: self.packet_bytes.unwrap_or(1500),
+            bw: self.bw.map(|bw| bw.into_model()),
+            prop: self.prop.map(|prop| prop.into_model()),
+            merger: SegmentMerger::new(),
+        }
+    }
+}
+
+impl Resettable for RepeatedDelayPattern {
+    fn reset(&mut self) {
+        self.current_model = None;
+        self.current_cycle = 0;
+        self.current_pattern = 0;
+        self.guard.reset();
+    }
+}
+
+impl Resettable for TraceDelay {
+    fn reset(&mut self) {
+        self.index = 0;
+    }
+}
+
+/// Implement [`DelayTraceConfig`] for the built-in delay trace model configs, and register their tags.
+///
+/// A config can be preceded by `#[cfg(...)]` attributes to implement and register it conditionally.
+/// A config whose model is only resettable through [`Rewind`] is followed by `(rewind)`.
 macro_rules! impl_delay_trace_config {
+    (@model $model:expr) => {
+        Box::new($model)
+    };
+    (@model $model:expr, rewind) => {
+        Box::new(Rewind::new($model))
+    };
+    ($($(#[$attr:meta])* $name:ident $(($flag:ident))?),+ $(,)?) => {
+        $(
+            $(#[$attr])*
+            #[cfg_attr(feature = "serde", typetag::serde)]
+            impl DelayTraceConfig for $name {
+                fn into_model(self: Box<$name>) -> Box<dyn DelayTrace> {
+                    impl_delay_trace_config!(@model self.build() $(, $flag)?)
+                }
+            }
+        )+
+
+        static REGISTERED_DELAY_CONFIG_TAGS: &[&str] = &[$($(#[$attr])* stringify!($name)),+];
+    };
+}
+
+impl_delay_trace_config!(
+    StaticDelayConfig(rewind),
+    RepeatedDelayPatternConfig,
+    NormalizedDelayConfig,
+    TraceDelayConfig,
+    DelayedStartDelayConfig,
+    #[cfg(feature = "delay-per-packet-model")]
+    PerPacketAsDelayConfig,
+    #[cfg(feature = "bw-model")]
+    SerializationPlusPropDelayConfig,
+);
+
+/// Returns the tags of the built-in delay trace model configs, i.e. the names used to identify them
+/// in configuration files (e.g. `"StaticDelayConfig"`).
+///
+/// Configs implementing [`DelayTraceConfig`] outside of this crate are not included.
+pub fn registered_delay_config_tags() -> &'static [&'static str] {
+    REGISTERED_DELAY_CONFIG_TAGS
+}
+
+/// Implement the [`Repeatable`] trait for the delay trace model config (any struct implements [`DelayTraceConfig`]).
+///
+/// The config will be repeated with [`RepeatedDelayPatternConfig`].
+macro_rules! impl_repeatable {
     ($name:ident) => {
-        #[cfg_attr(feature = "serde", typetag::serde)]
-        impl DelayTraceConfig for $name {
-            fn into_model(self: Box<$name>) -> Box<dyn DelayTrace> {
-                Box::new(self.build())
+        impl Repeatable for $name {
+            type Repeated = RepeatedDelayPatternConfig;
+
+            fn repeat(self, count: usize) -> RepeatedDelayPatternConfig {
+                RepeatedDelayPatternConfig::new()
+                    .pattern(vec![Box::new(self)])
+                    .count(count)
             }
         }
     };
 }
 
-impl_delay_trace_config!(StaticDelayConfig);
-impl_delay_trace_config!(RepeatedDelayPatternConfig);
+impl_repeatable!(StaticDelayConfig);
+impl_repeatable!(NormalizedDelayConfig);
+impl_repeatable!(TraceDelayConfig);
+impl_repeatable!(DelayedStartDelayConfig);
+#[cfg(feature = "delay-per-packet-model")]
+impl_repeatable!(PerPacketAsDelayConfig);
+#[cfg(feature = "bw-model")]
+impl_repeatable!(SerializationPlusPropDelayConfig);
+
+impl Repeatable for RepeatedDelayPatternConfig {
+    type Repeated = RepeatedDelayPatternConfig;
+
+    /// Repeat the whole pattern for `count` times, by multiplying the `count` of the pattern.
+    fn repeat(self, count: usize) -> RepeatedDelayPatternConfig {
+        let count = self.count.saturating_mul(count);
+        self.count(count)
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "human"))]
+mod tracedelay_serde {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    /// Deserializes the pattern with duration and delay in human-readable format.
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Duration, Delay)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let parse = |s: &str| {
+            crate::model::human_duration::parse_duration(s)
+                .map_err(|e| de::Error::custom(format!("Failed to parse duration '{}': {}", s, e)))
+        };
+        Vec::<(String, String)>::deserialize(d)?
+            .into_iter()
+            .map(|(duration, delay)| Ok((parse(&duration)?, parse(&delay)?)))
+            .collect()
+    }
+
+    /// Serializes the pattern with duration and delay in human-readable format.
+    pub fn serialize<S>(pattern: &[(Duration, Delay)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(pattern.iter().map(|(duration, delay)| {
+            (
+                humantime_serde::re::humantime::format_duration(*duration).to_string(),
+                humantime_serde::re::humantime::format_duration(*delay).to_string(),
+            )
+        }))
+    }
+}
+
+/// Compose a bandwidth trace and a propagation delay trace into a total delay trace.
+///
+/// This is a shorthand for a [`SerializationPlusPropDelayConfig`], see [`SerializationPlusPropDelay`]
+/// for the details. The traces are merged lazily while the result is played, so either of them may
+/// go on forever.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{serialization_plus_prop_delay, StaticBwConfig, StaticDelayConfig};
+/// # use netem_trace::{Bandwidth, Delay, Duration};
+/// // 12Mbps takes 1ms to send a 1500-byte packet
+/// let mut model = serialization_plus_prop_delay(
+///     Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))),
+///     Box::new(
+///         StaticDelayConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .duration(Duration::from_millis(500)),
+///     ),
+///     1500,
+/// )
+/// .into_model();
+/// assert_eq!(model.next_delay(), Some((Delay::from_millis(11), Duration::from_millis(500))));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+#[cfg(feature = "bw-model")]
+pub fn serialization_plus_prop_delay(
+    bw: Box<dyn BwTraceConfig>,
+    prop: Box<dyn DelayTraceConfig>,
+    packet_bytes: u64,
+) -> Box<dyn DelayTraceConfig> {
+    Box::new(
+        SerializationPlusPropDelayConfig::new()
+            .bw(bw)
+            .prop(prop)
+            .packet_bytes(packet_bytes),
+    )
+}
 
 #[cfg(test)]
 mod test {
@@ -328,6 +1154,480 @@ mod test {
         assert_eq!(static_delay.next_delay(), None);
     }
 
+    #[test]
+    fn test_delay_trace_iter() {
+        let config = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_secs(1));
+        assert_eq!(config.clone().build().into_iter().count(), 1);
+        let delays: Vec<_> = Box::new(config.repeat(0))
+            .into_model()
+            .into_iter()
+            .take(3)
+            .collect();
+        assert_eq!(
+            delays,
+            [(Delay::from_millis(10), Duration::from_secs(1)); 3]
+        );
+    }
+
+    #[test]
+    fn test_peekable_delay() {
+        let segment = (Delay::from_millis(10), Duration::from_secs(1));
+        let mut model = StaticDelayConfig::new()
+            .delay(segment.0)
+            .duration(segment.1)
+            .build()
+            .peekable();
+        assert_eq!(model.peek(), Some(&segment));
+        assert_eq!(model.peek(), Some(&segment));
+        assert_eq!(model.next_delay(), Some(segment));
+        assert_eq!(model.peek(), None);
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_many_empty_sub_models() {
+        // a long run of sub-models producing nothing must not overflow the stack
+        let mut pattern: Vec<Box<dyn DelayTraceConfig>> = (0..100_000)
+            .map(|_| {
+                Box::new(StaticDelayConfig::new().duration(Duration::ZERO))
+                    as Box<dyn DelayTraceConfig>
+            })
+            .collect();
+        pattern.push(Box::new(StaticDelayConfig::new()));
+        let mut model = RepeatedDelayPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            [(Delay::from_millis(10), Duration::from_secs(1)); 2]
+        );
+
+        // a pattern repeated forever ends if none of its sub-models produces anything
+        let mut model = StaticDelayConfig::new()
+            .duration(Duration::ZERO)
+            .forever()
+            .build();
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_trace_delay() {
+        let config = TraceDelayConfig::new().pattern(vec![
+            (Duration::from_secs(1), Delay::from_millis(10)),
+            (Duration::ZERO, Delay::from_millis(100)),
+            (Duration::from_millis(500), Delay::from_micros(20500)),
+            (Duration::from_millis(250), Delay::ZERO),
+        ]);
+        let mut model = config.clone().build();
+        let segments: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(
+            segments,
+            [
+                (Delay::from_millis(10), Duration::from_secs(1)),
+                (Delay::from_micros(20500), Duration::from_millis(500)),
+                (Delay::ZERO, Duration::from_millis(250)),
+            ]
+        );
+        assert_eq!(model.next_delay(), None);
+        model.reset();
+        assert_eq!(model.next_delay(), Some(segments[0]));
+        assert_eq!(
+            Box::new(config.repeat(2)).into_model().into_iter().count(),
+            6
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_trace_delay_serde() {
+        let config: Box<dyn DelayTraceConfig> = Box::new(TraceDelayConfig::new().pattern(vec![
+            (Duration::from_secs(1), Delay::from_millis(10)),
+            (Duration::from_millis(500), Delay::from_micros(20500)),
+        ]));
+        let ser_str = serde_json::to_string(&config).unwrap();
+        #[cfg(feature = "human")]
+        let des_str =
+            "{\"TraceDelayConfig\":{\"pattern\":[[\"1s\",\"10ms\"],[\"500ms\",\"20ms 500us\"]]}}";
+        #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
+        let des_str = "{\"TraceDelayConfig\":{\"pattern\":[[{\"secs\":1,\"nanos\":0},{\"secs\":0,\"nanos\":10000000}],[{\"secs\":0,\"nanos\":500000000},{\"secs\":0,\"nanos\":20500000}]]}}";
+        #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+        let des_str =
+            "{\"TraceDelayConfig\":{\"pattern\":[[1000000000,10000000],[500000000,20500000]]}}";
+        assert_eq!(ser_str, des_str);
+        let des: Box<dyn DelayTraceConfig> = serde_json::from_str(des_str).unwrap();
+        let mut model = des.into_model();
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_secs(1)))
+        );
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_micros(20500), Duration::from_millis(500)))
+        );
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let config = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_secs(1));
+        let pattern: Vec<Box<dyn DelayTraceConfig>> = vec![
+            Box::new(config.clone()),
+            Box::new(config.delay(Delay::from_millis(20))),
+        ];
+        let mut model = RepeatedDelayPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        let original: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(original.len(), 4);
+        model.reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(replayed, original);
+
+        // reset in the middle of the trace, through the trait object
+        let mut model: Box<dyn DelayTrace> = Box::new(model);
+        model.as_resettable().unwrap().reset();
+        model.next_delay();
+        model.as_resettable().unwrap().reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(replayed, original);
+
+        let mut model = Box::new(StaticDelayConfig::new()).into_model();
+        assert!(model.next_delay().is_some());
+        assert_eq!(model.next_delay(), None);
+        model.as_resettable().unwrap().reset();
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_secs(1)))
+        );
+    }
+
+    #[test]
+    fn test_normalized_delay() {
+        let config = NormalizedDelayConfig::new()
+            .mean(Delay::from_millis(10))
+            .std_dev(Delay::from_millis(5))
+            .upper_bound(Delay::from_millis(15))
+            .lower_bound(Delay::from_millis(8))
+            .duration(Duration::from_millis(2500))
+            .step(Duration::from_millis(100))
+            .seed(42);
+        let mut model = config.clone().build();
+        let delays: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(delays.len(), 25);
+        assert!(delays.iter().all(|(delay, duration)| {
+            *delay >= Delay::from_millis(8)
+                && *delay <= Delay::from_millis(15)
+                && *duration == Duration::from_millis(100)
+        }));
+        assert!(delays.iter().any(|(delay, _)| *delay != delays[0].0));
+
+        // reproducible with the same seed, or the same RNG
+        let mut model = config.clone().build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            delays
+        );
+        let mut model = config.clone().build_with_rng(StdRng::seed_from_u64(42));
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            delays
+        );
+        let mut model = config.clone().seed(43).build();
+        assert_ne!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            delays
+        );
+
+        // the last segment is truncated to the remaining duration
+        let mut model = config.clone().duration(Duration::from_millis(250)).build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay())
+                .map(|(_, duration)| duration)
+                .collect::<Vec<_>>(),
+            [
+                Duration::from_millis(100),
+                Duration::from_millis(100),
+                Duration::from_millis(50)
+            ]
+        );
+
+        // negative samples saturate to zero
+        let mut model = NormalizedDelayConfig::new()
+            .mean(Delay::from_millis(1))
+            .std_dev(Delay::from_millis(100))
+            .build();
+        assert!(std::iter::from_fn(|| model.next_delay()).any(|(delay, _)| delay.is_zero()));
+
+        // forever
+        let mut model = config.forever().build();
+        assert_eq!(model.next_delay(), Some(delays[0]));
+        for _ in 1..25 {
+            model.next_delay();
+        }
+        assert_eq!(model.next_delay(), Some(delays[0]));
+    }
+
+    #[test]
+    fn test_delayed_start_delay() {
+        let inner = TraceDelayConfig::new().pattern(vec![
+            (Duration::from_secs(1), Delay::from_millis(10)),
+            (Duration::from_millis(500), Delay::from_millis(20)),
+        ]);
+        let mut model = DelayedStartDelayConfig::new()
+            .inner(Box::new(inner.clone()))
+            .offset(Duration::from_millis(250))
+            .build();
+        let mut expected = inner.build();
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::ZERO, Duration::from_millis(250)))
+        );
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            std::iter::from_fn(|| expected.next_delay()).collect::<Vec<_>>()
+        );
+
+        let mut model = DelayedStartDelayConfig::new()
+            .offset(Duration::from_millis(250))
+            .delay(Delay::from_millis(5))
+            .build();
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_millis(5), Duration::from_millis(250)))
+        );
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_registered_delay_config_tags() {
+        assert_eq!(
+            registered_delay_config_tags(),
+            [
+                "StaticDelayConfig",
+                "RepeatedDelayPatternConfig",
+                "NormalizedDelayConfig",
+                "TraceDelayConfig",
+                "DelayedStartDelayConfig",
+                #[cfg(feature = "delay-per-packet-model")]
+                "PerPacketAsDelayConfig",
+                #[cfg(feature = "bw-model")]
+                "SerializationPlusPropDelayConfig",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "delay-per-packet-model")]
+    fn test_per_packet_as_delay() {
+        use crate::model::{
+            DelayPerPacketTraceConfig, RepeatedDelayPerPacketPatternConfig,
+            StaticDelayPerPacketConfig,
+        };
+
+        let pattern = vec![
+            Box::new(
+                StaticDelayPerPacketConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .count(2),
+            ) as Box<dyn DelayPerPacketTraceConfig>,
+            Box::new(
+                StaticDelayPerPacketConfig::new()
+                    .delay(Delay::from_millis(20))
+                    .count(1),
+            ),
+        ];
+        let config = PerPacketAsDelayConfig::new()
+            .inner(Box::new(
+                RepeatedDelayPerPacketPatternConfig::new()
+                    .pattern(pattern)
+                    .count(1),
+            ))
+            .packet_interval(Duration::from_millis(5));
+        let mut model = config.clone().build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            [
+                (Delay::from_millis(10), Duration::from_millis(5)),
+                (Delay::from_millis(10), Duration::from_millis(5)),
+                (Delay::from_millis(20), Duration::from_millis(5)),
+            ]
+        );
+
+        // an infinite inner model samples one packet per interval forever
+        let mut model = PerPacketAsDelayConfig::new()
+            .inner(Box::new(
+                StaticDelayPerPacketConfig::new().delay(Delay::from_millis(10)),
+            ))
+            .build();
+        for _ in 0..1000 {
+            assert_eq!(
+                model.next_delay(),
+                Some((Delay::from_millis(10), Duration::from_millis(1)))
+            );
+        }
+
+        // no inner model produces nothing
+        assert_eq!(PerPacketAsDelayConfig::new().build().next_delay(), None);
+
+        #[cfg(feature = "serde")]
+        {
+            let ser = Box::new(config) as Box<dyn DelayTraceConfig>;
+            let des: Box<dyn DelayTraceConfig> =
+                serde_json::from_str(&serde_json::to_string(&ser).unwrap()).unwrap();
+            let mut model = des.into_model();
+            assert_eq!(
+                model.next_delay(),
+                Some((Delay::from_millis(10), Duration::from_millis(5)))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "delay-per-packet-model")]
+    #[should_panic(expected = "PerPacketAsDelay: packet_interval must be positive")]
+    fn test_per_packet_as_delay_zero_interval() {
+        PerPacketAsDelayConfig::new()
+            .packet_interval(Duration::ZERO)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "NormalizedDelay: step must be positive")]
+    fn test_normalized_delay_zero_step() {
+        NormalizedDelayConfig::new()
+            .mean(Delay::from_millis(10))
+            .step(Duration::ZERO)
+            .build();
+    }
+
+    #[test]
+    fn test_repeat() {
+        let config = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_secs(1));
+        let mut model = Box::new(config.clone().repeat(2)).into_model();
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_secs(1)))
+        );
+        assert_eq!(
+            model.next_delay(),
+            Some((Delay::from_millis(10), Duration::from_secs(1)))
+        );
+        assert_eq!(model.next_delay(), None);
+        let mut model = Box::new(config.forever()).into_model();
+        for _ in 0..5 {
+            assert!(model.next_delay().is_some());
+        }
+    }
+
+    #[test]
+    fn test_once() {
+        let delays = [10, 20, 30].map(|ms| (Delay::from_millis(ms), Duration::from_secs(1)));
+        let mut model = RepeatedDelayPatternConfig::once(
+            delays
+                .iter()
+                .map(|(delay, duration)| {
+                    Box::new(StaticDelayConfig::new().delay(*delay).duration(*duration))
+                        as Box<dyn DelayTraceConfig>
+                })
+                .collect(),
+        )
+        .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            delays
+        );
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "bw-model")]
+    fn test_serialization_plus_prop_delay() {
+        use crate::model::{BwTraceConfig, StaticBwConfig, TraceBwConfig};
+        use crate::Bandwidth;
+
+        let prop = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_secs(1));
+        let collect = |bw: Box<dyn BwTraceConfig>, prop: Box<dyn DelayTraceConfig>, bytes| {
+            let mut model = serialization_plus_prop_delay(bw, prop, bytes).into_model();
+            std::iter::from_fn(move || model.next_delay()).collect::<Vec<_>>()
+        };
+
+        // 24Mbps takes 0.5ms to send a 1500-byte packet, and the bandwidth trace ends first
+        assert_eq!(
+            collect(
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(24))
+                        .duration(Duration::from_millis(300)),
+                ),
+                Box::new(prop.clone()),
+                1500
+            ),
+            vec![(Delay::from_micros(10_500), Duration::from_millis(300))]
+        );
+
+        // segments are split at the boundaries of both traces
+        let bw = TraceBwConfig::new().pattern(vec![
+            (
+                Duration::from_millis(400),
+                vec![Bandwidth::from_mbps(12), Bandwidth::from_mbps(12)],
+            ),
+            (
+                Duration::from_millis(400),
+                vec![Bandwidth::from_kbps(1500), Bandwidth::ZERO],
+            ),
+        ]);
+        let prop = RepeatedDelayPatternConfig::new()
+            .pattern(vec![
+                Box::new(prop.duration(Duration::from_millis(1000))) as Box<dyn DelayTraceConfig>,
+                Box::new(
+                    StaticDelayConfig::new()
+                        .delay(Delay::from_millis(20))
+                        .duration(Duration::from_millis(1000)),
+                ) as Box<dyn DelayTraceConfig>,
+            ])
+            .count(1);
+        assert_eq!(
+            collect(Box::new(bw), Box::new(prop), 1500),
+            vec![
+                (Delay::from_millis(11), Duration::from_millis(400)),
+                (Delay::from_millis(11), Duration::from_millis(400)),
+                (Delay::from_millis(18), Duration::from_millis(200)),
+                (Delay::from_millis(28), Duration::from_millis(200)),
+                (Delay::MAX, Duration::from_millis(400)),
+            ]
+        );
+
+        // both traces go on forever, the result is still played segment by segment
+        let mut model = serialization_plus_prop_delay(
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_millis(300))
+                    .forever(),
+            ),
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .forever(),
+            ),
+            1500,
+        )
+        .into_model();
+        for _ in 0..1000 {
+            assert_eq!(model.next_delay().unwrap().0, Delay::from_millis(11));
+        }
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn test_serde() {
@@ -348,8 +1648,10 @@ mod test {
         let ser_str = serde_json::to_string(&ser).unwrap();
         #[cfg(feature = "human")]
         let des_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":\"10ms\",\"duration\":\"1s\"}},{\"StaticDelayConfig\":{\"delay\":\"20ms\",\"duration\":\"1s\"}}],\"count\":2}}";
-        #[cfg(not(feature = "human"))]
+        #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
         let des_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":10000000},\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDelayConfig\":{\"delay\":{\"secs\":0,\"nanos\":20000000},\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+        #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+        let des_str = "{\"RepeatedDelayPatternConfig\":{\"pattern\":[{\"StaticDelayConfig\":{\"delay\":10000000,\"duration\":1000000000}},{\"StaticDelayConfig\":{\"delay\":20000000,\"duration\":1000000000}}],\"count\":2}}";
         assert_eq!(ser_str, des_str);
         let des: Box<dyn DelayTraceConfig> = serde_json::from_str(des_str).unwrap();
         let mut model = des.into_model();
@@ -0,0 +1,1336 @@
+//! This module contains some predefined per-packet delay trace models.
+//!
+//! Enabled with feature `delay-per-packet-model` or `model`.
+//!
+//! ## Predefined models
+//!
+//! - [`StaticDelayPerPacket`]: A trace model with static per-packet delay.
+//! - [`RepeatedDelayPerPacketPattern`]: A trace model with a repeated per-packet delay pattern.
+//! - [`NormalizedDelayPerPacket`]: A trace model whose per-packet delay subjects to a normal distribution.
+//! - [`LogNormalDelayPerPacket`]: A trace model whose per-packet delay subjects to a log-normal distribution.
+//! - [`TimeSlicedAsPerPacket`]: A model sampling a time-sliced delay model at the times packets are sent over a bandwidth model (with features `bw-model` and `delay-model` enabled).
+//!
+//! ## Examples
+//!
+//! An example to build model from configuration:
+//!
+//! ```
+//! # use netem_trace::model::StaticDelayPerPacketConfig;
+//! # use netem_trace::{Delay, DelayPerPacketTrace};
+//! let mut static_delay = StaticDelayPerPacketConfig::new()
+//!     .delay(Delay::from_millis(10))
+//!     .count(2)
+//!     .build();
+//! assert_eq!(static_delay.next_delay(), Some(Delay::from_millis(10)));
+//! assert_eq!(static_delay.next_delay(), Some(Delay::from_millis(10)));
+//! assert_eq!(static_delay.next_delay(), None);
+//! ```
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+use super::{BwTraceConfig, DelayTraceConfig, SegmentMerger};
+use super::{EmptyPatternGuard, ModelBuildError, Repeatable, DEFAULT_RNG_SEED};
+#[cfg(feature = "logging")]
+use crate::display::fmt_delay;
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+use crate::units::bps_u128;
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+use crate::{Bandwidth, BwTrace, DelayTrace};
+use crate::{Delay, DelayPerPacketTrace, Resettable};
+use dyn_clone::DynClone;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rand_distr::{Distribution, LogNormal, Normal};
+
+/// This trait is used to convert a per-packet delay trace configuration into a per-packet delay trace model.
+///
+/// Since trace model is often configured with files and often has inner states which
+/// is not suitable to be serialized/deserialized, this trait makes it possible to
+/// separate the configuration part into a simple struct for serialization/deserialization, and
+/// construct the model from the configuration.
+#[cfg_attr(feature = "serde", typetag::serde)]
+pub trait DelayPerPacketTraceConfig: DynClone + Send {
+    fn into_model(self: Box<Self>) -> Box<dyn DelayPerPacketTrace>;
+}
+
+dyn_clone::clone_trait_object!(DelayPerPacketTraceConfig);
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The model of a static per-packet delay trace.
+///
+/// The `delay` is applied to `count` packets. If `count` is 0, the delay is applied to
+/// all packets forever.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::StaticDelayPerPacketConfig;
+/// # use netem_trace::{Delay, DelayPerPacketTrace};
+/// let mut static_delay = StaticDelayPerPacketConfig::new()
+///     .delay(Delay::from_millis(10))
+///     .count(1)
+///     .build();
+/// assert_eq!(static_delay.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(static_delay.next_delay(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticDelayPerPacket {
+    pub delay: Delay,
+    pub count: usize,
+    current_count: usize,
+}
+
+/// The configuration struct for [`StaticDelayPerPacket`].
+///
+/// See [`StaticDelayPerPacket`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct StaticDelayPerPacketConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub delay: Option<Delay>,
+    pub count: usize,
+}
+
+/// The model contains an array of per-packet delay trace models.
+///
+/// Combine multiple per-packet delay trace models into one delay pattern,
+/// and repeat the pattern for `count` times.
+///
+/// If `count` is 0, the pattern will be repeated forever. To play each model once in sequence,
+/// use [`RepeatedDelayPerPacketPatternConfig::once`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{DelayPerPacketTraceConfig, RepeatedDelayPerPacketPatternConfig, StaticDelayPerPacketConfig};
+/// # use netem_trace::{Delay, DelayPerPacketTrace};
+/// let pat = vec![
+///     Box::new(
+///         StaticDelayPerPacketConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .count(1),
+///     ) as Box<dyn DelayPerPacketTraceConfig>,
+///     Box::new(
+///         StaticDelayPerPacketConfig::new()
+///             .delay(Delay::from_millis(20))
+///             .count(1),
+///     ) as Box<dyn DelayPerPacketTraceConfig>,
+/// ];
+/// let mut model = RepeatedDelayPerPacketPatternConfig::new()
+///     .pattern(pat)
+///     .count(2)
+///     .build();
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+pub struct RepeatedDelayPerPacketPattern {
+    pub pattern: Vec<Box<dyn DelayPerPacketTraceConfig>>,
+    pub count: usize,
+    current_model: Option<Box<dyn DelayPerPacketTrace>>,
+    current_cycle: usize,
+    current_pattern: usize,
+    guard: EmptyPatternGuard,
+}
+
+/// The configuration struct for [`RepeatedDelayPerPacketPattern`].
+///
+/// See [`RepeatedDelayPerPacketPattern`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct RepeatedDelayPerPacketPatternConfig {
+    pub pattern: Vec<Box<dyn DelayPerPacketTraceConfig>>,
+    pub count: usize,
+}
+
+/// The model of a per-packet delay trace subjects to a normal distribution.
+///
+/// The delay of each packet is sampled independently from a normal distribution with mean `mean`
+/// and standard deviation `std_dev`, and negative samples saturate to zero. `count` packets
+/// are generated, and if `count` is 0, the model goes on forever.
+///
+/// If `max_step` is set, each delay is clamped to within `max_step` of the previous delay, which
+/// limits the jitter between consecutive packets. The first delay is not limited. The bounds
+/// `lower_bound` and `upper_bound` are applied after `max_step`, so the delay never leaves the
+/// bounds, and as the previous delay is already within the bounds, `max_step` still holds.
+///
+/// The RNG is seeded with `seed` (42 by default), so that the same config always produces the same
+/// trace, and [`Resettable::reset`] re-seeds it to replay the trace.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::NormalizedDelayPerPacketConfig;
+/// # use netem_trace::{Delay, DelayPerPacketTrace};
+/// let mut normal_delay = NormalizedDelayPerPacketConfig::new()
+///     .mean(Delay::from_millis(12))
+///     .std_dev(Delay::from_millis(1))
+///     .max_step(Delay::from_micros(100))
+///     .count(3)
+///     .seed(42)
+///     .build();
+/// let mut previous = normal_delay.next_delay().unwrap();
+/// while let Some(delay) = normal_delay.next_delay() {
+///     assert!(delay.abs_diff(previous) <= Delay::from_micros(100));
+///     previous = delay;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalizedDelayPerPacket<R = StdRng> {
+    pub mean: Delay,
+    pub std_dev: Delay,
+    pub upper_bound: Option<Delay>,
+    pub lower_bound: Option<Delay>,
+    pub max_step: Option<Delay>,
+    pub count: usize,
+    pub seed: u64,
+    current_count: usize,
+    previous: Option<Delay>,
+    rng: R,
+    distribution: Normal<f64>,
+}
+
+/// The configuration struct for [`NormalizedDelayPerPacket`].
+///
+/// See [`NormalizedDelayPerPacket`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct NormalizedDelayPerPacketConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub mean: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub std_dev: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub upper_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub lower_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub max_step: Option<Delay>,
+    pub count: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model of a per-packet delay trace subjects to a log-normal distribution.
+///
+/// The delay of each packet is sampled independently from a log-normal distribution, whose own
+/// mean and standard deviation (not the ones of the underlying normal distribution) are `mean`
+/// and `std_dev`. The distribution is skewed towards large delays, and never goes negative.
+/// `count` packets are generated, and if `count` is 0, the model goes on forever.
+///
+/// `max_step`, `lower_bound`, `upper_bound` and `seed` work the same as the ones of
+/// [`NormalizedDelayPerPacket`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::LogNormalDelayPerPacketConfig;
+/// # use netem_trace::{Delay, DelayPerPacketTrace};
+/// let mut log_normal_delay = LogNormalDelayPerPacketConfig::new()
+///     .mean(Delay::from_millis(12))
+///     .std_dev(Delay::from_millis(4))
+///     .upper_bound(Delay::from_millis(20))
+///     .count(2)
+///     .build();
+/// assert!(log_normal_delay.next_delay().unwrap() <= Delay::from_millis(20));
+/// assert!(log_normal_delay.next_delay().unwrap() <= Delay::from_millis(20));
+/// assert_eq!(log_normal_delay.next_delay(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogNormalDelayPerPacket<R = StdRng> {
+    pub mean: Delay,
+    pub std_dev: Delay,
+    pub upper_bound: Option<Delay>,
+    pub lower_bound: Option<Delay>,
+    pub max_step: Option<Delay>,
+    pub count: usize,
+    pub seed: u64,
+    current_count: usize,
+    previous: Option<Delay>,
+    rng: R,
+    distribution: LogNormal<f64>,
+}
+
+/// The configuration struct for [`LogNormalDelayPerPacket`].
+///
+/// See [`LogNormalDelayPerPacket`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct LogNormalDelayPerPacketConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub mean: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub std_dev: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub upper_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub lower_bound: Option<Delay>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub max_step: Option<Delay>,
+    pub count: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
+/// The model sampling a time-sliced delay model at the times packets are sent over a bandwidth model.
+///
+/// Packets of `packet_bytes` bytes are sent back to back over the `bw` model, and a packet is
+/// regarded as sent once its last bit is transmitted. The `delay` model is sampled at that time,
+/// where a delay segment covers the time range `[start, end)`.
+///
+/// Both models are played lazily while packets are generated, so either of them may go on
+/// forever. The model ends when either of them ends, and produces nothing without both of them.
+/// Time still passes during zero-bandwidth segments, in which no packet is sent.
+///
+/// Enabled with feature `bw-model` and `delay-model` in addition.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{StaticBwConfig, StaticDelayConfig, TimeSlicedAsPerPacketConfig};
+/// # use netem_trace::{Bandwidth, Delay, DelayPerPacketTrace, Duration};
+/// // 12Mbps sends a 1500-byte packet every millisecond, forever
+/// let mut model = TimeSlicedAsPerPacketConfig::new()
+///     .delay(Box::new(
+///         StaticDelayConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .duration(Duration::from_millis(3)),
+///     ))
+///     .bw(Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))))
+///     .packet_bytes(1500)
+///     .build();
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+pub struct TimeSlicedAsPerPacket {
+    pub packet_bytes: u64,
+    delay: Option<Box<dyn DelayTrace>>,
+    bw: Option<Box<dyn BwTrace>>,
+    merger: SegmentMerger<Bandwidth, Delay>,
+    /// The bandwidth (in bps) and delay of the current merged segment, and its remaining nanoseconds.
+    segment: Option<(u128, Delay, u128)>,
+    /// The data of the next packet already transmitted, in bit-nanoseconds.
+    transmitted: u128,
+}
+
+/// The configuration struct for [`TimeSlicedAsPerPacket`].
+///
+/// See [`TimeSlicedAsPerPacket`] for more details.
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone)]
+pub struct TimeSlicedAsPerPacketConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub delay: Option<Box<dyn DelayTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bw: Option<Box<dyn BwTraceConfig>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub packet_bytes: Option<u64>,
+}
+
+impl DelayPerPacketTrace for StaticDelayPerPacket {
+    fn next_delay(&mut self) -> Option<Delay> {
+        if self.count != 0 && self.current_count >= self.count {
+            None
+        } else {
+            self.current_count += 1;
+            Some(self.delay)
+        }
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl DelayPerPacketTrace for RepeatedDelayPerPacketPattern {
+    fn next_delay(&mut self) -> Option<Delay> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        // iterate instead of recursing, so that a long run of sub-models producing nothing
+        // cannot overflow the stack
+        while self.count == 0 || self.current_cycle < self.count {
+            if self.current_model.is_none() {
+                self.current_model = Some(self.pattern[self.current_pattern].clone().into_model());
+            }
+            if let Some(delay) = self.current_model.as_mut().unwrap().next_delay() {
+                self.guard.produced();
+                return Some(delay);
+            }
+            self.current_model = None;
+            if !self.guard.ended(self.current_pattern) {
+                return None;
+            }
+            self.current_pattern += 1;
+            if self.current_pattern >= self.pattern.len() {
+                self.current_pattern = 0;
+                self.current_cycle += 1;
+            }
+        }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+/// Clamp a sampled `delay` to within `max_step` of the `previous` delay (if any), then to the bounds.
+///
+/// Negative samples are expected to have saturated to zero already.
+fn bound_delay(
+    mut delay: Delay,
+    previous: Option<Delay>,
+    max_step: Option<Delay>,
+    lower_bound: Option<Delay>,
+    upper_bound: Option<Delay>,
+) -> Delay {
+    if let (Some(previous), Some(max_step)) = (previous, max_step) {
+        delay = delay.clamp(
+            previous.saturating_sub(max_step),
+            previous.saturating_add(max_step),
+        );
+    }
+    if let Some(lower_bound) = lower_bound {
+        delay = delay.max(lower_bound);
+    }
+    if let Some(upper_bound) = upper_bound {
+        delay = delay.min(upper_bound);
+    }
+    delay
+}
+
+impl<R: RngCore + SeedableRng + Send> DelayPerPacketTrace for NormalizedDelayPerPacket<R> {
+    fn next_delay(&mut self) -> Option<Delay> {
+        if self.count != 0 && self.current_count >= self.count {
+            return None;
+        }
+        self.current_count += 1;
+        let delay = Delay::from_nanos(self.distribution.sample(&mut self.rng) as u64);
+        let delay = bound_delay(
+            delay,
+            self.previous,
+            self.max_step,
+            self.lower_bound,
+            self.upper_bound,
+        );
+        self.previous = Some(delay);
+        Some(delay)
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> DelayPerPacketTrace for LogNormalDelayPerPacket<R> {
+    fn next_delay(&mut self) -> Option<Delay> {
+        if self.count != 0 && self.current_count >= self.count {
+            return None;
+        }
+        self.current_count += 1;
+        let delay = Delay::from_nanos(self.distribution.sample(&mut self.rng) as u64);
+        let delay = bound_delay(
+            delay,
+            self.previous,
+            self.max_step,
+            self.lower_bound,
+            self.upper_bound,
+        );
+        self.previous = Some(delay);
+        Some(delay)
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+impl DelayPerPacketTrace for TimeSlicedAsPerPacket {
+    fn next_delay(&mut self) -> Option<Delay> {
+        // The amount of data is measured in bit-nanoseconds to keep the computation exact.
+        let packet_bit_nanos = (self.packet_bytes as u128)
+            .saturating_mul(8)
+            .saturating_mul(1_000_000_000);
+        let (Some(delay), Some(bw)) = (self.delay.as_mut(), self.bw.as_mut()) else {
+            return None;
+        };
+        loop {
+            if let Some((bps, delay, remaining)) = self.segment.as_mut() {
+                // a packet finished at the end of the previous segment is sent at the start of
+                // this one, i.e. with its delay
+                if self.transmitted >= packet_bit_nanos {
+                    self.transmitted -= packet_bit_nanos;
+                    return Some(*delay);
+                }
+                if *bps > 0 {
+                    // nanoseconds until the last bit of the next packet is transmitted
+                    let needed = (packet_bit_nanos - self.transmitted).div_ceil(*bps);
+                    if needed < *remaining {
+                        *remaining -= needed;
+                        self.transmitted = (self.transmitted + needed * *bps) - packet_bit_nanos;
+                        return Some(*delay);
+                    }
+                }
+                // no packet is sent before the segment ends, time passes without sending at zero bandwidth
+                self.transmitted = self
+                    .transmitted
+                    .saturating_add(remaining.saturating_mul(*bps));
+                self.segment = None;
+            }
+            let (bandwidth, delay, duration) =
+                self.merger.next(|| bw.next_bw(), || delay.next_delay())?;
+            let bps = bps_u128(*bandwidth);
+            self.segment = Some((bps, *delay, duration.as_nanos()));
+        }
+    }
+}
+
+impl StaticDelayPerPacketConfig {
+    pub fn new() -> Self {
+        Self {
+            delay: None,
+            count: 0,
+        }
+    }
+
+    pub fn delay(mut self, delay: Delay) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn build(self) -> StaticDelayPerPacket {
+        StaticDelayPerPacket {
+            delay: self.delay.unwrap_or_else(|| Delay::from_millis(10)),
+            count: self.count,
+            current_count: 0,
+        }
+    }
+}
+
+impl RepeatedDelayPerPacketPatternConfig {
+    pub fn new() -> Self {
+        Self {
+            pattern: vec![],
+            count: 0,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: Vec<Box<dyn DelayPerPacketTraceConfig>>) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Chain the per-packet delay traces in `pattern` to play each of them once in sequence, i.e. a pattern
+    /// with `count` 1, which ends after the last trace ends.
+    pub fn once(pattern: Vec<Box<dyn DelayPerPacketTraceConfig>>) -> Self {
+        Self::new().pattern(pattern).count(1)
+    }
+
+    pub fn build(self) -> RepeatedDelayPerPacketPattern {
+        RepeatedDelayPerPacketPattern {
+            guard: EmptyPatternGuard::new(self.pattern.len()),
+            pattern: self.pattern,
+            count: self.count,
+            current_model: None,
+            current_cycle: 0,
+            current_pattern: 0,
+        }
+    }
+}
+
+impl NormalizedDelayPerPacketConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            std_dev: None,
+            upper_bound: None,
+            lower_bound: None,
+            max_step: None,
+            count: 0,
+            seed: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: Delay) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Delay) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Delay) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Delay) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    /// Limit how much each delay can differ from the previous one, see [`NormalizedDelayPerPacket`].
+    pub fn max_step(mut self, max_step: Delay) -> Self {
+        self.max_step = Some(max_step);
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> NormalizedDelayPerPacket {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> NormalizedDelayPerPacket<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `lower_bound` is
+    /// larger than `upper_bound`.
+    ///
+    /// `mean` defaults to 10ms and `std_dev` to zero.
+    pub fn try_build(self) -> Result<NormalizedDelayPerPacket, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<NormalizedDelayPerPacket<R>, ModelBuildError> {
+        let mean = self.mean.unwrap_or_else(|| Delay::from_millis(10));
+        let std_dev = self.std_dev.unwrap_or(Delay::ZERO);
+        if let (Some(lower), Some(upper)) = (self.lower_bound, self.upper_bound) {
+            if lower > upper {
+                return Err(ModelBuildError::InvalidBounds {
+                    model: "NormalizedDelayPerPacket",
+                    lower: "lower_bound",
+                    upper: "upper_bound",
+                });
+            }
+        }
+        let distribution = Normal::new(mean.as_nanos() as f64, std_dev.as_nanos() as f64).unwrap();
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "NormalizedDelayPerPacket built: mean {}, std_dev {}, max_step {:?}, count {}, seed {}",
+            fmt_delay(mean),
+            fmt_delay(std_dev),
+            self.max_step.map(fmt_delay),
+            self.count,
+            seed
+        );
+        Ok(NormalizedDelayPerPacket {
+            mean,
+            std_dev,
+            upper_bound: self.upper_bound,
+            lower_bound: self.lower_bound,
+            max_step: self.max_step,
+            count: self.count,
+            seed,
+            current_count: 0,
+            previous: None,
+            rng,
+            distribution,
+        })
+    }
+}
+
+impl LogNormalDelayPerPacketConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            std_dev: None,
+            upper_bound: None,
+            lower_bound: None,
+            max_step: None,
+            count: 0,
+            seed: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: Delay) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: Delay) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: Delay) -> Self {
+        self.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn lower_bound(mut self, lower_bound: Delay) -> Self {
+        self.lower_bound = Some(lower_bound);
+        self
+    }
+
+    /// Limit how much each delay can differ from the previous one, see [`LogNormalDelayPerPacket`].
+    pub fn max_step(mut self, max_step: Delay) -> Self {
+        self.max_step = Some(max_step);
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, panicking if the configuration is invalid. See [`try_build`](Self::try_build)
+    /// for the conditions.
+    pub fn build(self) -> LogNormalDelayPerPacket {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> LogNormalDelayPerPacket<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `lower_bound` is
+    /// larger than `upper_bound`, or if `mean` is zero while `std_dev` is not.
+    ///
+    /// `mean` defaults to 10ms and `std_dev` to zero.
+    pub fn try_build(self) -> Result<LogNormalDelayPerPacket, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<LogNormalDelayPerPacket<R>, ModelBuildError> {
+        let mean = self.mean.unwrap_or_else(|| Delay::from_millis(10));
+        let std_dev = self.std_dev.unwrap_or(Delay::ZERO);
+        if let (Some(lower), Some(upper)) = (self.lower_bound, self.upper_bound) {
+            if lower > upper {
+                return Err(ModelBuildError::InvalidBounds {
+                    model: "LogNormalDelayPerPacket",
+                    lower: "lower_bound",
+                    upper: "upper_bound",
+                });
+            }
+        }
+        if mean.is_zero() && !std_dev.is_zero() {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "LogNormalDelayPerPacket",
+                name: "mean",
+                expected: "positive when std_dev is positive",
+                value: 0.0,
+            });
+        }
+        let distribution = LogNormal::from_mean_cv(
+            mean.as_nanos() as f64,
+            std_dev.as_nanos() as f64 / mean.as_nanos().max(1) as f64,
+        )
+        .unwrap();
+        let seed = self.seed.unwrap_or(DEFAULT_RNG_SEED);
+        debug_log!(
+            "LogNormalDelayPerPacket built: mean {}, std_dev {}, max_step {:?}, count {}, seed {}",
+            fmt_delay(mean),
+            fmt_delay(std_dev),
+            self.max_step.map(fmt_delay),
+            self.count,
+            seed
+        );
+        Ok(LogNormalDelayPerPacket {
+            mean,
+            std_dev,
+            upper_bound: self.upper_bound,
+            lower_bound: self.lower_bound,
+            max_step: self.max_step,
+            count: self.count,
+            seed,
+            current_count: 0,
+            previous: None,
+            rng,
+            distribution,
+        })
+    }
+}
+
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+impl TimeSlicedAsPerPacketConfig {
+    pub fn new() -> Self {
+        Self {
+            delay: None,
+            bw: None,
+            packet_bytes: None,
+        }
+    }
+
+    pub fn delay(mut self, delay: Box<dyn DelayTraceConfig>) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn bw(mut self, bw: Box<dyn BwTraceConfig>) -> Self {
+        self.bw = Some(bw);
+        self
+    }
+
+    pub fn packet_bytes(mut self, packet_bytes: u64) -> Self {
+        self.packet_bytes = Some(packet_bytes);
+        self
+    }
+
+    /// Build the model, where `packet_bytes` defaults to 1500.
+    ///
+    /// Panics if `packet_bytes` is 0.
+    pub fn build(self) -> TimeSlicedAsPerPacket {
+        let packet_bytes = self.packet_bytes.unwrap_or(1500);
+        if packet_bytes == 0 {
+            panic!("TimeSlicedAsPerPacket: packet_bytes must be positive");
+        }
+        TimeSlicedAsPerPacket {
+            packet_bytes,
+            delay: self.delay.map(|delay| delay.into_model()),
+            bw: self.bw.map(|bw| bw.into_model()),
+            merger: SegmentMerger::new(),
+            segment: None,
+            transmitted: 0,
+        }
+    }
+}
+
+impl Resettable for StaticDelayPerPacket {
+    fn reset(&mut self) {
+        self.current_count = 0;
+    }
+}
+
+impl Resettable for RepeatedDelayPerPacketPattern {
+    fn reset(&mut self) {
+        self.current_model = None;
+        self.current_cycle = 0;
+        self.current_pattern = 0;
+        self.guard.reset();
+    }
+}
+
+impl<R: SeedableRng> Resettable for NormalizedDelayPerPacket<R> {
+    fn reset(&mut self) {
+        self.current_count = 0;
+        self.previous = None;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+impl<R: SeedableRng> Resettable for LogNormalDelayPerPacket<R> {
+    fn reset(&mut self) {
+        self.current_count = 0;
+        self.previous = None;
+        self.rng = R::seed_from_u64(self.seed);
+    }
+}
+
+/// Implement [`DelayPerPacketTraceConfig`] for the built-in per-packet delay trace model configs, and register their tags.
+///
+/// A config can be preceded by `#[cfg(...)]` attributes to implement and register it conditionally.
+macro_rules! impl_delay_per_packet_trace_config {
+    ($($(#[$attr:meta])* $name:ident),+ $(,)?) => {
+        $(
+            $(#[$attr])*
+            #[cfg_attr(feature = "serde", typetag::serde)]
+            impl DelayPerPacketTraceConfig for $name {
+                fn into_model(self: Box<$name>) -> Box<dyn DelayPerPacketTrace> {
+                    Box::new(self.build())
+                }
+            }
+        )+
+
+        static REGISTERED_DELAY_PER_PACKET_CONFIG_TAGS: &[&str] =
+            &[$($(#[$attr])* stringify!($name)),+];
+    };
+}
+
+impl_delay_per_packet_trace_config!(
+    StaticDelayPerPacketConfig,
+    RepeatedDelayPerPacketPatternConfig,
+    NormalizedDelayPerPacketConfig,
+    LogNormalDelayPerPacketConfig,
+    #[cfg(all(feature = "bw-model", feature = "delay-model"))]
+    TimeSlicedAsPerPacketConfig,
+);
+
+/// Returns the tags of the built-in per-packet delay trace model configs, i.e. the names used to identify them
+/// in configuration files (e.g. `"StaticDelayPerPacketConfig"`).
+///
+/// Configs implementing [`DelayPerPacketTraceConfig`] outside of this crate are not included.
+pub fn registered_delay_per_packet_config_tags() -> &'static [&'static str] {
+    REGISTERED_DELAY_PER_PACKET_CONFIG_TAGS
+}
+
+/// Implement the [`Repeatable`] trait for the per-packet delay trace model config (any struct implements [`DelayPerPacketTraceConfig`]).
+///
+/// The config will be repeated with [`RepeatedDelayPerPacketPatternConfig`].
+macro_rules! impl_repeatable {
+    ($name:ident) => {
+        impl Repeatable for $name {
+            type Repeated = RepeatedDelayPerPacketPatternConfig;
+
+            fn repeat(self, count: usize) -> RepeatedDelayPerPacketPatternConfig {
+                RepeatedDelayPerPacketPatternConfig::new()
+                    .pattern(vec![Box::new(self)])
+                    .count(count)
+            }
+        }
+    };
+}
+
+impl_repeatable!(StaticDelayPerPacketConfig);
+impl_repeatable!(NormalizedDelayPerPacketConfig);
+impl_repeatable!(LogNormalDelayPerPacketConfig);
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+impl_repeatable!(TimeSlicedAsPerPacketConfig);
+
+impl Repeatable for RepeatedDelayPerPacketPatternConfig {
+    type Repeated = RepeatedDelayPerPacketPatternConfig;
+
+    /// Repeat the whole pattern for `count` times, by multiplying the `count` of the pattern.
+    fn repeat(self, count: usize) -> RepeatedDelayPerPacketPatternConfig {
+        let count = self.count.saturating_mul(count);
+        self.count(count)
+    }
+}
+
+/// Convert a time-sliced delay trace into a per-packet delay trace, using a bandwidth trace
+/// to determine when each packet is sent.
+///
+/// This is a shorthand for a [`TimeSlicedAsPerPacketConfig`], see [`TimeSlicedAsPerPacket`] for
+/// the details. The traces are merged lazily while the result is played, so either of them may go
+/// on forever.
+///
+/// Enabled with feature `bw-model` and `delay-model` in addition.
+///
+/// Panics if `packet_bytes` is 0.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::{time_sliced_to_per_packet, StaticBwConfig, StaticDelayConfig};
+/// # use netem_trace::{Bandwidth, Delay, Duration};
+/// // 12Mbps sends a 1500-byte packet every millisecond
+/// let mut model = time_sliced_to_per_packet(
+///     Box::new(
+///         StaticDelayConfig::new()
+///             .delay(Delay::from_millis(10))
+///             .duration(Duration::from_millis(3)),
+///     ),
+///     Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))),
+///     1500,
+/// )
+/// .into_model();
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+/// assert_eq!(model.next_delay(), None);
+/// ```
+#[cfg(all(feature = "bw-model", feature = "delay-model"))]
+pub fn time_sliced_to_per_packet(
+    delay: Box<dyn DelayTraceConfig>,
+    bw: Box<dyn BwTraceConfig>,
+    packet_bytes: u64,
+) -> Box<dyn DelayPerPacketTraceConfig> {
+    if packet_bytes == 0 {
+        panic!("time_sliced_to_per_packet: packet_bytes must be positive");
+    }
+    Box::new(
+        TimeSlicedAsPerPacketConfig::new()
+            .delay(delay)
+            .bw(bw)
+            .packet_bytes(packet_bytes),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_static_delay_per_packet_model() {
+        let mut static_delay = StaticDelayPerPacketConfig::new()
+            .delay(Delay::from_millis(10))
+            .count(2)
+            .build();
+        assert_eq!(static_delay.next_delay(), Some(Delay::from_millis(10)));
+        assert_eq!(static_delay.next_delay(), Some(Delay::from_millis(10)));
+        assert_eq!(static_delay.next_delay(), None);
+
+        let mut model = Box::new(
+            StaticDelayPerPacketConfig::new()
+                .delay(Delay::from_millis(10))
+                .count(1)
+                .repeat(3),
+        )
+        .into_model();
+        for _ in 0..3 {
+            assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        }
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_many_empty_sub_models() {
+        // a long run of sub-models producing nothing (here empty patterns) must not overflow the stack
+        let mut pattern: Vec<Box<dyn DelayPerPacketTraceConfig>> = (0..100_000)
+            .map(|_| {
+                Box::new(RepeatedDelayPerPacketPatternConfig::new())
+                    as Box<dyn DelayPerPacketTraceConfig>
+            })
+            .collect();
+        pattern.push(Box::new(
+            StaticDelayPerPacketConfig::new()
+                .delay(Delay::from_millis(10))
+                .count(1),
+        ));
+        let mut model = RepeatedDelayPerPacketPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_delay()).collect::<Vec<_>>(),
+            [Delay::from_millis(10); 2]
+        );
+
+        // a pattern repeated forever ends if none of its sub-models produces anything
+        let mut model = RepeatedDelayPerPacketPatternConfig::new()
+            .pattern(vec![Box::new(RepeatedDelayPerPacketPatternConfig::new())])
+            .count(0)
+            .build();
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let config = StaticDelayPerPacketConfig::new()
+            .delay(Delay::from_millis(10))
+            .count(2);
+        let pattern: Vec<Box<dyn DelayPerPacketTraceConfig>> = vec![
+            Box::new(config.clone()),
+            Box::new(config.delay(Delay::from_millis(20)).count(1)),
+        ];
+        let mut model = RepeatedDelayPerPacketPatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        let original: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(original.len(), 6);
+        model.reset();
+        model.next_delay();
+        model.reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(replayed, original);
+    }
+
+    #[test]
+    fn test_normalized_delay_per_packet_max_step() {
+        let config = NormalizedDelayPerPacketConfig::new()
+            .mean(Delay::from_millis(10))
+            .std_dev(Delay::from_millis(5))
+            .lower_bound(Delay::from_millis(5))
+            .max_step(Delay::from_micros(200))
+            .count(1000)
+            .seed(42);
+        let delays: Vec<_> = config.clone().build().into_iter().collect();
+        assert_eq!(delays.len(), 1000);
+        for pair in delays.windows(2) {
+            assert!(pair[0].abs_diff(pair[1]) <= Delay::from_micros(200));
+        }
+        assert!(delays.iter().all(|delay| *delay >= Delay::from_millis(5)));
+        // without max_step, the samples jump much further
+        let unlimited: Vec<_> = NormalizedDelayPerPacketConfig {
+            max_step: None,
+            ..config.clone()
+        }
+        .build()
+        .into_iter()
+        .collect();
+        assert!(unlimited
+            .windows(2)
+            .any(|pair| pair[0].abs_diff(pair[1]) > Delay::from_millis(1)));
+
+        let mut model = config.build();
+        let first: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        model.reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(first, replayed);
+    }
+
+    #[test]
+    fn test_log_normal_delay_per_packet_max_step() {
+        let mut model = LogNormalDelayPerPacketConfig::new()
+            .mean(Delay::from_millis(10))
+            .std_dev(Delay::from_millis(5))
+            .upper_bound(Delay::from_millis(15))
+            .max_step(Delay::from_micros(500))
+            .count(1000)
+            .seed(42)
+            .build();
+        let delays: Vec<_> = std::iter::from_fn(|| model.next_delay()).collect();
+        assert_eq!(delays.len(), 1000);
+        for pair in delays.windows(2) {
+            assert!(pair[0].abs_diff(pair[1]) <= Delay::from_micros(500));
+        }
+        assert!(delays.iter().all(|delay| *delay <= Delay::from_millis(15)));
+
+        assert_eq!(
+            LogNormalDelayPerPacketConfig::new()
+                .mean(Delay::ZERO)
+                .std_dev(Delay::from_millis(1))
+                .try_build()
+                .unwrap_err()
+                .to_string(),
+            "LogNormalDelayPerPacket: mean must be positive when std_dev is positive, got 0"
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "bw-model", feature = "delay-model"))]
+    fn test_time_sliced_to_per_packet() {
+        use crate::model::{
+            DelayTraceConfig, RepeatedDelayPatternConfig, StaticBwConfig, StaticDelayConfig,
+        };
+        use crate::{Bandwidth, Duration};
+
+        // 10ms for [0ms, 5ms), then 20ms for [5ms, 10ms)
+        let delay = RepeatedDelayPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    StaticDelayConfig::new()
+                        .delay(Delay::from_millis(10))
+                        .duration(Duration::from_millis(5)),
+                ) as Box<dyn DelayTraceConfig>,
+                Box::new(
+                    StaticDelayConfig::new()
+                        .delay(Delay::from_millis(20))
+                        .duration(Duration::from_millis(5)),
+                ) as Box<dyn DelayTraceConfig>,
+            ])
+            .count(1);
+        // 12Mbps sends a 1500-byte packet at 1ms, 2ms, 3ms, ...
+        let bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_secs(1));
+        let mut model =
+            time_sliced_to_per_packet(Box::new(delay.clone()), Box::new(bw), 1500).into_model();
+        for _ in 0..4 {
+            assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        }
+        for _ in 0..5 {
+            assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+        }
+        assert_eq!(model.next_delay(), None);
+
+        // 6Mbps sends a 1500-byte packet at 2ms, 4ms, 6ms and 8ms,
+        // and the bandwidth trace ends before the delay trace
+        let bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(6))
+            .duration(Duration::from_millis(9));
+        let mut model = time_sliced_to_per_packet(Box::new(delay), Box::new(bw), 1500).into_model();
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+        assert_eq!(model.next_delay(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "bw-model", feature = "delay-model"))]
+    fn test_time_sliced_as_per_packet_lazy() {
+        use crate::model::{
+            BwTraceConfig, RepeatedBwPatternConfig, RepeatedDelayPatternConfig, StaticBwConfig,
+            StaticDelayConfig,
+        };
+        use crate::{Bandwidth, Duration};
+
+        // both traces go on forever, the model is still played packet by packet
+        let delay = StaticDelayConfig::new()
+            .delay(Delay::from_millis(10))
+            .duration(Duration::from_millis(100))
+            .forever();
+        let bw = StaticBwConfig::new()
+            .bw(Bandwidth::from_mbps(12))
+            .duration(Duration::from_millis(100))
+            .forever();
+        let mut model = time_sliced_to_per_packet(Box::new(delay), Box::new(bw), 1500).into_model();
+        for _ in 0..10_000 {
+            assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        }
+
+        // 1ms of 12Mbps, 2ms of zero bandwidth, then 12Mbps: the packets are sent at 1ms, 4ms, 5ms, ...
+        let bw = RepeatedBwPatternConfig::new()
+            .pattern(vec![
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::from_mbps(12))
+                        .duration(Duration::from_millis(1)),
+                ) as Box<dyn BwTraceConfig>,
+                Box::new(
+                    StaticBwConfig::new()
+                        .bw(Bandwidth::ZERO)
+                        .duration(Duration::from_millis(2)),
+                ) as Box<dyn BwTraceConfig>,
+                Box::new(StaticBwConfig::new().bw(Bandwidth::from_mbps(12))),
+            ])
+            .count(1);
+        // 10ms for [0ms, 4ms), then 20ms
+        let delay = RepeatedDelayPatternConfig::once(vec![
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .duration(Duration::from_millis(4)),
+            ),
+            Box::new(StaticDelayConfig::new().delay(Delay::from_millis(20))),
+        ]);
+        let mut model = TimeSlicedAsPerPacketConfig::new()
+            .delay(Box::new(delay))
+            .bw(Box::new(bw))
+            .build();
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(10)));
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+        assert_eq!(model.next_delay(), Some(Delay::from_millis(20)));
+
+        // a model without both traces produces nothing
+        assert_eq!(
+            TimeSlicedAsPerPacketConfig::new()
+                .bw(Box::new(StaticBwConfig::new()))
+                .build()
+                .next_delay(),
+            None
+        );
+    }
+}
@@ -6,6 +6,7 @@
 //!
 //! - [`StaticDuplicate`]: A trace model with static duplicate rate.
 //! - [`RepeatedDuplicatePattern`]: A trace model with a repeated duplicate pattern.
+//! - [`NormalizedDuplicate`]: A trace model whose duplicate probability subjects to a normal distribution, changing at fixed intervals.
 //!
 //! ## Examples
 //!
@@ -28,7 +29,7 @@
 //! # use netem_trace::model::{StaticDuplicateConfig, DuplicateTraceConfig};
 //! # use netem_trace::{DuplicatePattern, Duration, DuplicateTrace};
 //! # #[cfg(feature = "human")]
-//! # let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":\"1s\"}}],\"count\":2}}";
+//! # let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[\"10%\",\"20%\"],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[\"20%\",\"40%\"],\"duration\":\"1s\"}}],\"count\":2}}";
 //! // The content would be "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 //! // if the `human` feature is not enabled.
 //! # #[cfg(not(feature = "human"))]
@@ -53,8 +54,12 @@
 //! );
 //! assert_eq!(model.next_duplicate(), None);
 //! ```
-use crate::{DuplicatePattern, DuplicateTrace, Duration};
+use super::{EmptyPatternGuard, ModelBuildError, Repeatable, Rewind, DEFAULT_RNG_SEED};
+use crate::{DuplicatePattern, DuplicateTrace, Duration, Resettable};
 use dyn_clone::DynClone;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rand_distr::{Distribution, Normal};
 
 /// This trait is used to convert a duplicate trace configuration into a duplicate trace model.
 ///
@@ -99,11 +104,19 @@ pub struct StaticDuplicate {
 #[derive(Debug, Clone, Default)]
 pub struct StaticDuplicateConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_percent")
+    )]
     pub duplicate: Option<DuplicatePattern>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(
         all(feature = "serde", feature = "human"),
-        serde(with = "humantime_serde")
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
     )]
     pub duration: Option<Duration>,
 }
@@ -113,7 +126,8 @@ pub struct StaticDuplicateConfig {
 /// Combine multiple duplicate trace models into one duplicate pattern,
 /// and repeat the pattern for `count` times.
 ///
-/// If `count` is 0, the pattern will be repeated forever.
+/// If `count` is 0, the pattern will be repeated forever. To play each model once in sequence,
+/// use [`RepeatedDuplicatePatternConfig::once`].
 ///
 /// ## Examples
 ///
@@ -124,11 +138,13 @@ pub struct StaticDuplicateConfig {
 /// # use netem_trace::model::{StaticDuplicateConfig, DuplicateTraceConfig};
 /// # use netem_trace::{DuplicatePattern, Duration, DuplicateTrace};
 /// # #[cfg(feature = "human")]
-/// # let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":\"1s\"}}],\"count\":2}}";
+/// # let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[\"10%\",\"20%\"],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[\"20%\",\"40%\"],\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The content would be "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let config_file_content = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":1000000000}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":1000000000}}],\"count\":2}}";
 /// let des: Box<dyn DuplicateTraceConfig> = serde_json::from_str(config_file_content).unwrap();
 /// let mut model = des.into_model();
 /// assert_eq!(
@@ -170,11 +186,13 @@ pub struct StaticDuplicateConfig {
 /// let ser = Box::new(RepeatedDuplicatePatternConfig::new().pattern(pat).count(2)) as Box<dyn DuplicateTraceConfig>;
 /// let ser_str = serde_json::to_string(&ser).unwrap();
 /// # #[cfg(feature = "human")]
-/// # let json_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":\"1s\"}}],\"count\":2}}";
+/// # let json_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[\"10%\",\"20%\"],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[\"20%\",\"40%\"],\"duration\":\"1s\"}}],\"count\":2}}";
 /// // The json string would be "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}"
 /// // if the `human` feature is not enabled.
-/// # #[cfg(not(feature = "human"))]
+/// # #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
 /// let json_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+/// # #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+/// # let json_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":1000000000}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":1000000000}}],\"count\":2}}";
 /// assert_eq!(ser_str, json_str);
 /// ```
 pub struct RepeatedDuplicatePattern {
@@ -183,6 +201,7 @@ pub struct RepeatedDuplicatePattern {
     current_model: Option<Box<dyn DuplicateTrace>>,
     current_cycle: usize,
     current_pattern: usize,
+    guard: EmptyPatternGuard,
 }
 
 /// The configuration struct for [`RepeatedDuplicatePattern`].
@@ -195,6 +214,78 @@ pub struct RepeatedDuplicatePatternConfig {
     pub count: usize,
 }
 
+/// The model of a duplicate trace whose duplicate probability subjects to a normal distribution.
+///
+/// The trace is split into steps of `step` (the last one is truncated to the remaining `duration`).
+/// For each step, the duplicate probability is sampled from a normal distribution of `mean` and
+/// `std_dev`, and clamped into `[0, 1]`. The duplicate pattern of the step is the single
+/// probability.
+///
+/// Like [`NormalizedLoss`](crate::model::NormalizedLoss), the model is generic over the RNG, which
+/// is [`StdRng`] when built with [`NormalizedDuplicateConfig::build`].
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::model::NormalizedDuplicateConfig;
+/// # use netem_trace::{Duration, DuplicateTrace};
+/// let mut normalized_duplicate = NormalizedDuplicateConfig::new()
+///     .mean(0.1)
+///     .std_dev(0.05)
+///     .duration(Duration::from_millis(10))
+///     .step(Duration::from_millis(1))
+///     .seed(42)
+///     .build();
+/// let (duplicate, duration) = normalized_duplicate.next_duplicate().unwrap();
+/// assert!(duplicate.len() == 1 && (0.0..=1.0).contains(&duplicate[0]));
+/// assert_eq!(duration, Duration::from_millis(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalizedDuplicate<R = StdRng> {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub duration: Duration,
+    pub step: Duration,
+    pub seed: u64,
+    total_duration: Duration,
+    rng: R,
+    normal: Normal<f64>,
+}
+
+/// The configuration struct for [`NormalizedDuplicate`].
+///
+/// See [`NormalizedDuplicate`] for more details.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct NormalizedDuplicateConfig {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub mean: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub std_dev: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        all(feature = "serde", feature = "human"),
+        serde(with = "crate::model::human_duration")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "ns-number", not(feature = "human")),
+        serde(with = "crate::model::ns_duration")
+    )]
+    pub step: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub seed: Option<u64>,
+}
+
 impl DuplicateTrace for StaticDuplicate {
     fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)> {
         if let Some(duration) = self.duration.take() {
@@ -209,30 +300,62 @@ impl DuplicateTrace for StaticDuplicate {
     }
 }
 
+impl DuplicateTrace for Rewind<StaticDuplicate> {
+    fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)> {
+        self.model.next_duplicate()
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
 impl DuplicateTrace for RepeatedDuplicatePattern {
     fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)> {
-        if self.pattern.is_empty() || (self.count != 0 && self.current_cycle >= self.count) {
-            None
-        } else {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        // iterate instead of recursing, so that a long run of sub-models producing nothing
+        // cannot overflow the stack
+        while self.count == 0 || self.current_cycle < self.count {
             if self.current_model.is_none() {
                 self.current_model = Some(self.pattern[self.current_pattern].clone().into_model());
             }
-            match self.current_model.as_mut().unwrap().next_duplicate() {
-                Some(duplicate) => Some(duplicate),
-                None => {
-                    self.current_model = None;
-                    self.current_pattern += 1;
-                    if self.current_pattern >= self.pattern.len() {
-                        self.current_pattern = 0;
-                        self.current_cycle += 1;
-                        if self.count != 0 && self.current_cycle >= self.count {
-                            return None;
-                        }
-                    }
-                    self.next_duplicate()
-                }
+            if let Some(duplicate) = self.current_model.as_mut().unwrap().next_duplicate() {
+                self.guard.produced();
+                return Some(duplicate);
+            }
+            self.current_model = None;
+            if !self.guard.ended(self.current_pattern) {
+                return None;
+            }
+            self.current_pattern += 1;
+            if self.current_pattern >= self.pattern.len() {
+                self.current_pattern = 0;
+                self.current_cycle += 1;
             }
         }
+        None
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
+    }
+}
+
+impl<R: RngCore + SeedableRng + Send> DuplicateTrace for NormalizedDuplicate<R> {
+    fn next_duplicate(&mut self) -> Option<(DuplicatePattern, Duration)> {
+        if self.duration.is_zero() {
+            return None;
+        }
+        let duration = self.step.min(self.duration);
+        self.duration -= duration;
+        let duplicate = self.normal.sample(&mut self.rng).clamp(0.0, 1.0);
+        Some((vec![duplicate], duration))
+    }
+
+    fn as_resettable(&mut self) -> Option<&mut dyn Resettable> {
+        Some(self)
     }
 }
 
@@ -280,8 +403,15 @@ impl RepeatedDuplicatePatternConfig {
         self
     }
 
+    /// Chain the duplicate traces in `pattern` to play each of them once in sequence, i.e. a pattern
+    /// with `count` 1, which ends after the last trace ends.
+    pub fn once(pattern: Vec<Box<dyn DuplicateTraceConfig>>) -> Self {
+        Self::new().pattern(pattern).count(1)
+    }
+
     pub fn build(self) -> RepeatedDuplicatePattern {
         RepeatedDuplicatePattern {
+            guard: EmptyPatternGuard::new(self.pattern.len()),
             pattern: self.pattern,
             count: self.count,
             current_model: None,
@@ -291,19 +421,193 @@ impl RepeatedDuplicatePatternConfig {
     }
 }
 
+impl NormalizedDuplicateConfig {
+    pub fn new() -> Self {
+        Self {
+            mean: None,
+            std_dev: None,
+            duration: None,
+            step: None,
+            seed: None,
+        }
+    }
+
+    pub fn mean(mut self, mean: f64) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+
+    pub fn std_dev(mut self, std_dev: f64) -> Self {
+        self.std_dev = Some(std_dev);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`.
+    ///
+    /// Panics if the configuration is invalid, see [`try_build`](Self::try_build).
+    pub fn build(self) -> NormalizedDuplicate {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model with the given RNG instead of a [`StdRng`] seeded by `seed`, see
+    /// [custom RNGs](crate::model#custom-rngs).
+    pub fn build_with_rng<R: RngCore + SeedableRng>(self, rng: R) -> NormalizedDuplicate<R> {
+        self.try_build_with_rng(rng)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Build the model, with a [`StdRng`] seeded by `seed`, or return an error if `mean` is not
+    /// finite, `std_dev` is negative or not finite, or `step` is zero.
+    pub fn try_build(self) -> Result<NormalizedDuplicate, ModelBuildError> {
+        let rng = StdRng::seed_from_u64(self.seed.unwrap_or(DEFAULT_RNG_SEED));
+        self.try_build_with_rng(rng)
+    }
+
+    /// Build the model with the given RNG, or return an error if the configuration is invalid.
+    /// See [`build_with_rng`](Self::build_with_rng) and [`try_build`](Self::try_build).
+    pub fn try_build_with_rng<R: RngCore + SeedableRng>(
+        self,
+        rng: R,
+    ) -> Result<NormalizedDuplicate<R>, ModelBuildError> {
+        let mean = self.mean.unwrap_or(0.1);
+        if !mean.is_finite() {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "NormalizedDuplicate",
+                name: "mean",
+                expected: "finite",
+                value: mean,
+            });
+        }
+        let std_dev = self.std_dev.unwrap_or(0.0);
+        if !(std_dev.is_finite() && std_dev >= 0.0) {
+            return Err(ModelBuildError::InvalidParameter {
+                model: "NormalizedDuplicate",
+                name: "std_dev",
+                expected: "non-negative and finite",
+                value: std_dev,
+            });
+        }
+        let normal = Normal::new(mean, std_dev).unwrap();
+        let duration = self.duration.unwrap_or_else(|| Duration::from_secs(1));
+        let step = self.step.unwrap_or_else(|| Duration::from_millis(1));
+        if step.is_zero() {
+            return Err(ModelBuildError::ZeroDuration {
+                model: "NormalizedDuplicate",
+                name: "step",
+            });
+        }
+        Ok(NormalizedDuplicate {
+            mean,
+            std_dev,
+            duration,
+            step,
+            seed: self.seed.unwrap_or(DEFAULT_RNG_SEED),
+            total_duration: duration,
+            rng,
+            normal,
+        })
+    }
+}
+
+impl<R: SeedableRng> Resettable for NormalizedDuplicate<R> {
+    /// Rerun the model from the beginning, with the RNG re-seeded by `seed`.
+    fn reset(&mut self) {
+        self.rng = R::seed_from_u64(self.seed);
+        self.duration = self.total_duration;
+    }
+}
+
+impl Resettable for RepeatedDuplicatePattern {
+    fn reset(&mut self) {
+        self.current_model = None;
+        self.current_cycle = 0;
+        self.current_pattern = 0;
+        self.guard.reset();
+    }
+}
+
+/// Implement [`DuplicateTraceConfig`] for the built-in duplicate trace model configs, and register their tags.
+///
+/// A config whose model is only resettable through [`Rewind`] is followed by `(rewind)`.
 macro_rules! impl_duplicate_trace_config {
+    (@model $model:expr) => {
+        Box::new($model)
+    };
+    (@model $model:expr, rewind) => {
+        Box::new(Rewind::new($model))
+    };
+    ($($name:ident $(($flag:ident))?),+ $(,)?) => {
+        $(
+            #[cfg_attr(feature = "serde", typetag::serde)]
+            impl DuplicateTraceConfig for $name {
+                fn into_model(self: Box<$name>) -> Box<dyn DuplicateTrace> {
+                    impl_duplicate_trace_config!(@model self.build() $(, $flag)?)
+                }
+            }
+        )+
+
+        static REGISTERED_DUPLICATE_CONFIG_TAGS: &[&str] = &[$(stringify!($name)),+];
+    };
+}
+
+impl_duplicate_trace_config!(
+    StaticDuplicateConfig(rewind),
+    RepeatedDuplicatePatternConfig,
+    NormalizedDuplicateConfig,
+);
+
+/// Returns the tags of the built-in duplicate trace model configs, i.e. the names used to identify them
+/// in configuration files (e.g. `"StaticDuplicateConfig"`).
+///
+/// Configs implementing [`DuplicateTraceConfig`] outside of this crate are not included.
+pub fn registered_duplicate_config_tags() -> &'static [&'static str] {
+    REGISTERED_DUPLICATE_CONFIG_TAGS
+}
+
+/// Implement the [`Repeatable`] trait for the duplicate trace model config (any struct implements [`DuplicateTraceConfig`]).
+///
+/// The config will be repeated with [`RepeatedDuplicatePatternConfig`].
+macro_rules! impl_repeatable {
     ($name:ident) => {
-        #[cfg_attr(feature = "serde", typetag::serde)]
-        impl DuplicateTraceConfig for $name {
-            fn into_model(self: Box<$name>) -> Box<dyn DuplicateTrace> {
-                Box::new(self.build())
+        impl Repeatable for $name {
+            type Repeated = RepeatedDuplicatePatternConfig;
+
+            fn repeat(self, count: usize) -> RepeatedDuplicatePatternConfig {
+                RepeatedDuplicatePatternConfig::new()
+                    .pattern(vec![Box::new(self)])
+                    .count(count)
             }
         }
     };
 }
 
-impl_duplicate_trace_config!(StaticDuplicateConfig);
-impl_duplicate_trace_config!(RepeatedDuplicatePatternConfig);
+impl_repeatable!(StaticDuplicateConfig);
+impl_repeatable!(NormalizedDuplicateConfig);
+
+impl Repeatable for RepeatedDuplicatePatternConfig {
+    type Repeated = RepeatedDuplicatePatternConfig;
+
+    /// Repeat the whole pattern for `count` times, by multiplying the `count` of the pattern.
+    fn repeat(self, count: usize) -> RepeatedDuplicatePatternConfig {
+        let count = self.count.saturating_mul(count);
+        self.count(count)
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -324,6 +628,166 @@ mod test {
         assert_eq!(static_loss.next_duplicate(), None);
     }
 
+    #[test]
+    fn test_normalized_duplicate() {
+        let config = NormalizedDuplicateConfig::new()
+            .mean(0.2)
+            .std_dev(0.05)
+            .duration(Duration::from_millis(35))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let trace = |model: &mut dyn DuplicateTrace| {
+            std::iter::from_fn(|| model.next_duplicate()).collect::<Vec<_>>()
+        };
+
+        // the same seed gives the same trace
+        let original = trace(&mut config.clone().build());
+        assert_eq!(original, trace(&mut config.clone().build()));
+        assert_ne!(original, trace(&mut config.clone().seed(43).build()));
+        let durations: Vec<_> = original.iter().map(|(_, duration)| *duration).collect();
+        assert_eq!(
+            durations,
+            [
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_millis(5)
+            ]
+        );
+        assert!(original
+            .iter()
+            .all(|(duplicate, _)| duplicate.len() == 1 && (duplicate[0] - 0.2).abs() < 0.25));
+
+        // reset re-seeds the RNG
+        let mut model = config.clone().build();
+        model.next_duplicate();
+        model.reset();
+        assert_eq!(trace(&mut model), original);
+
+        // repeated forever
+        let mut model = Box::new(config.forever()).into_model();
+        for _ in 0..10 {
+            assert!(model.next_duplicate().is_some());
+        }
+
+        assert!(matches!(
+            NormalizedDuplicateConfig::new().std_dev(-0.1).try_build(),
+            Err(ModelBuildError::InvalidParameter {
+                name: "std_dev",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_normalized_duplicate_clamped() {
+        let config = NormalizedDuplicateConfig::new()
+            .mean(0.5)
+            .std_dev(1.0)
+            .duration(Duration::from_secs(1))
+            .step(Duration::from_millis(10))
+            .seed(42);
+        let samples: Vec<_> = config
+            .clone()
+            .build()
+            .into_iter()
+            .map(|(duplicate, _)| duplicate[0])
+            .collect();
+        // the samples are clamped, which happens often with such a large deviation
+        assert!(samples.iter().all(|p| (0.0..=1.0).contains(p)));
+        assert!(samples.contains(&0.0));
+        assert!(samples.contains(&1.0));
+        assert!(samples.iter().any(|p| *p > 0.0 && *p < 1.0));
+
+        let mut model = config.clone().mean(5.0).build();
+        assert!(std::iter::from_fn(|| model.next_duplicate()).all(|(p, _)| p[0] == 1.0));
+        let mut model = config.mean(-1.0).std_dev(0.1).build();
+        assert!(std::iter::from_fn(|| model.next_duplicate()).all(|(p, _)| p[0] == 0.0));
+    }
+
+    #[test]
+    fn test_many_empty_sub_models() {
+        // a long run of sub-models producing nothing must not overflow the stack
+        let mut pattern: Vec<Box<dyn DuplicateTraceConfig>> = (0..100_000)
+            .map(|_| {
+                Box::new(StaticDuplicateConfig::new().duration(Duration::ZERO))
+                    as Box<dyn DuplicateTraceConfig>
+            })
+            .collect();
+        pattern.push(Box::new(
+            StaticDuplicateConfig::new()
+                .duplicate(vec![0.1])
+                .duration(Duration::from_secs(1)),
+        ));
+        let mut model = RepeatedDuplicatePatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        assert_eq!(
+            std::iter::from_fn(|| model.next_duplicate()).collect::<Vec<_>>(),
+            [
+                (vec![0.1], Duration::from_secs(1)),
+                (vec![0.1], Duration::from_secs(1))
+            ]
+        );
+
+        // a pattern repeated forever ends if none of its sub-models produces anything
+        let mut model = StaticDuplicateConfig::new()
+            .duration(Duration::ZERO)
+            .forever()
+            .build();
+        assert_eq!(model.next_duplicate(), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let config = StaticDuplicateConfig::new()
+            .duplicate(vec![0.1, 0.2])
+            .duration(Duration::from_secs(1));
+        let pattern: Vec<Box<dyn DuplicateTraceConfig>> = vec![
+            Box::new(config.clone()),
+            Box::new(config.duplicate(vec![0.3])),
+        ];
+        let mut model = RepeatedDuplicatePatternConfig::new()
+            .pattern(pattern)
+            .count(2)
+            .build();
+        let original: Vec<_> = std::iter::from_fn(|| model.next_duplicate()).collect();
+        assert_eq!(original.len(), 4);
+        model.reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_duplicate()).collect();
+        assert_eq!(replayed, original);
+
+        // reset in the middle of the trace, through the trait object
+        let mut model: Box<dyn DuplicateTrace> = Box::new(model);
+        model.as_resettable().unwrap().reset();
+        model.next_duplicate();
+        model.as_resettable().unwrap().reset();
+        let replayed: Vec<_> = std::iter::from_fn(|| model.next_duplicate()).collect();
+        assert_eq!(replayed, original);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let config = StaticDuplicateConfig::new()
+            .duplicate(vec![0.1, 0.2])
+            .duration(Duration::from_secs(1));
+        let mut model = Box::new(config.clone().repeat(2)).into_model();
+        assert_eq!(
+            model.next_duplicate(),
+            Some((vec![0.1, 0.2], Duration::from_secs(1)))
+        );
+        assert_eq!(
+            model.next_duplicate(),
+            Some((vec![0.1, 0.2], Duration::from_secs(1)))
+        );
+        assert_eq!(model.next_duplicate(), None);
+        let mut model = Box::new(config.forever()).into_model();
+        for _ in 0..5 {
+            assert!(model.next_duplicate().is_some());
+        }
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn test_serde() {
@@ -343,9 +807,11 @@ mod test {
             as Box<dyn DuplicateTraceConfig>;
         let ser_str = serde_json::to_string(&ser).unwrap();
         #[cfg(feature = "human")]
-        let des_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":\"1s\"}}],\"count\":2}}";
-        #[cfg(not(feature = "human"))]
+        let des_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[\"10%\",\"20%\"],\"duration\":\"1s\"}},{\"StaticDuplicateConfig\":{\"duplicate\":[\"20%\",\"40%\"],\"duration\":\"1s\"}}],\"count\":2}}";
+        #[cfg(all(not(feature = "human"), not(feature = "ns-number")))]
         let des_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":{\"secs\":1,\"nanos\":0}}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":{\"secs\":1,\"nanos\":0}}}],\"count\":2}}";
+        #[cfg(all(not(feature = "human"), feature = "ns-number"))]
+        let des_str = "{\"RepeatedDuplicatePatternConfig\":{\"pattern\":[{\"StaticDuplicateConfig\":{\"duplicate\":[0.1,0.2],\"duration\":1000000000}},{\"StaticDuplicateConfig\":{\"duplicate\":[0.2,0.4],\"duration\":1000000000}}],\"count\":2}}";
         assert_eq!(ser_str, des_str);
         let des: Box<dyn DuplicateTraceConfig> = serde_json::from_str(des_str).unwrap();
         let mut model = des.into_model();
@@ -0,0 +1,201 @@
+//! This module (de)serializes durations in human-readable format, e.g. `"10ms"` or `"1s"`.
+//!
+//! Enabled with feature `serde` and `human`.
+//!
+//! It is a thin wrapper over [humantime](https://docs.rs/humantime), which is used for all duration fields
+//! of the model configurations. Any duration accepted by `humantime` is accepted here.
+//!
+//! `humantime` also accepts units like `d` (day) or `y` (year), which are almost certainly mistakes
+//! in a network trace. The [`strict`] module rejects durations using units coarser than seconds with
+//! a descriptive error, and can be opted into per field with `#[serde(with = "...")]`.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::human_duration::{parse_duration, parse_duration_strict};
+//! # use netem_trace::Duration;
+//! assert_eq!(parse_duration("1y").unwrap(), Duration::from_secs(31_557_600));
+//! assert_eq!(parse_duration_strict("500ms").unwrap(), Duration::from_millis(500));
+//! assert!(parse_duration_strict("1y").is_err());
+//! ```
+use crate::Duration;
+use serde::{Deserializer, Serializer};
+
+/// Units no coarser than seconds, which are accepted in strict mode.
+const STRICT_UNITS: [&str; 11] = [
+    "nsec", "ns", "usec", "us", "µs", "msec", "ms", "seconds", "second", "sec", "s",
+];
+
+/// Parse a duration in human-readable format.
+///
+/// Returns `Err` string if the duration is invalid.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    humantime_serde::re::humantime::parse_duration(s).map_err(|e| e.to_string())
+}
+
+/// Parse a duration in human-readable format, rejecting units coarser than seconds (e.g. `"1m"`,
+/// `"2h"`, `"1y"`).
+///
+/// Returns `Err` string if the duration is invalid, or if it uses units coarser than seconds.
+pub fn parse_duration_strict(s: &str) -> Result<Duration, String> {
+    let duration = parse_duration(s)?;
+    let coarse_unit = s
+        .split(|c: char| !c.is_alphabetic())
+        .find(|unit| !unit.is_empty() && !STRICT_UNITS.contains(unit));
+    match coarse_unit {
+        Some(unit) => Err(format!(
+            "unit '{}' is coarser than seconds, which is rejected in strict mode",
+            unit
+        )),
+        None => Ok(duration),
+    }
+}
+
+/// Deserializes an optional duration in human-readable format.
+///
+/// Integer nanoseconds are also accepted if `ns-number` feature is enabled.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `deserialize_with` annotations.
+pub fn deserialize<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[cfg(feature = "ns-number")]
+    {
+        crate::model::ns_duration::deserialize(d)
+    }
+    #[cfg(not(feature = "ns-number"))]
+    {
+        use serde::Deserialize;
+        Option::<String>::deserialize(d)?
+            .map(|s| {
+                parse_duration(&s).map_err(|e| {
+                    serde::de::Error::custom(format!("Failed to parse duration '{}': {}", s, e))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Serializes an optional duration in human-readable format.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `serialize_with` annotations.
+pub fn serialize<S>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    humantime_serde::serialize(d, s)
+}
+
+/// (De)serializes an optional duration in human-readable format, rejecting units coarser than
+/// seconds on deserialization.
+///
+/// Only human-readable strings are accepted, even if `ns-number` feature is enabled.
+///
+/// ## Examples
+///
+/// ```
+/// # use netem_trace::Duration;
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(with = "netem_trace::model::human_duration::strict", default)]
+///     duration: Option<Duration>,
+/// }
+///
+/// let config: Config = serde_json::from_str("{\"duration\":\"500ms\"}").unwrap();
+/// assert_eq!(config.duration, Some(Duration::from_millis(500)));
+/// assert!(serde_json::from_str::<Config>("{\"duration\":\"1y\"}").is_err());
+/// ```
+pub mod strict {
+    use crate::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Deserializes an optional duration in human-readable format, rejecting units coarser than
+    /// seconds.
+    ///
+    /// This function can be used with `serde_derive`'s `with` and
+    /// `deserialize_with` annotations.
+    pub fn deserialize<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(d)?
+            .map(|s| {
+                super::parse_duration_strict(&s).map_err(|e| {
+                    serde::de::Error::custom(format!("Failed to parse duration '{}': {}", s, e))
+                })
+            })
+            .transpose()
+    }
+
+    /// Serializes an optional duration in human-readable format.
+    ///
+    /// This function can be used with `serde_derive`'s `with` and
+    /// `serialize_with` annotations.
+    pub fn serialize<S>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        super::serialize(d, s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Strict {
+        #[serde(with = "super::strict", default)]
+        duration: Option<Duration>,
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(
+            parse_duration("1y").unwrap(),
+            Duration::from_secs(31_557_600)
+        );
+        assert_eq!(
+            parse_duration_strict("500ms").unwrap(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            parse_duration_strict("1s 500us").unwrap(),
+            Duration::from_micros(1_000_500)
+        );
+        assert_eq!(
+            parse_duration_strict("1y"),
+            Err("unit 'y' is coarser than seconds, which is rejected in strict mode".to_string())
+        );
+        assert!(parse_duration_strict("2min").is_err());
+
+        let config: Strict = serde_json::from_str("{\"duration\":\"500ms\"}").unwrap();
+        assert_eq!(config.duration, Some(Duration::from_millis(500)));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            "{\"duration\":\"500ms\"}"
+        );
+        let config: Strict = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.duration, None);
+        let err = serde_json::from_str::<Strict>("{\"duration\":\"1y\"}")
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse duration '1y': unit 'y' is coarser than seconds"));
+
+        // the lenient mode is kept for the model configurations
+        #[cfg(feature = "bw-model")]
+        {
+            let config: crate::model::StaticBwConfig =
+                serde_json::from_str("{\"duration\":\"1y\"}").unwrap();
+            assert_eq!(config.duration, Some(Duration::from_secs(31_557_600)));
+        }
+    }
+}
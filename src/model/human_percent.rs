@@ -0,0 +1,191 @@
+//! This module (de)serializes loss and duplicate patterns as percentages, e.g. `["10%","20%"]`
+//! for `[0.1, 0.2]`.
+//!
+//! Enabled with feature `serde` and `human`.
+//!
+//! It is used for the pattern fields of [`StaticLossConfig`](super::loss::StaticLossConfig) and
+//! [`StaticDuplicateConfig`](super::duplicate::StaticDuplicateConfig). Raw probabilities like
+//! `[0.1,0.2]` are still accepted when deserializing, so that existing configurations keep working.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::human_percent::{format_percent, parse_percent};
+//! assert_eq!(format_percent(0.1), Some("10%".to_string()));
+//! assert_eq!(parse_percent("12.5%"), Ok(0.125));
+//! assert!(parse_percent("0.1").is_err());
+//! ```
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A probability in a pattern, either a raw number or a percentage string.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Probability {
+    Raw(f64),
+    Percent(String),
+}
+
+/// Format a probability as a percentage, e.g. `"10%"` for `0.1`.
+///
+/// The decimal point of the shortest representation of `value` is shifted instead of multiplying
+/// it by 100, so that the percentage parses back to the exact same value with [`parse_percent`].
+///
+/// Returns `None` if `value` is NaN or infinite.
+pub fn format_percent(value: f64) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    // e.g. "-1.25e-1" for -0.125
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e')?;
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    if digits.bytes().all(|digit| digit == b'0') {
+        return Some("0%".to_string());
+    }
+    // the position of the decimal point in `digits` after multiplying by 100
+    let point = exponent.parse::<i64>().ok()? + 3;
+    let percent = if point <= 0 {
+        format!("0.{}{}", "0".repeat(point.unsigned_abs() as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        let (integer, fraction) = digits.split_at(point as usize);
+        format!("{}.{}", integer, fraction)
+    };
+    Some(format!("{}{}%", sign, percent))
+}
+
+/// Parse a percentage like `"10%"` or `"12.5 %"` into a probability.
+///
+/// The number is parsed with its decimal point shifted instead of dividing it by 100, so that
+/// e.g. `"33.3%"` is exactly `0.333`.
+///
+/// Returns `Err` string if the `%` suffix is missing or the number is invalid.
+pub fn parse_percent(s: &str) -> Result<f64, String> {
+    let number = s
+        .trim()
+        .strip_suffix('%')
+        .ok_or_else(|| "missing '%' suffix".to_string())?
+        .trim();
+    let percent = number.parse::<f64>().map_err(|e| e.to_string())?;
+    if !percent.is_finite() {
+        return Ok(percent / 100.0);
+    }
+    let shifted = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            let exponent = exponent.parse::<i64>().map_err(|e| e.to_string())?;
+            format!("{}e{}", mantissa, exponent - 2)
+        }
+        None => format!("{}e-2", number),
+    };
+    shifted.parse::<f64>().map_err(|e| e.to_string())
+}
+
+/// Deserializes an optional pattern of probabilities, each either a percentage string or a raw
+/// number.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `deserialize_with` annotations.
+pub fn deserialize<'de, D>(d: D) -> Result<Option<Vec<f64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Vec<Probability>>::deserialize(d)?
+        .map(|pattern| {
+            pattern
+                .into_iter()
+                .map(|probability| match probability {
+                    Probability::Raw(value) => Ok(value),
+                    Probability::Percent(s) => parse_percent(&s).map_err(|e| {
+                        serde::de::Error::custom(format!(
+                            "Failed to parse percentage '{}': {}",
+                            s, e
+                        ))
+                    }),
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Serializes an optional pattern of probabilities as percentage strings.
+///
+/// A probability which cannot be represented exactly as a percentage is serialized as a raw number.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `serialize_with` annotations.
+pub fn serialize<S>(pattern: &Option<Vec<f64>>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    pattern
+        .as_ref()
+        .map(|pattern| {
+            pattern
+                .iter()
+                .map(|value| match format_percent(*value) {
+                    Some(percent) => Probability::Percent(percent),
+                    None => Probability::Raw(*value),
+                })
+                .collect::<Vec<_>>()
+        })
+        .serialize(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Pattern {
+        #[serde(with = "super")]
+        pattern: Option<Vec<f64>>,
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let pattern = Pattern {
+            pattern: Some(vec![0.0, 0.5, 1.0, 0.1, 0.07, 0.125]),
+        };
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(
+            json,
+            "{\"pattern\":[\"0%\",\"50%\",\"100%\",\"10%\",\"7%\",\"12.5%\"]}"
+        );
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), pattern);
+
+        // raw numbers are still accepted
+        let raw: Pattern = serde_json::from_str("{\"pattern\":[0.5,\"20 %\",1]}").unwrap();
+        assert_eq!(raw.pattern, Some(vec![0.5, 0.2, 1.0]));
+
+        let none = Pattern { pattern: None };
+        let json = serde_json::to_string(&none).unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), none);
+
+        let err = serde_json::from_str::<Pattern>("{\"pattern\":[\"20\"]}")
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse percentage '20': missing '%' suffix"));
+    }
+
+    #[test]
+    fn test_format_percent() {
+        assert_eq!(format_percent(0.0).as_deref(), Some("0%"));
+        assert_eq!(format_percent(0.333).as_deref(), Some("33.3%"));
+        assert_eq!(format_percent(0.00025).as_deref(), Some("0.025%"));
+        assert_eq!(format_percent(12.0).as_deref(), Some("1200%"));
+        assert_eq!(format_percent(-0.125).as_deref(), Some("-12.5%"));
+        assert_eq!(format_percent(f64::NAN), None);
+        for value in [0.001, 0.3, 0.29, 1.0 / 3.0, 1e-300, f64::MIN_POSITIVE] {
+            let percent = format_percent(value).unwrap();
+            assert_eq!(parse_percent(&percent), Ok(value), "{}", percent);
+        }
+        assert_eq!(parse_percent("1.5e1%"), Ok(0.15));
+    }
+}
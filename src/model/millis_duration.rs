@@ -0,0 +1,184 @@
+//! This module (de)serializes optional durations as plain integer milliseconds, e.g. `1000` for 1s,
+//! for tooling which emits durations that way.
+//!
+//! Enabled with feature `serde`.
+//!
+//! Unlike [`ns_duration`](super::ns_duration), this format is opt-in: the built-in model
+//! configurations do not use it. Use it with `#[serde(with = "netem_trace::model::millis_duration")]`
+//! on the `Option<Duration>` fields of your own structs, or on the fields of a
+//! [remote](https://serde.rs/remote-derive.html) definition mirroring a built-in configuration, as
+//! shown below.
+//!
+//! On deserialization, the usual format of durations (the `{secs, nanos}` struct, or the
+//! human-readable string if `human` feature is enabled) is accepted as well. On serialization,
+//! a duration which is not a whole number of milliseconds is an error instead of being silently
+//! truncated, and durations longer than [`u64::MAX`] milliseconds saturate.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::StaticBwConfig;
+//! # use netem_trace::{Bandwidth, Duration};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! #[serde(remote = "StaticBwConfig")]
+//! struct StaticBwConfigMillis {
+//!     #[serde(skip_serializing_if = "Option::is_none")]
+//!     bw: Option<Bandwidth>,
+//!     #[serde(with = "netem_trace::model::millis_duration")]
+//!     duration: Option<Duration>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config(#[serde(with = "StaticBwConfigMillis")] StaticBwConfig);
+//!
+//! let config: Config = serde_json::from_str("{\"duration\":1000}").unwrap();
+//! assert_eq!(config.0.duration, Some(Duration::from_secs(1)));
+//! assert_eq!(serde_json::to_string(&config).unwrap(), "{\"duration\":1000}");
+//! ```
+use crate::Duration;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// A duration (de)serialized as integer milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Millis(Duration);
+
+impl Serialize for Millis {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !self.0.subsec_nanos().is_multiple_of(1_000_000) {
+            return Err(ser::Error::custom(format!(
+                "duration {:?} is not a whole number of milliseconds",
+                self.0
+            )));
+        }
+        s.serialize_u64(u64::try_from(self.0.as_millis()).unwrap_or(u64::MAX))
+    }
+}
+
+impl<'de> Deserialize<'de> for Millis {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> de::Visitor<'de> for V {
+            type Value = Millis;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt.write_str("integer milliseconds or a duration")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Millis, E> {
+                Ok(Millis(Duration::from_millis(v)))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Millis, E> {
+                u64::try_from(v)
+                    .map(|v| Millis(Duration::from_millis(v)))
+                    .map_err(|_| E::custom(format!("negative duration {}ms", v)))
+            }
+
+            #[cfg(feature = "human")]
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Millis, E> {
+                crate::model::human_duration::parse_duration(v)
+                    .map(Millis)
+                    .map_err(|e| E::custom(format!("Failed to parse duration '{}': {}", v, e)))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Millis, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                Duration::deserialize(de::value::MapAccessDeserializer::new(map)).map(Millis)
+            }
+        }
+
+        d.deserialize_any(V)
+    }
+}
+
+/// Deserializes an optional duration from integer milliseconds.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `deserialize_with` annotations.
+pub fn deserialize<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Millis>::deserialize(d)?.map(|millis| millis.0))
+}
+
+/// Serializes an optional duration as integer milliseconds.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `serialize_with` annotations.
+pub fn serialize<S>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    d.map(Millis).serialize(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_millis() {
+        let millis: Millis = serde_json::from_str("1500").unwrap();
+        assert_eq!(millis, Millis(Duration::from_millis(1500)));
+        let millis: Millis = serde_json::from_str("{\"secs\":1,\"nanos\":5}").unwrap();
+        assert_eq!(millis, Millis(Duration::new(1, 5)));
+        assert!(serde_json::from_str::<Millis>("-1").is_err());
+        assert!(serde_json::from_str::<Millis>("1.5").is_err());
+        #[cfg(feature = "human")]
+        assert_eq!(
+            serde_json::from_str::<Millis>("\"10ms\"").unwrap(),
+            Millis(Duration::from_millis(10))
+        );
+        assert_eq!(
+            serde_json::to_string(&Millis(Duration::from_secs(1))).unwrap(),
+            "1000"
+        );
+        assert!(serde_json::to_string(&Millis(Duration::from_micros(1500))).is_err());
+        assert_eq!(
+            serde_json::to_string(&Millis(Duration::from_secs(u64::MAX))).unwrap(),
+            u64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bw-model")]
+    fn test_static_bw_config_millis() {
+        use crate::model::StaticBwConfig;
+        use crate::Bandwidth;
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(remote = "StaticBwConfig")]
+        struct StaticBwConfigMillis {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            bw: Option<Bandwidth>,
+            #[serde(with = "super")]
+            duration: Option<Duration>,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct Config(#[serde(with = "StaticBwConfigMillis")] StaticBwConfig);
+
+        let config: Config = serde_json::from_str("{\"duration\":1000}").unwrap();
+        assert_eq!(config.0.duration, Some(Duration::from_secs(1)));
+        let config = Config(StaticBwConfig::new().duration(Duration::from_secs(1)));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            "{\"duration\":1000}"
+        );
+        let config: Config = serde_json::from_str("{\"duration\":null}").unwrap();
+        assert_eq!(config.0.duration, None);
+    }
+}
@@ -3,48 +3,444 @@
 //! A model has two parts: a configuration struct and a model struct.
 //! The configuration struct is used to configure the model and
 //! used for serialization/deserialization if `serde` feature is enabled.
-//! The model struct which implements trait `BwTrace`, `DelayTrace`, `LossTrace` or `DuplicateTrace`
+//! The model struct which implements trait `BwTrace`, `DelayTrace`, `DelayPerPacketTrace`, `LossTrace` or `DuplicateTrace`
 //! is used to generate the trace and maintain inner states.
 //!
 //! Enable `bw-model` feature to use the BwTrace models.
 //! Enable `delay-model` feature to use the DelayTrace models.
+//! Enable `delay-per-packet-model` feature to use the DelayPerPacketTrace models.
 //! Enable `loss-model` feature to use the LossTrace models.
 //! Enable `duplicate-model` feature to use the DuplicateTrace models.
+//!
+//! ## Custom RNGs
+//!
+//! The random models draw from a `StdRng` seeded by the `seed` of their configs. The configs of
+//! these models also provide `build_with_rng` to draw from any other RNG instead, e.g. a portable
+//! RNG to reproduce a trace across versions of `rand`, or a RNG whose state can be checkpointed
+//! with feature `rng-state` enabled.
+//!
+//! The trace then depends only on the state of the given RNG, not on `seed`. The `seed` is still
+//! taken from the config, and is used to re-seed a RNG of the same type when the model is reset
+//! (e.g. with [`Resettable::reset`](crate::Resettable::reset)), so the trace replayed after a reset
+//! differs from the first one unless the given RNG was seeded with the same `seed`.
+
+/// Turn a trace configuration into a repeated pattern configuration, e.g. `RepeatedBwPatternConfig`
+/// for bandwidth trace configurations.
+///
+/// This trait is implemented for all pre-defined configurations. Use the `impl_forever` macro
+/// to implement it for your own bandwidth trace configurations.
+pub trait Repeatable: Sized {
+    /// The configuration type of the repeated pattern.
+    type Repeated;
+
+    /// Repeat the trace for `count` times. If `count` is 0, the trace will be repeated forever.
+    fn repeat(self, count: usize) -> Self::Repeated;
+
+    /// Repeat the trace forever.
+    fn forever(self) -> Self::Repeated {
+        self.repeat(0)
+    }
+}
+
+/// The seed of the RNG of the random models, if not configured.
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+pub(crate) const DEFAULT_RNG_SEED: u64 = 42;
+
+/// The error returned when a model configuration is invalid, e.g. by
+/// [`SawtoothBwConfig::try_build`](bw::SawtoothBwConfig::try_build).
+///
+/// The `build` method of the configuration panics with the same message instead.
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ModelBuildError {
+    /// The lower bound `lower` of the model is larger than its upper bound `upper`.
+    InvalidBounds {
+        model: &'static str,
+        lower: &'static str,
+        upper: &'static str,
+    },
+    /// The parameter `name` of the model is `value`, which is not `expected`.
+    InvalidParameter {
+        model: &'static str,
+        name: &'static str,
+        expected: &'static str,
+        value: f64,
+    },
+    /// The duration parameter `name` of the model (e.g. `step`) is zero, which must be positive.
+    ZeroDuration {
+        model: &'static str,
+        name: &'static str,
+    },
+    /// The configuration of the model is inconsistent, e.g. a transition matrix whose size does not
+    /// match the number of states, as explained by `reason`.
+    InvalidConfig { model: &'static str, reason: String },
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl std::fmt::Display for ModelBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelBuildError::InvalidBounds {
+                model,
+                lower,
+                upper,
+            } => write!(f, "{}: {} must not be larger than {}", model, lower, upper),
+            ModelBuildError::InvalidParameter {
+                model,
+                name,
+                expected,
+                value,
+            } => write!(f, "{}: {} must be {}, got {}", model, name, expected, value),
+            ModelBuildError::ZeroDuration { model, name } => {
+                write!(f, "{}: {} must be positive", model, name)
+            }
+            ModelBuildError::InvalidConfig { model, reason } => write!(f, "{}: {}", model, reason),
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl std::error::Error for ModelBuildError {}
+
+/// Detects a pattern whose sub-models all end without producing anything, so that a pattern
+/// repeated forever ends instead of spinning, e.g. one of zero-duration sub-models.
+///
+/// Shared by the repeated pattern models and [`RandomPatternBw`](bw::RandomPatternBw). As the
+/// sub-models are built from their configs, a sub-model which ends without producing anything is
+/// expected to do the same whenever it is played again.
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EmptyPatternGuard {
+    /// Whether each sub-model of the pattern is known to produce nothing.
+    empty: Vec<bool>,
+    /// The number of sub-models not known to produce nothing.
+    remaining: usize,
+    /// Whether the current sub-model has produced anything.
+    produced: bool,
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "delay-per-packet-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl EmptyPatternGuard {
+    /// Guard a pattern of `len` sub-models.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            empty: vec![false; len],
+            remaining: len,
+            produced: false,
+        }
+    }
+
+    /// Leave the sub-model at `index` out, e.g. because it is never played.
+    pub(crate) fn exclude(&mut self, index: usize) {
+        if let Some(empty) = self.empty.get_mut(index) {
+            if !*empty {
+                *empty = true;
+                self.remaining -= 1;
+            }
+        }
+    }
+
+    /// Record that the current sub-model produced something.
+    pub(crate) fn produced(&mut self) {
+        self.produced = true;
+    }
+
+    /// Record that the current sub-model, the one at `index`, ended.
+    ///
+    /// Returns `false` if none of the sub-models can produce anything any more.
+    pub(crate) fn ended(&mut self, index: usize) -> bool {
+        if !std::mem::take(&mut self.produced) {
+            self.exclude(index);
+        }
+        self.remaining > 0
+    }
+
+    /// Forget the current sub-model, when the pattern is reset.
+    pub(crate) fn reset(&mut self) {
+        self.produced = false;
+    }
+}
+
+/// Replays a model from a copy of its initial state, so that it is resettable without keeping its
+/// initial state in its own public fields, e.g. [`StaticBw`](bw::StaticBw) whose `duration` is
+/// taken once played.
+///
+/// Returned by `into_model` of the configs of such models.
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+#[derive(Debug, Clone)]
+pub(crate) struct Rewind<T> {
+    initial: T,
+    pub(crate) model: T,
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl<T: Clone> Rewind<T> {
+    pub(crate) fn new(model: T) -> Self {
+        Self {
+            initial: model.clone(),
+            model,
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+impl<T: Clone> crate::Resettable for Rewind<T> {
+    fn reset(&mut self) {
+        self.model.clone_from(&self.initial);
+    }
+}
+
+/// Merges two traces at the boundaries of their segments, shared by the models combining two
+/// traces, e.g. [`SerializationPlusPropDelay`](delay::SerializationPlusPropDelay).
+///
+/// The traces are played lazily, segment by segment, so either of them may go on forever.
+/// Zero-duration segments are skipped, and the merged trace ends when either trace ends.
+#[cfg(any(
+    all(feature = "bw-model", feature = "delay-model"),
+    feature = "loss-model"
+))]
+#[derive(Debug, Clone)]
+pub(crate) struct SegmentMerger<A, B> {
+    /// The current segment of each trace, with its remaining duration.
+    a: Option<(A, crate::Duration)>,
+    b: Option<(B, crate::Duration)>,
+}
+
+#[cfg(any(
+    all(feature = "bw-model", feature = "delay-model"),
+    feature = "loss-model"
+))]
+impl<A, B> SegmentMerger<A, B> {
+    pub(crate) fn new() -> Self {
+        Self { a: None, b: None }
+    }
+
+    /// Returns the next merged segment, i.e. the current values of both traces and the duration
+    /// until either of them changes, or `None` once either trace ends.
+    ///
+    /// `next_a` and `next_b` are called for the next segment of a trace once its current segment
+    /// is used up.
+    pub(crate) fn next(
+        &mut self,
+        next_a: impl FnMut() -> Option<(A, crate::Duration)>,
+        next_b: impl FnMut() -> Option<(B, crate::Duration)>,
+    ) -> Option<(&A, &B, crate::Duration)> {
+        let (a, a_remaining) = Self::refill(&mut self.a, next_a)?;
+        let (b, b_remaining) = Self::refill(&mut self.b, next_b)?;
+        let duration = (*a_remaining).min(*b_remaining);
+        *a_remaining -= duration;
+        *b_remaining -= duration;
+        Some((a, b, duration))
+    }
+
+    /// Pull segments into `current` until it has some duration left.
+    fn refill<T>(
+        current: &mut Option<(T, crate::Duration)>,
+        mut next: impl FnMut() -> Option<(T, crate::Duration)>,
+    ) -> Option<&mut (T, crate::Duration)> {
+        while current
+            .as_ref()
+            .is_none_or(|(_, remaining)| remaining.is_zero())
+        {
+            *current = Some(next()?);
+        }
+        current.as_mut()
+    }
+}
 
 #[cfg(feature = "bw-model")]
 pub mod bw;
 
+#[cfg(feature = "bw-model")]
+pub use bw::registered_bw_config_tags;
 #[cfg(feature = "bw-model")]
 pub use bw::{
-    BwTraceConfig, Forever, NormalizedBwConfig, RepeatedBwPatternConfig, SawtoothBwConfig,
-    StaticBwConfig, TraceBwConfig,
+    BwTraceConfig, ClampedBwConfig, DelayedStartBwConfig, EmpiricalBwConfig, EnvBwConfig,
+    ExponentialDwellBwConfig, FloorPlusVariableBwConfig, Forever, GammaBwConfig,
+    HysteresisBwConfig, Interpolation, MarkovBwConfig, NormalizedBwConfig, ParetoBwConfig,
+    PoissonBwConfig, QuantizedBwConfig, RampBwConfig, RandomOutageBwConfig, RandomPatternBwConfig,
+    RandomPhaseBwConfig, RepeatedBwPatternConfig, ReverseBwConfig, SawtoothBwConfig,
+    ScaledBwConfig, ScheduleBwConfig, SequenceBwConfig, SineBwConfig, SpliceBwConfig,
+    SplineBwConfig, SquareBwConfig, StaticBwConfig, StepBwConfig, SumBwConfig, TaggedBwConfig,
+    TraceBwConfig, WeibullBwConfig, WindowMaxBwConfig, DEFAULT_MAX_NESTING_DEPTH,
 };
 #[cfg(feature = "bw-model")]
-pub use bw::{NormalizedBw, RepeatedBwPattern, SawtoothBw, StaticBw, TraceBw};
+pub use bw::{
+    ClampedBw, DelayedStartBw, EmpiricalBw, ExponentialDwellBw, FloorPlusVariableBw, GammaBw,
+    HysteresisBw, MarkovBw, NormalizedBw, ParetoBw, PoissonBw, QuantizedBw, RampBw, RandomOutageBw,
+    RandomPatternBw, RandomPhaseBw, RepeatedBwPattern, ReverseBw, SawtoothBw, ScaledBw, ScheduleBw,
+    SequenceBw, SineBw, SpliceBw, SplineBw, SquareBw, StaticBw, StepBw, SumBw, TaggedBw, TraceBw,
+    WeibullBw, WindowMaxBw,
+};
+#[cfg(all(feature = "bw-model", feature = "loss-model"))]
+pub use bw::{LossAsGapsBw, LossAsGapsBwConfig};
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "csv")]
+pub use csv::TraceBwError;
+
+#[cfg(feature = "checksum")]
+pub mod checksum;
+
+#[cfg(feature = "checksum")]
+pub use checksum::ChecksummedConfig;
+
+#[cfg(feature = "schema")]
+pub mod schema;
+
+#[cfg(feature = "schema")]
+pub use schema::config_schema_json;
+
+#[cfg(feature = "rng-state")]
+pub mod rng_state;
+
+#[cfg(feature = "rng-state")]
+pub use rng_state::{RngState, RngStateError};
 
 #[cfg(feature = "delay-model")]
 pub mod delay;
 
 #[cfg(feature = "delay-model")]
-pub use delay::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+pub use delay::registered_delay_config_tags;
+#[cfg(all(feature = "delay-model", feature = "bw-model"))]
+pub use delay::{
+    serialization_plus_prop_delay, SerializationPlusPropDelay, SerializationPlusPropDelayConfig,
+};
+#[cfg(feature = "delay-model")]
+pub use delay::{
+    DelayTraceConfig, DelayedStartDelayConfig, NormalizedDelayConfig, RepeatedDelayPatternConfig,
+    StaticDelayConfig, TraceDelayConfig,
+};
 #[cfg(feature = "delay-model")]
-pub use delay::{RepeatedDelayPattern, StaticDelay};
+pub use delay::{
+    DelayedStartDelay, NormalizedDelay, RepeatedDelayPattern, StaticDelay, TraceDelay,
+};
+#[cfg(all(feature = "delay-model", feature = "delay-per-packet-model"))]
+pub use delay::{PerPacketAsDelay, PerPacketAsDelayConfig};
+
+#[cfg(feature = "delay-per-packet-model")]
+pub mod delay_per_packet;
+
+#[cfg(feature = "delay-per-packet-model")]
+pub use delay_per_packet::registered_delay_per_packet_config_tags;
+#[cfg(all(
+    feature = "delay-per-packet-model",
+    feature = "bw-model",
+    feature = "delay-model"
+))]
+pub use delay_per_packet::{
+    time_sliced_to_per_packet, TimeSlicedAsPerPacket, TimeSlicedAsPerPacketConfig,
+};
+#[cfg(feature = "delay-per-packet-model")]
+pub use delay_per_packet::{
+    DelayPerPacketTraceConfig, LogNormalDelayPerPacketConfig, NormalizedDelayPerPacketConfig,
+    RepeatedDelayPerPacketPatternConfig, StaticDelayPerPacketConfig,
+};
+#[cfg(feature = "delay-per-packet-model")]
+pub use delay_per_packet::{
+    LogNormalDelayPerPacket, NormalizedDelayPerPacket, RepeatedDelayPerPacketPattern,
+    StaticDelayPerPacket,
+};
 
 #[cfg(feature = "loss-model")]
 pub mod loss;
 
+#[cfg(all(feature = "loss-model", feature = "bw-model"))]
+pub use loss::apply_loss_as_gaps;
 #[cfg(feature = "loss-model")]
-pub use loss::{LossTraceConfig, RepeatedLossPatternConfig, StaticLossConfig};
+pub use loss::{combine_loss, registered_loss_config_tags, CombinedLoss, CombinedLossConfig};
 #[cfg(feature = "loss-model")]
-pub use loss::{RepeatedLossPattern, StaticLoss};
+pub use loss::{
+    LossTraceConfig, NormalizedLossConfig, RandomLossConfig, RepeatedLossPatternConfig,
+    StaticLossConfig, TraceLossConfig,
+};
+#[cfg(feature = "loss-model")]
+pub use loss::{NormalizedLoss, RandomLoss, RepeatedLossPattern, StaticLoss, TraceLoss};
 
 #[cfg(feature = "duplicate-model")]
 pub mod duplicate;
 
 #[cfg(feature = "duplicate-model")]
-pub use duplicate::{DuplicateTraceConfig, RepeatedDuplicatePatternConfig, StaticDuplicateConfig};
+pub use duplicate::registered_duplicate_config_tags;
+#[cfg(feature = "duplicate-model")]
+pub use duplicate::{
+    DuplicateTraceConfig, NormalizedDuplicateConfig, RepeatedDuplicatePatternConfig,
+    StaticDuplicateConfig,
+};
 #[cfg(feature = "duplicate-model")]
-pub use duplicate::{RepeatedDuplicatePattern, StaticDuplicate};
+pub use duplicate::{NormalizedDuplicate, RepeatedDuplicatePattern, StaticDuplicate};
 
 #[cfg(feature = "truncated-normal")]
 pub mod solve_truncate;
+
+#[cfg(all(feature = "serde", feature = "human"))]
+pub mod human_duration;
+
+#[cfg(all(feature = "serde", feature = "human"))]
+pub mod human_percent;
+
+#[cfg(all(feature = "serde", feature = "ns-number"))]
+pub mod ns_duration;
+
+#[cfg(feature = "serde")]
+pub mod millis_duration;
+
+#[cfg(all(feature = "bw-model", feature = "human"))]
+pub mod compact;
+
+#[cfg(all(feature = "bw-model", feature = "human"))]
+pub use compact::ParseConfigError;
@@ -0,0 +1,310 @@
+//! This module (de)serializes durations as plain integer nanoseconds, e.g. `1000000` for 1ms.
+//!
+//! Enabled with feature `serde` and `ns-number`.
+//!
+//! With this feature, all duration fields of the model configurations accept a bare integer as
+//! nanoseconds on deserialization, in addition to their usual format (the `{secs, nanos}` struct,
+//! or the human-readable string if `human` feature is enabled).
+//!
+//! If `human` feature is **not** enabled, durations are serialized as integer nanoseconds.
+//! If `human` feature is enabled, it takes precedence on serialization, i.e. durations are still
+//! serialized as human-readable strings, while integer nanoseconds are accepted on deserialization.
+//! The durations nested in [`TraceBwConfig`](super::TraceBwConfig) and [`SplineBwConfig`](super::SplineBwConfig)
+//! only accept human-readable strings in that case.
+//!
+//! Durations longer than [`u64::MAX`] nanoseconds (about 584 years) saturate on serialization.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::StaticBwConfig;
+//! # use netem_trace::Duration;
+//! let config: StaticBwConfig = serde_json::from_str("{\"duration\":1000000}").unwrap();
+//! assert_eq!(config.duration, Some(Duration::from_millis(1)));
+//! ```
+use crate::Duration;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// A duration (de)serialized as integer nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Nanos(pub Duration);
+
+impl Serialize for Nanos {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_u64(u64::try_from(self.0.as_nanos()).unwrap_or(u64::MAX))
+    }
+}
+
+impl<'de> Deserialize<'de> for Nanos {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> de::Visitor<'de> for V {
+            type Value = Nanos;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt.write_str("integer nanoseconds or a duration")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Nanos, E> {
+                Ok(Nanos(Duration::from_nanos(v)))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Nanos, E> {
+                u64::try_from(v)
+                    .map(|v| Nanos(Duration::from_nanos(v)))
+                    .map_err(|_| E::custom(format!("negative duration {}ns", v)))
+            }
+
+            #[cfg(feature = "human")]
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Nanos, E> {
+                crate::model::human_duration::parse_duration(v)
+                    .map(Nanos)
+                    .map_err(|e| E::custom(format!("Failed to parse duration '{}': {}", v, e)))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Nanos, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                Duration::deserialize(de::value::MapAccessDeserializer::new(map)).map(Nanos)
+            }
+        }
+
+        d.deserialize_any(V)
+    }
+}
+
+/// Deserializes an optional duration from integer nanoseconds.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `deserialize_with` annotations.
+pub fn deserialize<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Nanos>::deserialize(d)?.map(|nanos| nanos.0))
+}
+
+/// Serializes an optional duration as integer nanoseconds.
+///
+/// This function can be used with `serde_derive`'s `with` and
+/// `serialize_with` annotations.
+pub fn serialize<S>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    d.map(Nanos).serialize(s)
+}
+
+/// (De)serializes the pattern of [`TraceBwConfig`](super::TraceBwConfig) with integer nanoseconds.
+#[cfg(all(feature = "bw-model", not(feature = "human")))]
+pub(crate) mod trace_pattern {
+    use super::*;
+    use crate::Bandwidth;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Duration, Vec<Bandwidth>)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Nanos, Vec<Bandwidth>)>::deserialize(d)?
+            .into_iter()
+            .map(|(duration, bandwidths)| (duration.0, bandwidths))
+            .collect())
+    }
+
+    pub fn serialize<S>(pattern: &[(Duration, Vec<Bandwidth>)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            pattern
+                .iter()
+                .map(|(duration, bandwidths)| (Nanos(*duration), bandwidths)),
+        )
+    }
+}
+
+/// (De)serializes the points of [`SplineBwConfig`](super::SplineBwConfig) with integer nanoseconds.
+#[cfg(all(feature = "bw-model", not(feature = "human")))]
+pub(crate) mod points {
+    use super::*;
+    use crate::Bandwidth;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Duration, Bandwidth)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Nanos, Bandwidth)>::deserialize(d)?
+            .into_iter()
+            .map(|(time, bw)| (time.0, bw))
+            .collect())
+    }
+
+    pub fn serialize<S>(points: &[(Duration, Bandwidth)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(points.iter().map(|(time, bw)| (Nanos(*time), bw)))
+    }
+}
+
+/// (De)serializes the sequence of [`SequenceBwConfig`](super::SequenceBwConfig) with integer nanoseconds.
+#[cfg(all(feature = "bw-model", not(feature = "human")))]
+pub(crate) mod sequence {
+    use super::*;
+    use crate::Bandwidth;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Bandwidth, Duration)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Bandwidth, Nanos)>::deserialize(d)?
+            .into_iter()
+            .map(|(bw, duration)| (bw, duration.0))
+            .collect())
+    }
+
+    pub fn serialize<S>(sequence: &[(Bandwidth, Duration)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(sequence.iter().map(|(bw, duration)| (bw, Nanos(*duration))))
+    }
+}
+
+/// (De)serializes the pattern of [`TraceDelayConfig`](super::TraceDelayConfig) with integer nanoseconds.
+#[cfg(all(feature = "delay-model", not(feature = "human")))]
+pub(crate) mod delay_pattern {
+    use super::*;
+    use crate::Delay;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Duration, Delay)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Nanos, Nanos)>::deserialize(d)?
+            .into_iter()
+            .map(|(duration, delay)| (duration.0, delay.0))
+            .collect())
+    }
+
+    pub fn serialize<S>(pattern: &[(Duration, Delay)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            pattern
+                .iter()
+                .map(|(duration, delay)| (Nanos(*duration), Nanos(*delay))),
+        )
+    }
+}
+
+/// (De)serializes the pattern of [`TraceLossConfig`](super::TraceLossConfig) with integer nanoseconds.
+#[cfg(all(feature = "loss-model", not(feature = "human")))]
+pub(crate) mod loss_pattern {
+    use super::*;
+    use crate::LossPattern;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<(Duration, LossPattern)>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Nanos, LossPattern)>::deserialize(d)?
+            .into_iter()
+            .map(|(duration, loss)| (duration.0, loss))
+            .collect())
+    }
+
+    pub fn serialize<S>(pattern: &[(Duration, LossPattern)], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            pattern
+                .iter()
+                .map(|(duration, loss)| (Nanos(*duration), loss)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nanos() {
+        let nanos: Nanos = serde_json::from_str("1000000").unwrap();
+        assert_eq!(nanos, Nanos(Duration::from_millis(1)));
+        let nanos: Nanos = serde_json::from_str("{\"secs\":1,\"nanos\":5}").unwrap();
+        assert_eq!(nanos, Nanos(Duration::new(1, 5)));
+        assert!(serde_json::from_str::<Nanos>("-1").is_err());
+        assert!(serde_json::from_str::<Nanos>("1.5").is_err());
+        #[cfg(feature = "human")]
+        assert_eq!(
+            serde_json::from_str::<Nanos>("\"10ms\"").unwrap(),
+            Nanos(Duration::from_millis(10))
+        );
+        assert_eq!(
+            serde_json::to_string(&Nanos(Duration::from_millis(1))).unwrap(),
+            "1000000"
+        );
+        assert_eq!(
+            serde_json::to_string(&Nanos(Duration::MAX)).unwrap(),
+            u64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bw-model")]
+    fn test_ns_number() {
+        use crate::model::{BwTraceConfig, StaticBwConfig};
+
+        let config: StaticBwConfig = serde_json::from_str("{\"duration\":1000000}").unwrap();
+        assert_eq!(config.duration, Some(Duration::from_millis(1)));
+        let config = serde_json::from_str::<Box<dyn BwTraceConfig>>(
+            "{\"RepeatedBwPatternConfig\":{\"pattern\":[{\"StaticBwConfig\":{\"duration\":2000}}],\"count\":1}}",
+        )
+        .unwrap();
+        let mut model = config.into_model();
+        assert_eq!(model.next_bw().unwrap().1, Duration::from_micros(2));
+
+        let ser_str =
+            serde_json::to_string(&StaticBwConfig::new().duration(Duration::from_millis(1)))
+                .unwrap();
+        #[cfg(feature = "human")]
+        assert_eq!(ser_str, "{\"duration\":\"1ms\"}");
+        #[cfg(not(feature = "human"))]
+        {
+            assert_eq!(ser_str, "{\"duration\":1000000}");
+            use crate::model::{SplineBwConfig, TraceBwConfig};
+            use crate::Bandwidth;
+            let config: TraceBwConfig =
+                serde_json::from_str("{\"pattern\":[[1000000,[{\"gbps\":0,\"bps\":12000000}]]]}")
+                    .unwrap();
+            assert_eq!(
+                config.pattern,
+                vec![(Duration::from_millis(1), vec![Bandwidth::from_mbps(12)])]
+            );
+            assert_eq!(
+                serde_json::to_string(&config).unwrap(),
+                "{\"pattern\":[[1000000,[{\"gbps\":0,\"bps\":12000000}]]]}"
+            );
+            let config: SplineBwConfig =
+                serde_json::from_str("{\"points\":[[1000000,{\"gbps\":0,\"bps\":12000000}]]}")
+                    .unwrap();
+            assert_eq!(
+                config.points,
+                vec![(Duration::from_millis(1), Bandwidth::from_mbps(12))]
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! This module exposes the state of the RNG of the random models, so that a model can be
+//! checkpointed mid-stream and resumed later to the identical continuation.
+//!
+//! Enable `rng-state` feature to use this module.
+//!
+//! The state of [`StdRng`](rand::rngs::StdRng) is not exposed by `rand`, so the models must be
+//! built with a RNG implementing [`RngState`] instead, e.g. with
+//! [`NormalizedBwConfig::build_with_rng`](super::NormalizedBwConfig::build_with_rng) and a
+//! [`ChaCha20Rng`], which is also portable across versions of `rand`.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::RngState;
+//! use rand::{RngCore, SeedableRng};
+//! use rand_chacha::ChaCha20Rng;
+//!
+//! let mut rng = ChaCha20Rng::seed_from_u64(42);
+//! rng.next_u64();
+//! let state = rng.rng_state();
+//! let next = rng.next_u64();
+//!
+//! let mut restored = ChaCha20Rng::seed_from_u64(0);
+//! restored.restore_rng_state(&state).unwrap();
+//! assert_eq!(restored.next_u64(), next);
+//! ```
+use rand::SeedableRng;
+use rand_chacha::{ChaCha12Rng, ChaCha20Rng, ChaCha8Rng};
+
+/// The length of the state of the ChaCha RNGs: the seed (32 bytes), the stream (8 bytes) and the
+/// word position (16 bytes).
+const CHACHA_STATE_LEN: usize = 56;
+
+/// The error returned when restoring a RNG from an invalid state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RngStateError {
+    /// The state is `actual` bytes long instead of `expected`.
+    InvalidLength { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for RngStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RngStateError::InvalidLength { expected, actual } => write!(
+                f,
+                "RNG state must be {} bytes long, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RngStateError {}
+
+/// The `RngState` trait provides methods to save and restore the state of a RNG as bytes.
+pub trait RngState {
+    /// Save the state of the RNG.
+    fn rng_state(&self) -> Vec<u8>;
+
+    /// Restore the state of the RNG saved by [`rng_state`](Self::rng_state), so that it generates
+    /// the same numbers as the RNG it was saved from.
+    ///
+    /// Returns `Err` and leaves the RNG untouched if the state is invalid.
+    fn restore_rng_state(&mut self, state: &[u8]) -> Result<(), RngStateError>;
+}
+
+macro_rules! impl_chacha_rng_state {
+    ($($rng:ty),+ $(,)?) => {
+        $(
+            impl RngState for $rng {
+                fn rng_state(&self) -> Vec<u8> {
+                    let mut state = Vec::with_capacity(CHACHA_STATE_LEN);
+                    state.extend_from_slice(&self.get_seed());
+                    state.extend_from_slice(&self.get_stream().to_le_bytes());
+                    state.extend_from_slice(&self.get_word_pos().to_le_bytes());
+                    state
+                }
+
+                fn restore_rng_state(&mut self, state: &[u8]) -> Result<(), RngStateError> {
+                    if state.len() != CHACHA_STATE_LEN {
+                        return Err(RngStateError::InvalidLength {
+                            expected: CHACHA_STATE_LEN,
+                            actual: state.len(),
+                        });
+                    }
+                    let (seed, rest) = state.split_at(32);
+                    let (stream, word_pos) = rest.split_at(8);
+                    let mut rng = <$rng>::from_seed(seed.try_into().unwrap());
+                    rng.set_stream(u64::from_le_bytes(stream.try_into().unwrap()));
+                    rng.set_word_pos(u128::from_le_bytes(word_pos.try_into().unwrap()));
+                    *self = rng;
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+impl_chacha_rng_state!(ChaCha8Rng, ChaCha12Rng, ChaCha20Rng);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn test_chacha_rng_state() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        rng.set_stream(3);
+        // stop in the middle of a block
+        rng.next_u32();
+        let state = rng.rng_state();
+        assert_eq!(state.len(), CHACHA_STATE_LEN);
+        let expected: Vec<u64> = (0..10).map(|_| rng.next_u64()).collect();
+
+        let mut restored = ChaCha8Rng::seed_from_u64(0);
+        restored.restore_rng_state(&state).unwrap();
+        let resumed: Vec<u64> = (0..10).map(|_| restored.next_u64()).collect();
+        assert_eq!(resumed, expected);
+
+        assert_eq!(
+            restored.restore_rng_state(&state[1..]),
+            Err(RngStateError::InvalidLength {
+                expected: 56,
+                actual: 55
+            })
+        );
+        // untouched by the failed restore
+        assert_eq!(restored.rng_state(), rng.rng_state());
+    }
+}
@@ -0,0 +1,889 @@
+//! This module exports a machine-readable description of the built-in model configurations,
+//! e.g. for generating editors of configuration files.
+//!
+//! Enabled with feature `schema`.
+//!
+//! The description lists, for each enabled kind of trace (`bw`, `delay`, `delay_per_packet`, `loss`
+//! and `duplicate`), the registered configs with their tags and fields. Each field has a name, a type
+//! and the default value applied when the field is omitted, written in the same format as in a
+//! configuration file (so it depends on the `human` and `ns-number` features), or `null` if the field
+//! has no default.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::config_schema_json;
+//! let schema = config_schema_json();
+//! let static_bw = schema["bw"]
+//!     .as_array()
+//!     .unwrap()
+//!     .iter()
+//!     .find(|config| config["tag"] == "StaticBwConfig")
+//!     .unwrap();
+//! assert_eq!(static_bw["fields"][0]["name"], "bw");
+//! assert_eq!(static_bw["fields"][0]["type"], "Bandwidth");
+//! # #[cfg(feature = "human")]
+//! assert_eq!(static_bw["fields"][0]["default"], "12Mbps");
+//! ```
+#[cfg(feature = "bw-model")]
+use crate::Bandwidth;
+#[cfg(any(feature = "delay-model", feature = "delay-per-packet-model"))]
+use crate::Delay;
+#[cfg(any(
+    feature = "bw-model",
+    feature = "delay-model",
+    feature = "loss-model",
+    feature = "duplicate-model"
+))]
+use crate::Duration;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Describe a config by its fields as `(name, type)` pairs, with the defaults taken from the
+/// serialization of `defaults`, i.e. the config with every defaulted field set.
+#[allow(dead_code)]
+fn describe<C: Serialize>(tag: &str, fields: &[(&str, &str)], defaults: C) -> Value {
+    let defaults = serde_json::to_value(defaults).unwrap_or_default();
+    let fields = fields
+        .iter()
+        .map(|(name, ty)| {
+            json!({
+                "name": name,
+                "type": ty,
+                "default": defaults.get(name).cloned().unwrap_or_default(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "tag": tag, "fields": fields })
+}
+
+#[cfg(feature = "bw-model")]
+fn bw_configs() -> Vec<Value> {
+    use super::bw::*;
+
+    let seed = super::DEFAULT_RNG_SEED;
+    vec![
+        describe(
+            "StaticBwConfig",
+            &[("bw", "Bandwidth"), ("duration", "Duration")],
+            StaticBwConfig::new()
+                .bw(Bandwidth::from_mbps(12))
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "NormalizedBwConfig",
+            &[
+                ("mean", "Bandwidth"),
+                ("std_dev", "Bandwidth"),
+                ("upper_bound", "Bandwidth"),
+                ("lower_bound", "Bandwidth"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("exact_duration", "bool"),
+                ("seed", "u64"),
+            ],
+            NormalizedBwConfig::new()
+                .mean(Bandwidth::from_mbps(12))
+                .std_dev(Bandwidth::ZERO)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .exact_duration(true)
+                .seed(seed),
+        ),
+        describe(
+            "SawtoothBwConfig",
+            &[
+                ("bottom", "Bandwidth"),
+                ("top", "Bandwidth"),
+                ("interval", "Duration"),
+                ("duty_ratio", "f64"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+                ("std_dev", "Bandwidth"),
+                ("upper_noise_bound", "Bandwidth"),
+                ("lower_noise_bound", "Bandwidth"),
+            ],
+            SawtoothBwConfig::new()
+                .bottom(Bandwidth::ZERO)
+                .top(Bandwidth::from_mbps(12))
+                .interval(Duration::from_secs(1))
+                .duty_ratio(0.5)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed)
+                .std_dev(Bandwidth::ZERO),
+        ),
+        describe(
+            "TraceBwConfig",
+            &[
+                ("pattern", "Vec<(Duration, Vec<Bandwidth>)>"),
+                ("interpolation", "Interpolation"),
+                ("step", "Duration"),
+            ],
+            TraceBwConfig::new().interpolation(Interpolation::Hold),
+        ),
+        describe(
+            "SequenceBwConfig",
+            &[("sequence", "Vec<(Bandwidth, Duration)>")],
+            SequenceBwConfig::new(),
+        ),
+        describe(
+            "RepeatedBwPatternConfig",
+            &[("pattern", "Vec<BwTraceConfig>"), ("count", "usize")],
+            RepeatedBwPatternConfig::new(),
+        ),
+        describe(
+            "TaggedBwConfig",
+            &[("inner", "BwTraceConfig"), ("tag", "String")],
+            TaggedBwConfig::new(),
+        ),
+        describe(
+            "RandomOutageBwConfig",
+            &[
+                ("inner", "BwTraceConfig"),
+                ("drop_prob", "f64"),
+                ("drop_dur", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            RandomOutageBwConfig::new()
+                .drop_prob(0.01)
+                .drop_dur(Duration::from_millis(100))
+                .step(Duration::from_millis(100))
+                .seed(seed),
+        ),
+        describe(
+            "EmpiricalBwConfig",
+            &[
+                ("cdf", "Vec<(Bandwidth, f64)>"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            EmpiricalBwConfig::new()
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed),
+        ),
+        describe(
+            "WindowMaxBwConfig",
+            &[("inner", "BwTraceConfig"), ("window", "Duration")],
+            WindowMaxBwConfig::new().window(Duration::from_secs(1)),
+        ),
+        describe(
+            "SplineBwConfig",
+            &[
+                ("points", "Vec<(Duration, Bandwidth)>"),
+                ("step", "Duration"),
+                ("duration", "Duration"),
+            ],
+            SplineBwConfig::new().step(Duration::from_millis(100)),
+        ),
+        describe(
+            "SineBwConfig",
+            &[
+                ("mean", "Bandwidth"),
+                ("amplitude", "Bandwidth"),
+                ("period", "Duration"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+                ("std_dev", "Bandwidth"),
+                ("upper_noise_bound", "Bandwidth"),
+                ("lower_noise_bound", "Bandwidth"),
+            ],
+            SineBwConfig::new()
+                .mean(Bandwidth::from_mbps(12))
+                .amplitude(Bandwidth::from_mbps(6))
+                .period(Duration::from_secs(1))
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed)
+                .std_dev(Bandwidth::ZERO),
+        ),
+        describe(
+            "SquareBwConfig",
+            &[
+                ("low", "Bandwidth"),
+                ("high", "Bandwidth"),
+                ("period", "Duration"),
+                ("duty_ratio", "f64"),
+                ("step", "Duration"),
+                ("duration", "Duration"),
+            ],
+            SquareBwConfig::new()
+                .low(Bandwidth::ZERO)
+                .high(Bandwidth::from_mbps(12))
+                .period(Duration::from_secs(1))
+                .duty_ratio(0.5)
+                .step(Duration::from_millis(1))
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "ScheduleBwConfig",
+            &[
+                ("slots", "Vec<(u32, u32, Bandwidth)>"),
+                ("default_bw", "Bandwidth"),
+            ],
+            ScheduleBwConfig::new().default_bw(Bandwidth::from_mbps(12)),
+        ),
+        describe(
+            "FloorPlusVariableBwConfig",
+            &[("floor", "Bandwidth"), ("variable", "BwTraceConfig")],
+            FloorPlusVariableBwConfig::new().floor(Bandwidth::ZERO),
+        ),
+        describe(
+            "ScaledBwConfig",
+            &[("inner", "BwTraceConfig"), ("factor", "f64")],
+            ScaledBwConfig::new().factor(1.0),
+        ),
+        describe(
+            "ClampedBwConfig",
+            &[
+                ("inner", "BwTraceConfig"),
+                ("min", "Bandwidth"),
+                ("max", "Bandwidth"),
+            ],
+            ClampedBwConfig::new(),
+        ),
+        describe(
+            "RandomPhaseBwConfig",
+            &[
+                ("inner", "BwTraceConfig"),
+                ("period", "Duration"),
+                ("seed", "u64"),
+            ],
+            RandomPhaseBwConfig::new().period(Duration::ZERO).seed(seed),
+        ),
+        describe(
+            "MarkovBwConfig",
+            &[
+                ("states", "Vec<Bandwidth>"),
+                ("transitions", "Vec<Vec<f64>>"),
+                ("initial_state", "usize"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            MarkovBwConfig::new()
+                .initial_state(0)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed),
+        ),
+        describe(
+            "EnvBwConfig",
+            &[
+                ("var", "String"),
+                ("default_bw", "Bandwidth"),
+                ("duration", "Duration"),
+            ],
+            EnvBwConfig::new()
+                .default_bw(Bandwidth::from_mbps(12))
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "HysteresisBwConfig",
+            &[
+                ("inner", "BwTraceConfig"),
+                ("threshold", "Bandwidth"),
+                ("max_hold", "Duration"),
+            ],
+            HysteresisBwConfig::new()
+                .threshold(Bandwidth::ZERO)
+                .max_hold(Duration::from_secs(60)),
+        ),
+        describe(
+            "RampBwConfig",
+            &[
+                ("start", "Bandwidth"),
+                ("end", "Bandwidth"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+            ],
+            RampBwConfig::new()
+                .start(Bandwidth::from_mbps(12))
+                .end(Bandwidth::from_mbps(24))
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1)),
+        ),
+        describe(
+            "RandomPatternBwConfig",
+            &[
+                ("pattern", "Vec<(f64, BwTraceConfig)>"),
+                ("count", "usize"),
+                ("seed", "u64"),
+            ],
+            RandomPatternBwConfig::new().seed(seed),
+        ),
+        describe(
+            "ReverseBwConfig",
+            &[("inner", "BwTraceConfig")],
+            ReverseBwConfig::new(),
+        ),
+        describe(
+            "PoissonBwConfig",
+            &[
+                ("rate_pps", "f64"),
+                ("packet_size", "u64"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            PoissonBwConfig::new()
+                .rate_pps(1000.0)
+                .packet_size(1500)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed),
+        ),
+        describe(
+            "SumBwConfig",
+            &[("inputs", "Vec<BwTraceConfig>")],
+            SumBwConfig::new(),
+        ),
+        describe(
+            "WeibullBwConfig",
+            &[
+                ("scale", "Bandwidth"),
+                ("shape", "f64"),
+                ("upper_bound", "Bandwidth"),
+                ("lower_bound", "Bandwidth"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            WeibullBwConfig::new()
+                .scale(Bandwidth::from_mbps(12))
+                .shape(1.0)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed),
+        ),
+        describe(
+            "GammaBwConfig",
+            &[
+                ("shape", "f64"),
+                ("scale", "Bandwidth"),
+                ("upper_bound", "Bandwidth"),
+                ("lower_bound", "Bandwidth"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            GammaBwConfig::new()
+                .shape(1.0)
+                .scale(Bandwidth::from_mbps(12))
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(seed),
+        ),
+        describe(
+            "SpliceBwConfig",
+            &[
+                ("base", "BwTraceConfig"),
+                ("start", "Duration"),
+                ("end", "Duration"),
+                ("override_bw", "Bandwidth"),
+            ],
+            SpliceBwConfig::new()
+                .start(Duration::ZERO)
+                .end(Duration::ZERO)
+                .override_bw(Bandwidth::ZERO),
+        ),
+        describe(
+            "ExponentialDwellBwConfig",
+            &[
+                ("levels", "Vec<Bandwidth>"),
+                ("rate", "f64"),
+                ("duration", "Duration"),
+                ("seed", "u64"),
+            ],
+            ExponentialDwellBwConfig::new()
+                .rate(1.0)
+                .duration(Duration::from_secs(1))
+                .seed(seed),
+        ),
+        describe(
+            "DelayedStartBwConfig",
+            &[("inner", "BwTraceConfig"), ("offset", "Duration")],
+            DelayedStartBwConfig::new().offset(Duration::ZERO),
+        ),
+        describe(
+            "ParetoBwConfig",
+            &[
+                ("scale", "f64"),
+                ("shape", "f64"),
+                ("on_bw", "Bandwidth"),
+                ("off_bw", "Bandwidth"),
+                ("duration", "Duration"),
+                ("seed", "u64"),
+            ],
+            ParetoBwConfig::new()
+                .scale(0.01)
+                .shape(1.5)
+                .on_bw(Bandwidth::from_mbps(12))
+                .off_bw(Bandwidth::ZERO)
+                .duration(Duration::from_secs(1))
+                .seed(seed),
+        ),
+        describe(
+            "StepBwConfig",
+            &[
+                ("steps", "Vec<Bandwidth>"),
+                ("hold", "Duration"),
+                ("repeat", "usize"),
+            ],
+            StepBwConfig::new().hold(Duration::from_secs(1)).repeat(1),
+        ),
+        describe(
+            "QuantizedBwConfig",
+            &[("inner", "BwTraceConfig"), ("levels", "Vec<Bandwidth>")],
+            QuantizedBwConfig::new(),
+        ),
+        #[cfg(feature = "loss-model")]
+        describe(
+            "LossAsGapsBwConfig",
+            &[
+                ("bw", "BwTraceConfig"),
+                ("loss", "LossTraceConfig"),
+                ("seed", "u64"),
+            ],
+            LossAsGapsBwConfig::new().seed(super::DEFAULT_RNG_SEED),
+        ),
+    ]
+}
+
+#[cfg(feature = "delay-model")]
+fn delay_configs() -> Vec<Value> {
+    use super::delay::*;
+
+    vec![
+        describe(
+            "StaticDelayConfig",
+            &[("delay", "Delay"), ("duration", "Duration")],
+            StaticDelayConfig::new()
+                .delay(Delay::from_millis(10))
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "RepeatedDelayPatternConfig",
+            &[("pattern", "Vec<DelayTraceConfig>"), ("count", "usize")],
+            RepeatedDelayPatternConfig::new(),
+        ),
+        describe(
+            "NormalizedDelayConfig",
+            &[
+                ("mean", "Delay"),
+                ("std_dev", "Delay"),
+                ("upper_bound", "Delay"),
+                ("lower_bound", "Delay"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            NormalizedDelayConfig::new()
+                .mean(Delay::from_millis(10))
+                .std_dev(Delay::ZERO)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+        describe(
+            "TraceDelayConfig",
+            &[("pattern", "Vec<(Duration, Delay)>")],
+            TraceDelayConfig::new(),
+        ),
+        describe(
+            "DelayedStartDelayConfig",
+            &[
+                ("inner", "DelayTraceConfig"),
+                ("offset", "Duration"),
+                ("delay", "Delay"),
+            ],
+            DelayedStartDelayConfig::new()
+                .offset(Duration::ZERO)
+                .delay(Delay::ZERO),
+        ),
+        #[cfg(feature = "delay-per-packet-model")]
+        describe(
+            "PerPacketAsDelayConfig",
+            &[
+                ("inner", "DelayPerPacketTraceConfig"),
+                ("packet_interval", "Duration"),
+            ],
+            PerPacketAsDelayConfig::new().packet_interval(Duration::from_millis(1)),
+        ),
+        #[cfg(feature = "bw-model")]
+        describe(
+            "SerializationPlusPropDelayConfig",
+            &[
+                ("bw", "BwTraceConfig"),
+                ("prop", "DelayTraceConfig"),
+                ("packet_bytes", "u64"),
+            ],
+            SerializationPlusPropDelayConfig::new().packet_bytes(1500),
+        ),
+    ]
+}
+
+#[cfg(feature = "delay-per-packet-model")]
+fn delay_per_packet_configs() -> Vec<Value> {
+    use super::delay_per_packet::*;
+
+    vec![
+        describe(
+            "StaticDelayPerPacketConfig",
+            &[("delay", "Delay"), ("count", "usize")],
+            StaticDelayPerPacketConfig::new().delay(Delay::from_millis(10)),
+        ),
+        describe(
+            "RepeatedDelayPerPacketPatternConfig",
+            &[
+                ("pattern", "Vec<DelayPerPacketTraceConfig>"),
+                ("count", "usize"),
+            ],
+            RepeatedDelayPerPacketPatternConfig::new(),
+        ),
+        describe(
+            "NormalizedDelayPerPacketConfig",
+            &[
+                ("mean", "Delay"),
+                ("std_dev", "Delay"),
+                ("upper_bound", "Delay"),
+                ("lower_bound", "Delay"),
+                ("max_step", "Delay"),
+                ("count", "usize"),
+                ("seed", "u64"),
+            ],
+            NormalizedDelayPerPacketConfig::new()
+                .mean(Delay::from_millis(10))
+                .std_dev(Delay::ZERO)
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+        describe(
+            "LogNormalDelayPerPacketConfig",
+            &[
+                ("mean", "Delay"),
+                ("std_dev", "Delay"),
+                ("upper_bound", "Delay"),
+                ("lower_bound", "Delay"),
+                ("max_step", "Delay"),
+                ("count", "usize"),
+                ("seed", "u64"),
+            ],
+            LogNormalDelayPerPacketConfig::new()
+                .mean(Delay::from_millis(10))
+                .std_dev(Delay::ZERO)
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+        #[cfg(all(feature = "bw-model", feature = "delay-model"))]
+        describe(
+            "TimeSlicedAsPerPacketConfig",
+            &[
+                ("delay", "DelayTraceConfig"),
+                ("bw", "BwTraceConfig"),
+                ("packet_bytes", "u64"),
+            ],
+            TimeSlicedAsPerPacketConfig::new().packet_bytes(1500),
+        ),
+    ]
+}
+
+#[cfg(feature = "loss-model")]
+fn loss_configs() -> Vec<Value> {
+    use super::loss::*;
+
+    vec![
+        describe(
+            "StaticLossConfig",
+            &[("loss", "LossPattern"), ("duration", "Duration")],
+            StaticLossConfig::new()
+                .loss(vec![0.1, 0.2])
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "RepeatedLossPatternConfig",
+            &[("pattern", "Vec<LossTraceConfig>"), ("count", "usize")],
+            RepeatedLossPatternConfig::new(),
+        ),
+        describe(
+            "RandomLossConfig",
+            &[
+                ("loss", "f64"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            RandomLossConfig::new()
+                .loss(0.1)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+        describe(
+            "NormalizedLossConfig",
+            &[
+                ("mean", "f64"),
+                ("std_dev", "f64"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            NormalizedLossConfig::new()
+                .mean(0.1)
+                .std_dev(0.0)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+        describe(
+            "TraceLossConfig",
+            &[("pattern", "Vec<(Duration, LossPattern)>")],
+            TraceLossConfig::new(),
+        ),
+        describe(
+            "CombinedLossConfig",
+            &[("a", "LossTraceConfig"), ("b", "LossTraceConfig")],
+            CombinedLossConfig::new(),
+        ),
+    ]
+}
+
+#[cfg(feature = "duplicate-model")]
+fn duplicate_configs() -> Vec<Value> {
+    use super::duplicate::*;
+
+    vec![
+        describe(
+            "StaticDuplicateConfig",
+            &[("duplicate", "DuplicatePattern"), ("duration", "Duration")],
+            StaticDuplicateConfig::new()
+                .duplicate(vec![0.1, 0.2])
+                .duration(Duration::from_secs(1)),
+        ),
+        describe(
+            "RepeatedDuplicatePatternConfig",
+            &[("pattern", "Vec<DuplicateTraceConfig>"), ("count", "usize")],
+            RepeatedDuplicatePatternConfig::new(),
+        ),
+        describe(
+            "NormalizedDuplicateConfig",
+            &[
+                ("mean", "f64"),
+                ("std_dev", "f64"),
+                ("duration", "Duration"),
+                ("step", "Duration"),
+                ("seed", "u64"),
+            ],
+            NormalizedDuplicateConfig::new()
+                .mean(0.1)
+                .std_dev(0.0)
+                .duration(Duration::from_secs(1))
+                .step(Duration::from_millis(1))
+                .seed(super::DEFAULT_RNG_SEED),
+        ),
+    ]
+}
+
+/// Returns the description of all built-in model configurations of the enabled model features.
+///
+/// The result is an object keyed by the kind of trace, each holding an array of
+/// `{"tag": ..., "fields": [{"name": ..., "type": ..., "default": ...}, ...]}`, in the order of
+/// registration. See the [module-level documentation](self) for details.
+///
+/// Configs implemented outside of this crate are not included.
+pub fn config_schema_json() -> Value {
+    #[allow(unused_mut)]
+    let mut schema = Map::new();
+    #[cfg(feature = "bw-model")]
+    schema.insert("bw".to_owned(), Value::Array(bw_configs()));
+    #[cfg(feature = "delay-model")]
+    schema.insert("delay".to_owned(), Value::Array(delay_configs()));
+    #[cfg(feature = "delay-per-packet-model")]
+    schema.insert(
+        "delay_per_packet".to_owned(),
+        Value::Array(delay_per_packet_configs()),
+    );
+    #[cfg(feature = "loss-model")]
+    schema.insert("loss".to_owned(), Value::Array(loss_configs()));
+    #[cfg(feature = "duplicate-model")]
+    schema.insert("duplicate".to_owned(), Value::Array(duplicate_configs()));
+    Value::Object(schema)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tags(schema: &Value, kind: &str) -> Vec<String> {
+        schema[kind]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|config| config["tag"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_config_schema_json() {
+        let schema = config_schema_json();
+
+        // every registered config is described
+        #[cfg(feature = "bw-model")]
+        assert_eq!(
+            tags(&schema, "bw"),
+            super::super::registered_bw_config_tags()
+        );
+        #[cfg(feature = "delay-model")]
+        assert_eq!(
+            tags(&schema, "delay"),
+            super::super::registered_delay_config_tags()
+        );
+        #[cfg(feature = "delay-per-packet-model")]
+        assert_eq!(
+            tags(&schema, "delay_per_packet"),
+            super::super::registered_delay_per_packet_config_tags()
+        );
+        #[cfg(feature = "loss-model")]
+        assert_eq!(
+            tags(&schema, "loss"),
+            super::super::registered_loss_config_tags()
+        );
+        #[cfg(feature = "duplicate-model")]
+        assert_eq!(
+            tags(&schema, "duplicate"),
+            super::super::registered_duplicate_config_tags()
+        );
+
+        #[cfg(feature = "bw-model")]
+        {
+            let static_bw = &schema["bw"][0];
+            assert_eq!(static_bw["tag"], "StaticBwConfig");
+            let fields = static_bw["fields"].as_array().unwrap();
+            assert_eq!(fields.len(), 2);
+            assert_eq!(fields[0]["name"], "bw");
+            assert_eq!(fields[0]["type"], "Bandwidth");
+            assert_eq!(fields[1]["name"], "duration");
+            assert_eq!(fields[1]["type"], "Duration");
+            // the defaults can be read back as the config
+            let defaults: Map<String, Value> = fields
+                .iter()
+                .map(|field| {
+                    (
+                        field["name"].as_str().unwrap().to_owned(),
+                        field["default"].clone(),
+                    )
+                })
+                .collect();
+            let config: super::super::StaticBwConfig =
+                serde_json::from_value(Value::Object(defaults)).unwrap();
+            assert_eq!(config.bw, Some(Bandwidth::from_mbps(12)));
+            assert_eq!(config.duration, Some(Duration::from_secs(1)));
+            #[cfg(feature = "human")]
+            assert_eq!(fields[1]["default"], "1s");
+
+            // fields without default
+            let normalized_bw = &schema["bw"][1];
+            assert_eq!(normalized_bw["fields"][2]["name"], "upper_bound");
+            assert!(normalized_bw["fields"][2]["default"].is_null());
+        }
+    }
+
+    /// Returns, for each config of `kind` in the schema, its tag, the config with no field set, and
+    /// the config with every field set to its default in the schema.
+    fn default_configs(schema: &Value, kind: &str) -> Vec<(String, Value, Value)> {
+        schema[kind]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|config| {
+                let tag = config["tag"].as_str().unwrap().to_owned();
+                let defaults: Map<String, Value> = config["fields"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .filter(|field| !field["default"].is_null())
+                    .map(|field| {
+                        (
+                            field["name"].as_str().unwrap().to_owned(),
+                            field["default"].clone(),
+                        )
+                    })
+                    .collect();
+                (
+                    tag.clone(),
+                    json!({ tag.clone(): {} }),
+                    json!({ tag: defaults }),
+                )
+            })
+            .collect()
+    }
+
+    /// Collects the first outputs of a model.
+    fn drain<T>(next: impl FnMut() -> Option<T>) -> Vec<T> {
+        std::iter::from_fn(next).take(1000).collect()
+    }
+
+    #[test]
+    fn test_schema_defaults() {
+        // the defaults in the schema are those applied when building the models
+        let schema = config_schema_json();
+        #[cfg(feature = "bw-model")]
+        for (tag, empty, defaults) in default_configs(&schema, "bw") {
+            // the segments of ScheduleBw follow the wall clock, so only the bandwidths are compared
+            let timed = tag != "ScheduleBwConfig";
+            let build = |config: Value| {
+                let config: Box<dyn super::super::BwTraceConfig> =
+                    serde_json::from_value(config).unwrap();
+                let mut model = config.into_model();
+                drain(|| {
+                    let (bw, duration) = model.next_bw()?;
+                    Some((bw, if timed { duration } else { Duration::ZERO }))
+                })
+            };
+            assert_eq!(build(empty), build(defaults), "{}", tag);
+        }
+        #[cfg(feature = "delay-model")]
+        for (tag, empty, defaults) in default_configs(&schema, "delay") {
+            let build = |config: Value| {
+                let config: Box<dyn super::super::DelayTraceConfig> =
+                    serde_json::from_value(config).unwrap();
+                let mut model = config.into_model();
+                drain(|| model.next_delay())
+            };
+            assert_eq!(build(empty), build(defaults), "{}", tag);
+        }
+        #[cfg(feature = "delay-per-packet-model")]
+        for (tag, empty, defaults) in default_configs(&schema, "delay_per_packet") {
+            let build = |config: Value| {
+                let config: Box<dyn super::super::DelayPerPacketTraceConfig> =
+                    serde_json::from_value(config).unwrap();
+                let mut model = config.into_model();
+                drain(|| model.next_delay())
+            };
+            assert_eq!(build(empty), build(defaults), "{}", tag);
+        }
+        #[cfg(feature = "loss-model")]
+        for (tag, empty, defaults) in default_configs(&schema, "loss") {
+            let build = |config: Value| {
+                let config: Box<dyn super::super::LossTraceConfig> =
+                    serde_json::from_value(config).unwrap();
+                let mut model = config.into_model();
+                drain(|| model.next_loss())
+            };
+            assert_eq!(build(empty), build(defaults), "{}", tag);
+        }
+        #[cfg(feature = "duplicate-model")]
+        for (tag, empty, defaults) in default_configs(&schema, "duplicate") {
+            let build = |config: Value| {
+                let config: Box<dyn super::super::DuplicateTraceConfig> =
+                    serde_json::from_value(config).unwrap();
+                let mut model = config.into_model();
+                drain(|| model.next_duplicate())
+            };
+            assert_eq!(build(empty), build(defaults), "{}", tag);
+        }
+    }
+}
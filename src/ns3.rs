@@ -0,0 +1,182 @@
+//! This module translates bandwidth and delay traces into [ns-3](https://www.nsnam.org) scripts
+//! scheduling the changes of the simulated link, e.g. for co-simulation.
+//!
+//! Each `(value, duration)` segment of the trace becomes one line scheduling the value at the time the
+//! segment starts, which accumulates the durations of the previous segments:
+//!
+//! ```text
+//! Simulator::Schedule(Seconds(<t>), &SetRate, <var>, "<bps>bps");
+//! Simulator::Schedule(Seconds(<t>), &SetDelay, <var>, "<ms>ms");
+//! ```
+//!
+//! where `<t>` is in seconds, `<bps>` is an integer and `<ms>` may have a fractional part (e.g.
+//! `"0.5ms"`). Only the segments starting before `total` are scheduled. The lines are joined with
+//! `\n`, and paste into an ns-3 program which defines the callbacks and the variable `<var>`, e.g.
+//! for a point-to-point link:
+//!
+//! ```cpp
+//! void SetRate(Ptr<PointToPointNetDevice> device, std::string rate) {
+//!     device->SetDataRate(DataRate(rate));
+//! }
+//!
+//! void SetDelay(Ptr<PointToPointChannel> channel, std::string delay) {
+//!     channel->SetAttribute("Delay", StringValue(delay));
+//! }
+//! ```
+//!
+//! Enable `ns3` feature to use this module.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::StaticBwConfig;
+//! # use netem_trace::{Bandwidth, Duration};
+//! use netem_trace::ns3::BwNs3;
+//!
+//! let mut static_bw = StaticBwConfig::new()
+//!     .bw(Bandwidth::from_mbps(12))
+//!     .duration(Duration::from_secs(1))
+//!     .build();
+//! assert_eq!(
+//!     static_bw.to_ns3_script(Duration::from_secs(1), "device"),
+//!     "Simulator::Schedule(Seconds(0), &SetRate, device, \"12000000bps\");"
+//! );
+//! ```
+use crate::display::format_scaled;
+use crate::{Bandwidth, BwTrace, Delay, DelayTrace, Duration};
+
+/// Schedule the segments starting before `total` with the callback `setter` on `var`.
+fn to_script<T>(
+    total: Duration,
+    setter: &str,
+    var: &str,
+    mut next: impl FnMut() -> Option<(T, Duration)>,
+    value: impl Fn(&T) -> String,
+) -> String {
+    let mut lines = vec![];
+    let mut start = Duration::ZERO;
+    while start < total {
+        let Some((segment, duration)) = next() else {
+            break;
+        };
+        lines.push(format!(
+            "Simulator::Schedule(Seconds({}), &{}, {}, \"{}\");",
+            format_scaled(start.as_nanos(), 9),
+            setter,
+            var,
+            value(&segment)
+        ));
+        start = start.saturating_add(duration);
+    }
+    lines.join("\n")
+}
+
+/// The `BwNs3` trait provides a method to translate a bandwidth trace into an ns-3 script.
+///
+/// This trait is automatically implemented for all types that implement [`BwTrace`].
+pub trait BwNs3: BwTrace {
+    /// Schedule each segment starting before `total` with a
+    /// `Simulator::Schedule(Seconds(<t>), &SetRate, <var>, "<bps>bps");` line.
+    ///
+    /// See the [`ns3`](crate::ns3) module for more details.
+    fn to_ns3_script(&mut self, total: Duration, var: &str) -> String {
+        to_script(
+            total,
+            "SetRate",
+            var,
+            || self.next_bw(),
+            |bw: &Bandwidth| format!("{}bps", bw.as_bps()),
+        )
+    }
+}
+
+impl<T: BwTrace + ?Sized> BwNs3 for T {}
+
+/// The `DelayNs3` trait provides a method to translate a delay trace into an ns-3 script.
+///
+/// This trait is automatically implemented for all types that implement [`DelayTrace`].
+pub trait DelayNs3: DelayTrace {
+    /// Schedule each segment starting before `total` with a
+    /// `Simulator::Schedule(Seconds(<t>), &SetDelay, <var>, "<ms>ms");` line.
+    ///
+    /// See the [`ns3`](crate::ns3) module for more details.
+    fn to_ns3_script(&mut self, total: Duration, var: &str) -> String {
+        to_script(
+            total,
+            "SetDelay",
+            var,
+            || self.next_delay(),
+            |delay: &Delay| format!("{}ms", format_scaled(delay.as_nanos(), 6)),
+        )
+    }
+}
+
+impl<T: DelayTrace + ?Sized> DelayNs3 for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bw-model")]
+    fn test_bw_script() {
+        use crate::model::{BwTraceConfig, RepeatedBwPatternConfig, StaticBwConfig};
+
+        let pattern = vec![
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_mbps(12))
+                    .duration(Duration::from_millis(1500)),
+            ) as Box<dyn BwTraceConfig>,
+            Box::new(
+                StaticBwConfig::new()
+                    .bw(Bandwidth::from_kbps(500))
+                    .duration(Duration::from_secs(2)),
+            ),
+        ];
+        let config = RepeatedBwPatternConfig::new().pattern(pattern).count(1);
+        assert_eq!(
+            config
+                .clone()
+                .build()
+                .to_ns3_script(Duration::from_secs(10), "device"),
+            "Simulator::Schedule(Seconds(0), &SetRate, device, \"12000000bps\");\n\
+             Simulator::Schedule(Seconds(1.5), &SetRate, device, \"500000bps\");"
+        );
+        // the segments starting at or after `total` are not scheduled
+        assert_eq!(
+            config
+                .build()
+                .to_ns3_script(Duration::from_millis(1500), "device"),
+            "Simulator::Schedule(Seconds(0), &SetRate, device, \"12000000bps\");"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "delay-model")]
+    fn test_delay_script() {
+        use crate::model::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+
+        let pattern = vec![
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn DelayTraceConfig>,
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_micros(20500))
+                    .duration(Duration::from_millis(250)),
+            ),
+        ];
+        let mut model = RepeatedDelayPatternConfig::new()
+            .pattern(pattern)
+            .count(1)
+            .build();
+        assert_eq!(
+            model.to_ns3_script(Duration::from_secs(10), "channel"),
+            "Simulator::Schedule(Seconds(0), &SetDelay, channel, \"10ms\");\n\
+             Simulator::Schedule(Seconds(1), &SetDelay, channel, \"20.5ms\");"
+        );
+    }
+}
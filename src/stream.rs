@@ -0,0 +1,88 @@
+//! This module plays the traces generated by struct implementing [`BwTrace`] in real time as
+//! async streams, e.g. to drive a live emulator.
+//!
+//! Enable `async` feature to use this module. The streams wait with [`tokio::time::sleep`], so
+//! they must be polled within a tokio runtime with the `time` driver enabled.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::stream::BwTraceStream;
+//! # use netem_trace::{Bandwidth, Duration, IterBw};
+//! use futures::StreamExt;
+//!
+//! # #[tokio::main(flavor = "current_thread", start_paused = true)]
+//! # async fn main() {
+//! let trace = IterBw::new([
+//!     (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+//!     (Bandwidth::from_mbps(24), Duration::from_millis(200)),
+//! ]);
+//! let mut stream = Box::pin(trace.into_timed_stream());
+//! while let Some(bw) = stream.next().await {
+//!     // apply `bw` to the emulated link until the next one arrives
+//! #   let _ = bw;
+//! }
+//! # }
+//! ```
+
+use crate::{Bandwidth, BwTrace, Duration};
+use futures::Stream;
+
+/// The `BwTraceStream` trait turns a bandwidth trace into an async stream played in real time.
+pub trait BwTraceStream: BwTrace + Sized {
+    /// Turn the trace into a stream which yields each bandwidth when its segment starts.
+    ///
+    /// The first bandwidth is yielded immediately, and each of the next ones after sleeping for
+    /// the duration of the previous segment. The stream ends when the trace ends, after sleeping
+    /// for the duration of the last segment, so that it lasts as long as the trace.
+    fn into_timed_stream(self) -> impl Stream<Item = Bandwidth> {
+        futures::stream::unfold(
+            (self, Duration::ZERO),
+            |(mut trace, wait): (Self, Duration)| async move {
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+                let (bw, duration) = trace.next_bw()?;
+                Some((bw, (trace, duration)))
+            },
+        )
+    }
+}
+
+impl<T: BwTrace> BwTraceStream for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::IterBw;
+    use futures::StreamExt;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_into_timed_stream() {
+        let trace = IterBw::new([
+            (Bandwidth::from_mbps(12), Duration::from_millis(100)),
+            (Bandwidth::from_mbps(24), Duration::from_millis(200)),
+            (Bandwidth::from_mbps(36), Duration::from_millis(300)),
+        ]);
+        let start = Instant::now();
+        let mut stream = Box::pin(trace.into_timed_stream());
+        let mut yielded = vec![];
+        while let Some(bw) = stream.next().await {
+            yielded.push((bw, start.elapsed()));
+        }
+        assert_eq!(
+            yielded,
+            [
+                (Bandwidth::from_mbps(12), Duration::ZERO),
+                (Bandwidth::from_mbps(24), Duration::from_millis(100)),
+                (Bandwidth::from_mbps(36), Duration::from_millis(300)),
+            ]
+        );
+        // the stream ends after the last segment
+        assert_eq!(start.elapsed(), Duration::from_millis(600));
+
+        let empty = IterBw::new(std::iter::empty());
+        assert_eq!(empty.into_timed_stream().count().await, 0);
+    }
+}
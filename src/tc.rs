@@ -0,0 +1,197 @@
+//! This module translates delay and loss traces into Linux `tc qdisc ... netem` commands.
+//!
+//! Each `(value, duration)` segment of the trace becomes a `tc qdisc change dev <dev> root netem ...`
+//! command, followed by a `sleep <seconds>` command holding the value for the duration of the segment.
+//! The commands can be joined into a shell script driving netem on the device, which must already
+//! have a netem root qdisc (e.g. `tc qdisc add dev <dev> root netem`).
+//!
+//! The trace is consumed to its end, so it must be finite.
+//!
+//! Enable `tc` feature to use this module.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::model::StaticDelayConfig;
+//! # use netem_trace::{Delay, Duration};
+//! use netem_trace::tc::DelayTcNetem;
+//!
+//! let mut static_delay = StaticDelayConfig::new()
+//!     .delay(Delay::from_millis(10))
+//!     .duration(Duration::from_secs(1))
+//!     .build();
+//! assert_eq!(
+//!     static_delay.to_tc_commands("eth0"),
+//!     ["tc qdisc change dev eth0 root netem delay 10ms", "sleep 1"]
+//! );
+//! ```
+use crate::display::format_scaled;
+use crate::{Delay, DelayTrace, Duration, LossPattern, LossTrace};
+
+/// Format a delay in milliseconds for netem, e.g. `"10ms"` or `"0.5ms"`.
+fn format_delay(delay: Delay) -> String {
+    format!("{}ms", format_scaled(delay.as_nanos(), 6))
+}
+
+/// Format a probability as a percentage for netem (clamped to `[0, 1]`), e.g. `"10%"`.
+fn format_percent(prob: f64) -> String {
+    // in units of 10^-6 percent, which is finer than the precision of netem
+    let micro_percent = (prob.clamp(0.0, 1.0) * 1e8).round() as u128;
+    format!("{}%", format_scaled(micro_percent, 6))
+}
+
+/// Format the netem arguments of a loss pattern.
+///
+/// A pattern with at most one element is a random loss, e.g. `"loss 10%"`. A longer pattern is
+/// translated into the Gilbert model `"loss gemodel <p> <r>"`, where `p` is the probability of loss
+/// after a delivered packet (i.e. the first element) and `1 - r` is the probability of loss after a
+/// lost packet (i.e. the second element). As the Gilbert model only depends on the last packet, the
+/// elements after the second are ignored.
+fn format_loss(loss: &LossPattern) -> String {
+    match loss.as_slice() {
+        [] => format!("loss {}", format_percent(0.0)),
+        [prob] => format!("loss {}", format_percent(*prob)),
+        [p, bad, ..] => format!(
+            "loss gemodel {} {}",
+            format_percent(*p),
+            format_percent(1.0 - bad.clamp(0.0, 1.0))
+        ),
+    }
+}
+
+/// Translate the segments into pairs of netem commands and sleeps.
+fn to_commands<T>(
+    dev: &str,
+    mut next: impl FnMut() -> Option<(T, Duration)>,
+    args: impl Fn(&T) -> String,
+) -> Vec<String> {
+    let mut commands = vec![];
+    while let Some((value, duration)) = next() {
+        commands.push(format!(
+            "tc qdisc change dev {} root netem {}",
+            dev,
+            args(&value)
+        ));
+        commands.push(format!("sleep {}", format_scaled(duration.as_nanos(), 9)));
+    }
+    commands
+}
+
+/// The `DelayTcNetem` trait provides a method to translate a delay trace into netem commands.
+///
+/// This trait is automatically implemented for all types that implement [`DelayTrace`].
+pub trait DelayTcNetem: DelayTrace {
+    /// Translate each segment into a `tc qdisc change dev <dev> root netem delay <ms>ms` command,
+    /// followed by a `sleep <seconds>` command for the duration of the segment.
+    ///
+    /// See the [`tc`](crate::tc) module for more details.
+    fn to_tc_commands(&mut self, dev: &str) -> Vec<String> {
+        to_commands(
+            dev,
+            || self.next_delay(),
+            |delay| format!("delay {}", format_delay(*delay)),
+        )
+    }
+}
+
+impl<T: DelayTrace + ?Sized> DelayTcNetem for T {}
+
+/// The `LossTcNetem` trait provides a method to translate a loss trace into netem commands.
+///
+/// This trait is automatically implemented for all types that implement [`LossTrace`].
+pub trait LossTcNetem: LossTrace {
+    /// Translate each segment into a `tc qdisc change dev <dev> root netem loss <pct>%` command (or
+    /// `loss gemodel <p> <r>` for a [`LossPattern`] with multiple elements), followed by a
+    /// `sleep <seconds>` command for the duration of the segment.
+    ///
+    /// See the [`tc`](crate::tc) module for more details.
+    fn to_tc_commands(&mut self, dev: &str) -> Vec<String> {
+        to_commands(dev, || self.next_loss(), format_loss)
+    }
+}
+
+impl<T: LossTrace + ?Sized> LossTcNetem for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_delay(Delay::ZERO), "0ms");
+        assert_eq!(format_delay(Delay::from_micros(500)), "0.5ms");
+        assert_eq!(format_delay(Delay::from_nanos(10_000_001)), "10.000001ms");
+        assert_eq!(format_percent(0.1), "10%");
+        assert_eq!(format_percent(0.00125), "0.125%");
+        assert_eq!(format_percent(1.5), "100%");
+        assert_eq!(format_loss(&vec![]), "loss 0%");
+        assert_eq!(format_loss(&vec![0.2, 0.75, 0.9]), "loss gemodel 20% 25%");
+    }
+
+    #[test]
+    #[cfg(feature = "delay-model")]
+    fn test_delay_commands() {
+        use crate::model::{DelayTraceConfig, RepeatedDelayPatternConfig, StaticDelayConfig};
+
+        let pattern = vec![
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_millis(10))
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn DelayTraceConfig>,
+            Box::new(
+                StaticDelayConfig::new()
+                    .delay(Delay::from_micros(20500))
+                    .duration(Duration::from_millis(1500)),
+            ),
+        ];
+        let mut model = RepeatedDelayPatternConfig::new()
+            .pattern(pattern)
+            .count(1)
+            .build();
+        assert_eq!(
+            model.to_tc_commands("eth0"),
+            [
+                "tc qdisc change dev eth0 root netem delay 10ms",
+                "sleep 1",
+                "tc qdisc change dev eth0 root netem delay 20.5ms",
+                "sleep 1.5",
+            ]
+        );
+        assert!(model.to_tc_commands("eth0").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "loss-model")]
+    fn test_loss_commands() {
+        use crate::model::{LossTraceConfig, RepeatedLossPatternConfig, StaticLossConfig};
+
+        let pattern = vec![
+            Box::new(
+                StaticLossConfig::new()
+                    .loss(vec![0.01])
+                    .duration(Duration::from_secs(1)),
+            ) as Box<dyn LossTraceConfig>,
+            Box::new(
+                StaticLossConfig::new()
+                    .loss(vec![0.05, 0.5])
+                    .duration(Duration::from_millis(250)),
+            ),
+        ];
+        let mut model: Box<dyn LossTrace> = Box::new(
+            RepeatedLossPatternConfig::new()
+                .pattern(pattern)
+                .count(1)
+                .build(),
+        );
+        assert_eq!(
+            model.to_tc_commands("veth-1"),
+            [
+                "tc qdisc change dev veth-1 root netem loss 1%",
+                "sleep 1",
+                "tc qdisc change dev veth-1 root netem loss gemodel 5% 50%",
+                "sleep 0.25",
+            ]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! This module provides helpers to check traces in property tests, e.g. of downstream crates
+//! validating their own captured traces.
+//!
+//! Enable `testing` feature to use this module.
+//!
+//! ## Examples
+//!
+//! ```
+//! use netem_trace::testing::is_mahimahi_roundtrip_stable;
+//!
+//! assert!(is_mahimahi_roundtrip_stable(&[1, 1, 5, 6]));
+//! // zero timestamps are regenerated as copies of the last timestamp
+//! assert!(!is_mahimahi_roundtrip_stable(&[0, 1, 1, 5, 6]));
+//! ```
+use crate::{load_mahimahi_trace, Duration, Mahimahi};
+
+/// Returns whether the mahimahi `trace` is regenerated exactly after loading it with
+/// [`load_mahimahi_trace`] and generating it again with [`Mahimahi::mahimahi`] up to its last
+/// timestamp.
+///
+/// Returns `false` if the trace cannot be loaded, e.g. if it is empty or not monotonically
+/// nondecreasing.
+///
+/// Known deviations, for which `false` is returned although the trace is valid:
+///
+/// - A trace with zero timestamps (i.e. a zero-timestamp prefix). mahimahi sends their packets at
+///   the end of each period, so they are regenerated as copies of the last timestamp instead. The
+///   regenerated trace has no zero timestamps, so it is stable from then on.
+pub fn is_mahimahi_roundtrip_stable(trace: &[u64]) -> bool {
+    let Some(&last) = trace.last() else {
+        return false;
+    };
+    match load_mahimahi_trace(trace.to_vec(), None) {
+        Ok(config) => config.build().mahimahi(&Duration::from_millis(last)) == trace,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_mahimahi_roundtrip_stable() {
+        assert!(is_mahimahi_roundtrip_stable(&[1, 1, 5, 6]));
+        assert!(is_mahimahi_roundtrip_stable(&[
+            2, 2, 3, 3, 4, 4, 5, 5, 8, 9
+        ]));
+
+        let zero_leading = [0, 0, 2, 2, 3, 3, 6, 6];
+        assert!(!is_mahimahi_roundtrip_stable(&zero_leading));
+        let regenerated = load_mahimahi_trace(zero_leading.to_vec(), None)
+            .unwrap()
+            .build()
+            .mahimahi(&Duration::from_millis(6));
+        assert_eq!(regenerated, [2, 2, 3, 3, 6, 6, 6, 6]);
+        assert!(is_mahimahi_roundtrip_stable(&regenerated));
+
+        assert!(!is_mahimahi_roundtrip_stable(&[]));
+        assert!(!is_mahimahi_roundtrip_stable(&[2, 1]));
+    }
+}
@@ -0,0 +1,96 @@
+//! This module contains convenience constructors of [`Bandwidth`] missing from the `bandwidth` crate.
+//!
+//! They are provided by the extension trait [`BandwidthExt`], which is re-exported at the crate root.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use netem_trace::{Bandwidth, BandwidthExt};
+//! assert_eq!(Bandwidth::from_tbps(1), Bandwidth::from_gbps(1000));
+//! // rounded instead of truncated
+//! assert_eq!(Bandwidth::from_bps_f64(2.7), Bandwidth::from_bps(3));
+//! ```
+use crate::Bandwidth;
+
+/// Extension constructors of [`Bandwidth`].
+pub trait BandwidthExt {
+    /// Creates a new `Bandwidth` from the specified number of terabits per second.
+    ///
+    /// Saturates at the maximum bandwidth instead of overflowing.
+    fn from_tbps(tbps: u64) -> Bandwidth;
+
+    /// Creates a new `Bandwidth` from a fractional number of bits per second, rounded to the
+    /// nearest bps.
+    ///
+    /// Unlike `Bandwidth::from_bps(bps as u64)`, which truncates towards zero and thus biases the
+    /// bandwidth low, half a bps is rounded up. A negative or NaN value gives zero, and a value
+    /// beyond `u64::MAX` bps saturates.
+    fn from_bps_f64(bps: f64) -> Bandwidth;
+}
+
+impl BandwidthExt for Bandwidth {
+    fn from_tbps(tbps: u64) -> Bandwidth {
+        Bandwidth::from_gbps(tbps.saturating_mul(1000))
+    }
+
+    fn from_bps_f64(bps: f64) -> Bandwidth {
+        // float-to-int casts saturate, and NaN is cast to 0
+        Bandwidth::from_bps(bps.round() as u64)
+    }
+}
+
+/// Returns the bandwidth in bits per second, without the precision loss of going through `f64`.
+pub(crate) fn bps_u128(bw: Bandwidth) -> u128 {
+    bw.as_gbps() as u128 * 1_000_000_000 + bw.subgbps_bps() as u128
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_tbps() {
+        assert_eq!(Bandwidth::from_tbps(0), Bandwidth::ZERO);
+        assert_eq!(Bandwidth::from_tbps(3), Bandwidth::from_gbps(3000));
+        assert_eq!(
+            Bandwidth::from_tbps(u64::MAX),
+            Bandwidth::from_gbps(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_from_bps_f64() {
+        // rounding vs truncation at small bps
+        for (bps, rounded, truncated) in [
+            (0.4, 0, 0),
+            (0.5, 1, 0),
+            (1.49, 1, 1),
+            (2.5, 3, 2),
+            (2.99, 3, 2),
+        ] {
+            assert_eq!(Bandwidth::from_bps_f64(bps), Bandwidth::from_bps(rounded));
+            assert_eq!(
+                Bandwidth::from_bps(bps as u64),
+                Bandwidth::from_bps(truncated)
+            );
+        }
+        // the mean of uniformly spread samples is kept by rounding but biased low by truncation
+        let samples: Vec<f64> = (0..1000).map(|i| 10.0 + i as f64 / 1000.0).collect();
+        let mean = |convert: fn(f64) -> Bandwidth| {
+            samples
+                .iter()
+                .map(|bps| convert(*bps).as_bps() as f64)
+                .sum::<f64>()
+                / 1000.0
+        };
+        assert_eq!(mean(Bandwidth::from_bps_f64), 10.5);
+        assert_eq!(mean(|bps| Bandwidth::from_bps(bps as u64)), 10.0);
+
+        assert_eq!(Bandwidth::from_bps_f64(-1.0), Bandwidth::ZERO);
+        assert_eq!(Bandwidth::from_bps_f64(f64::NAN), Bandwidth::ZERO);
+        assert_eq!(
+            Bandwidth::from_bps_f64(f64::INFINITY),
+            Bandwidth::from_bps(u64::MAX)
+        );
+    }
+}